@@ -9,6 +9,7 @@
 
 pub mod gen;
 pub mod pkce;
+pub mod signature;
 pub mod urlencode;
 
 use serde::{Deserialize, Serialize};
@@ -0,0 +1,89 @@
+//! # Signature Verification
+//!
+//! Verify a signature over a message using the signer's public key, expressed
+//! as a JWK. This is the single verifier used for JWS and COSE payloads across
+//! the Vercre crates.
+
+use anyhow::{anyhow, bail};
+use base64ct::{Base64UrlUnpadded, Encoding};
+use ed25519_dalek::Verifier as _;
+use vercre_infosec::jose::jwk::{Curve, PublicKeyJwk};
+
+/// Verify `signature` over `msg` using the public key `jwk`.
+///
+/// Ed25519 (`EdDSA`) and `secp256k1` (`ES256K`) keys are supported. `ES256K`
+/// signatures are expected in the fixed-size `r || s` form used by JWS and
+/// COSE.
+///
+/// # Errors
+///
+/// Returns an error if the key is malformed or uses an unsupported curve, or
+/// the signature is invalid.
+pub fn verify(jwk: &PublicKeyJwk, msg: &[u8], signature: &[u8]) -> anyhow::Result<()> {
+    let x = Base64UrlUnpadded::decode_vec(&jwk.x)
+        .map_err(|e| anyhow!("issue decoding public key `x`: {e}"))?;
+
+    match jwk.crv {
+        Curve::Ed25519 => {
+            let key = ed25519_dalek::VerifyingKey::try_from(x.as_slice())?;
+            let signature = ed25519_dalek::Signature::from_slice(signature)?;
+            key.verify(msg, &signature).map_err(|e| anyhow!("invalid signature: {e}"))
+        }
+        Curve::Es256K => {
+            let Some(y) = &jwk.y else {
+                bail!("secp256k1 key is missing `y`");
+            };
+            let y = Base64UrlUnpadded::decode_vec(y)
+                .map_err(|e| anyhow!("issue decoding public key `y`: {e}"))?;
+
+            // uncompressed SEC1 point
+            let mut sec1 = vec![0x04];
+            sec1.extend(x);
+            sec1.extend(y);
+
+            let key = k256::ecdsa::VerifyingKey::from_sec1_bytes(&sec1)?;
+            let signature = k256::ecdsa::Signature::from_slice(signature)?;
+            key.verify(msg, &signature).map_err(|e| anyhow!("invalid signature: {e}"))
+        }
+        Curve::X25519 => bail!("X25519 keys cannot be used to verify signatures"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::Signer as _;
+
+    use super::*;
+
+    const MSG: &[u8] = b"the quick brown fox";
+
+    #[test]
+    fn ed25519() {
+        let signing_key = ed25519_dalek::SigningKey::from_bytes(&[7; 32]);
+        let jwk = PublicKeyJwk {
+            crv: Curve::Ed25519,
+            x: Base64UrlUnpadded::encode_string(signing_key.verifying_key().as_bytes()),
+            ..PublicKeyJwk::default()
+        };
+
+        let signature = signing_key.sign(MSG).to_bytes();
+        verify(&jwk, MSG, &signature).expect("should verify");
+        verify(&jwk, b"tampered", &signature).expect_err("should not verify");
+    }
+
+    #[test]
+    fn es256k() {
+        let signing_key = k256::ecdsa::SigningKey::from_slice(&[7; 32]).expect("should be a key");
+        let point = signing_key.verifying_key().to_encoded_point(false);
+        let jwk = PublicKeyJwk {
+            crv: Curve::Es256K,
+            x: Base64UrlUnpadded::encode_string(point.x().expect("should have x")),
+            y: point.y().map(|y| Base64UrlUnpadded::encode_string(y)),
+            ..PublicKeyJwk::default()
+        };
+
+        let signature: k256::ecdsa::Signature = signing_key.sign(MSG);
+        verify(&jwk, MSG, &signature.to_bytes()).expect("should verify");
+        verify(&jwk, b"tampered", &signature.to_bytes()).expect_err("should not verify");
+    }
+}
@@ -124,6 +124,27 @@ mod tests {
         assert_eq!(ser::to_string(&s).unwrap(), expected);
     }
 
+    #[test]
+    fn encode_flattened() {
+        #[derive(Serialize)]
+        struct Flattened {
+            field_1: String,
+            #[serde(flatten)]
+            nested: Nested,
+        }
+
+        let data = Flattened {
+            field_1: "value1".to_owned(),
+            nested: Nested {
+                field_3: "value3".to_owned(),
+                field_4: "value4".to_owned(),
+            },
+        };
+
+        let serialized = super::to_string(&data).expect("should serialize");
+        assert_eq!(serialized, "field_1=value1&field_3=value3&field_4=value4");
+    }
+
     #[test]
     fn decode_struct() {
         let url = r#"field_1=value1&field_2=value2&nested=%7B%22field_3%22%3A%22value3%22%2C%22field_4%22%3A%22value4%22%7D"#;
@@ -0,0 +1,556 @@
+//! # Digital Credentials Query Language
+//!
+//! A [DCQL] query is the `OpenID4VP` alternative to a Presentation Definition.
+//! It lists the credentials a Verifier is requesting, the claims each must
+//! contain, and, optionally, sets of credentials that may be submitted
+//! together.
+//!
+//! [DCQL]: (https://openid.net/specs/openid-4-verifiable-presentations-1_0.html#name-digital-credentials-query-l)
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::requirements::product;
+use crate::Claims;
+
+/// A DCQL query requesting one or more credentials.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct DcqlQuery {
+    /// The credentials requested by the Verifier.
+    pub credentials: Vec<CredentialQuery>,
+
+    /// Constraints on which combinations of credentials may be returned. When
+    /// not set, every requested credential must be returned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub credential_sets: Option<Vec<CredentialSetQuery>>,
+}
+
+/// A request for a single credential.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct CredentialQuery {
+    /// Identifies the query and the presentation returned for it in the
+    /// `vp_token`.
+    pub id: String,
+
+    /// The format of the requested credential. For example, `jwt_vc_json` or
+    /// `vc+sd-jwt`.
+    pub format: String,
+
+    /// Whether more than one presentation may be returned for the query.
+    /// Defaults to `false`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub multiple: Option<bool>,
+
+    /// Format-specific constraints on the credential's metadata.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+
+    /// The claims requested from the credential. When not set, no specific
+    /// claims are requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub claims: Option<Vec<ClaimsQuery>>,
+
+    /// Alternative sets of claims (by claims query `id`) that satisfy the
+    /// query, in order of the Verifier's preference.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub claim_sets: Option<Vec<Vec<String>>>,
+}
+
+/// Format-specific credential metadata constraints.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Meta {
+    /// SD-JWT credential types (`vct`), any one of which is acceptable.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vct_values: Option<Vec<String>>,
+
+    /// W3C credential types. The credential must include every type in at
+    /// least one of the listed sets.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub type_values: Option<Vec<Vec<String>>>,
+
+    /// The `ISO mDL` document type.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub doctype_value: Option<String>,
+}
+
+/// A request for a single claim.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ClaimsQuery {
+    /// Identifies the claim in `claim_sets`. Required when the credential
+    /// query has `claim_sets`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+
+    /// The path to the claim within the credential.
+    pub path: Vec<PathElement>,
+
+    /// Acceptable values for the claim. When not set, any value is accepted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub values: Option<Vec<Value>>,
+}
+
+/// An element of a claims path pointer.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum PathElement {
+    /// Select the named property of an object.
+    Name(String),
+
+    /// Select the element at the index of an array.
+    Index(usize),
+
+    /// Select every element of an array.
+    Wildcard,
+}
+
+/// Alternative combinations of credentials that may be returned.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct CredentialSetQuery {
+    /// Each option is a list of credential query `id`s that, together,
+    /// satisfy the set.
+    pub options: Vec<Vec<String>>,
+
+    /// Whether the set must be satisfied. Defaults to `true`.
+    #[serde(default = "required")]
+    pub required: bool,
+
+    /// Describes the purpose of the request to the Holder.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub purpose: Option<Value>,
+}
+
+const fn required() -> bool {
+    true
+}
+
+/// A credential query to be answered along with the candidates that satisfy
+/// it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CredentialMatch {
+    /// The `id` of the credential query.
+    pub id: String,
+
+    /// Indexes of the candidates satisfying the credential query.
+    pub candidates: Vec<usize>,
+}
+
+impl DcqlQuery {
+    /// Returns each combination of credential queries that can be answered
+    /// using the candidate claims. Each query in a combination is returned
+    /// with the indexes of the candidates satisfying it.
+    ///
+    /// Combinations are ordered from fewest to most credential queries. An
+    /// empty result means the query cannot be satisfied.
+    ///
+    /// Candidates are matched on claims and metadata only: callers should
+    /// ensure candidates are in the format requested.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a candidate cannot be serialized or the query is
+    /// malformed.
+    pub fn combinations(&self, candidates: &[impl Claims]) -> Result<Vec<Vec<CredentialMatch>>> {
+        let mut matches = vec![];
+        for query in &self.credentials {
+            let mut satisfied = vec![];
+            for (i, candidate) in candidates.iter().enumerate() {
+                if query.satisfied(candidate)? {
+                    satisfied.push(i);
+                }
+            }
+            matches.push(satisfied);
+        }
+
+        let available = matches.iter().map(|m| !m.is_empty()).collect::<Vec<_>>();
+        let combinations = self
+            .select(&available)?
+            .into_iter()
+            .map(|queries| {
+                queries
+                    .into_iter()
+                    .map(|i| CredentialMatch {
+                        id: self.credentials[i].id.clone(),
+                        candidates: matches[i].clone(),
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Ok(combinations)
+    }
+
+    /// Check whether presentations returned for the specified credential
+    /// queries satisfy the query's credential sets or, when there are none,
+    /// answer every credential query.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a credential set refers to an unknown credential
+    /// query.
+    pub fn satisfied_by(&self, submitted: &[&str]) -> Result<bool> {
+        if submitted.iter().any(|id| !self.credentials.iter().any(|query| query.id == *id)) {
+            return Ok(false);
+        }
+
+        let available = self
+            .credentials
+            .iter()
+            .map(|query| submitted.contains(&query.id.as_str()))
+            .collect::<Vec<_>>();
+        let submitted = (0..available.len()).filter(|&i| available[i]).collect::<Vec<_>>();
+
+        Ok(self.select(&available)?.contains(&submitted))
+    }
+
+    // Combinations (as sorted indexes) of the available credential queries
+    // satisfying the query.
+    fn select(&self, available: &[bool]) -> Result<Vec<Vec<usize>>> {
+        let Some(sets) = &self.credential_sets else {
+            if available.iter().all(|a| *a) {
+                return Ok(vec![(0..available.len()).collect()]);
+            }
+            return Ok(vec![]);
+        };
+
+        let mut combinations = vec![vec![]];
+        for set in sets {
+            let mut options = vec![];
+            for option in &set.options {
+                let mut indexes = vec![];
+                for id in option {
+                    let Some(i) = self.credentials.iter().position(|query| &query.id == id) else {
+                        return Err(anyhow!("credential set refers to unknown credential {id}"));
+                    };
+                    indexes.push(i);
+                }
+                if indexes.iter().all(|&i| available[i]) {
+                    options.push(indexes);
+                }
+            }
+
+            // an optional set may be left out
+            if !set.required {
+                options.insert(0, vec![]);
+            }
+            combinations = product(&combinations, &options);
+        }
+
+        combinations.sort_by_key(Vec::len);
+        Ok(combinations)
+    }
+}
+
+impl CredentialQuery {
+    /// Check whether the credential's claims satisfy the query.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the credential cannot be serialized or the query is
+    /// malformed.
+    pub fn satisfied(&self, claims: &impl Claims) -> Result<bool> {
+        Ok(self.matched_claims(claims)?.is_some())
+    }
+
+    /// Returns the claims queries satisfied by the credential, using the
+    /// first satisfiable claim set when the query has `claim_sets`. Returns
+    /// `None` when the credential does not satisfy the query.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the credential cannot be serialized or the query is
+    /// malformed.
+    pub fn matched_claims(&self, claims: &impl Claims) -> Result<Option<Vec<&ClaimsQuery>>> {
+        let Ok(vc_val) = claims.to_json() else {
+            return Err(anyhow!("error serializing credential"));
+        };
+
+        if let Some(meta) = &self.meta {
+            if !meta.matched(&vc_val) {
+                return Ok(None);
+            }
+        }
+        let Some(queries) = &self.claims else {
+            return Ok(Some(vec![]));
+        };
+        let Some(claim_sets) = &self.claim_sets else {
+            if queries.iter().all(|query| query.matched(&vc_val)) {
+                return Ok(Some(queries.iter().collect()));
+            }
+            return Ok(None);
+        };
+
+        for claim_set in claim_sets {
+            let mut matched = vec![];
+            for id in claim_set {
+                let Some(query) = queries.iter().find(|q| q.id.as_ref() == Some(id)) else {
+                    return Err(anyhow!("claim set refers to unknown claim {id}"));
+                };
+                if !query.matched(&vc_val) {
+                    break;
+                }
+                matched.push(query);
+            }
+            if matched.len() == claim_set.len() {
+                return Ok(Some(matched));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+impl Meta {
+    fn matched(&self, vc_val: &Value) -> bool {
+        let types = match &vc_val["type"] {
+            Value::String(type_) => vec![type_.as_str()],
+            Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+            _ => vec![],
+        };
+
+        if let Some(vct_values) = &self.vct_values {
+            let vct = vc_val["vct"].as_str();
+            if !vct_values.iter().any(|v| vct == Some(v) || types.contains(&v.as_str())) {
+                return false;
+            }
+        }
+        if let Some(type_values) = &self.type_values {
+            if !type_values.iter().any(|set| set.iter().all(|t| types.contains(&t.as_str()))) {
+                return false;
+            }
+        }
+        if let Some(doctype) = &self.doctype_value {
+            if vc_val["docType"].as_str() != Some(doctype) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl ClaimsQuery {
+    /// Check whether the claim is present in the credential and, when `values`
+    /// is set, has one of the acceptable values.
+    #[must_use]
+    pub fn matched(&self, vc_val: &Value) -> bool {
+        let selected = self.select(vc_val);
+        if selected.is_empty() {
+            return false;
+        }
+        let Some(values) = &self.values else {
+            return true;
+        };
+        selected.iter().any(|v| values.contains(v))
+    }
+
+    // Process the claims path pointer, returning the selected values.
+    fn select<'a>(&self, vc_val: &'a Value) -> Vec<&'a Value> {
+        let mut selected = vec![vc_val];
+        for element in &self.path {
+            selected = selected
+                .into_iter()
+                .flat_map(|value| match (element, value) {
+                    (PathElement::Name(name), Value::Object(map)) => {
+                        map.get(name).into_iter().collect()
+                    }
+                    (PathElement::Index(i), Value::Array(arr)) => arr.get(*i).into_iter().collect(),
+                    (PathElement::Wildcard, Value::Array(arr)) => arr.iter().collect(),
+                    _ => vec![],
+                })
+                .collect();
+        }
+        selected
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn claims() {
+        let query = credential_query(json!({
+            "id": "employee",
+            "format": "jwt_vc_json",
+            "meta": {"type_values": [["VerifiableCredential", "EmployeeIDCredential"]]},
+            "claims": [
+                {"path": ["credentialSubject", "givenName"]},
+                {"path": ["credentialSubject", "address", "locality"], "values": ["Sydney", "Perth"]},
+                {"path": ["credentialSubject", "roles", null], "values": ["manager"]}
+            ]
+        }));
+        assert!(query.satisfied(&Credential(employee())).unwrap());
+
+        let mut vc = employee();
+        vc["credentialSubject"]["address"]["locality"] = json!("Melbourne");
+        assert!(!query.satisfied(&Credential(vc)).unwrap());
+
+        let mut vc = employee();
+        vc["credentialSubject"]["roles"] = json!(["engineer"]);
+        assert!(!query.satisfied(&Credential(vc)).unwrap());
+    }
+
+    #[test]
+    fn meta() {
+        let mut query = credential_query(json!({
+            "id": "employee",
+            "format": "jwt_vc_json",
+            "meta": {"type_values": [["VerifiableCredential", "DriversLicense"]]}
+        }));
+        assert!(!query.satisfied(&Credential(employee())).unwrap());
+
+        query.meta = Some(Meta {
+            vct_values: Some(vec!["EmployeeIDCredential".into()]),
+            ..Meta::default()
+        });
+        assert!(query.satisfied(&Credential(employee())).unwrap());
+    }
+
+    #[test]
+    fn path_index() {
+        let query = credential_query(json!({
+            "id": "employee",
+            "format": "jwt_vc_json",
+            "claims": [{"path": ["credentialSubject", "roles", 1], "values": ["manager"]}]
+        }));
+        assert!(query.satisfied(&Credential(employee())).unwrap());
+        assert_eq!(query.claims.as_ref().unwrap()[0].path[2], PathElement::Index(1));
+    }
+
+    #[test]
+    fn claim_sets() {
+        let query = credential_query(json!({
+            "id": "employee",
+            "format": "jwt_vc_json",
+            "claims": [
+                {"id": "a", "path": ["credentialSubject", "dateOfBirth"]},
+                {"id": "b", "path": ["credentialSubject", "givenName"]},
+                {"id": "c", "path": ["credentialSubject", "familyName"]}
+            ],
+            "claim_sets": [["a"], ["b", "c"]]
+        }));
+
+        let matched = query.matched_claims(&Credential(employee())).unwrap().unwrap();
+        let ids = matched.iter().map(|q| q.id.as_deref().unwrap()).collect::<Vec<_>>();
+        assert_eq!(ids, vec!["b", "c"]);
+
+        let query = CredentialQuery {
+            claim_sets: Some(vec![vec!["d".into()]]),
+            ..query
+        };
+        assert!(query.satisfied(&Credential(employee())).is_err());
+    }
+
+    #[test]
+    fn credential_sets() {
+        let dcql = dcql_query(json!([
+            {"options": [["employee"]]},
+            {"options": [["license"], ["passport"]], "required": false}
+        ]));
+
+        let combinations = dcql.combinations(&[Credential(employee())]).unwrap();
+        assert_eq!(ids(&combinations), vec![vec!["employee"]]);
+
+        let candidates = [Credential(employee()), Credential(license())];
+        let combinations = dcql.combinations(&candidates).unwrap();
+        assert_eq!(ids(&combinations), vec![vec!["employee"], vec!["employee", "license"]]);
+        assert_eq!(combinations[1][1].candidates, vec![1]);
+
+        // the required set is not satisfied
+        let combinations = dcql.combinations(&[Credential(license())]).unwrap();
+        assert!(combinations.is_empty());
+    }
+
+    #[test]
+    fn no_credential_sets() {
+        let dcql = dcql_query(json!(null));
+
+        let mut passport = license();
+        passport["type"] = json!(["VerifiableCredential", "Passport"]);
+        let mut candidates = vec![Credential(employee()), Credential(license())];
+
+        // every credential query must be answered
+        assert!(dcql.combinations(&candidates).unwrap().is_empty());
+
+        candidates.push(Credential(passport));
+        let combinations = dcql.combinations(&candidates).unwrap();
+        assert_eq!(ids(&combinations), vec![vec!["employee", "license", "passport"]]);
+    }
+
+    #[test]
+    fn submission() {
+        let dcql = dcql_query(json!([
+            {"options": [["employee"]]},
+            {"options": [["license"], ["passport"]], "required": false}
+        ]));
+
+        assert!(dcql.satisfied_by(&["employee"]).unwrap());
+        assert!(dcql.satisfied_by(&["employee", "passport"]).unwrap());
+        assert!(!dcql.satisfied_by(&["license"]).unwrap());
+        assert!(!dcql.satisfied_by(&["employee", "unknown"]).unwrap());
+
+        let dcql = dcql_query(json!([{"options": [["unknown"]]}]));
+        assert!(dcql.satisfied_by(&["employee"]).is_err());
+    }
+
+    // Queries for an employee ID credential, a driver's license, and a
+    // passport.
+    fn dcql_query(credential_sets: Value) -> DcqlQuery {
+        let query = |id: &str, type_: &str| {
+            json!({
+                "id": id,
+                "format": "jwt_vc_json",
+                "meta": {"type_values": [[type_]]},
+                "claims": [{"path": ["credentialSubject", "familyName"]}]
+            })
+        };
+
+        let mut dcql = json!({
+            "credentials": [
+                query("employee", "EmployeeIDCredential"),
+                query("license", "DriversLicense"),
+                query("passport", "Passport")
+            ]
+        });
+        if !credential_sets.is_null() {
+            dcql["credential_sets"] = credential_sets;
+        }
+        serde_json::from_value(dcql).expect("should deserialize")
+    }
+
+    fn credential_query(query: Value) -> CredentialQuery {
+        serde_json::from_value(query).expect("should deserialize")
+    }
+
+    fn ids(combinations: &[Vec<CredentialMatch>]) -> Vec<Vec<&str>> {
+        combinations.iter().map(|c| c.iter().map(|m| m.id.as_str()).collect()).collect()
+    }
+
+    fn employee() -> Value {
+        json!({
+            "type": ["VerifiableCredential", "EmployeeIDCredential"],
+            "credentialSubject": {
+                "givenName": "Normal",
+                "familyName": "Person",
+                "address": {"locality": "Sydney"},
+                "roles": ["engineer", "manager"]
+            }
+        })
+    }
+
+    fn license() -> Value {
+        json!({
+            "type": ["VerifiableCredential", "DriversLicense"],
+            "credentialSubject": {"familyName": "Person"}
+        })
+    }
+
+    struct Credential(Value);
+    impl Claims for Credential {
+        fn to_json(&self) -> Result<Value> {
+            Ok(self.0.clone())
+        }
+    }
+}
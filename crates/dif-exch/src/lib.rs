@@ -3,17 +3,27 @@
 //! This crate provides common utilities for the Vercre project and is not
 //! intended to be used directly.
 //!
+//! Along with Presentation Exchange, the crate supports the Digital
+//! Credentials Query Language (DCQL) defined by `OpenID4VP`.
+//!
 //! Specifications:
 //! - <https://identity.foundation/presentation-exchange/spec/v2.0.0>
+//! - <https://openid.net/specs/openid-4-verifiable-presentations-1_0.html#name-digital-credentials-query-l>
 //! - <https://identity.foundation/jwt-vc-presentation-profile>
 //! - <https://identity.foundation/claim-format-registry>
 
+pub mod dcql;
 pub mod matcher;
+pub mod requirements;
 
 use std::collections::HashMap;
 use std::str::FromStr;
 
 use serde::{Deserialize, Serialize};
+use serde_json::{Number, Value};
+
+pub use crate::dcql::DcqlQuery;
+pub use crate::requirements::DescriptorMatch;
 
 /// Used to provide `serde`-compatible set of Claims  serialized as JSON (as
 /// [`serde_json::Value`]).
@@ -67,6 +77,66 @@ pub struct PresentationDefinition {
     /// ```
     #[serde(skip_serializing_if = "Option::is_none")]
     pub format: Option<HashMap<String, ClaimFormat>>,
+
+    /// If present, specifies which combinations of Input Descriptors the
+    /// Holder may submit. When omitted, every Input Descriptor MUST be
+    /// satisfied.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub submission_requirements: Option<Vec<SubmissionRequirement>>,
+}
+
+/// A Submission Requirement specifies the combination of Input Descriptors
+/// (grouped using the `group` property) a Holder must submit.
+///
+/// <https://identity.foundation/presentation-exchange/spec/v2.0.0/#submission-requirements>
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct SubmissionRequirement {
+    /// If present, a human-friendly name that describes what the requirement
+    /// is for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// If present, the purpose for which the requirement is being made.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub purpose: Option<String>,
+
+    /// The rule used to evaluate the requirement.
+    pub rule: Rule,
+
+    /// For the `pick` rule, the exact number of inputs to submit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<usize>,
+
+    /// For the `pick` rule, the minimum number of inputs to submit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<usize>,
+
+    /// For the `pick` rule, the maximum number of inputs to submit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<usize>,
+
+    /// The group of Input Descriptors the rule applies to. MUST be present
+    /// when `from_nested` is not.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+
+    /// Nested Submission Requirements the rule applies to. MUST be present
+    /// when `from` is not.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_nested: Option<Vec<Self>>,
+}
+
+/// Submission Requirement rules.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Rule {
+    /// All inputs in the group (or nested requirements) must be submitted.
+    #[default]
+    All,
+
+    /// A subset of inputs in the group (or nested requirements) must be
+    /// submitted, as specified by `count`, `min`, and `max`.
+    Pick,
 }
 
 /// Input Descriptors describe the information a Verifier requires from the
@@ -95,6 +165,11 @@ pub struct InputDescriptor {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub format: Option<HashMap<String, ClaimFormat>>,
 
+    /// The groups the Input Descriptor belongs to. Used by Submission
+    /// Requirements to refer to sets of Input Descriptors.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<Vec<String>>,
+
     /// Contraints specify constraints on data values, and an explanation why a
     /// certain item or set of data is being requested.
     pub constraints: Constraints,
@@ -149,7 +224,20 @@ pub struct Constraints {
     /// response that contains more than the data described in the fields
     /// array.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub limit_disclosure: Option<String>,
+    pub limit_disclosure: Option<Directive>,
+}
+
+/// Indicates whether a Conformant Consumer MUST (`required`) or SHOULD
+/// (`preferred`) comply with a property such as `limit_disclosure` or
+/// `predicate`.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Directive {
+    /// The property MUST be complied with.
+    Required,
+
+    /// The property SHOULD be complied with.
+    Preferred,
 }
 
 /// Fields are used to specify attributes of credential data the Verifier
@@ -185,7 +273,7 @@ pub struct Field {
     ///  - it MUST be one of "required" or "preferred"
     ///  - the `filter` field containing the predicate MUST also be present.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub predicate: Option<String>,
+    pub predicate: Option<Directive>,
 
     /// If present, its MUST describe the purpose for which the field is being
     /// requested.
@@ -211,37 +299,79 @@ pub struct Field {
 
 /// A JSON Schema descriptor used to filter against the values returned from
 /// evaluation of the `JSONPath` expressions in the path array.
+///
+/// Each keyword present constrains the value further. Keywords specific to a
+/// JSON type (e.g. `minLength` for strings) are ignored for values of other
+/// types, as per JSON Schema.
+///
+/// See <https://json-schema.org/draft/2020-12/json-schema-validation>
 #[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
-#[serde(deny_unknown_fields)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
 pub struct Filter {
-    /// The type of filter to apply.
+    /// The JSON type of the value: one of "string", "number", "integer",
+    /// "boolean", "object", "array", or "null".
     #[serde(rename = "type")]
-    pub type_: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub type_: Option<String>,
 
-    /// The value of the filter to apply.
-    #[serde(flatten)]
-    pub value: FilterValue,
-}
+    /// The value must be equal to this constant.
+    #[serde(rename = "const")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub const_: Option<Value>,
 
-/// `FilterValue` represents the type and value of a `JSONPath` filter.
-#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
-#[serde(rename_all = "snake_case")]
-pub enum FilterValue {
-    /// The value of the filter is a constant.
-    Const(String),
+    /// The value must be equal to one of these values.
+    #[serde(rename = "enum")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enum_: Option<Vec<Value>>,
 
-    /// The value of the filter is a regular expression.
-    Pattern(String),
+    /// A string value must match this regular expression.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
 
-    /// The value of the filter is a JSON Schema type format. For example,
-    /// "date-time".
-    Format(String),
-}
+    /// A string value must be in this format. Supported formats are "date"
+    /// and "date-time".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
 
-impl Default for FilterValue {
-    fn default() -> Self {
-        Self::Const(String::new())
-    }
+    /// A string value must have at least this many characters.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_length: Option<usize>,
+
+    /// A string value must have at most this many characters.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<usize>,
+
+    /// A numeric value must be greater than or equal to this number.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minimum: Option<Number>,
+
+    /// A numeric value must be less than or equal to this number.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maximum: Option<Number>,
+
+    /// A numeric value must be greater than this number.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exclusive_minimum: Option<Number>,
+
+    /// A numeric value must be less than this number.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exclusive_maximum: Option<Number>,
+
+    /// An array value must contain at least one item matching this filter.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contains: Option<Box<Self>>,
+
+    /// The value must NOT match this filter.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub not: Option<Box<Self>>,
+
+    /// Filters for the properties of an object value, keyed by property name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub properties: Option<HashMap<String, Self>>,
+
+    /// Properties an object value must have.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required: Option<Vec<String>>,
 }
 
 /// A Presentation Submission expresses how proofs presented to the Verifier, in
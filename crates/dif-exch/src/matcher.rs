@@ -9,13 +9,10 @@
 use anyhow::{anyhow, Result};
 use chrono::{DateTime, NaiveDate};
 use regex::Regex;
-use serde_json::Value;
-use serde_json_path::JsonPath;
+use serde_json::{Number, Value};
+use serde_json_path::{JsonPath, PathElement};
 
-use super::{Claims, Constraints, Field, FilterValue};
-
-// LATER: add support for Zero-Knowledge Proofs by enabling the `predicate`
-// feature
+use super::{Claims, Constraints, Directive, Field, Filter};
 
 impl Constraints {
     /// Check if a `VerifiableCredential` satisfies constraints provided in the
@@ -23,7 +20,7 @@ impl Constraints {
     ///
     /// # Errors
     ///
-    /// Returns an error if the `JSONPath` query is invalid.
+    /// Returns an error if the `JSONPath` query or a filter is invalid.
     pub fn satisfied(&self, claims: &impl Claims) -> Result<bool> {
         let Some(fields) = &self.fields else {
             return Ok(true);
@@ -34,8 +31,11 @@ impl Constraints {
 
         // EVERY field must match
         for field in fields {
+            if field.predicate.is_some() && field.filter.is_none() {
+                return Err(anyhow!("a field with a predicate must have a filter"));
+            }
             // if no match AND the field is not optional, constraints are not satisfied
-            if !field.matched(&vc_val).unwrap_or_default() && !field.optional.unwrap_or_default() {
+            if !field.matched(&vc_val)? && !field.optional.unwrap_or_default() {
                 return Ok(false);
             }
         }
@@ -43,6 +43,83 @@ impl Constraints {
         // all fields match
         Ok(true)
     }
+
+    /// Returns the location of each claim matched by the constraint fields.
+    ///
+    /// A location is the list of object keys (or array indexes) leading from
+    /// the root of the credential to the matched claim. Locations are used to
+    /// determine which claims need to be disclosed to satisfy the constraints.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `JSONPath` query is invalid.
+    pub fn matched_paths(&self, claims: &impl Claims) -> Result<Vec<Vec<String>>> {
+        let Some(fields) = &self.fields else {
+            return Ok(vec![]);
+        };
+        let Ok(vc_val) = claims.to_json() else {
+            return Err(anyhow!("error serializing credential"));
+        };
+
+        let mut paths = vec![];
+        for field in fields {
+            for path in field.located(&vc_val)? {
+                if !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
+        }
+
+        Ok(paths)
+    }
+
+    /// Check the claims disclose no more than is needed to satisfy the
+    /// constraints when `limit_disclosure` is `required`. That is, every
+    /// `credentialSubject` claim (other than `id`) must be matched by one of
+    /// the constraint fields.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `JSONPath` query is invalid.
+    pub fn disclosure_limited(&self, claims: &impl Claims) -> Result<bool> {
+        if self.limit_disclosure != Some(Directive::Required) {
+            return Ok(true);
+        }
+        let Ok(vc_val) = claims.to_json() else {
+            return Err(anyhow!("error serializing credential"));
+        };
+
+        // the name of each matched credential subject claim
+        let matched = self
+            .matched_paths(claims)?
+            .into_iter()
+            .filter(|path| path.first().is_some_and(|p| p == "credentialSubject"))
+            .filter_map(|path| path.into_iter().skip(1).find(|p| p.parse::<usize>().is_err()))
+            .collect::<Vec<_>>();
+
+        let subjects = match &vc_val["credentialSubject"] {
+            Value::Array(subjects) => subjects.iter().collect(),
+            subject => vec![subject],
+        };
+        for subject in subjects {
+            let Some(subject) = subject.as_object() else {
+                continue;
+            };
+            if subject.keys().any(|name| name != "id" && !matched.contains(name)) {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Whether any field requires a predicate (a boolean result proven
+    /// without revealing the claim's value) to be submitted in place of the
+    /// claim.
+    #[must_use]
+    pub fn predicate_required(&self) -> bool {
+        self.fields.iter().flatten().any(|field| field.predicate == Some(Directive::Required))
+    }
 }
 
 impl Field {
@@ -72,7 +149,7 @@ impl Field {
 
             // find FIRST node matching filter (in practice, there should only be one node)
             if let Some(node) = nodes.into_iter().next() {
-                match filter.value.matched(node) {
+                match filter.matched(node) {
                     Ok(true) => return Ok(true),
                     Ok(false) => break,
                     Err(e) => return Err(e),
@@ -82,71 +159,187 @@ impl Field {
 
         Ok(false)
     }
-}
 
-impl FilterValue {
-    /// Check whether the result of a `JSONPath` query can be matched to the
-    /// specified `FilterValue`.
-    fn matched(&self, vc_node: &Value) -> Result<bool> {
-        match self {
-            Self::Const(_) => match_const(self, vc_node),
-            Self::Pattern(_) => match_pattern(self, vc_node),
-            Self::Format(_) => match_format(self, vc_node),
+    // Find the location of claims matched by the FIRST matching JSON path
+    // expression for the field.
+    fn located(&self, vc: &Value) -> Result<Vec<Vec<String>>> {
+        for path in &self.path {
+            let Ok(jpath) = JsonPath::parse(path) else {
+                return Err(anyhow!("Invalid JSONPath: {path}"));
+            };
+
+            let mut located = vec![];
+            for node in jpath.query_located(vc) {
+                if let Some(filter) = &self.filter {
+                    if !filter.matched(node.node())? {
+                        continue;
+                    }
+                }
+                let location = node
+                    .location()
+                    .iter()
+                    .map(|elem| match elem {
+                        PathElement::Name(name) => (*name).to_string(),
+                        PathElement::Index(index) => index.to_string(),
+                    })
+                    .collect();
+                located.push(location);
+            }
+
+            if !located.is_empty() {
+                return Ok(located);
+            }
         }
+
+        Ok(vec![])
     }
 }
 
-// TODO: check filter.type
+impl Filter {
+    /// Check whether the result of a `JSONPath` query matches the filter.
+    ///
+    /// When the result is an array and the filter does not describe an array,
+    /// the filter is matched against each item. This allows a filter such as
+    /// `{"type": "string", "const": "EmployeeIDCredential"}` to be matched
+    /// against a credential's `type` array.
+    fn matched(&self, node: &Value) -> Result<bool> {
+        if let Value::Array(items) = node {
+            if self.type_.as_deref() != Some("array") && self.contains.is_none() {
+                for item in items {
+                    if self.matched(item)? {
+                        return Ok(true);
+                    }
+                }
+                return Ok(false);
+            }
+        }
+        self.matched_value(node)
+    }
 
-// Check whether JSONPath result matches (exactly) `FilterValue`.
-fn match_const(filter_val: &FilterValue, vc_node: &Value) -> Result<bool> {
-    let FilterValue::Const(const_str) = filter_val else {
-        return Err(anyhow!("unexpected filter"));
-    };
-    let Ok(const_val) = serde_json::to_value(const_str) else {
-        return Err(anyhow!("issue serializing match criteria"));
-    };
+    // Check the value against each of the filter's keywords.
+    fn matched_value(&self, value: &Value) -> Result<bool> {
+        if let Some(type_) = &self.type_ {
+            if !match_type(type_, value)? {
+                return Ok(false);
+            }
+        }
+        if self.const_.as_ref().is_some_and(|c| c != value) {
+            return Ok(false);
+        }
+        if self.enum_.as_ref().is_some_and(|e| !e.contains(value)) {
+            return Ok(false);
+        }
+        if let Some(not) = &self.not {
+            if not.matched_value(value)? {
+                return Ok(false);
+            }
+        }
 
-    match vc_node {
-        Value::Array(arr_node) => Ok(arr_node.contains(&const_val)),
-        Value::String(str_node) => Ok(str_node == &const_val.to_string()),
-        Value::Number(num_node) => Ok(num_node.to_string() == const_val),
-        Value::Bool(bool_node) => Ok(Some(*bool_node) == const_val.as_bool()),
-        Value::Null => Ok(true),
-        Value::Object(_) => {
-            unimplemented!("object matching not implemented")
+        match value {
+            Value::String(s) => self.match_string(s),
+            Value::Number(n) => Ok(self.match_number(n)),
+            Value::Array(items) => {
+                let Some(contains) = &self.contains else {
+                    return Ok(true);
+                };
+                for item in items {
+                    if contains.matched_value(item)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            Value::Object(obj) => {
+                if let Some(required) = &self.required {
+                    if required.iter().any(|name| !obj.contains_key(name)) {
+                        return Ok(false);
+                    }
+                }
+                let Some(properties) = &self.properties else {
+                    return Ok(true);
+                };
+                for (name, filter) in properties {
+                    if let Some(property) = obj.get(name) {
+                        if !filter.matched_value(property)? {
+                            return Ok(false);
+                        }
+                    }
+                }
+                Ok(true)
+            }
+            Value::Bool(_) | Value::Null => Ok(true),
         }
     }
-}
 
-// Check whether JSONPath result matches regex specified by `FilterValue`.
-fn match_pattern(filter_val: &FilterValue, vc_node: &Value) -> Result<bool> {
-    let FilterValue::Pattern(pattern) = filter_val else {
-        return Err(anyhow!("unexpected filter"));
-    };
-    let Ok(re) = Regex::new(pattern) else {
-        return Err(anyhow!("invalid regex pattern: {pattern}"));
-    };
+    // Check a string value against the string keywords.
+    fn match_string(&self, value: &str) -> Result<bool> {
+        let len = value.chars().count();
+        if self.min_length.is_some_and(|min| len < min) {
+            return Ok(false);
+        }
+        if self.max_length.is_some_and(|max| len > max) {
+            return Ok(false);
+        }
+        if let Some(pattern) = &self.pattern {
+            let Ok(re) = Regex::new(pattern) else {
+                return Err(anyhow!("invalid regex pattern: {pattern}"));
+            };
+            if !re.is_match(value) {
+                return Ok(false);
+            }
+        }
+        if let Some(format) = &self.format {
+            return match_format(format, value);
+        }
+        Ok(true)
+    }
 
-    Ok(re.captures(&vc_node.to_string()).is_some())
+    // Check a numeric value against the numeric keywords.
+    fn match_number(&self, value: &Number) -> bool {
+        let Some(value) = value.as_f64() else {
+            return false;
+        };
+        let bound = |n: Option<&Number>| n.and_then(Number::as_f64);
+
+        if bound(self.minimum.as_ref()).is_some_and(|min| value < min) {
+            return false;
+        }
+        if bound(self.maximum.as_ref()).is_some_and(|max| value > max) {
+            return false;
+        }
+        if bound(self.exclusive_minimum.as_ref()).is_some_and(|min| value <= min) {
+            return false;
+        }
+        if bound(self.exclusive_maximum.as_ref()).is_some_and(|max| value >= max) {
+            return false;
+        }
+        true
+    }
 }
 
-// Check whether JSONPath result format matches that specified by `FilterValue`.
-fn match_format(filter_val: &FilterValue, vc_node: &Value) -> Result<bool> {
-    let FilterValue::Format(format) = filter_val else {
-        return Err(anyhow!("unexpected filter"));
+// Check whether a value is of the specified JSON Schema type.
+fn match_type(type_: &str, value: &Value) -> Result<bool> {
+    let matched = match type_ {
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|f| f.fract() == 0.0)
+        }
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "null" => value.is_null(),
+        _ => return Err(anyhow!("unsupported filter type: {type_}")),
     };
+    Ok(matched)
+}
 
-    match format.as_str() {
-        "date" => match vc_node {
-            Value::String(str_node) => Ok(NaiveDate::parse_from_str(str_node, "%Y-%m-%d").is_ok()),
-            _ => Ok(false),
-        },
-        "date-time" => match vc_node {
-            Value::String(str_node) => Ok(DateTime::parse_from_rfc3339(str_node).is_ok()),
-            _ => Ok(false),
-        },
-        _ => unimplemented!("format matching not implemented for {format}"),
+// Check whether a string value is in the specified format.
+fn match_format(format: &str, value: &str) -> Result<bool> {
+    match format {
+        "date" => Ok(NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok()),
+        "date-time" => Ok(DateTime::parse_from_rfc3339(value).is_ok()),
+        _ => Err(anyhow!("unsupported filter format: {format}")),
     }
 }
 
@@ -206,6 +399,176 @@ mod test {
         assert!(constraints.satisfied(&Credential).unwrap());
     }
 
+    #[test]
+    fn test_type() {
+        let constraints = field("$.credentialSubject.employeeId", &json!({"type": "number"}));
+        assert!(!constraints.satisfied(&Credential).unwrap());
+
+        let constraints = field("$.credentialSubject.yearsEmployed", &json!({"type": "integer"}));
+        assert!(constraints.satisfied(&Credential).unwrap());
+    }
+
+    #[test]
+    fn test_enum() {
+        let filter = json!({"type": "string", "enum": ["Engineering", "Sales"]});
+        let constraints = field("$.credentialSubject.department", &filter);
+        assert!(constraints.satisfied(&Credential).unwrap());
+
+        let filter = json!({"type": "string", "enum": ["Marketing"]});
+        let constraints = field("$.credentialSubject.department", &filter);
+        assert!(!constraints.satisfied(&Credential).unwrap());
+    }
+
+    #[test]
+    fn test_range() {
+        let path = "$.credentialSubject.yearsEmployed";
+        assert!(field(path, &json!({"minimum": 5, "maximum": 5})).satisfied(&Credential).unwrap());
+        assert!(!field(path, &json!({"exclusiveMinimum": 5})).satisfied(&Credential).unwrap());
+        assert!(field(path, &json!({"exclusiveMaximum": 5.5})).satisfied(&Credential).unwrap());
+        assert!(!field(path, &json!({"maximum": 4})).satisfied(&Credential).unwrap());
+    }
+
+    #[test]
+    fn test_length() {
+        let path = "$.credentialSubject.employeeId";
+        assert!(field(path, &json!({"minLength": 10})).satisfied(&Credential).unwrap());
+        assert!(!field(path, &json!({"maxLength": 9})).satisfied(&Credential).unwrap());
+    }
+
+    #[test]
+    fn test_contains() {
+        let filter = json!({"type": "array", "contains": {"const": "EmployeeIDCredential"}});
+        assert!(field("$.type", &filter).satisfied(&Credential).unwrap());
+
+        let filter = json!({"type": "array", "contains": {"const": "PassportCredential"}});
+        assert!(!field("$.type", &filter).satisfied(&Credential).unwrap());
+    }
+
+    #[test]
+    fn test_not() {
+        let filter = json!({"type": "string", "not": {"const": "Sales"}});
+        let constraints = field("$.credentialSubject.department", &filter);
+        assert!(constraints.satisfied(&Credential).unwrap());
+
+        let filter = json!({"type": "string", "not": {"const": "Engineering"}});
+        let constraints = field("$.credentialSubject.department", &filter);
+        assert!(!constraints.satisfied(&Credential).unwrap());
+    }
+
+    #[test]
+    fn test_object() {
+        let filter = json!({
+            "type": "object",
+            "required": ["locality"],
+            "properties": {
+                "locality": {"type": "string", "const": "Sydney"},
+                "postcode": {"type": "string", "pattern": "^[0-9]{4}$"}
+            }
+        });
+        assert!(field("$.credentialSubject.address", &filter).satisfied(&Credential).unwrap());
+
+        let filter = json!({"type": "object", "required": ["country"]});
+        assert!(!field("$.credentialSubject.address", &filter).satisfied(&Credential).unwrap());
+    }
+
+    #[test]
+    fn test_invalid_filter() {
+        let constraints = field("$.validFrom", &json!({"type": "string", "format": "email"}));
+        assert!(constraints.satisfied(&Credential).is_err());
+
+        let constraints = field("$.validFrom", &json!({"type": "date"}));
+        assert!(constraints.satisfied(&Credential).is_err());
+
+        let constraints = field("$.validFrom", &json!({"type": "string", "pattern": "("}));
+        assert!(constraints.satisfied(&Credential).is_err());
+    }
+
+    #[test]
+    fn test_matched_paths() {
+        let constr = json!({
+            "fields": [{
+                "path":["$.type"],
+                "filter": {
+                    "type": "string",
+                    "const": "EmployeeIDCredential"
+                }
+            }, {
+                "path":["$.credentialSubject.givenName", "$.credentialSubject.employeeId"]
+            }]
+        });
+
+        let constraints: Constraints = serde_json::from_value(constr).expect("should deserialize");
+        let paths = constraints.matched_paths(&Credential).expect("should match");
+        assert_eq!(paths, vec![vec!["type"], vec!["credentialSubject", "employeeId"]]);
+    }
+
+    #[test]
+    fn test_limit_disclosure() {
+        let mut constraints: Constraints = serde_json::from_value(json!({
+            "limit_disclosure": "required",
+            "fields": [{
+                "path": ["$.credentialSubject.employeeId"]
+            }]
+        }))
+        .expect("should deserialize");
+        assert!(!constraints.disclosure_limited(&Credential).unwrap());
+
+        constraints.limit_disclosure = Some(Directive::Preferred);
+        assert!(constraints.disclosure_limited(&Credential).unwrap());
+
+        // every subject claim is requested
+        let constraints: Constraints = serde_json::from_value(json!({
+            "limit_disclosure": "required",
+            "fields": [{
+                "path": ["$.credentialSubject.employeeId"]
+            }, {
+                "path": ["$.credentialSubject.department"]
+            }, {
+                "path": ["$.credentialSubject.yearsEmployed"]
+            }, {
+                "path": ["$.credentialSubject.address.locality"]
+            }]
+        }))
+        .expect("should deserialize");
+        assert!(constraints.disclosure_limited(&Credential).unwrap());
+    }
+
+    #[test]
+    fn test_predicate() {
+        let constraints: Constraints = serde_json::from_value(json!({
+            "fields": [{
+                "path": ["$.credentialSubject.yearsEmployed"],
+                "filter": {"type": "integer", "minimum": 2},
+                "predicate": "required"
+            }]
+        }))
+        .expect("should deserialize");
+        assert!(constraints.predicate_required());
+        assert!(constraints.satisfied(&Credential).unwrap());
+
+        // a predicate must have a filter
+        let constraints: Constraints = serde_json::from_value(json!({
+            "fields": [{
+                "path": ["$.credentialSubject.yearsEmployed"],
+                "predicate": "preferred"
+            }]
+        }))
+        .expect("should deserialize");
+        assert!(!constraints.predicate_required());
+        assert!(constraints.satisfied(&Credential).is_err());
+    }
+
+    // Constraints with a single field filtering the value at `path`.
+    fn field(path: &str, filter: &Value) -> Constraints {
+        serde_json::from_value(json!({
+            "fields": [{
+                "path": [path],
+                "filter": filter
+            }]
+        }))
+        .expect("should deserialize")
+    }
+
     struct Credential;
     impl Claims for Credential {
         fn to_json(&self) -> Result<Value> {
@@ -224,6 +587,12 @@ mod test {
                 "validUntil":"2023-12-20T23:21:55Z",
                 "credentialSubject":{
                     "employeeId":"1234567890",
+                    "department":"Engineering",
+                    "yearsEmployed":5,
+                    "address":{
+                        "locality":"Sydney",
+                        "postcode":"2000"
+                    },
                     "id":"did:example:ebfeb1f712ebc6f1c276e12ec21"
                 }
             }))
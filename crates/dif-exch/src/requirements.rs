@@ -0,0 +1,351 @@
+//! # [Presentation Exchange] Submission Requirements
+//!
+//! Evaluates the [Submission Requirements] of a Presentation Definition to
+//! determine which combinations of Input Descriptors (and the credentials
+//! satisfying them) can be submitted.
+//!
+//! [Presentation Exchange]: (https://identity.foundation/presentation-exchange/spec/v2.0.0)
+//! [Submission Requirements]: (https://identity.foundation/presentation-exchange/spec/v2.0.0/#submission-requirements)
+
+use anyhow::{anyhow, Result};
+
+use super::{Claims, PresentationDefinition, Rule, SubmissionRequirement};
+
+/// An Input Descriptor to be submitted along with the candidates that satisfy
+/// it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DescriptorMatch {
+    /// The `id` of the Input Descriptor.
+    pub id: String,
+
+    /// Indexes of the candidates satisfying the Input Descriptor's
+    /// constraints.
+    pub candidates: Vec<usize>,
+}
+
+impl PresentationDefinition {
+    /// Returns each combination of Input Descriptors that can be submitted
+    /// using the candidate claims. Each Input Descriptor in a combination is
+    /// returned with the indexes of the candidates satisfying it.
+    ///
+    /// Combinations are ordered from fewest to most Input Descriptors. When the
+    /// definition has no Submission Requirements, the only valid combination
+    /// is every Input Descriptor. An empty result means the definition cannot
+    /// be satisfied.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a constraint cannot be evaluated or a Submission
+    /// Requirement is malformed.
+    pub fn combinations(&self, candidates: &[impl Claims]) -> Result<Vec<Vec<DescriptorMatch>>> {
+        let mut matches = vec![];
+        for input in &self.input_descriptors {
+            let mut satisfied = vec![];
+            for (i, candidate) in candidates.iter().enumerate() {
+                if input.constraints.satisfied(candidate)? {
+                    satisfied.push(i);
+                }
+            }
+            matches.push(satisfied);
+        }
+
+        let available = matches.iter().map(|m| !m.is_empty()).collect::<Vec<_>>();
+        let combinations = self
+            .select(&available)?
+            .into_iter()
+            .map(|inputs| {
+                inputs
+                    .into_iter()
+                    .map(|i| DescriptorMatch {
+                        id: self.input_descriptors[i].id.clone(),
+                        candidates: matches[i].clone(),
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Ok(combinations)
+    }
+
+    /// Check whether a submission of the specified Input Descriptors satisfies
+    /// the definition's Submission Requirements, or, when there are none,
+    /// includes every Input Descriptor.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a Submission Requirement is malformed.
+    pub fn satisfied_by(&self, submitted: &[&str]) -> Result<bool> {
+        if submitted.iter().any(|id| !self.input_descriptors.iter().any(|input| input.id == *id)) {
+            return Ok(false);
+        }
+
+        let available = self
+            .input_descriptors
+            .iter()
+            .map(|input| submitted.contains(&input.id.as_str()))
+            .collect::<Vec<_>>();
+        let submitted = (0..available.len()).filter(|&i| available[i]).collect::<Vec<_>>();
+
+        Ok(self.select(&available)?.contains(&submitted))
+    }
+
+    // Combinations (as sorted indexes) of the available Input Descriptors
+    // satisfying the definition.
+    fn select(&self, available: &[bool]) -> Result<Vec<Vec<usize>>> {
+        let Some(requirements) = &self.submission_requirements else {
+            if available.iter().all(|a| *a) {
+                return Ok(vec![(0..available.len()).collect()]);
+            }
+            return Ok(vec![]);
+        };
+
+        // every requirement must be met, so combine the options for each
+        let mut combinations = vec![vec![]];
+        for requirement in requirements {
+            let options = self.evaluate(requirement, available)?;
+            combinations = product(&combinations, &options);
+        }
+
+        combinations.sort_by_key(Vec::len);
+        Ok(combinations)
+    }
+
+    // The combinations of Input Descriptors satisfying a single requirement.
+    fn evaluate(
+        &self, requirement: &SubmissionRequirement, available: &[bool],
+    ) -> Result<Vec<Vec<usize>>> {
+        let options = match (&requirement.from, &requirement.from_nested) {
+            (Some(group), None) => {
+                let members = self
+                    .input_descriptors
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, input)| input.group.as_ref().is_some_and(|g| g.contains(group)))
+                    .map(|(i, _)| i)
+                    .collect::<Vec<_>>();
+                if members.is_empty() {
+                    return Err(anyhow!("no input descriptors in group {group}"));
+                }
+
+                match requirement.rule {
+                    Rule::All if members.iter().all(|&i| available[i]) => vec![members],
+                    Rule::All => vec![],
+                    Rule::Pick => {
+                        let members =
+                            members.into_iter().filter(|&i| available[i]).map(|i| vec![vec![i]]);
+                        pick(requirement, &members.collect::<Vec<_>>())?
+                    }
+                }
+            }
+            (None, Some(nested)) => {
+                let mut options = vec![];
+                for requirement in nested {
+                    options.push(self.evaluate(requirement, available)?);
+                }
+
+                match requirement.rule {
+                    Rule::All => options.iter().fold(vec![vec![]], |acc, o| product(&acc, o)),
+                    Rule::Pick => {
+                        options.retain(|o| !o.is_empty());
+                        pick(requirement, &options)?
+                    }
+                }
+            }
+            _ => {
+                return Err(anyhow!(
+                    "submission requirement must have one of `from` or `from_nested`"
+                ));
+            }
+        };
+
+        Ok(options)
+    }
+}
+
+// Pick from the satisfiable sources (Input Descriptors or nested requirements)
+// within the bounds set by the requirement's `count`, `min`, and `max`. Each
+// source is a list of alternative combinations.
+fn pick(
+    requirement: &SubmissionRequirement, sources: &[Vec<Vec<usize>>],
+) -> Result<Vec<Vec<usize>>> {
+    let (min, max) = requirement.count.map_or_else(
+        || (requirement.min.unwrap_or_default(), requirement.max.unwrap_or(sources.len())),
+        |count| (count, count),
+    );
+    if min > max {
+        return Err(anyhow!("submission requirement `min` is greater than `max`"));
+    }
+
+    let mut combinations = vec![];
+    for size in min..=max.min(sources.len()) {
+        for chosen in subsets(sources.len(), size) {
+            let combined = chosen.iter().fold(vec![vec![]], |acc, &i| product(&acc, &sources[i]));
+            for combination in combined {
+                if !combinations.contains(&combination) {
+                    combinations.push(combination);
+                }
+            }
+        }
+    }
+
+    Ok(combinations)
+}
+
+// Every way of choosing `size` indexes from `0..len`.
+fn subsets(len: usize, size: usize) -> Vec<Vec<usize>> {
+    if size == 0 {
+        return vec![vec![]];
+    }
+    if size > len {
+        return vec![];
+    }
+
+    let mut chosen = vec![];
+    for first in 0..=len - size {
+        for rest in subsets(len - first - 1, size - 1) {
+            let mut subset = vec![first];
+            subset.extend(rest.into_iter().map(|i| i + first + 1));
+            chosen.push(subset);
+        }
+    }
+    chosen
+}
+
+// Combine each of the left combinations with each of the right combinations.
+pub(crate) fn product(left: &[Vec<usize>], right: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let mut combinations = vec![];
+    for l in left {
+        for r in right {
+            let mut combination = l.clone();
+            combination.extend(r);
+            combination.sort_unstable();
+            combination.dedup();
+            if !combinations.contains(&combination) {
+                combinations.push(combination);
+            }
+        }
+    }
+    combinations
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    #[test]
+    fn no_requirements() {
+        let pd = definition(json!(null));
+        let combinations = pd.combinations(&[credential("A"), credential("B")]).unwrap();
+        assert_eq!(ids(&combinations), vec![vec!["a", "b", "c"]]);
+        assert_eq!(combinations[0][2].candidates, vec![1]);
+
+        // every descriptor must be satisfied
+        let combinations = pd.combinations(&[credential("A")]).unwrap();
+        assert!(combinations.is_empty());
+    }
+
+    #[test]
+    fn all_and_pick() {
+        let pd = definition(json!([
+            {"rule": "all", "from": "A"},
+            {"rule": "pick", "count": 1, "from": "B"}
+        ]));
+
+        let combinations = pd.combinations(&[credential("A"), credential("B")]).unwrap();
+        assert_eq!(ids(&combinations), vec![vec!["a", "b"], vec!["a", "c"]]);
+
+        // group A is not satisfied
+        let combinations = pd.combinations(&[credential("B")]).unwrap();
+        assert!(combinations.is_empty());
+    }
+
+    #[test]
+    fn pick_min_max() {
+        let pd = definition(json!([{"rule": "pick", "min": 1, "max": 2, "from": "B"}]));
+        let combinations = pd.combinations(&[credential("A"), credential("B")]).unwrap();
+        assert_eq!(ids(&combinations), vec![vec!["b"], vec!["c"], vec!["b", "c"]]);
+    }
+
+    #[test]
+    fn nested() {
+        let pd = definition(json!([{
+            "rule": "pick",
+            "count": 1,
+            "from_nested": [
+                {"rule": "all", "from": "A"},
+                {"rule": "all", "from": "B"}
+            ]
+        }]));
+
+        let combinations = pd.combinations(&[credential("A")]).unwrap();
+        assert_eq!(ids(&combinations), vec![vec!["a"]]);
+
+        let combinations = pd.combinations(&[credential("A"), credential("B")]).unwrap();
+        assert_eq!(ids(&combinations), vec![vec!["a"], vec!["b", "c"]]);
+    }
+
+    #[test]
+    fn submission() {
+        let pd = definition(json!([
+            {"rule": "all", "from": "A"},
+            {"rule": "pick", "count": 1, "from": "B"}
+        ]));
+
+        assert!(pd.satisfied_by(&["a", "c"]).unwrap());
+        assert!(!pd.satisfied_by(&["a"]).unwrap());
+        assert!(!pd.satisfied_by(&["a", "b", "c"]).unwrap());
+        assert!(!pd.satisfied_by(&["a", "b", "d"]).unwrap());
+    }
+
+    #[test]
+    fn malformed() {
+        let pd = definition(json!([{"rule": "all"}]));
+        assert!(pd.combinations(&[credential("A")]).is_err());
+
+        let pd = definition(json!([{"rule": "all", "from": "C"}]));
+        assert!(pd.combinations(&[credential("A")]).is_err());
+    }
+
+    // Descriptor `a` is in group A and requests a credential of type "A".
+    // Descriptors `b` and `c` are in group B and request type "B".
+    fn definition(requirements: Value) -> PresentationDefinition {
+        let input = |id: &str, group: &str| {
+            json!({
+                "id": id,
+                "group": [group],
+                "constraints": {
+                    "fields": [{
+                        "path": ["$.type"],
+                        "filter": {"type": "string", "const": group}
+                    }]
+                }
+            })
+        };
+
+        let mut pd = json!({
+            "id": "definition",
+            "input_descriptors": [input("a", "A"), input("b", "B"), input("c", "B")],
+        });
+        if !requirements.is_null() {
+            pd["submission_requirements"] = requirements;
+        }
+        serde_json::from_value(pd).expect("should deserialize")
+    }
+
+    fn ids(combinations: &[Vec<DescriptorMatch>]) -> Vec<Vec<&str>> {
+        combinations.iter().map(|c| c.iter().map(|m| m.id.as_str()).collect()).collect()
+    }
+
+    fn credential(type_: &str) -> Credential {
+        Credential(type_.to_string())
+    }
+
+    struct Credential(String);
+    impl Claims for Credential {
+        fn to_json(&self) -> Result<Value> {
+            Ok(json!({"type": ["VerifiableCredential", self.0]}))
+        }
+    }
+}
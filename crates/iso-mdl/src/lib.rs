@@ -12,27 +12,41 @@
 
 mod mdoc;
 mod mso;
+mod session;
+mod x509;
 
-use anyhow::anyhow;
+use std::collections::BTreeMap;
+use std::future::Future;
+
+use anyhow::{anyhow, bail};
 use base64ct::{Base64UrlUnpadded as Base64, Encoding};
 use ciborium::cbor;
 use coset::{iana, CoseSign1Builder, HeaderBuilder};
 use rand::{thread_rng, Rng};
 use serde_json::{Map, Value};
 use sha2::{Digest, Sha256};
-use vercre_infosec::cose::{CoseKey, Tag24};
-use vercre_infosec::{Algorithm, Curve, KeyType, Signer};
+use vercre_core::signature;
+use vercre_infosec::cose::{cbor as cbor_codec, CoseKey, Tag24};
+use vercre_infosec::{Algorithm, PublicKeyJwk, Signer};
 
-use crate::mdoc::{IssuerSigned, IssuerSignedItem};
-use crate::mso::{DigestIdGenerator, MobileSecurityObject};
+pub use crate::mdoc::IssuerSigned;
+use crate::mdoc::{DeviceAuth, DeviceResponse, DeviceSigned, Document, IssuerSignedItem};
+use crate::mso::DigestIdGenerator;
+pub use crate::mso::{MobileSecurityObject, ValidityInfo};
+pub use crate::session::SessionTranscript;
 
 /// Convert a Credential Dataset to a base64url-encoded, CBOR-encoded, ISO mDL
 /// `IssuerSigned` object.
 ///
+/// The credential is bound to the holder's device key, which must be used to
+/// authenticate presentations of the credential.
+///
 /// # Errors
-/// // TODO: add errors
+///
+/// Returns an error if the dataset is invalid, the device key cannot be
+/// decoded, or the signer's algorithm is not supported.
 pub async fn to_credential(
-    dataset: Map<String, Value>, signer: impl Signer,
+    dataset: Map<String, Value>, device_key: &PublicKeyJwk, signer: impl Signer,
 ) -> anyhow::Result<String> {
     // populate mdoc and accompanying MSO
     let mut mdoc = IssuerSigned::new();
@@ -64,35 +78,34 @@ pub async fn to_credential(
         }
     }
 
-    // add public key to MSO
+    // add holder's device key to MSO
+    let decode = |v: &str| Base64::decode_vec(v).map_err(|e| anyhow!("invalid device key: {e}"));
     mso.device_key_info.device_key = CoseKey {
-        kty: KeyType::Okp,
-        crv: Curve::Ed25519,
-        x: signer.public_key().await?,
-        y: None,
+        kty: device_key.kty.clone(),
+        crv: device_key.crv.clone(),
+        x: decode(&device_key.x)?,
+        y: device_key.y.as_deref().map(decode).transpose()?,
     };
 
-    // sign
-    let mso_bytes = Tag24(mso).to_vec()?;
-    let signature = signer.sign(&mso_bytes).await;
-
     // build COSE_Sign1
     let algorithm = match signer.algorithm() {
         Algorithm::EdDSA => iana::Algorithm::EdDSA,
-        Algorithm::ES256K => return Err(anyhow!("unsupported algorithm")),
+        Algorithm::ES256K => iana::Algorithm::ES256K,
     };
 
     let key_id = signer.verification_method().as_bytes().to_vec();
 
     let protected = HeaderBuilder::new().algorithm(algorithm).build();
     let unprotected = HeaderBuilder::new().key_id(key_id).build();
-    let cose_sign_1 = CoseSign1Builder::new()
+    let mut cose_sign_1 = CoseSign1Builder::new()
         .protected(protected)
         .unprotected(unprotected)
-        .payload(mso_bytes)
-        .signature(signature)
+        .payload(Tag24(mso).to_vec()?)
         .build();
 
+    // sign
+    cose_sign_1.signature = signer.try_sign(&cose_sign_1.tbs_data(&[])).await?;
+
     // add COSE_Sign1 to IssuerSigned object
     mdoc.issuer_auth = mso::IssuerAuth(cose_sign_1);
 
@@ -100,11 +113,190 @@ pub async fn to_credential(
     Ok(Base64::encode_string(&mdoc.to_vec()?))
 }
 
+/// Decode a base64url-encoded, CBOR-encoded `IssuerSigned` credential (as
+/// returned by [`to_credential`]) without verifying it.
+///
+/// # Errors
+///
+/// Returns an error if the credential cannot be decoded.
+pub fn from_credential(issued: &str) -> anyhow::Result<IssuerSigned> {
+    let mdoc_bytes =
+        Base64::decode_vec(issued).map_err(|e| anyhow!("issue decoding credential: {e}"))?;
+    cbor_codec::from_slice(&mdoc_bytes)
+}
+
+/// Verify a base64url-encoded, CBOR-encoded `IssuerSigned` credential,
+/// returning its data elements.
+///
+/// The issuer's signature over the Mobile Security Object (MSO), the digest of
+/// each data element, and the MSO's validity period are checked. The issuer's
+/// public key is resolved using the key ID of the issuer's signature or, when
+/// there is none, taken from the signature's `x5chain`. In the latter case,
+/// trust in the returned chain must be established by the caller.
+///
+/// # Errors
+///
+/// Returns an error if the credential cannot be decoded or any check fails.
+pub async fn verify<F, Fut>(issued: &str, resolve: F) -> anyhow::Result<VerifiedDocument>
+where
+    F: Fn(String) -> Fut + Send + Sync,
+    Fut: Future<Output = anyhow::Result<PublicKeyJwk>> + Send,
+{
+    from_credential(issued)?.verify(resolve).await
+}
+
+/// Create a base64url-encoded, CBOR-encoded `DeviceResponse` presenting an
+/// issued mdoc credential (as returned by [`to_credential`]).
+///
+/// Only the requested data elements, keyed by namespace, are included. The
+/// response is authenticated with a device signature over the session
+/// transcript, binding it to the Verifier's request.
+///
+/// # Errors
+///
+/// Returns an error if the credential cannot be decoded or the device
+/// signature cannot be created.
+pub async fn present(
+    issued: &str, requested: &BTreeMap<String, Vec<String>>, transcript: &SessionTranscript,
+    signer: impl Signer,
+) -> anyhow::Result<String> {
+    let mut issuer_signed = from_credential(issued)?;
+    let doc_type = issuer_signed.mso()?.doc_type;
+
+    // include only requested data elements
+    issuer_signed.name_spaces.retain(|name_space, items| {
+        let Some(names) = requested.get(name_space) else {
+            return false;
+        };
+        items.retain(|item| names.contains(&item.element_identifier));
+        !items.is_empty()
+    });
+
+    // device signature over the (detached) `DeviceAuthenticationBytes`
+    let name_spaces = Tag24(BTreeMap::new());
+    let device_authentication = transcript.device_authentication(&doc_type, &name_spaces)?;
+
+    let algorithm = match signer.algorithm() {
+        Algorithm::EdDSA => iana::Algorithm::EdDSA,
+        Algorithm::ES256K => iana::Algorithm::ES256K,
+    };
+    let protected = HeaderBuilder::new().algorithm(algorithm).build();
+    let mut device_signature = CoseSign1Builder::new().protected(protected).build();
+    device_signature.signature =
+        signer.try_sign(&device_signature.tbs_detached_data(&device_authentication, &[])).await?;
+
+    let response = DeviceResponse::new(vec![Document {
+        doc_type,
+        issuer_signed,
+        device_signed: DeviceSigned {
+            name_spaces,
+            device_auth: DeviceAuth { device_signature },
+        },
+    }]);
+    Ok(Base64::encode_string(&cbor_codec::to_vec(&response)?))
+}
+
+/// A verified mdoc credential or document from a `DeviceResponse`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VerifiedDocument {
+    /// The document type. For example, "`org.iso.18013.5.1.mDL`".
+    pub doc_type: String,
+
+    /// The issuer-signed data elements, keyed by namespace then element
+    /// identifier.
+    pub name_spaces: Map<String, Value>,
+
+    /// The validity period of the issuer's signature.
+    pub validity_info: ValidityInfo,
+
+    /// The key ID of the issuer's signing key, when the key was resolved by
+    /// key ID.
+    pub key_id: Option<String>,
+
+    /// The DER-encoded X.509 certificate chain of the issuer's signing key,
+    /// when the key was taken from the chain. The caller is responsible for
+    /// validating the chain.
+    pub x5chain: Option<Vec<Vec<u8>>>,
+}
+
+/// Serializes to a JSON object with the document type as `docType` and the
+/// data elements of each namespace under the namespace's name.
+impl vercre_dif_exch::Claims for VerifiedDocument {
+    fn to_json(&self) -> anyhow::Result<Value> {
+        let mut claims = self.name_spaces.clone();
+        claims.insert("docType".into(), Value::String(self.doc_type.clone()));
+        Ok(Value::Object(claims))
+    }
+}
+
+/// Verify a base64url-encoded, CBOR-encoded `DeviceResponse`, returning the
+/// data elements presented in each document.
+///
+/// Each document's issuer-signed data is verified as for [`verify`], along
+/// with the device's signature over the session transcript.
+///
+/// # Errors
+///
+/// Returns an error if the response cannot be decoded or any check fails.
+pub async fn verify_presentation<F, Fut>(
+    presentation: &str, transcript: &SessionTranscript, resolve: F,
+) -> anyhow::Result<Vec<VerifiedDocument>>
+where
+    F: Fn(String) -> Fut + Send + Sync,
+    Fut: Future<Output = anyhow::Result<PublicKeyJwk>> + Send,
+{
+    let response_bytes =
+        Base64::decode_vec(presentation).map_err(|e| anyhow!("issue decoding response: {e}"))?;
+    let response: DeviceResponse = cbor_codec::from_slice(&response_bytes)?;
+    if response.status != 0 {
+        bail!("device response has status {}", response.status);
+    }
+    let Some(documents) = response.documents else {
+        bail!("device response has no documents");
+    };
+
+    let mut verified = vec![];
+    for document in documents {
+        let verified_document = document.issuer_signed.verify(&resolve).await?;
+        if verified_document.doc_type != document.doc_type {
+            bail!("document type does not match MSO");
+        }
+
+        // the device must have signed the session transcript using the key
+        // bound to the credential by the issuer
+        let mso = document.issuer_signed.mso()?;
+        let device_key = &mso.device_key_info.device_key;
+        let device_jwk = PublicKeyJwk {
+            kty: device_key.kty.clone(),
+            crv: device_key.crv.clone(),
+            x: Base64::encode_string(&device_key.x),
+            y: device_key.y.as_ref().map(|y| Base64::encode_string(y)),
+            ..PublicKeyJwk::default()
+        };
+        let device_signed = &document.device_signed;
+        let device_authentication =
+            transcript.device_authentication(&document.doc_type, &device_signed.name_spaces)?;
+        device_signed
+            .device_auth
+            .device_signature
+            .verify_detached_signature(&device_authentication, &[], |sig, data| {
+                signature::verify(&device_jwk, data, sig)
+            })
+            .map_err(|e| anyhow!("invalid device signature: {e}"))?;
+
+        verified.push(verified_document);
+    }
+
+    Ok(verified)
+}
+
 #[cfg(test)]
 mod tests {
+    use k256::ecdsa::signature::Signer as _;
     use serde_json::json;
     use vercre_infosec::cose::cbor;
-    use vercre_infosec::SecOps;
+    use vercre_infosec::{Curve, KeyType, SecOps};
+    use vercre_test_utils::holder;
     use vercre_test_utils::issuer::{Provider, CREDENTIAL_ISSUER};
 
     use super::*;
@@ -125,7 +317,8 @@ mod tests {
         let dataset = serde_json::from_value(dataset).unwrap();
         let provider = Provider::new();
         let signer = SecOps::signer(&provider, CREDENTIAL_ISSUER).unwrap();
-        let mdl = to_credential(dataset, signer).await.unwrap();
+        let device_key = public_key(&holder::Provider::new()).await;
+        let mdl = to_credential(dataset, &device_key, signer).await.unwrap();
         // println!("{}", mdl);
 
         // check credential deserializes back into original mdoc/mso structures
@@ -140,4 +333,186 @@ mod tests {
         assert_eq!(mso.digest_algorithm, DigestAlgorithm::Sha256);
         assert_eq!(mso.device_key_info.device_key.kty, KeyType::Okp);
     }
+
+    #[tokio::test]
+    async fn verify_credential() {
+        let mdl = issue().await;
+        let document = verify(&mdl, resolve).await.expect("should verify");
+        assert_eq!(document.doc_type, "org.iso.18013.5.1.mDL");
+        assert_eq!(document.key_id, Some("did:web:demo.credibil.io#key-0".into()));
+        assert_eq!(document.name_spaces["org.iso.18013.5.1.mDL"]["given_name"], "Normal");
+
+        // changing a data element invalidates its digest
+        let mut issuer_signed = from_credential(&mdl).unwrap();
+        let items = issuer_signed.name_spaces.get_mut("org.iso.18013.5.1.mDL").unwrap();
+        items[0].0.element_value = cbor!("Someone").unwrap();
+        let tampered = Base64::encode_string(&issuer_signed.to_vec().unwrap());
+        let err = verify(&tampered, resolve).await.unwrap_err();
+        assert!(err.to_string().contains("digest"));
+    }
+
+    #[tokio::test]
+    async fn x5chain() {
+        // identify the issuer's key using a (self-signed) certificate
+        let mut issuer_signed = from_credential(&issue().await).unwrap();
+        let certificate = Base64::decode_vec(CERTIFICATE).unwrap();
+        let unprotected = &mut issuer_signed.issuer_auth.0.unprotected;
+        unprotected.key_id = vec![];
+        unprotected.rest.push((
+            coset::Label::Int(iana::HeaderParameter::X5Chain as i64),
+            ciborium::Value::Bytes(certificate.clone()),
+        ));
+        let mdl = Base64::encode_string(&issuer_signed.to_vec().unwrap());
+
+        let unresolvable = |_| async { Err(anyhow!("should not resolve")) };
+        let document = verify(&mdl, unresolvable).await.expect("should verify");
+        assert_eq!(document.key_id, None);
+        assert_eq!(document.x5chain, Some(vec![certificate]));
+    }
+
+    #[tokio::test]
+    async fn present_and_verify() {
+        let mdl = issue().await;
+        let transcript = transcript("1234");
+        let requested = BTreeMap::from([(
+            "org.iso.18013.5.1.mDL".to_string(),
+            vec!["given_name".to_string(), "family_name".to_string()],
+        )]);
+        let presentation =
+            present(&mdl, &requested, &transcript, holder::Provider::new()).await.unwrap();
+
+        let documents = verify_presentation(&presentation, &transcript, resolve).await.unwrap();
+        assert_eq!(documents.len(), 1);
+        assert_eq!(documents[0].doc_type, "org.iso.18013.5.1.mDL");
+
+        // only the requested data elements are presented
+        let expected = json!({
+            "org.iso.18013.5.1.mDL": {
+                "family_name": "Person",
+                "given_name": "Normal"
+            }
+        });
+        assert_eq!(Value::Object(documents[0].name_spaces.clone()), expected);
+    }
+
+    #[tokio::test]
+    async fn tampered_element() {
+        let mdl = issue().await;
+        let transcript = transcript("1234");
+        let requested =
+            BTreeMap::from([("org.iso.18013.5.1.mDL".to_string(), vec!["email".to_string()])]);
+        let presentation =
+            present(&mdl, &requested, &transcript, holder::Provider::new()).await.unwrap();
+
+        // change the presented data element's value
+        let bytes = Base64::decode_vec(&presentation).unwrap();
+        let mut response: DeviceResponse = cbor::from_slice(&bytes).unwrap();
+        let documents = response.documents.as_mut().unwrap();
+        let items = documents[0].issuer_signed.name_spaces.get_mut("org.iso.18013.5.1.mDL");
+        items.unwrap()[0].0.element_value = cbor!("someone.else@example.com").unwrap();
+        let tampered = Base64::encode_string(&cbor::to_vec(&response).unwrap());
+
+        let err = verify_presentation(&tampered, &transcript, resolve).await.unwrap_err();
+        assert!(err.to_string().contains("digest"));
+    }
+
+    #[tokio::test]
+    async fn wrong_transcript() {
+        let mdl = issue().await;
+        let requested =
+            BTreeMap::from([("org.iso.18013.5.1.mDL".to_string(), vec!["email".to_string()])]);
+        let presentation =
+            present(&mdl, &requested, &transcript("1234"), holder::Provider::new()).await.unwrap();
+
+        // device authentication is bound to the request's nonce
+        let err =
+            verify_presentation(&presentation, &transcript("5678"), resolve).await.unwrap_err();
+        assert!(err.to_string().contains("device signature"));
+    }
+
+    #[tokio::test]
+    async fn es256k_device_key() {
+        let device = DeviceSigner(k256::ecdsa::SigningKey::from_slice(&[7; 32]).unwrap());
+        let point = device.0.verifying_key().to_encoded_point(false);
+        let device_key = PublicKeyJwk {
+            kty: KeyType::Ec,
+            crv: Curve::Es256K,
+            x: Base64::encode_string(point.x().unwrap()),
+            y: point.y().map(|y| Base64::encode_string(y)),
+            ..PublicKeyJwk::default()
+        };
+        let mdl = issue_to(&device_key).await;
+
+        let transcript = transcript("1234");
+        let requested =
+            BTreeMap::from([("org.iso.18013.5.1.mDL".to_string(), vec!["email".to_string()])]);
+        let presentation = present(&mdl, &requested, &transcript, device).await.unwrap();
+
+        let documents = verify_presentation(&presentation, &transcript, resolve).await.unwrap();
+        assert_eq!(documents.len(), 1);
+    }
+
+    async fn issue() -> String {
+        issue_to(&public_key(&holder::Provider::new()).await).await
+    }
+
+    async fn issue_to(device_key: &PublicKeyJwk) -> String {
+        let dataset = json!({
+            "org.iso.18013.5.1.mDL": {
+                "given_name": "Normal",
+                "family_name": "Person",
+                "email": "normal.user@example.com"
+            }
+        });
+        let dataset = serde_json::from_value(dataset).unwrap();
+        let provider = Provider::new();
+        let signer = SecOps::signer(&provider, CREDENTIAL_ISSUER).unwrap();
+        to_credential(dataset, device_key, signer).await.unwrap()
+    }
+
+    fn transcript(nonce: &str) -> SessionTranscript {
+        SessionTranscript::openid4vp("http://vercre.io", nonce, Some("http://vercre.io/post"))
+            .unwrap()
+    }
+
+    // Self-signed certificate for the issuer's signing key.
+    const CERTIFICATE: &str = "MIIBWTCCAQugAwIBAgIUN3IbKBW__O81alSAxWPWQJ7rXyswBQYDK2VwMCExEjAQBgNVBAMMCXZlcmNyZS5pbzELMAkGA1UEBhMCTlowIBcNMjYxMDE4MDg1MTUwWhgPMjEyNjA5MjQwODUxNTBaMCExEjAQBgNVBAMMCXZlcmNyZS5pbzELMAkGA1UEBhMCTlowKjAFBgMrZXADIQCrquNGcQf9crvaO8HwU0Em06X3-AOzo1bj1wDPqnaxbqNTMFEwHQYDVR0OBBYEFNSU2NPtSIUCg7JVcWSHyew98Lq2MB8GA1UdIwQYMBaAFNSU2NPtSIUCg7JVcWSHyew98Lq2MA8GA1UdEwEB_wQFMAMBAf8wBQYDK2VwA0EAa75_6mHay80gOhOwKXi54uMh9eWMmevA2lHmKLqCQ55y_V6j840jlfmhlU_OGa_51MWdJX5npf4Pgokxnp6dDA";
+
+    // Resolve the issuer's public key.
+    async fn resolve(_kid: String) -> anyhow::Result<PublicKeyJwk> {
+        let provider = Provider::new();
+        let signer = SecOps::signer(&provider, CREDENTIAL_ISSUER)?;
+        Ok(public_key(&signer).await)
+    }
+
+    async fn public_key(signer: &impl Signer) -> PublicKeyJwk {
+        PublicKeyJwk {
+            kty: KeyType::Okp,
+            crv: Curve::Ed25519,
+            x: Base64::encode_string(&signer.public_key().await.unwrap()),
+            ..PublicKeyJwk::default()
+        }
+    }
+
+    // A device holding a secp256k1 key.
+    struct DeviceSigner(k256::ecdsa::SigningKey);
+
+    impl Signer for DeviceSigner {
+        async fn try_sign(&self, msg: &[u8]) -> anyhow::Result<Vec<u8>> {
+            let signature: k256::ecdsa::Signature = self.0.sign(msg);
+            Ok(signature.to_vec())
+        }
+
+        async fn public_key(&self) -> anyhow::Result<Vec<u8>> {
+            Ok(self.0.verifying_key().to_sec1_bytes().to_vec())
+        }
+
+        fn algorithm(&self) -> Algorithm {
+            Algorithm::ES256K
+        }
+
+        fn verification_method(&self) -> String {
+            "did:example:device#key-0".into()
+        }
+    }
 }
@@ -12,11 +12,19 @@
 //! `DeviceResponse` structure.
 
 use std::collections::BTreeMap;
+use std::future::Future;
 
+use anyhow::{anyhow, bail};
+use coset::{iana, CoseSign1, Label};
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use vercre_core::signature;
 use vercre_infosec::cose::{cbor, Tag24};
+use vercre_infosec::PublicKeyJwk;
 
-use crate::mso;
+use crate::mso::{self, MobileSecurityObject};
+use crate::{x509, VerifiedDocument};
 
 pub type NameSpace = String;
 
@@ -35,8 +43,15 @@ pub struct IssuerSigned {
     pub issuer_auth: mso::IssuerAuth,
 }
 
+impl Default for IssuerSigned {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl IssuerSigned {
     /// Create a new `IssuerSigned` with default values.
+    #[must_use]
     pub fn new() -> Self {
         Self {
             name_spaces: BTreeMap::new(),
@@ -44,9 +59,143 @@ impl IssuerSigned {
         }
     }
 
+    /// Serialize to CBOR.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization fails.
     pub fn to_vec(&self) -> anyhow::Result<Vec<u8>> {
         cbor::to_vec(self)
     }
+
+    /// The Mobile Security Object signed by the issuer, without verifying it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the MSO is missing or cannot be deserialized.
+    pub fn mso(&self) -> anyhow::Result<MobileSecurityObject> {
+        let Some(payload) = &self.issuer_auth.0.payload else {
+            bail!("issuer auth has no payload");
+        };
+        let mso: Tag24<MobileSecurityObject> = cbor::from_slice(payload)?;
+        Ok(mso.0)
+    }
+
+    /// The key ID of the issuer's signing key, if set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key ID is not valid UTF-8.
+    pub fn key_id(&self) -> anyhow::Result<Option<String>> {
+        let issuer_auth = &self.issuer_auth.0;
+        let key_id = if issuer_auth.unprotected.key_id.is_empty() {
+            &issuer_auth.protected.header.key_id
+        } else {
+            &issuer_auth.unprotected.key_id
+        };
+        if key_id.is_empty() {
+            return Ok(None);
+        }
+        let key_id =
+            String::from_utf8(key_id.clone()).map_err(|e| anyhow!("invalid issuer key ID: {e}"))?;
+        Ok(Some(key_id))
+    }
+
+    /// The DER-encoded X.509 certificate chain of the issuer's signing key,
+    /// leaf first, if set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `x5chain` header is malformed.
+    pub fn x5chain(&self) -> anyhow::Result<Option<Vec<Vec<u8>>>> {
+        let issuer_auth = &self.issuer_auth.0;
+        let x5chain = Label::Int(iana::HeaderParameter::X5Chain as i64);
+        let headers = issuer_auth.unprotected.rest.iter().chain(&issuer_auth.protected.header.rest);
+        let Some((_, value)) = headers.into_iter().find(|(label, _)| *label == x5chain) else {
+            return Ok(None);
+        };
+
+        // a single certificate is not wrapped in an array
+        let chain = match value {
+            ciborium::Value::Bytes(certificate) => vec![certificate.clone()],
+            ciborium::Value::Array(certificates) => certificates
+                .iter()
+                .map(|c| c.as_bytes().cloned().ok_or_else(|| anyhow!("invalid x5chain")))
+                .collect::<anyhow::Result<_>>()?,
+            _ => bail!("invalid x5chain"),
+        };
+        if chain.is_empty() {
+            bail!("empty x5chain");
+        }
+        Ok(Some(chain))
+    }
+
+    /// Verify the issuer's signature over the MSO, the digest of each data
+    /// element, and the MSO's validity period, returning the data elements.
+    ///
+    /// The issuer's public key is resolved from the `COSE_Sign1` key ID when
+    /// set. Otherwise, the key in the leaf certificate of the `x5chain` is
+    /// used and the chain is returned for the caller to establish trust in.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the issuer's key cannot be found or any check fails.
+    pub async fn verify<F, Fut>(&self, resolve: F) -> anyhow::Result<VerifiedDocument>
+    where
+        F: Fn(String) -> Fut + Send + Sync,
+        Fut: Future<Output = anyhow::Result<PublicKeyJwk>> + Send,
+    {
+        let key_id = self.key_id()?;
+        let mut x5chain = None;
+        let public_key = if let Some(kid) = &key_id {
+            resolve(kid.clone()).await?
+        } else if let Some(chain) = self.x5chain()? {
+            let public_key = x509::public_key(&chain[0])?;
+            x5chain = Some(chain);
+            public_key
+        } else {
+            bail!("issuer key ID or x5chain is required");
+        };
+
+        let issuer_auth = &self.issuer_auth.0;
+        issuer_auth
+            .verify_signature(&[], |sig, data| signature::verify(&public_key, data, sig))
+            .map_err(|e| anyhow!("invalid issuer signature: {e}"))?;
+
+        // each data element must match the digest signed by the issuer
+        let mso = self.mso()?;
+        for (name_space, items) in &self.name_spaces {
+            let Some(digests) = mso.value_digests.get(name_space) else {
+                bail!("no digests for name space {name_space}");
+            };
+            for item in items {
+                let digest = Sha256::digest(&item.to_vec()?);
+                if digests.get(&item.digest_id).map(Vec::as_slice) != Some(digest.as_slice()) {
+                    bail!("digest does not match for {}", item.element_identifier);
+                }
+            }
+        }
+
+        mso.validity_info.verify()?;
+
+        let mut name_spaces = Map::new();
+        for (name_space, items) in &self.name_spaces {
+            let mut elements = Map::new();
+            for item in items {
+                let value = item.element_value.deserialized()?;
+                elements.insert(item.element_identifier.clone(), value);
+            }
+            name_spaces.insert(name_space.clone(), Value::Object(elements));
+        }
+
+        Ok(VerifiedDocument {
+            doc_type: mso.doc_type,
+            name_spaces,
+            validity_info: mso.validity_info,
+            key_id,
+            x5chain,
+        })
+    }
 }
 
 /// `IssuerSignedItemBytes` represents the tagged `IssuerSignedItem` after
@@ -69,3 +218,77 @@ pub struct IssuerSignedItem {
     /// Data element value. For example, "`Smith`"
     pub element_value: ciborium::Value,
 }
+
+/// A response to an mdoc request containing the requested documents.
+///
+/// See 8.3.2.1.2.2 Device retrieval mdoc response.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceResponse {
+    /// Version of the `DeviceResponse`. Must be 1.0.
+    pub version: String,
+
+    /// The returned documents.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub documents: Option<Vec<Document>>,
+
+    /// Status code. 0 indicates the response is OK.
+    pub status: u64,
+}
+
+impl DeviceResponse {
+    /// Create a new `DeviceResponse` containing the documents.
+    pub fn new(documents: Vec<Document>) -> Self {
+        Self {
+            version: "1.0".to_string(),
+            documents: Some(documents),
+            status: 0,
+        }
+    }
+}
+
+/// A document returned in a `DeviceResponse`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Document {
+    /// The document type. For example, "`org.iso.18013.5.1.mDL`".
+    pub doc_type: String,
+
+    /// Data elements signed by the issuer.
+    pub issuer_signed: IssuerSigned,
+
+    /// Data elements signed by the mdoc (device).
+    pub device_signed: DeviceSigned,
+}
+
+/// Data elements (claims) returned by the mdoc along with the device's
+/// authentication of the response.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceSigned {
+    /// Returned data elements for each namespace (`DeviceNameSpacesBytes`
+    /// element).
+    pub name_spaces: DeviceNameSpacesBytes,
+
+    /// Authentication of the response by the mdoc's device key.
+    pub device_auth: DeviceAuth,
+}
+
+/// `DeviceNameSpacesBytes` represents the tagged `DeviceNameSpaces` after
+/// CBOR serialization: `#6.24(bstr .cbor DeviceNameSpaces)`
+pub type DeviceNameSpacesBytes = Tag24<DeviceNameSpaces>;
+
+/// Device-signed data elements by namespace.
+pub type DeviceNameSpaces = BTreeMap<NameSpace, BTreeMap<String, ciborium::Value>>;
+
+/// Device authentication. Only device signatures (rather than MACs) are
+/// supported.
+///
+/// See 9.1.3 mdoc authentication.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceAuth {
+    /// `COSE_Sign1` with a detached payload of `DeviceAuthenticationBytes`.
+    #[serde(with = "mso::sign1")]
+    pub device_signature: CoseSign1,
+}
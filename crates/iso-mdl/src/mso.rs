@@ -8,7 +8,8 @@
 
 use std::collections::{BTreeMap, HashSet};
 
-use chrono::{Duration, SecondsFormat, Utc};
+use anyhow::bail;
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
 use ciborium::Value;
 use coset::{AsCborValue, CoseSign1};
 use rand::Rng;
@@ -27,14 +28,29 @@ pub struct IssuerAuth(pub CoseSign1);
 
 impl Serialize for IssuerAuth {
     fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
-        self.0.clone().to_cbor_value().map_err(ser::Error::custom)?.serialize(serializer)
+        sign1::serialize(&self.0, serializer)
     }
 }
 
 impl<'de> Deserialize<'de> for IssuerAuth {
     fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        sign1::deserialize(deserializer).map(Self)
+    }
+}
+
+/// (De)serializes an untagged `COSE_Sign1`.
+pub mod sign1 {
+    use super::{
+        de, ser, AsCborValue, CoseSign1, Deserialize, Deserializer, Serialize, Serializer, Value,
+    };
+
+    pub fn serialize<S: Serializer>(sign1: &CoseSign1, serializer: S) -> Result<S::Ok, S::Error> {
+        sign1.clone().to_cbor_value().map_err(ser::Error::custom)?.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<CoseSign1, D::Error> {
         let value = Value::deserialize(deserializer)?;
-        CoseSign1::from_cbor_value(value).map_err(de::Error::custom).map(Self)
+        CoseSign1::from_cbor_value(value).map_err(de::Error::custom)
     }
 }
 
@@ -57,14 +73,21 @@ pub struct MobileSecurityObject {
     pub device_key_info: DeviceKeyInfo,
 
     /// The document type of the document being signed.
-    doc_type: String,
+    pub doc_type: String,
 
     /// Validity information for the MSO
     pub validity_info: ValidityInfo,
 }
 
+impl Default for MobileSecurityObject {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl MobileSecurityObject {
     /// Create a new `MobileSecurityObject` with default values.
+    #[must_use]
     pub fn new() -> Self {
         // TODO: get valid_xxx dates from issuer
         let until = Utc::now() + Duration::days(365);
@@ -157,7 +180,7 @@ pub struct KeyAuthorization {
 }
 
 /// Contains information related to the validity of the MSO and its signature.
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct ValidityInfo {
     /// Time the MSO was signed
@@ -177,6 +200,25 @@ pub struct ValidityInfo {
     pub expected_update: Option<String>,
 }
 
+impl ValidityInfo {
+    /// Check the MSO is valid at the current time.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the validity timestamps cannot be parsed or the MSO
+    /// is not yet, or no longer, valid.
+    pub fn verify(&self) -> anyhow::Result<()> {
+        let now = Utc::now();
+        if now < DateTime::parse_from_rfc3339(&self.valid_from)? {
+            bail!("MSO is not yet valid");
+        }
+        if now > DateTime::parse_from_rfc3339(&self.valid_until)? {
+            bail!("MSO has expired");
+        }
+        Ok(())
+    }
+}
+
 /// Generates unique `DigestId` values.
 pub struct DigestIdGenerator {
     used: HashSet<DigestId>,
@@ -0,0 +1,75 @@
+//! # Session Transcript
+//!
+//! The session transcript binds the device's authentication of a
+//! `DeviceResponse` to the session the response is returned in. When an mdoc is
+//! presented using `OpenID4VP`, the transcript's handover is derived from the
+//! Verifier's Authorization Request.
+//!
+//! See 9.1.5.1 Session transcript and [OpenID4VP] Appendix B.2.6.1.
+//!
+//! [OpenID4VP]: (https://openid.net/specs/openid-4-verifiable-presentations-1_0.html)
+
+use ciborium::Value;
+use serde::{Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use vercre_infosec::cose::{cbor, Tag24};
+
+use crate::mdoc::DeviceNameSpacesBytes;
+
+/// The session transcript for an mdoc presented in response to an `OpenID4VP`
+/// Authorization Request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SessionTranscript {
+    // SHA-256 digest of the `OpenID4VPHandoverInfo`.
+    handover_info_hash: Vec<u8>,
+}
+
+impl SessionTranscript {
+    /// Create a session transcript from the Authorization Request's
+    /// `client_id`, `nonce`, and `response_uri` (when set).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the handover cannot be serialized.
+    pub fn openid4vp(
+        client_id: &str, nonce: &str, response_uri: Option<&str>,
+    ) -> anyhow::Result<Self> {
+        let handover_info = Value::Array(vec![
+            Value::Text(client_id.to_string()),
+            Value::Text(nonce.to_string()),
+            // the JWK thumbprint is only set for encrypted responses
+            Value::Null,
+            response_uri.map_or(Value::Null, |uri| Value::Text(uri.to_string())),
+        ]);
+
+        Ok(Self {
+            handover_info_hash: Sha256::digest(cbor::to_vec(&handover_info)?).to_vec(),
+        })
+    }
+
+    /// The `DeviceAuthenticationBytes` signed by the device to authenticate a
+    /// document in the session.
+    pub(crate) fn device_authentication(
+        &self, doc_type: &str, name_spaces: &DeviceNameSpacesBytes,
+    ) -> anyhow::Result<Vec<u8>> {
+        let device_authentication = Value::Array(vec![
+            Value::Text("DeviceAuthentication".to_string()),
+            Value::serialized(self)?,
+            Value::Text(doc_type.to_string()),
+            Value::serialized(name_spaces)?,
+        ]);
+        Tag24(device_authentication).to_vec()
+    }
+}
+
+// SessionTranscript = [DeviceEngagementBytes, EReaderKeyBytes, Handover], where
+// both engagement structures are null for OpenID4VP.
+impl Serialize for SessionTranscript {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let handover = Value::Array(vec![
+            Value::Text("OpenID4VPHandover".to_string()),
+            Value::Bytes(self.handover_info_hash.clone()),
+        ]);
+        Value::Array(vec![Value::Null, Value::Null, handover]).serialize(serializer)
+    }
+}
@@ -0,0 +1,58 @@
+//! # X.509 Certificates
+//!
+//! An issuer may identify its signing key with an `x5chain` header containing
+//! the X.509 certificate chain for the key, rather than a key ID.
+//!
+//! Only the public key of the leaf certificate is read here. Establishing
+//! trust in the chain (for example, against an IACA root) is left to the
+//! caller.
+//!
+//! See 9.1.2.4 Signing method and structure for MSO.
+
+use anyhow::{anyhow, bail};
+use base64ct::{Base64UrlUnpadded, Encoding};
+use spki::der::asn1::AnyRef;
+use spki::der::{Decode, Reader, SliceReader, Tag, TagNumber};
+use spki::{ObjectIdentifier, SubjectPublicKeyInfoRef};
+use vercre_infosec::{Curve, KeyType, PublicKeyJwk};
+
+// id-Ed25519 from RFC 8410.
+const ED25519: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.112");
+
+/// The Ed25519 public key of a DER-encoded X.509 certificate, as a JWK.
+pub fn public_key(certificate: &[u8]) -> anyhow::Result<PublicKeyJwk> {
+    let invalid = |e: spki::der::Error| anyhow!("invalid certificate: {e}");
+
+    // Certificate ::= SEQUENCE { tbsCertificate, signatureAlgorithm, signature }
+    let certificate = AnyRef::from_der(certificate).map_err(invalid)?;
+    let mut reader = SliceReader::new(certificate.value()).map_err(invalid)?;
+    let tbs_certificate = AnyRef::decode(&mut reader).map_err(invalid)?;
+
+    // TBSCertificate ::= SEQUENCE { [0] version OPTIONAL, serialNumber,
+    // signature, issuer, validity, subject, subjectPublicKeyInfo, ... }
+    let mut reader = SliceReader::new(tbs_certificate.value()).map_err(invalid)?;
+    let version = Tag::ContextSpecific {
+        constructed: true,
+        number: TagNumber::N0,
+    };
+    if reader.peek_tag().map_err(invalid)? == version {
+        AnyRef::decode(&mut reader).map_err(invalid)?;
+    }
+    for _ in 0..5 {
+        AnyRef::decode(&mut reader).map_err(invalid)?;
+    }
+    let spki = SubjectPublicKeyInfoRef::decode(&mut reader).map_err(invalid)?;
+
+    if spki.algorithm.oid != ED25519 {
+        bail!("unsupported certificate key algorithm {}", spki.algorithm.oid);
+    }
+    let Some(public_key) = spki.subject_public_key.as_bytes() else {
+        bail!("invalid certificate public key");
+    };
+    Ok(PublicKeyJwk {
+        kty: KeyType::Okp,
+        crv: Curve::Ed25519,
+        x: Base64UrlUnpadded::encode_string(public_key),
+        ..PublicKeyJwk::default()
+    })
+}
@@ -63,3 +63,17 @@ fn authorization_details() {
 
     assert_snapshot!("authorization_details", &request);
 }
+
+#[test]
+fn refresh_token() {
+    let refresh_token = "ABCDEF";
+
+    let request = token_request!({
+        "credential_issuer": CREDENTIAL_ISSUER,
+        "client_id": CLIENT_ID,
+        "grant_type": "refresh_token",
+        "refresh_token": refresh_token,
+    });
+
+    assert_snapshot!("refresh_token", &request);
+}
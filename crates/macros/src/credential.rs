@@ -14,6 +14,7 @@ pub fn request(input: &Json) -> Result<TokenStream> {
     let credential = credential_issuance(&mut input)?;
     let proof = proof(&mut input)?;
     let credential_response_encryption = input.option("credential_response_encryption");
+    let dpop = input.option("dpop");
 
     // return error for any unexpected fields
     input.check_consumed()?;
@@ -27,6 +28,7 @@ pub fn request(input: &Json) -> Result<TokenStream> {
             credential: #credential,
             proof: #proof,
             credential_response_encryption: #credential_response_encryption,
+            dpop: #dpop,
         }
     })
 }
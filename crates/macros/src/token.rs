@@ -17,6 +17,7 @@ pub fn request(input: &Json) -> Result<TokenStream> {
 
     // optional fields — return Some or None
     let client_id = input.option("client_id");
+    let dpop = input.option("dpop");
 
     let Some(grant_type) = input.get("grant_type") else {
         return Err(Error::new(Span::call_site(), "`grant_type` is not set"));
@@ -27,6 +28,8 @@ pub fn request(input: &Json) -> Result<TokenStream> {
             pre_authorized_code(&mut input)?
         } else if grant_type.as_str() == Some("authorization_code") {
             authorization_code(&mut input)?
+        } else if grant_type.as_str() == Some("refresh_token") {
+            refresh_token(&mut input)?
         } else {
             return Err(Error::new(Span::call_site(), "unknown `grant_type`"));
         };
@@ -50,6 +53,7 @@ pub fn request(input: &Json) -> Result<TokenStream> {
             grant_type: #grant_type,
             authorization_details: #authorization_details,
             client_assertion: None,
+            dpop: #dpop,
         }
     })
 }
@@ -84,6 +88,18 @@ fn authorization_code(input: &mut Json) -> Result<TokenStream> {
     })
 }
 
+fn refresh_token(input: &mut Json) -> Result<TokenStream> {
+    let path = quote! {vercre_issuer};
+
+    let refresh_token = input.expect("refresh_token")?;
+
+    Ok(quote! {
+        #path::TokenGrantType::RefreshToken {
+            refresh_token: #refresh_token,
+        }
+    })
+}
+
 fn authorization_details(details: &Value) -> Result<TokenStream> {
     let span = Span::call_site();
     let path = quote! {vercre_issuer};
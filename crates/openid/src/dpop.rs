@@ -0,0 +1,171 @@
+//! # Demonstrating Proof of Possession (DPoP)
+//!
+//! [RFC9449] DPoP proofs are JWTs the Wallet signs with a key it holds and
+//! sends with requests to the Authorization Server and Credential Issuer. An
+//! access token issued in response to a request carrying a DPoP proof is bound
+//! to the proof's key (by its [RFC7638] thumbprint) and cannot be used without
+//! a fresh proof signed by the same key.
+//!
+//! Each proof's public key is carried in its `jwk` header parameter, so
+//! proofs are self-verifying.
+//!
+//! [RFC9449]: (https://www.rfc-editor.org/rfc/rfc9449.html)
+//! [RFC7638]: (https://www.rfc-editor.org/rfc/rfc7638.html)
+
+use anyhow::{anyhow, bail};
+use base64ct::{Base64UrlUnpadded, Encoding};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use vercre_infosec::jose::jwk::{Curve, KeyType, PublicKeyJwk};
+use vercre_infosec::{Algorithm, Signer};
+
+/// The `typ` header parameter value of a DPoP proof.
+pub const DPOP_TYPE: &str = "dpop+jwt";
+
+/// DPoP proof JWT header.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Header {
+    /// Always "dpop+jwt".
+    pub typ: String,
+
+    /// The algorithm used to sign the proof.
+    pub alg: Algorithm,
+
+    /// The public key the proof is signed with.
+    pub jwk: PublicKeyJwk,
+}
+
+/// Claims of a DPoP proof JWT.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct DpopClaims {
+    /// A unique identifier for the proof, used to prevent it being replayed.
+    pub jti: String,
+
+    /// The HTTP method of the request the proof is attached to.
+    pub htm: String,
+
+    /// The HTTP target URI of the request, without query and fragment parts.
+    pub htu: String,
+
+    /// The time at which the proof was created, as a `NumericDate`.
+    pub iat: i64,
+
+    /// Hash of the access token the proof is presented with. REQUIRED when
+    /// presenting an access token to the Credential Issuer.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ath: Option<String>,
+
+    /// The most recent `DPoP-Nonce` provided by the server.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+}
+
+/// Create a DPoP proof, signed by `signer`.
+///
+/// # Errors
+///
+/// Returns an error if the signer's algorithm is not supported or the signer
+/// fails to sign the proof.
+pub async fn encode(claims: &DpopClaims, signer: &impl Signer) -> anyhow::Result<String> {
+    let Algorithm::EdDSA = signer.algorithm() else {
+        bail!("unsupported DPoP signing algorithm");
+    };
+    let header = Header {
+        typ: DPOP_TYPE.into(),
+        alg: Algorithm::EdDSA,
+        jwk: PublicKeyJwk {
+            kty: KeyType::Okp,
+            crv: Curve::Ed25519,
+            x: Base64UrlUnpadded::encode_string(&signer.public_key().await?),
+            ..PublicKeyJwk::default()
+        },
+    };
+
+    let header = Base64UrlUnpadded::encode_string(&serde_json::to_vec(&header)?);
+    let claims = Base64UrlUnpadded::encode_string(&serde_json::to_vec(claims)?);
+    let payload = format!("{header}.{claims}");
+
+    let signature = signer.try_sign(payload.as_bytes()).await?;
+    let signature = Base64UrlUnpadded::encode_string(&signature);
+
+    Ok(format!("{payload}.{signature}"))
+}
+
+/// Verify a DPoP proof's signature using the key in its header, returning the
+/// header and claims. Checking the claims against the request is left to the
+/// caller.
+///
+/// # Errors
+///
+/// Returns an error if the proof is malformed, is not a DPoP proof, or its
+/// signature is invalid.
+pub fn verify(proof: &str) -> anyhow::Result<(Header, DpopClaims)> {
+    let parts = proof.split('.').collect::<Vec<_>>();
+    if parts.len() != 3 {
+        bail!("invalid DPoP proof: expected 3 segments");
+    }
+
+    let header = Base64UrlUnpadded::decode_vec(parts[0])
+        .map_err(|e| anyhow!("issue decoding DPoP proof header: {e}"))?;
+    let header: Header = serde_json::from_slice(&header)?;
+    if header.typ != DPOP_TYPE {
+        bail!("invalid DPoP proof typ: {}", header.typ);
+    }
+
+    let signature = Base64UrlUnpadded::decode_vec(parts[2])
+        .map_err(|e| anyhow!("issue decoding DPoP proof signature: {e}"))?;
+    let payload = format!("{}.{}", parts[0], parts[1]);
+    vercre_core::signature::verify(&header.jwk, payload.as_bytes(), &signature)?;
+
+    let claims = Base64UrlUnpadded::decode_vec(parts[1])
+        .map_err(|e| anyhow!("issue decoding DPoP proof claims: {e}"))?;
+    Ok((header, serde_json::from_slice(&claims)?))
+}
+
+/// The [RFC7638] JWK thumbprint of `jwk`, used as the `jkt` an access token
+/// is bound to.
+///
+/// [RFC7638]: (https://www.rfc-editor.org/rfc/rfc7638.html)
+///
+/// # Errors
+///
+/// Returns an error if the key is missing required members.
+pub fn thumbprint(jwk: &PublicKeyJwk) -> anyhow::Result<String> {
+    // required members only, in lexicographic order
+    let members = match jwk.kty {
+        KeyType::Okp => json!({"crv": jwk.crv, "kty": jwk.kty, "x": jwk.x}),
+        KeyType::Ec => {
+            let Some(y) = &jwk.y else {
+                bail!("EC key is missing `y`");
+            };
+            json!({"crv": jwk.crv, "kty": jwk.kty, "x": jwk.x, "y": y})
+        }
+    };
+    let digest = Sha256::digest(serde_json::to_vec(&members)?);
+    Ok(Base64UrlUnpadded::encode_string(&digest))
+}
+
+/// The `ath` claim value for an access token.
+#[must_use]
+pub fn access_token_hash(access_token: &str) -> String {
+    Base64UrlUnpadded::encode_string(&Sha256::digest(access_token.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Example from RFC 8037, Appendix A.3.
+    #[test]
+    fn ed25519_thumbprint() {
+        let jwk = PublicKeyJwk {
+            kty: KeyType::Okp,
+            crv: Curve::Ed25519,
+            x: "11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo".into(),
+            ..PublicKeyJwk::default()
+        };
+        let jkt = thumbprint(&jwk).expect("should compute thumbprint");
+        assert_eq!(jkt, "kPrK_qmxVWaYVA9wwBF6Iuo3vVzz7TxHCTwXBygrS4k");
+    }
+}
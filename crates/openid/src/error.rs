@@ -122,6 +122,18 @@ pub enum Error {
     #[error(r#"{{"error": "invalid_encryption_parameters", "error_description": "{0}"}}"#)]
     InvalidEncryptionParameters(String),
 
+    /// The DPoP proof JWT is missing, invalid, or does not match the request
+    /// or the key the access token is bound to.
+    #[error(r#"{{"error": "invalid_dpop_proof", "error_description": "{0}"}}"#)]
+    InvalidDpopProof(String),
+
+    /// The server requires the DPoP proof to contain a server-provided nonce.
+    /// The error response contains the `dpop_nonce` to use when retrying the
+    /// request, returned to the client in the `DPoP-Nonce` HTTP header.
+    #[allow(missing_docs)]
+    #[error(r#"{{"error": "use_dpop_nonce", "error_description": "{hint}", "dpop_nonce": "{dpop_nonce}"}}"#)]
+    UseDpopNonce { hint: String, dpop_nonce: String },
+
     /// The Credential issuance is still pending. The error response SHOULD also
     /// contain the interval member, determining the minimum amount of time
     /// in seconds that the Wallet needs to wait before providing a new
@@ -185,6 +197,10 @@ pub struct OidError {
     /// The expiry time of the `c_nonce`.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub c_nonce_expires_in: Option<i64>,
+
+    /// A fresh DPoP nonce to use when retrying the request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dpop_nonce: Option<String>,
 }
 
 impl Serialize for Error {
@@ -12,12 +12,12 @@ use qrcode::QrCode;
 use serde::de::{self, Deserializer, Visitor};
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
-use vercre_core::{urlencode, Kind};
+use vercre_core::{urlencode, Kind, Quota};
 use vercre_did::DidResolver;
 use vercre_infosec::jose::jwk::PublicKeyJwk;
 use vercre_infosec::SecOps;
 use vercre_status::issuer::Status;
-use vercre_w3c_vc::model::VerifiableCredential;
+use vercre_w3c_vc::model::{StatusPurpose, VerifiableCredential};
 
 use crate::oauth;
 pub use crate::oauth::{
@@ -975,6 +975,11 @@ pub struct TokenRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(flatten)]
     pub client_assertion: Option<ClientAssertion>,
+
+    /// A DPoP proof JWT, as extracted from the `DPoP` header of the Token
+    /// Request. When set, the access token issued is bound to the proof's key.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dpop: Option<String>,
 }
 
 /// Token authorization grant types.
@@ -1016,6 +1021,14 @@ pub enum TokenGrantType {
         #[serde(skip_serializing_if = "Option::is_none")]
         tx_code: Option<String>,
     },
+
+    /// Attributes required for the Refresh Token grant type.
+    #[serde(rename = "refresh_token")]
+    RefreshToken {
+        /// The refresh token issued to the Wallet with a previous access
+        /// token.
+        refresh_token: String,
+    },
 }
 
 impl Default for TokenGrantType {
@@ -1041,6 +1054,35 @@ pub enum ClientAssertion {
     },
 }
 
+/// Claims of a client assertion JWT used to authenticate the client as defined
+/// in [RFC7523].
+///
+/// [RFC7523]: (https://www.rfc-editor.org/rfc/rfc7523.html)
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ClientAssertionClaims {
+    /// The `client_id` of the client the assertion was issued by.
+    pub iss: String,
+
+    /// The `client_id` of the client the assertion was issued for. Must be the
+    /// same as `iss`.
+    pub sub: String,
+
+    /// The authorization server the assertion is intended for: its issuer
+    /// identifier or the URL of the endpoint receiving the assertion.
+    pub aud: Quota<String>,
+
+    /// The time the assertion expires, as a `NumericDate`.
+    pub exp: i64,
+
+    /// The time the assertion was issued, as a `NumericDate`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iat: Option<i64>,
+
+    /// A unique identifier for the assertion, used to prevent it being
+    /// replayed.
+    pub jti: String,
+}
+
 /// Token Response as defined in [RFC6749].
 ///
 /// [RFC6749]: (https://www.rfc-editor.org/rfc/rfc6749.html)
@@ -1050,7 +1092,8 @@ pub struct TokenResponse {
     /// or more Credentials.
     pub access_token: String,
 
-    /// The type of the token issued. Must be "`Bearer`".
+    /// The type of the token issued: "`DPoP`" when the token is bound to the
+    /// Wallet's DPoP key, otherwise "`Bearer`".
     pub token_type: TokenType,
 
     /// The lifetime in seconds of the access token.
@@ -1065,6 +1108,13 @@ pub struct TokenResponse {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub c_nonce_expires_in: Option<i64>,
 
+    /// A token the Wallet can use to obtain a new access token for the same
+    /// credentials without the End-User authorizing issuance again. Issued
+    /// when both the client and authorization server support the
+    /// `refresh_token` grant type. The token is replaced each time it is used.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+
     /// REQUIRED when `authorization_details` parameter is used to request
     /// issuance of a certain Credential type. MUST NOT be used otherwise.
     ///
@@ -1072,15 +1122,25 @@ pub struct TokenResponse {
     /// populated for use in subsequent Credential Requests.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub authorization_details: Option<Vec<AuthorizedDetail>>,
+
+    /// A nonce for the Wallet to use in subsequent DPoP proofs, to be returned
+    /// in the `DPoP-Nonce` HTTP header.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dpop_nonce: Option<String>,
 }
 
-/// Access token type as defined in [RFC6749]. Per the specification, the only
-/// value allowed is "`Bearer`".
+/// Access token type as defined in [RFC6749] and [RFC9449].
+///
+/// [RFC9449]: (https://www.rfc-editor.org/rfc/rfc9449.html)
 #[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
 pub enum TokenType {
-    /// The only valid value is "`Bearer`".
+    /// A bearer token, usable by any party in possession of it.
     #[default]
     Bearer,
+
+    /// A token bound to the Wallet's DPoP key. Requests using the token must
+    /// be accompanied by a DPoP proof signed with the key.
+    DPoP,
 }
 
 /// Authorization Details object specifically for use in successful Access Token
@@ -1118,6 +1178,11 @@ pub struct CredentialRequest {
     #[serde(skip_serializing_if = "String::is_empty", default)]
     pub access_token: String,
 
+    /// A DPoP proof JWT, as extracted from the `DPoP` header of the request.
+    /// REQUIRED when the access token is DPoP-bound.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dpop: Option<String>,
+
     /// Identifies the credential requested for issuance using either a
     /// `credential_identifier` or a supported format.
     ///
@@ -1323,6 +1388,11 @@ pub enum CredentialResponseType {
     /// obtain the respective Credential with the Deferred Credential
     /// Endpoint.
     TransactionId(String),
+
+    /// The Credential Response encrypted as a compact JWE, using the
+    /// parameters specified by the Wallet in `credential_response_encryption`.
+    /// When set, the remaining Credential Response fields are omitted.
+    Encrypted(String),
 }
 
 impl Default for CredentialResponseType {
@@ -1346,6 +1416,11 @@ pub struct DeferredCredentialRequest {
     #[serde(skip_serializing_if = "String::is_empty", default)]
     pub access_token: String,
 
+    /// A DPoP proof JWT, as extracted from the `DPoP` header of the request.
+    /// REQUIRED when the access token is DPoP-bound.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dpop: Option<String>,
+
     /// Identifies a Deferred Issuance transaction from an earlier Credential
     /// Request.
     pub transaction_id: String,
@@ -1855,7 +1930,10 @@ pub struct CredentialDefinition {
     /// aliases, in accordance with the W3C Verifiable Credentials Data
     /// Model.
     ///
-    /// REQUIRED when `format` is "`jwt_vc_json-ld`" or "`ldp_vc`".
+    /// REQUIRED when `format` is "`jwt_vc_json-ld`" or "`ldp_vc`". The first
+    /// context must be the base VC context, followed by at least one context
+    /// defining the credential's type and claims. Contexts are not
+    /// dereferenced when issuing, so the terms they define are not checked.
     #[serde(rename = "@context")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub context: Option<Vec<String>>,
@@ -2048,6 +2126,11 @@ pub struct NotificationRequest {
     #[serde(skip_serializing_if = "String::is_empty", default)]
     pub access_token: String,
 
+    /// A DPoP proof JWT, as extracted from the `DPoP` header of the request.
+    /// REQUIRED when the access token is DPoP-bound.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dpop: Option<String>,
+
     /// As received from the issuer in the Credential Response.
     pub notification_id: String,
 
@@ -2089,6 +2172,60 @@ pub enum NotificationEvent {
 #[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
 pub struct NotificationResponse {}
 
+/// Used by the Credential Issuer's administrative tooling (for example, a
+/// support desk) to change the status of a previously issued credential.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct UpdateStatusRequest {
+    /// The Credential Issuer that issued the credential.
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    pub credential_issuer: String,
+
+    /// Identifier of the credential to update.
+    pub credential_id: String,
+
+    /// The status to update.
+    pub purpose: StatusPurpose,
+
+    /// The new status value. For [`StatusPurpose::Revocation`] and
+    /// [`StatusPurpose::Suspension`], 1 sets the status and 0 clears it. For
+    /// [`StatusPurpose::Message`] the value is the status of the message.
+    pub value: u8,
+}
+
+/// Response to an `UpdateStatusRequest` containing the re-signed status list
+/// affected by the update.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct UpdateStatusResponse {
+    /// URL the status list credential is published at.
+    pub status_list_url: String,
+
+    /// The signed `BitstringStatusListCredential`.
+    pub credential: String,
+}
+
+/// Request for a published status list, as dereferenced by a Verifier from a
+/// credential's `statusListCredential` URL.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct StatusListRequest {
+    /// The Credential Issuer publishing the status list.
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    pub credential_issuer: String,
+
+    /// Identifier of the status list.
+    pub list: usize,
+
+    /// Shard of the status list.
+    #[serde(default)]
+    pub shard: usize,
+}
+
+/// Response to a `StatusListRequest`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct StatusListResponse {
+    /// The signed `BitstringStatusListCredential`.
+    pub credential: String,
+}
+
 #[cfg(test)]
 mod tests {
     use insta::assert_yaml_snapshot as assert_snapshot;
@@ -0,0 +1,314 @@
+//! # JSON Web Encryption
+//!
+//! Compact [JWE] serialization used to encrypt responses exchanged between
+//! Wallet, Credential Issuer, and Verifier (for example, an encrypted
+//! Credential Response).
+//!
+//! Only ECDH-ES direct key agreement over X25519 ([RFC8037]) is supported, with
+//! `A128GCM` or `A256GCM` content encryption ([RFC7518]). The sender generates
+//! an ephemeral key pair for each message and carries the public key in the
+//! `epk` header parameter. The content encryption key is derived from the
+//! shared secret using the Concat KDF, and the protected header is used as
+//! Additional Authenticated Data.
+//!
+//! Recipients provide the key agreement step using their own (private) key by
+//! implementing [`KeyAgreement`].
+//!
+//! [JWE]: https://www.rfc-editor.org/rfc/rfc7516
+//! [RFC7518]: https://www.rfc-editor.org/rfc/rfc7518
+//! [RFC8037]: https://www.rfc-editor.org/rfc/rfc8037
+
+use std::future::Future;
+
+use aes_gcm::aead::consts::U12;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes128Gcm, Aes256Gcm, Nonce};
+use anyhow::{anyhow, bail};
+use base64ct::{Base64UrlUnpadded, Encoding};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use vercre_infosec::jose::jwk::{Curve, KeyType, PublicKeyJwk};
+use x25519_dalek::{EphemeralSecret, PublicKey};
+
+/// ECDH-ES direct key agreement.
+pub const ECDH_ES: &str = "ECDH-ES";
+
+/// AES-GCM content encryption using a 128-bit key.
+pub const A128GCM: &str = "A128GCM";
+
+/// AES-GCM content encryption using a 256-bit key.
+pub const A256GCM: &str = "A256GCM";
+
+/// `KeyAgreement` is implemented by the recipient of encrypted content to
+/// derive the ECDH shared secret from their private key and the sender's
+/// ephemeral public key.
+pub trait KeyAgreement: Send + Sync {
+    /// Compute the X25519 shared secret between the recipient key identified
+    /// by `kid` (when set) and the sender's public key.
+    fn shared_secret(
+        &self, kid: Option<&str>, sender_public_key: &[u8],
+    ) -> impl Future<Output = anyhow::Result<Vec<u8>>> + Send;
+}
+
+/// JWE protected header.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Header {
+    /// Key management algorithm used to agree the content encryption key.
+    pub alg: String,
+
+    /// Content encryption algorithm.
+    pub enc: String,
+
+    /// The sender's ephemeral public key.
+    pub epk: PublicKeyJwk,
+
+    /// Identifies the recipient key the content was encrypted for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kid: Option<String>,
+}
+
+/// Check content can be encrypted for `recipient` using the `alg` and `enc`
+/// algorithms.
+///
+/// # Errors
+///
+/// Returns an error if either algorithm or the recipient key is not
+/// supported.
+pub fn check(recipient: &PublicKeyJwk, alg: &str, enc: &str) -> anyhow::Result<()> {
+    if alg != ECDH_ES {
+        bail!("unsupported JWE alg: {alg}");
+    }
+    key_len(enc)?;
+    if !matches!((&recipient.kty, &recipient.crv), (KeyType::Okp, Curve::X25519)) {
+        bail!("recipient key is not an X25519 key");
+    }
+    Ok(())
+}
+
+/// Encrypt `payload` for `recipient`, returning a compact JWE.
+///
+/// # Errors
+///
+/// Returns an error if the algorithms or recipient key are not supported, the
+/// payload cannot be serialized, or encryption fails.
+pub fn encrypt<T: Serialize>(
+    payload: &T, recipient: &PublicKeyJwk, alg: &str, enc: &str,
+) -> anyhow::Result<String> {
+    check(recipient, alg, enc)?;
+
+    let recipient_key: [u8; 32] = Base64UrlUnpadded::decode_vec(&recipient.x)
+        .map_err(|e| anyhow!("issue decoding recipient key: {e}"))?
+        .try_into()
+        .map_err(|_| anyhow!("invalid recipient key length"))?;
+
+    // ephemeral key agreement
+    let secret = EphemeralSecret::random_from_rng(OsRng);
+    let epk = PublicKey::from(&secret);
+    let shared = secret.diffie_hellman(&PublicKey::from(recipient_key));
+    if !shared.was_contributory() {
+        bail!("recipient key is a low order point");
+    }
+
+    let header = Header {
+        alg: alg.to_string(),
+        enc: enc.to_string(),
+        epk: PublicKeyJwk {
+            kty: KeyType::Okp,
+            crv: Curve::X25519,
+            x: Base64UrlUnpadded::encode_string(epk.as_bytes()),
+            ..PublicKeyJwk::default()
+        },
+        kid: recipient.kid.clone(),
+    };
+    let header = Base64UrlUnpadded::encode_string(&serde_json::to_vec(&header)?);
+
+    let cek = concat_kdf(shared.as_bytes(), enc)?;
+    let plaintext = serde_json::to_vec(payload)?;
+    let (iv, sealed) = match enc {
+        A128GCM => seal::<Aes128Gcm>(&cek, header.as_bytes(), &plaintext)?,
+        _ => seal::<Aes256Gcm>(&cek, header.as_bytes(), &plaintext)?,
+    };
+
+    // AES-GCM appends the 16-byte authentication tag to the ciphertext
+    let (ciphertext, tag) = sealed.split_at(sealed.len() - 16);
+    let iv = Base64UrlUnpadded::encode_string(&iv);
+    let ciphertext = Base64UrlUnpadded::encode_string(ciphertext);
+    let tag = Base64UrlUnpadded::encode_string(tag);
+
+    Ok(format!("{header}..{iv}.{ciphertext}.{tag}"))
+}
+
+/// Decrypt a compact JWE, deserializing its payload.
+///
+/// # Errors
+///
+/// Returns an error if the JWE is malformed, uses unsupported algorithms,
+/// decryption fails, or the plaintext cannot be deserialized as `T`.
+pub async fn decrypt<T: DeserializeOwned>(
+    compact: &str, recipient: &impl KeyAgreement,
+) -> anyhow::Result<T> {
+    let header = decode_header(compact)?;
+    if header.alg != ECDH_ES {
+        bail!("unsupported JWE alg: {}", header.alg);
+    }
+    if !matches!((&header.epk.kty, &header.epk.crv), (KeyType::Okp, Curve::X25519)) {
+        bail!("ephemeral key is not an X25519 key");
+    }
+
+    let parts = compact.split('.').collect::<Vec<_>>();
+    if !parts[1].is_empty() {
+        bail!("JWE encrypted key must be empty for direct key agreement");
+    }
+    let decode = |part: &str, name: &str| {
+        Base64UrlUnpadded::decode_vec(part).map_err(|e| anyhow!("issue decoding JWE {name}: {e}"))
+    };
+    let iv = decode(parts[2], "initialization vector")?;
+    let mut sealed = decode(parts[3], "ciphertext")?;
+    sealed.extend(decode(parts[4], "authentication tag")?);
+
+    let epk = decode(&header.epk.x, "ephemeral key")?;
+    let shared = recipient.shared_secret(header.kid.as_deref(), &epk).await?;
+    if shared.iter().all(|b| *b == 0) {
+        bail!("ephemeral key is a low order point");
+    }
+
+    let cek = concat_kdf(&shared, &header.enc)?;
+    let plaintext = match header.enc.as_str() {
+        A128GCM => open::<Aes128Gcm>(&cek, parts[0].as_bytes(), &iv, &sealed)?,
+        _ => open::<Aes256Gcm>(&cek, parts[0].as_bytes(), &iv, &sealed)?,
+    };
+    Ok(serde_json::from_slice(&plaintext)?)
+}
+
+/// Decode the protected header of a compact JWE without decrypting it. Used by
+/// recipients to find the key (`kid`) the content was encrypted for.
+///
+/// # Errors
+///
+/// Returns an error if the JWE is malformed.
+pub fn decode_header(compact: &str) -> anyhow::Result<Header> {
+    let parts = compact.split('.').collect::<Vec<_>>();
+    if parts.len() != 5 {
+        bail!("invalid JWE: expected 5 segments");
+    }
+    let header = Base64UrlUnpadded::decode_vec(parts[0])
+        .map_err(|e| anyhow!("issue decoding JWE header: {e}"))?;
+    Ok(serde_json::from_slice(&header)?)
+}
+
+// Content encryption key length, in bytes, for the `enc` algorithm.
+fn key_len(enc: &str) -> anyhow::Result<usize> {
+    match enc {
+        A128GCM => Ok(16),
+        A256GCM => Ok(32),
+        _ => bail!("unsupported JWE enc: {enc}"),
+    }
+}
+
+// Derive the content encryption key from the shared secret using the Concat
+// KDF (RFC 7518, section 4.6.2). The `apu` and `apv` parameters are not used.
+fn concat_kdf(shared_secret: &[u8], enc: &str) -> anyhow::Result<Vec<u8>> {
+    let key_len = key_len(enc)?;
+    let enc_len = u32::try_from(enc.len())?;
+    let key_bits = u32::try_from(key_len * 8)?;
+
+    // a single round of SHA-256 yields enough key material for both algorithms
+    let digest = Sha256::new()
+        .chain_update(1_u32.to_be_bytes())
+        .chain_update(shared_secret)
+        .chain_update(enc_len.to_be_bytes())
+        .chain_update(enc.as_bytes())
+        .chain_update(0_u32.to_be_bytes())
+        .chain_update(0_u32.to_be_bytes())
+        .chain_update(key_bits.to_be_bytes())
+        .finalize();
+    Ok(digest[..key_len].to_vec())
+}
+
+// Encrypt `plaintext` with a random IV, returning the IV and the ciphertext
+// with the authentication tag appended.
+fn seal<C>(cek: &[u8], aad: &[u8], plaintext: &[u8]) -> anyhow::Result<(Vec<u8>, Vec<u8>)>
+where
+    C: Aead + AeadCore<NonceSize = U12> + KeyInit,
+{
+    let cipher = C::new_from_slice(cek).map_err(|e| anyhow!("invalid content key: {e}"))?;
+    let iv = C::generate_nonce(&mut OsRng);
+    let sealed = cipher
+        .encrypt(&iv, Payload { msg: plaintext, aad })
+        .map_err(|e| anyhow!("issue encrypting content: {e}"))?;
+    Ok((iv.to_vec(), sealed))
+}
+
+// Decrypt and authenticate `sealed` (ciphertext with the authentication tag
+// appended).
+fn open<C>(cek: &[u8], aad: &[u8], iv: &[u8], sealed: &[u8]) -> anyhow::Result<Vec<u8>>
+where
+    C: Aead + AeadCore<NonceSize = U12> + KeyInit,
+{
+    if iv.len() != 12 {
+        bail!("invalid JWE initialization vector length");
+    }
+    let cipher = C::new_from_slice(cek).map_err(|e| anyhow!("invalid content key: {e}"))?;
+    cipher
+        .decrypt(Nonce::from_slice(iv), Payload { msg: sealed, aad })
+        .map_err(|e| anyhow!("issue decrypting content: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use x25519_dalek::StaticSecret;
+
+    use super::*;
+
+    struct Recipient(StaticSecret);
+
+    impl KeyAgreement for Recipient {
+        async fn shared_secret(
+            &self, _kid: Option<&str>, sender_public_key: &[u8],
+        ) -> anyhow::Result<Vec<u8>> {
+            let sender: [u8; 32] =
+                sender_public_key.try_into().map_err(|_| anyhow!("invalid key length"))?;
+            Ok(self.0.diffie_hellman(&PublicKey::from(sender)).as_bytes().to_vec())
+        }
+    }
+
+    #[tokio::test]
+    async fn round_trip() {
+        let recipient = Recipient(StaticSecret::random_from_rng(OsRng));
+        let jwk = PublicKeyJwk {
+            kty: KeyType::Okp,
+            crv: Curve::X25519,
+            x: Base64UrlUnpadded::encode_string(PublicKey::from(&recipient.0).as_bytes()),
+            kid: Some("key-0".into()),
+            ..PublicKeyJwk::default()
+        };
+        let payload = serde_json::json!({"credential": "abc"});
+
+        for enc in [A128GCM, A256GCM] {
+            let compact = encrypt(&payload, &jwk, ECDH_ES, enc).expect("should encrypt");
+            let header = decode_header(&compact).expect("should decode header");
+            assert_eq!(header.enc, enc);
+            assert_eq!(header.kid.as_deref(), Some("key-0"));
+
+            let decrypted: serde_json::Value =
+                decrypt(&compact, &recipient).await.expect("should decrypt");
+            assert_eq!(decrypted, payload);
+
+            // the protected header is authenticated
+            let tampered = Header {
+                kid: Some("key-1".into()),
+                ..header
+            };
+            let tampered = Base64UrlUnpadded::encode_string(
+                &serde_json::to_vec(&tampered).expect("should serialize"),
+            );
+            let (_, rest) = compact.split_once('.').expect("should split");
+            let tampered = format!("{tampered}.{rest}");
+            assert!(decrypt::<serde_json::Value>(&tampered, &recipient).await.is_err());
+        }
+
+        assert!(encrypt(&payload, &jwk, ECDH_ES, "A128CBC-HS256").is_err());
+        assert!(encrypt(&payload, &jwk, "RSA-OAEP", A256GCM).is_err());
+    }
+}
@@ -9,8 +9,10 @@
 //! be used directly by the end users. Any public types are re-exported through
 //! the respective top-level `vercre-xxx` crates.
 
+pub mod dpop;
 mod error;
 pub mod issuer;
+pub mod jwe;
 pub mod oauth;
 pub mod provider;
 pub mod verifier;
@@ -6,6 +6,7 @@ use std::fmt::{self, Display};
 use std::str::FromStr;
 
 use serde::{Deserialize, Serialize};
+use vercre_infosec::jose::jwk::PublicKeyJwk;
 
 use crate::error;
 
@@ -103,7 +104,7 @@ pub struct OAuthClient {
     ///
     /// [RFC7517]: (https://www.rfc-editor.org/rfc/rfc7517)
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub jwks: Option<String>,
+    pub jwks: Option<Jwks>,
 
     /// A unique identifier string (e.g., a Universally Unique Identifier
     /// (UUID)) assigned by the client developer
@@ -139,6 +140,15 @@ impl OAuthClient {
     }
 }
 
+/// A JSON Web Key Set [RFC7517].
+///
+/// [RFC7517]: (https://www.rfc-editor.org/rfc/rfc7517)
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Jwks {
+    /// The keys in the set.
+    pub keys: Vec<PublicKeyJwk>,
+}
+
 impl Display for OAuthClient {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> fmt::Result {
         let Ok(s) = serde_json::to_string(self) else {
@@ -282,6 +292,14 @@ pub struct OAuthServer {
     /// data only via PAR. If omitted, the default value is false.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub require_pushed_authorization_requests: Option<bool>,
+
+    /// A list of the JWS algorithms supported for DPoP proof JWTs. When set,
+    /// the authorization server issues access tokens bound to the key used to
+    /// sign the client's DPoP proof ([RFC9449]).
+    ///
+    /// [RFC9449]: (https://www.rfc-editor.org/rfc/rfc9449.html)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dpop_signing_alg_values_supported: Option<Vec<String>>,
 }
 
 /// Grant Types supported by the Authorization Server.
@@ -295,6 +313,11 @@ pub enum GrantType {
     #[default]
     #[serde(rename = "urn:ietf:params:oauth:grant-type:pre-authorized_code")]
     PreAuthorizedCode,
+
+    /// The OAuth 2.0 Grant Type for exchanging a refresh token for a new
+    /// access token.
+    #[serde(rename = "refresh_token")]
+    RefreshToken,
 }
 
 /// Response types supported by the authorization endpoint.
@@ -14,15 +14,23 @@ use serde::ser::{SerializeMap, Serializer};
 use serde::{Deserialize, Serialize};
 use vercre_core::{urlencode, Kind};
 use vercre_did::DidResolver;
-use vercre_dif_exch::{InputDescriptor, PresentationDefinition, PresentationSubmission};
+use vercre_dif_exch::{
+    DcqlQuery, InputDescriptor, PresentationDefinition, PresentationSubmission,
+    SubmissionRequirement,
+};
 pub use vercre_infosec::SecOps;
+use vercre_status::verifier::Status;
 use vercre_w3c_vc::model::VerifiablePresentation;
 
-pub use crate::oauth::{OAuthClient, OAuthServer};
+pub use crate::jwe::KeyAgreement;
+pub use crate::oauth::{Jwks, OAuthClient, OAuthServer};
 pub use crate::provider::{self, Result, StateStore};
 
 /// Verifier Provider trait.
-pub trait Provider: Metadata + StateStore + SecOps + DidResolver + Clone {}
+pub trait Provider:
+    Metadata + StateStore + SecOps + KeyAgreement + DidResolver + Status + Clone
+{
+}
 
 /// The `Metadata` trait is used by implementers to provide `Verifier` (client)
 /// metadata to the library.
@@ -55,12 +63,37 @@ pub struct CreateRequestRequest {
     /// Holder.
     pub input_descriptors: Vec<InputDescriptor>,
 
+    /// Submission Requirements specifying which combinations of Input
+    /// Descriptors the Holder may submit. When omitted, every Input Descriptor
+    /// must be satisfied.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub submission_requirements: Option<Vec<SubmissionRequirement>>,
+
+    /// A DCQL query describing the credentials required from the Holder. When
+    /// set, the query is sent to the Wallet instead of a Presentation
+    /// Definition and `input_descriptors` are ignored.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub dcql_query: Option<DcqlQuery>,
+
     /// The Verifier can specify whether Authorization Requests and Responses
     /// are to be passed between endpoints on the same device or across devices
     pub device_flow: DeviceFlow,
 
     /// The Client ID
     pub client_id_scheme: String,
+
+    /// Pass the Presentation Definition to the Wallet by reference, as a
+    /// `presentation_definition_uri`, rather than embedding it in the Request
+    /// Object. Ignored when the Wallet is known not to support it.
+    pub definition_by_reference: bool,
+
+    /// Identifies the Wallet the request is intended for, when known. The
+    /// Wallet's `vp_formats_supported` metadata is used to limit the formats
+    /// requested to those both the Verifier and Wallet support.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wallet_id: Option<String>,
 }
 
 /// Used to specify whether Authorization Requests and Responses are to be
@@ -201,8 +234,17 @@ pub struct RequestObject {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub state: Option<String>,
 
-    /// The Presentation Definition
-    pub presentation_definition: Kind<PresentationDefinition>,
+    /// The Presentation Definition, either embedded in the Request Object
+    /// (`presentation_definition`) or passed by reference as a URL the Wallet
+    /// can dereference to retrieve it (`presentation_definition_uri`). Not set
+    /// when credentials are requested using `dcql_query`.
+    #[serde(flatten, with = "definition")]
+    pub presentation_definition: Option<Kind<PresentationDefinition>>,
+
+    /// A DCQL query describing the credentials requested. Used in place of a
+    /// Presentation Definition.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dcql_query: Option<DcqlQuery>,
 
     /// The `client_id_scheme` is used to specify how the Wallet should to
     /// obtain and validate Verifier metadata. The following values indicate
@@ -317,28 +359,63 @@ pub enum ClientIdScheme {
     // X509SanUri,
 }
 
-// /// The type of Presentation Definition returned by the `RequestObject`:
-// either an object /// or a URI.
-// #[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
-// #[allow(clippy::module_name_repetitions)]
-// pub enum PresentationDefinitionType {
-//     /// A Presentation Definition object embedded in the `RequestObject`.
-//     #[serde(rename = "presentation_definition")]
-//     Object(PresentationDefinition),
-
-//     /// A URI pointing to where a Presentation Definition object can be
-//     /// retrieved. This parameter MUST be set when neither
-//     /// `presentation_definition` nor a Presentation Definition scope value
-//     /// are set.
-//     #[serde(rename = "presentation_definition_uri")]
-//     Uri(String),
-// }
-
-// impl Default for PresentationDefinitionType {
-//     fn default() -> Self {
-//         Self::Object(PresentationDefinition::default())
-//     }
-// }
+// Serializes the Presentation Definition as `presentation_definition` when
+// embedded or `presentation_definition_uri` when passed by reference. Neither
+// is serialized when the request uses a DCQL query.
+mod definition {
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+    use vercre_core::Kind;
+    use vercre_dif_exch::PresentationDefinition;
+
+    #[derive(Serialize)]
+    struct DefinitionRef<'a> {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        presentation_definition: Option<&'a PresentationDefinition>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        presentation_definition_uri: Option<&'a str>,
+    }
+
+    #[derive(Deserialize)]
+    struct Definition {
+        presentation_definition: Option<PresentationDefinition>,
+        presentation_definition_uri: Option<String>,
+    }
+
+    #[allow(clippy::ref_option)]
+    pub fn serialize<S: Serializer>(
+        definition: &Option<Kind<PresentationDefinition>>, serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let definition = match definition {
+            Some(Kind::Object(pd)) => DefinitionRef {
+                presentation_definition: Some(pd),
+                presentation_definition_uri: None,
+            },
+            Some(Kind::String(uri)) => DefinitionRef {
+                presentation_definition: None,
+                presentation_definition_uri: Some(uri),
+            },
+            None => DefinitionRef {
+                presentation_definition: None,
+                presentation_definition_uri: None,
+            },
+        };
+        definition.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Kind<PresentationDefinition>>, D::Error> {
+        let definition = Definition::deserialize(deserializer)?;
+        match (definition.presentation_definition, definition.presentation_definition_uri) {
+            (Some(pd), None) => Ok(Some(Kind::Object(pd))),
+            (None, Some(uri)) => Ok(Some(Kind::String(uri))),
+            (None, None) => Ok(None),
+            (Some(_), Some(_)) => Err(de::Error::custom(
+                "only one of `presentation_definition` or `presentation_definition_uri` may be set",
+            )),
+        }
+    }
+}
 
 impl RequestObject {
     /// Generate qrcode for Request Object.
@@ -432,6 +509,29 @@ impl Default for RequestObjectType {
     }
 }
 
+/// The Presentation Definition Request is used (indirectly) by the Wallet to
+/// retrieve a Presentation Definition passed by reference in a Request Object's
+/// `presentation_definition_uri`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct PresentationDefinitionRequest {
+    /// The ID of the Verifier the Presentation Definition belongs to.
+    #[serde(default)]
+    pub client_id: String,
+
+    /// The unique identifier of the Request Object the Presentation Definition
+    /// was created for.
+    pub id: String,
+}
+
+/// The Presentation Definition Response returns the Presentation Definition
+/// referenced by a Request Object.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct PresentationDefinitionResponse {
+    /// The requested Presentation Definition.
+    #[serde(flatten)]
+    pub presentation_definition: PresentationDefinition,
+}
+
 /// Serialize to 'unwrapped' JWT if Request Object is JWT (`jwt parameter is
 /// set`).
 impl Serialize for RequestObjectResponse {
@@ -512,9 +612,12 @@ pub struct ResponseRequest {
     /// When a single Verifiable Presentation is returned, array syntax MUST NOT
     /// be used.
     ///
+    /// In response to a DCQL query, presentations are keyed by the `id` of the
+    /// credential query they answer.
+    ///
     /// [OpenID4VCI]: (https://openid.net/specs/openid-4-verifiable-credential-issuance-1_0.html)
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub vp_token: Option<Vec<Kind<VerifiablePresentation>>>,
+    pub vp_token: Option<VpToken>,
 
     /// The `presentation_submission` element as defined in
     /// [DIF.PresentationExchange]. It contains mappings between the
@@ -526,6 +629,27 @@ pub struct ResponseRequest {
     /// The client state value from the Authorization Request.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub state: Option<String>,
+
+    /// The Authorization Response encrypted as a compact JWE when the Response
+    /// Mode is "`direct_post.jwt`". The remaining response parameters are
+    /// carried in the JWE payload rather than set directly. See [JARM].
+    ///
+    /// [JARM]: (https://openid.net/specs/oauth-v2-jarm-final.html)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<String>,
+}
+
+/// The VP Token returned in an Authorization Response.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum VpToken {
+    /// Presentations submitted against a Presentation Definition, located
+    /// using the `presentation_submission`.
+    Presentations(Vec<Kind<VerifiablePresentation>>),
+
+    /// Presentations keyed by the `id` of the DCQL credential query they
+    /// answer.
+    Keyed(HashMap<String, Vec<Kind<VerifiablePresentation>>>),
 }
 
 /// Authorization Response response object is used to return a `redirect_uri` to
@@ -625,6 +749,29 @@ pub struct Verifier {
     /// ```
     #[serde(skip_serializing_if = "Option::is_none")]
     pub vp_formats: Option<HashMap<Format, VpFormat>>,
+
+    /// JWE [RFC7516] `alg` algorithm [RFC7518] the Verifier requires the
+    /// Wallet to use when encrypting the Authorization Response. When set,
+    /// cross-device requests use the Response Mode "`direct_post.jwt`" and the
+    /// encryption key is published in `jwks`.
+    ///
+    /// [RFC7516]: (https://www.rfc-editor.org/rfc/rfc7516)
+    /// [RFC7518]: (https://www.rfc-editor.org/rfc/rfc7518)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authorization_encrypted_response_alg: Option<String>,
+
+    /// JWE [RFC7516] `enc` algorithm [RFC7518] for encrypting the
+    /// Authorization Response. If `authorization_encrypted_response_alg` is
+    /// set, the default is "`A128CBC-HS256`".
+    ///
+    /// Only "`A128GCM`" and "`A256GCM`" are supported (see [`crate::jwe`]),
+    /// so the Verifier always sets this value, using "`A256GCM`" when not
+    /// configured.
+    ///
+    /// [RFC7516]: (https://www.rfc-editor.org/rfc/rfc7516)
+    /// [RFC7518]: (https://www.rfc-editor.org/rfc/rfc7518)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authorization_encrypted_response_enc: Option<String>,
 }
 
 /// The `OpenID4VCI` specification defines commonly used [Credential Format
@@ -634,9 +781,47 @@ pub struct Verifier {
 /// [Credential Format Profiles]: (https://openid.net/specs/openid-4-verifiable-credential-issuance-1_0.html#name-credential-format-profiles)
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq, Hash)]
 pub enum Format {
-    /// W3C Verifiable Credential.
+    /// W3C Verifiable Credential signed as a JWT, not using JSON-LD.
+    #[serde(rename = "jwt_vc_json")]
+    JwtVcJson,
+
+    /// W3C Verifiable Credential signed as a JWT, using JSON-LD.
+    #[serde(rename = "jwt_vc_json-ld")]
+    JwtVcJsonLd,
+
+    /// W3C Verifiable Credential secured with a Data Integrity proof.
+    #[serde(rename = "ldp_vc")]
+    LdpVc,
+
+    /// W3C Verifiable Presentation signed as a JWT.
     #[serde(rename = "jwt_vp_json")]
     JwtVpJson,
+
+    /// W3C Verifiable Presentation secured with a Data Integrity proof.
+    #[serde(rename = "ldp_vp")]
+    LdpVp,
+
+    /// ISO mDL (ISO/IEC 18013-5) mobile document.
+    #[serde(rename = "mso_mdoc")]
+    MsoMdoc,
+
+    /// IETF SD-JWT based Verifiable Credential.
+    #[serde(rename = "vc+sd-jwt")]
+    VcSdJwt,
+}
+
+impl std::fmt::Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::JwtVcJson => write!(f, "jwt_vc_json"),
+            Self::JwtVcJsonLd => write!(f, "jwt_vc_json-ld"),
+            Self::LdpVc => write!(f, "ldp_vc"),
+            Self::JwtVpJson => write!(f, "jwt_vp_json"),
+            Self::LdpVp => write!(f, "ldp_vp"),
+            Self::MsoMdoc => write!(f, "mso_mdoc"),
+            Self::VcSdJwt => write!(f, "vc+sd-jwt"),
+        }
+    }
 }
 
 /// OAuth 2.0 Authorization Server metadata.
@@ -3,36 +3,40 @@
 //! Types and helpers for implementing a status list using a bitstring. Follows
 //! the specification [Bitstring Status List v1.0](https://www.w3.org/TR/vc-bitstring-status-list/).
 
-use std::io::Write;
+use std::io::{Read, Write};
 
 use anyhow::anyhow;
 use base64ct::{Base64UrlUnpadded, Encoding};
-use bitvec::bits;
-use bitvec::order::Lsb0;
-use bitvec::view::BitView;
+use bitvec::order::Msb0;
+use bitvec::vec::BitVec;
 use chrono::Utc;
+use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 use thiserror::Error;
+use vercre_core::{Kind, Quota};
+use vercre_did::DidResolver;
 use vercre_infosec::Signer;
-use vercre_w3c_vc::model::{CredentialStatus, CredentialSubject, StatusPurpose, VcBuilder};
-use vercre_w3c_vc::proof::{self, Payload, W3cFormat};
+use vercre_w3c_vc::model::{
+    CredentialStatus, CredentialStatusType, CredentialSubject, StatusPurpose, VcBuilder,
+};
+use vercre_w3c_vc::proof::{self, Payload, Verify, W3cFormat};
 
 use crate::config::ListConfig;
 use crate::log::StatusLogEntry;
 use crate::verifier;
 
-// TODO: Configurable.
-// TODO: This is minimum length as per spec. May need to be configurable
-// for data VCs where potentially huge numbers of credentials of a type
-// can be issued. Or we need to use a more sophisticated list sharding.
-// (Currently assumes one list per credential type.) Business requirements
-// for short-lived data credentials may not require status lists anyway and
-// we could just bump up this limit to something large but practical and
-// only support lists up to that length. Alternatively, we can re-use list
-// entries once a credential expires.
-const MAX_ENTRIES: usize = 131_072;
+/// Minimum number of entries in a status list required for herd privacy.
+pub const MIN_ENTRIES: usize = 131_072;
+
+/// Maximum size of a status list, in bits. Larger lists are rejected so that a
+/// compressed list cannot expand without bound when decoded.
+pub const MAX_LIST_BITS: usize = 1 << 27;
+
+/// Multibase prefix for base64url-encoded (no padding) values.
+const MULTIBASE_BASE64URL: &str = "u";
 
 /// Default time-to-live in milliseconds for a status list credential.
 pub const DEFAULT_TTL: u64 = 300_000;
@@ -117,9 +121,9 @@ pub struct ValidationError {
     pub detail: Option<String>,
 }
 
-/// Generates a compressed, encoded bitstring representing the status list for
-/// the given issued credentials and the purpose implied by a list
-/// configuration.
+/// Generates a compressed, encoded bitstring representing a shard of the
+/// status list identified by a list configuration for the given issued
+/// credentials.
 ///
 /// # Errors
 ///
@@ -136,50 +140,435 @@ pub struct ValidationError {
 /// right-most bit in the bitstring.
 ///
 /// Note: This function scans the entire status log presented to construct a
-/// bitstring from scratch which will be inefficient compared to a method for
-/// processing a known update to the status of an individual credential. (A new
-/// credential issued or an update to the status of an existing one).
-//
-// TODO: Provide methods for updating the bitstring incrementally.
+/// bitstring from scratch. Use [`StatusList`] or [`ShardedList`] to maintain
+/// a list incrementally as credentials are issued or their status changes.
 #[allow(clippy::module_name_repetitions)]
-pub fn bitstring(config: &ListConfig, issued: &[StatusLogEntry]) -> anyhow::Result<String> {
-    let bits = bits![mut 0; MAX_ENTRIES];
+pub fn bitstring(
+    config: &ListConfig, shard: usize, issued: &[StatusLogEntry],
+) -> anyhow::Result<String> {
+    let entries = config.entries.unwrap_or(MIN_ENTRIES);
+    let mut list = StatusList::new(entries, config.size)?;
+
     for entry in issued {
         for status in &entry.status {
-            if status.purpose != config.purpose {
+            if status.list != config.list || status.shard != shard {
                 continue;
             }
+            list.set(status.list_index, usize::from(status.value))?;
+        }
+    }
 
-            let position = status.list_index * config.size;
-            if position >= bits.len() {
-                return Err(anyhow!("status index out of range"));
-            }
-            match config.purpose {
-                StatusPurpose::Revocation | StatusPurpose::Suspension => {
-                    bits.set(position, status.value != 0);
-                }
-                StatusPurpose::Message => {
-                    let value = status.value.view_bits::<Lsb0>();
-                    for (i, bit) in value.iter().enumerate() {
-                        bits.set(position + i, *bit);
-                    }
-                }
-            }
+    list.encode()
+}
+
+/// A bitstring status list that can be updated one entry at a time.
+///
+/// Each entry is `status_size` bits wide, with the first entry starting at the
+/// left-most bit of the bitstring. The list serializes to its `statusSize`
+/// and compressed, encoded form.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "EncodedList")]
+#[allow(clippy::module_name_repetitions)]
+pub struct StatusList {
+    bits: BitVec<u8, Msb0>,
+    status_size: usize,
+}
+
+impl StatusList {
+    /// Create an empty list of `entries` statuses, each `status_size` bits
+    /// wide.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the status size is not supported, the list has
+    /// fewer than [`MIN_ENTRIES`] entries, or it is larger than
+    /// [`MAX_LIST_BITS`].
+    pub fn new(entries: usize, status_size: usize) -> anyhow::Result<Self> {
+        check_size(status_size)?;
+        if entries < MIN_ENTRIES {
+            return Err(anyhow!("status list must have at least {MIN_ENTRIES} entries"));
+        }
+        if entries.saturating_mul(status_size) > MAX_LIST_BITS {
+            return Err(anyhow!("status list must not exceed {MAX_LIST_BITS} bits"));
+        }
+        Ok(Self {
+            bits: BitVec::repeat(false, entries * status_size),
+            status_size,
+        })
+    }
+
+    /// Decode a multibase base64url-encoded, GZIP-compressed list (such as the
+    /// `encodedList` of a status list credential).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the list cannot be decoded or decompressed, is larger
+    /// than [`MAX_LIST_BITS`], or the status size is not supported.
+    pub fn decode(encoded: &str, status_size: usize) -> anyhow::Result<Self> {
+        check_size(status_size)?;
+        let bits = expand(encoded)?;
+        Ok(Self { bits, status_size })
+    }
+
+    /// Compress and encode the list as a multibase base64url string.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the list cannot be compressed.
+    pub fn encode(&self) -> anyhow::Result<String> {
+        let mut gz_encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz_encoder.write_all(self.bits.as_raw_slice())?;
+        let compressed = gz_encoder.finish()?;
+
+        let encoded = Base64UrlUnpadded::encode_string(&compressed);
+        Ok(format!("{MULTIBASE_BASE64URL}{encoded}"))
+    }
+
+    /// The number of entries in the list.
+    #[must_use]
+    pub fn entries(&self) -> usize {
+        self.bits.len() / self.status_size
+    }
+
+    /// The number of bits used for each entry.
+    #[must_use]
+    pub const fn status_size(&self) -> usize {
+        self.status_size
+    }
+
+    /// Read the status value at `index`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the index is out of range.
+    pub fn get(&self, index: usize) -> anyhow::Result<usize> {
+        let position = self.position(index)?;
+        let value = self.bits[position..position + self.status_size]
+            .iter()
+            .fold(0, |value, bit| (value << 1) | usize::from(*bit));
+        Ok(value)
+    }
+
+    /// Set the status value at `index`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the index is out of range or the value does not fit
+    /// in the list's status size.
+    pub fn set(&mut self, index: usize, value: usize) -> anyhow::Result<()> {
+        let position = self.position(index)?;
+        if self.status_size < usize::BITS as usize && value >> self.status_size != 0 {
+            return Err(anyhow!(
+                "status value {value:#x} does not fit in {} bits",
+                self.status_size
+            ));
+        }
+        for i in 0..self.status_size {
+            let bit = (value >> (self.status_size - 1 - i)) & 1 == 1;
+            self.bits.set(position + i, bit);
         }
+        Ok(())
+    }
+
+    /// Reset the status value at `index` to 0.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the index is out of range.
+    pub fn clear(&mut self, index: usize) -> anyhow::Result<()> {
+        self.set(index, 0)
+    }
+
+    // Position of the first bit of the entry at `index`.
+    fn position(&self, index: usize) -> anyhow::Result<usize> {
+        if index >= self.entries() {
+            return Err(anyhow!("status list index {index} is out of range"));
+        }
+        Ok(index * self.status_size)
+    }
+}
+
+impl Serialize for StatusList {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::Error as SerdeError;
+
+        let encoded_list = self.encode().map_err(SerdeError::custom)?;
+        EncodedList {
+            status_size: self.status_size,
+            encoded_list,
+        }
+        .serialize(serializer)
+    }
+}
+
+// Serialized form of a `StatusList`.
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EncodedList {
+    status_size: usize,
+    encoded_list: String,
+}
+
+impl TryFrom<EncodedList> for StatusList {
+    type Error = anyhow::Error;
+
+    fn try_from(list: EncodedList) -> anyhow::Result<Self> {
+        Self::decode(&list.encoded_list, list.status_size)
+    }
+}
+
+// Uncompressed serialization of a `StatusList` for storage.
+mod raw {
+    use base64ct::{Base64UrlUnpadded, Encoding};
+    use bitvec::order::Msb0;
+    use bitvec::vec::BitVec;
+    use serde::de::Error as _;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{check_size, StatusList};
+
+    #[derive(Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct RawList {
+        status_size: usize,
+        len: usize,
+        bits: String,
+    }
+
+    pub fn serialize<S: Serializer>(list: &StatusList, serializer: S) -> Result<S::Ok, S::Error> {
+        RawList {
+            status_size: list.status_size,
+            len: list.bits.len(),
+            bits: Base64UrlUnpadded::encode_string(list.bits.as_raw_slice()),
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<StatusList, D::Error> {
+        let raw = RawList::deserialize(deserializer)?;
+        check_size(raw.status_size).map_err(D::Error::custom)?;
+        let bytes = Base64UrlUnpadded::decode_vec(&raw.bits).map_err(D::Error::custom)?;
+        let mut bits = BitVec::<u8, Msb0>::from_vec(bytes);
+        if bits.len() < raw.len {
+            return Err(D::Error::custom("stored status list is truncated"));
+        }
+        bits.truncate(raw.len);
+        Ok(StatusList {
+            bits,
+            status_size: raw.status_size,
+        })
+    }
+}
+
+/// The location of a credential's status in a [`ShardedList`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Position {
+    /// The shard containing the status.
+    pub shard: usize,
+
+    /// The index of the status in the shard.
+    pub index: usize,
+}
+
+/// A status list split across shards of a configured number of entries.
+///
+/// Indexes are allocated from the existing shards in order and a new shard is
+/// started once they are all in use. Each shard is published as a separate
+/// status list credential at [`ListConfig::list_url`].
+///
+/// The list's [`version`](Self::version) is incremented each time it is saved
+/// so that stores can detect concurrent updates.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShardedList {
+    config: ListConfig,
+    shards: Vec<Shard>,
+    #[serde(default)]
+    version: u64,
+}
+
+// A shard holds the status values and a 1-bit list recording which indexes
+// have been allocated, since an allocated index may hold a status of 0.
+//
+// Shards are stored uncompressed: only published lists need the compressed
+// encoding and compressing on every save is wasted work.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+struct Shard {
+    #[serde(with = "raw")]
+    list: StatusList,
+    #[serde(with = "raw")]
+    allocated: StatusList,
+}
+
+impl ShardedList {
+    /// Create a list with a single, empty shard sized by the list
+    /// configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the configured size or number of entries is not
+    /// supported.
+    pub fn new(config: &ListConfig) -> anyhow::Result<Self> {
+        let mut list = Self {
+            config: config.clone(),
+            shards: vec![],
+            version: 0,
+        };
+        list.add_shard()?;
+        Ok(list)
+    }
+
+    /// Allocate an unused position, starting a new shard if all existing
+    /// shards are full.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a new shard cannot be created.
+    pub fn allocate(&mut self) -> anyhow::Result<Position> {
+        let free = self.shards.iter().enumerate().find_map(|(shard, s)| {
+            s.allocated.bits.first_zero().map(|index| Position { shard, index })
+        });
+        let position = match free {
+            Some(position) => position,
+            None => Position {
+                shard: self.add_shard()?,
+                index: 0,
+            },
+        };
+
+        self.shards[position.shard].allocated.set(position.index, 1)?;
+        Ok(position)
+    }
+
+    /// Allocate an unused position at random from the first shard with free
+    /// entries, starting a new shard if all existing shards are full.
+    ///
+    /// Random allocation prevents a credential's position in the list from
+    /// revealing the order in which credentials were issued.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a new shard cannot be created.
+    pub fn allocate_random(&mut self) -> anyhow::Result<Position> {
+        let free = self.shards.iter().enumerate().find_map(|(shard, s)| {
+            let bits = &s.allocated.bits;
+            bits.first_zero()?;
+
+            // take the first free entry at or after a random index, wrapping
+            // around to the start of the shard
+            let start = rand::thread_rng().gen_range(0..bits.len());
+            let index = bits[start..]
+                .first_zero()
+                .map(|offset| start + offset)
+                .or_else(|| bits[..start].first_zero())?;
+            Some(Position { shard, index })
+        });
+        let position = match free {
+            Some(position) => position,
+            None => Position {
+                shard: self.add_shard()?,
+                index: rand::thread_rng().gen_range(0..self.entries()),
+            },
+        };
+
+        self.shards[position.shard].allocated.set(position.index, 1)?;
+        Ok(position)
+    }
+
+    /// Returns `true` if the position has been allocated.
+    #[must_use]
+    pub fn is_allocated(&self, position: Position) -> bool {
+        self.shards
+            .get(position.shard)
+            .is_some_and(|s| s.allocated.get(position.index).is_ok_and(|v| v == 1))
+    }
+
+    /// Release an allocated position so it can be re-used, for example once
+    /// the credential it was allocated to has expired. The status value is
+    /// reset to 0.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the position is out of range.
+    pub fn release(&mut self, position: Position) -> anyhow::Result<()> {
+        let shard = self.shard_mut(position.shard)?;
+        shard.list.clear(position.index)?;
+        shard.allocated.clear(position.index)
+    }
+
+    /// Read the status value at a position.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the position is out of range.
+    pub fn get(&self, position: Position) -> anyhow::Result<usize> {
+        let Some(shard) = self.shards.get(position.shard) else {
+            return Err(anyhow!("status list shard {} does not exist", position.shard));
+        };
+        shard.list.get(position.index)
+    }
+
+    /// Set the status value at a position.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the position is out of range or the value does not
+    /// fit in the list's status size.
+    pub fn set(&mut self, position: Position, value: usize) -> anyhow::Result<()> {
+        self.shard_mut(position.shard)?.list.set(position.index, value)
+    }
+
+    /// The status list for a shard.
+    #[must_use]
+    pub fn shard(&self, shard: usize) -> Option<&StatusList> {
+        self.shards.get(shard).map(|s| &s.list)
+    }
+
+    /// The number of shards in the list.
+    #[must_use]
+    pub fn shards(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// The configuration the list was created with.
+    #[must_use]
+    pub const fn config(&self) -> &ListConfig {
+        &self.config
     }
 
-    let uncompressed = bits.into_iter().map(|b| if *b { '1' } else { '0' }).collect::<String>();
+    /// The number of times the list has been saved.
+    #[must_use]
+    pub const fn version(&self) -> u64 {
+        self.version
+    }
 
-    let mut gz_encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
-    gz_encoder.write_all(uncompressed.as_bytes())?;
-    let compressed = gz_encoder.finish()?;
+    // Mark the list as a new version, ready to be saved.
+    pub(crate) fn next_version(&mut self) {
+        self.version += 1;
+    }
 
-    let encoded = Base64UrlUnpadded::encode_string(&compressed);
+    fn entries(&self) -> usize {
+        self.config.entries.unwrap_or(MIN_ENTRIES)
+    }
 
-    Ok(encoded)
+    fn add_shard(&mut self) -> anyhow::Result<usize> {
+        let entries = self.entries();
+        self.shards.push(Shard {
+            list: StatusList::new(entries, self.config.size)?,
+            allocated: StatusList::new(entries, 1)?,
+        });
+        Ok(self.shards.len() - 1)
+    }
+
+    fn shard_mut(&mut self, shard: usize) -> anyhow::Result<&mut Shard> {
+        self.shards
+            .get_mut(shard)
+            .ok_or_else(|| anyhow!("status list shard {shard} does not exist"))
+    }
 }
 
-/// Generates a bitstring status list credential for the given status type.
+/// Generates a bitstring status list credential for the given status type and
+/// shard of the list.
 ///
 /// The credential is suitable for publishing on an endpoint for verifiers to
 /// check.
@@ -196,18 +585,14 @@ pub fn bitstring(config: &ListConfig, issued: &[StatusLogEntry]) -> anyhow::Resu
 /// * verifiable credential building errors.
 /// * signing errors.
 pub async fn credential(
-    credential_issuer: &str, config: &ListConfig, status_list_base_url: &str, bitstring: &str,
-    ttl: Option<u64>, signer: impl Signer,
+    credential_issuer: &str, config: &ListConfig, shard: usize, status_list_base_url: &str,
+    bitstring: &str, ttl: Option<u64>, signer: impl Signer,
 ) -> anyhow::Result<String> {
-    let mut base_url = status_list_base_url.to_string();
-    if !base_url.ends_with('/') {
-        base_url.push('/');
-    }
-    let id = format!("{base_url}/{}", config.list);
+    let id = config.list_url(status_list_base_url, shard);
 
     let mut claims = Map::new();
     claims.insert("type".into(), Value::String("BitstringStatusList".into()));
-    claims.insert("purpose".into(), Value::String(config.purpose.to_string()));
+    claims.insert("statusPurpose".into(), Value::String(config.purpose.to_string()));
     claims.insert("encodedList".into(), Value::String(bitstring.into()));
 
     let cache_time = ttl.unwrap_or(DEFAULT_TTL);
@@ -216,6 +601,7 @@ pub async fn credential(
     let issued_at = Utc::now().timestamp();
 
     let vc = VcBuilder::new()
+        .add_context(Kind::String("https://www.w3.org/ns/credentials/v2".into()))
         .id(id.clone())
         .add_type("BitstringStatusListCredential")
         .issuer(credential_issuer)
@@ -229,78 +615,492 @@ pub async fn credential(
     Ok(jwt)
 }
 
+/// The result of validating a credential's status against a bitstring status
+/// list.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[allow(clippy::module_name_repetitions)]
+pub struct ValidationResult {
+    /// The status value read from the status list.
+    pub status: usize,
+
+    /// The purpose of the status list.
+    pub purpose: StatusPurpose,
+
+    /// `true` if the status value is 0 (the credential is not revoked,
+    /// suspended, etc.), `false` otherwise.
+    pub valid: bool,
+
+    /// The message corresponding to the status value for
+    /// [`StatusPurpose::Message`] lists.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
 /// Validates a credential from the status information contained inside it.
 ///
 /// Uses a provider to retrieve the status list, which is assumed to be in
-/// bitstring format.
+/// bitstring format, and to resolve the key used to verify its proof. The
+/// status list must be issued by `issuer`, the issuer of the credential being
+/// checked.
 ///
 /// # Errors
 ///
 /// Will return a specific `ValidationError` if the status list is not resolved
 /// or processing the status list fails for the given `CredentialStatus`.
-pub fn validate(
-    _resolver: &impl verifier::Status, _status: &CredentialStatus,
-) -> Result<bool, Error> {
-    // The following process, or one generating the exact output, MUST be followed
-    // when validating a verifiable credential that is contained in a
-    // BitstringStatusListCredential. The algorithm takes a status list verifiable
-    // credential as input and either throws an error or returns a status list
-    // credential as output.
-
-    // Let credentialToValidate be a verifiable credential containing a
-    // credentialStatus entry that is a BitstringStatusListEntry.
-    // Let minimumNumberOfEntries be 131,072 unless a different lower bound is
-    // established by a specific ecosystem specification. Let status purpose be
-    // the value of statusPurpose in the credentialStatus entry in the
-    // credentialToValidate. Dereference the statusListCredential URL, and
-    // ensure that all proofs verify successfully. If the dereference fails, raise a
-    // STATUS_RETRIEVAL_ERROR. If any of the proof verifications fail, raise a
-    // STATUS_VERIFICATION_ERROR. Verify that the status purpose is equal to a
-    // statusPurpose value in the statusListCredential. Note: The
-    // statusListCredential might contain multiple status purposes in a single list.
-    // If the values are not equal, raise a STATUS_VERIFICATION_ERROR.
-    // Let compressed bitstring be the value of the encodedList property of the
-    // BitstringStatusListCredential. Let credentialIndex be the value of the
-    // statusListIndex property of the BitstringStatusListEntry. Generate a
-    // revocation bitstring by passing compressed bitstring to the Bitstring
-    // Expansion Algorithm. If the length of the revocation bitstring divided by
-    // statusSize is less than minimumNumberOfEntries, raise a
-    // STATUS_LIST_LENGTH_ERROR. Let status be the value in the bitstring at the
-    // position indicated by the credentialIndex multiplied by the size. If the
-    // credentialIndex multiplied by the size is a value outside of the range of the
-    // bitstring, a RANGE_ERROR MUST be raised. Let result be an empty map.
-    // Set the status key in result to status, and set the purpose key in result to
-    // the value of statusPurpose. If status is 0, set the valid key in result
-    // to true; otherwise, set it to false. If the statusPurpose is message, set
-    // the message key in result to the corresponding message of the value as
-    // indicated in the statusMessages array. Return result.
-    // When a statusListCredential URL is dereferenced, server implementations MAY
-    // provide a mechanism to dereference the status list as of a particular point
-    // in time. When an issuer provides such a mechanism, it enables a verifier to
-    // determine changes in status to a precision chosen by the issuer, such as
-    // hourly, daily, or weekly. If such a feature is supported, and if query
-    // parameters are supported by the URL scheme, then the name of the query
-    // parameter MUST be timestamp and the value MUST be a valid URL-encoded
-    // [XMLSCHEMA11-2] dateTimeStamp string value. The result of dereferencing such
-    // a timestamp-parameterized URL MUST be either a status list credential
-    // containing the status list as it existed at the given point in time, or a
-    // STATUS_RETRIEVAL_ERROR. If the result is an error, implementations MAY
-    // attempt the retrieval again with a different timestamp value, or without a
-    // timestamp value, as long as the verifier's validation rules permit such an
-    // action.
-
-    // Verifiers SHOULD cache the retrieved status list and SHOULD use proxies or
-    // other mechanisms, such as Oblivious HTTP, that hide retrieval behavior from
-    // the issuer.
-
-    // Note: Issuer validation is use case dependent
-    // It is expected that a verifier will ensure that it trusts the issuer of a
-    // verifiable credential, as well as the issuer of the associated
-    // BitstringStatusListCredential, before using the information contained in
-    // either credential for further decision making purposes. Implementers are
-    // advised that the issuers of these credential might differ, such as when the
-    // original issuer of the verifiable credential does not maintain a record of
-    // its validity.
-
-    todo!()
+pub async fn validate(
+    resolver: &(impl verifier::Status + DidResolver), status: &CredentialStatus, issuer: &str,
+) -> Result<ValidationResult, Error> {
+    let CredentialStatusType::Bitstring(entry) = &status.credential_status_type;
+
+    // dereference the status list credential and verify its proof
+    let list_vc = verifier::Status::status_list(resolver, &entry.status_list_credential)
+        .await
+        .map_err(|e| Error::Retrieval(e.to_string()))?;
+    let Payload::Vc { vc, .. } = proof::verify(Verify::Vc(&list_vc), resolver)
+        .await
+        .map_err(|e| Error::Verification(e.to_string()))?
+    else {
+        return Err(Error::Verification("proof payload is not a credential".into()));
+    };
+    let is_list = match &vc.type_ {
+        Quota::One(type_) => type_ == "BitstringStatusListCredential",
+        Quota::Many(types) => types.iter().any(|t| t == "BitstringStatusListCredential"),
+    };
+    if !is_list {
+        return Err(Error::Verification("not a BitstringStatusListCredential".into()));
+    }
+    let list_issuer = match &vc.issuer {
+        Kind::String(id) => id,
+        Kind::Object(issuer) => &issuer.id,
+    };
+    if list_issuer != issuer {
+        return Err(Error::Verification(format!("status list is not issued by {issuer}")));
+    }
+    if vc.valid_until.is_some_and(|exp| exp < Utc::now()) {
+        return Err(Error::Verification("status list credential has expired".into()));
+    }
+    let Quota::One(subject) = &vc.credential_subject else {
+        return Err(Error::Verification("expected a single credential subject".into()));
+    };
+
+    // the list may contain multiple purposes
+    let purposes = match subject.claims.get("statusPurpose") {
+        Some(Value::String(purpose)) => vec![purpose.as_str()],
+        Some(Value::Array(purposes)) => purposes.iter().filter_map(Value::as_str).collect(),
+        _ => return Err(Error::Verification("statusPurpose not found".into())),
+    };
+    if !purposes.contains(&entry.status_purpose.to_string().as_str()) {
+        return Err(Error::Verification(format!(
+            "status list does not have purpose {}",
+            entry.status_purpose
+        )));
+    }
+
+    let Some(Value::String(encoded)) = subject.claims.get("encodedList") else {
+        return Err(Error::Verification("encodedList not found".into()));
+    };
+    let size = entry.status_size.unwrap_or(1);
+    let value = status_value(encoded, entry.status_list_index, size)?;
+
+    let message = if entry.status_purpose == StatusPurpose::Message {
+        let messages = entry.status_message.as_deref().unwrap_or_default();
+        let Some(message) = messages.iter().find(|m| parse_status(&m.status) == Some(value)) else {
+            return Err(Error::Verification(format!("no message for status {value:#x}")));
+        };
+        Some(message.message.clone())
+    } else {
+        None
+    };
+
+    Ok(ValidationResult {
+        status: value,
+        purpose: entry.status_purpose.clone(),
+        valid: value == 0,
+        message,
+    })
+}
+
+// Expand the encoded list and read the `size` bits at the credential's index.
+fn status_value(encoded: &str, index: usize, size: usize) -> Result<usize, Error> {
+    let list = StatusList::decode(encoded, size).map_err(|e| Error::Verification(e.to_string()))?;
+    if list.entries() < MIN_ENTRIES {
+        return Err(Error::ListLength(format!("status list has fewer than {MIN_ENTRIES} entries")));
+    }
+    list.get(index).map_err(|e| Error::Range(e.to_string()))
+}
+
+// Entries must be at least one bit wide and fit in a `usize` value.
+fn check_size(status_size: usize) -> anyhow::Result<()> {
+    if status_size == 0 || status_size > usize::BITS as usize {
+        return Err(anyhow!("invalid status size {status_size}"));
+    }
+    Ok(())
+}
+
+// The Bitstring Expansion Algorithm: decode the multibase base64url-encoded
+// list and decompress it.
+fn expand(encoded: &str) -> anyhow::Result<BitVec<u8, Msb0>> {
+    let Some(encoded) = encoded.strip_prefix(MULTIBASE_BASE64URL) else {
+        return Err(anyhow!("encodedList is not multibase base64url"));
+    };
+    let compressed = Base64UrlUnpadded::decode_vec(encoded)
+        .map_err(|e| anyhow!("issue decoding encodedList: {e}"))?;
+
+    // read one byte more than the largest list allowed to detect oversized
+    // lists without decompressing them in full
+    let limit = MAX_LIST_BITS / 8;
+    let mut uncompressed = Vec::new();
+    GzDecoder::new(compressed.as_slice())
+        .take(limit as u64 + 1)
+        .read_to_end(&mut uncompressed)
+        .map_err(|e| anyhow!("issue decompressing encodedList: {e}"))?;
+    if uncompressed.len() > limit {
+        return Err(anyhow!("encodedList exceeds {MAX_LIST_BITS} bits"));
+    }
+
+    Ok(BitVec::from_vec(uncompressed))
+}
+
+// Parse a status message's hexadecimal status value, e.g. "0x1".
+fn parse_status(status: &str) -> Option<usize> {
+    let hex = status.strip_prefix("0x").unwrap_or(status);
+    usize::from_str_radix(hex, 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, TimeDelta};
+    use ed25519_dalek::{Signer as _, SigningKey};
+    use serde_json::json;
+    use vercre_infosec::Algorithm;
+    use vercre_w3c_vc::model::{Bitstring, VerifiableCredential};
+
+    use super::*;
+    use crate::log::StatusValue;
+
+    const ISSUER: &str = "http://vercre.io";
+    const LIST_URL: &str = "http://vercre.io/status/1/0";
+    const ISSUER_DID: &str = "did:web:demo.credibil.io";
+    const ISSUER_SECRET: &str = "cCxmHfFfIJvP74oNKjAuRC3zYoDMo0pFsAs19yKMowY";
+
+    // Index of the revoked credential in published status lists.
+    const REVOKED: usize = 7;
+
+    // Signs status list credentials with the issuer's key.
+    struct Issuer;
+
+    impl Issuer {
+        fn signing_key() -> anyhow::Result<SigningKey> {
+            let secret = Base64UrlUnpadded::decode_vec(ISSUER_SECRET)?;
+            let secret: [u8; 32] = secret.try_into().map_err(|_| anyhow!("invalid secret key"))?;
+            Ok(SigningKey::from_bytes(&secret))
+        }
+    }
+
+    impl Signer for Issuer {
+        async fn try_sign(&self, msg: &[u8]) -> anyhow::Result<Vec<u8>> {
+            Ok(Self::signing_key()?.sign(msg).to_bytes().to_vec())
+        }
+
+        async fn public_key(&self) -> anyhow::Result<Vec<u8>> {
+            Ok(Self::signing_key()?.verifying_key().as_bytes().to_vec())
+        }
+
+        fn algorithm(&self) -> Algorithm {
+            Algorithm::EdDSA
+        }
+
+        fn verification_method(&self) -> String {
+            format!("{ISSUER_DID}#key-0")
+        }
+    }
+
+    // A verifier retrieving a single published status list credential and
+    // resolving the issuer's DID.
+    struct Resolver {
+        list: String,
+    }
+
+    impl verifier::Status for Resolver {
+        async fn status_list(&self, url: &str) -> anyhow::Result<Kind<VerifiableCredential>> {
+            if url != LIST_URL {
+                return Err(anyhow!("status list {url} not found"));
+            }
+            Ok(Kind::String(self.list.clone()))
+        }
+    }
+
+    impl DidResolver for Resolver {
+        async fn resolve(&self, _url: &str) -> anyhow::Result<vercre_did::Document> {
+            let document = json!({
+                "@context": ["https://www.w3.org/ns/did/v1"],
+                "id": ISSUER_DID,
+                "verificationMethod": [{
+                    "id": format!("{ISSUER_DID}#key-0"),
+                    "type": "Multikey",
+                    "controller": ISSUER_DID,
+                    "publicKeyMultibase": "z6Mkr1NtupNezZtcUAMxJ79HPex6ZTR9RnGh8xfV257ZQdss"
+                }],
+                "assertionMethod": [format!("{ISSUER_DID}#key-0")]
+            });
+            Ok(serde_json::from_value(document)?)
+        }
+    }
+
+    // Publish a revocation list, issued by `issuer`, with the credential at
+    // `REVOKED` revoked.
+    async fn publish(issuer: &str, valid_until: Option<DateTime<Utc>>) -> Resolver {
+        let mut list = StatusList::new(MIN_ENTRIES, 1).expect("should create");
+        list.set(REVOKED, 1).expect("should set");
+
+        let mut claims = Map::new();
+        claims.insert("type".into(), json!("BitstringStatusList"));
+        claims.insert("statusPurpose".into(), json!("revocation"));
+        claims.insert("encodedList".into(), json!(list.encode().expect("should encode")));
+        let mut vc = VcBuilder::new()
+            .add_context(Kind::String("https://www.w3.org/ns/credentials/v2".into()))
+            .id(LIST_URL)
+            .add_type("BitstringStatusListCredential")
+            .issuer(issuer)
+            .add_subject(CredentialSubject {
+                id: Some(format!("{LIST_URL}#list")),
+                claims,
+            })
+            .build()
+            .expect("should build");
+        vc.valid_until = valid_until;
+
+        let payload = Payload::Vc {
+            vc,
+            issued_at: Utc::now().timestamp(),
+        };
+        let list = proof::create(W3cFormat::JwtVcJson, payload, Issuer).await.expect("should sign");
+        Resolver { list }
+    }
+
+    fn status_entry(purpose: StatusPurpose, index: usize) -> CredentialStatus {
+        CredentialStatus {
+            id: None,
+            credential_status_type: CredentialStatusType::Bitstring(Bitstring {
+                status_purpose: purpose,
+                status_list_index: index,
+                status_list_credential: LIST_URL.into(),
+                status_size: None,
+                status_message: None,
+                status_reference: None,
+            }),
+        }
+    }
+
+    fn encode(bits: &BitVec<u8, Msb0>) -> String {
+        let mut gz_encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz_encoder.write_all(bits.as_raw_slice()).expect("should compress");
+        let compressed = gz_encoder.finish().expect("should compress");
+        format!("{MULTIBASE_BASE64URL}{}", Base64UrlUnpadded::encode_string(&compressed))
+    }
+
+    #[test]
+    fn read_status() {
+        let mut bits = BitVec::<u8, Msb0>::repeat(false, MIN_ENTRIES * 2);
+        bits.set(7, true);
+        bits.set(20, true);
+        bits.set(21, true);
+        let encoded = encode(&bits);
+
+        assert_eq!(status_value(&encoded, 6, 1).expect("should read"), 0);
+        assert_eq!(status_value(&encoded, 7, 1).expect("should read"), 1);
+        assert_eq!(status_value(&encoded, 10, 2).expect("should read"), 3);
+        assert_eq!(status_value(&encoded, 9, 2).expect("should read"), 0);
+    }
+
+    #[test]
+    fn list_errors() {
+        let bits = BitVec::<u8, Msb0>::repeat(false, MIN_ENTRIES);
+        let encoded = encode(&bits);
+
+        let Err(Error::Range(_)) = status_value(&encoded, MIN_ENTRIES, 1) else {
+            panic!("should be a range error");
+        };
+        let Err(Error::ListLength(_)) = status_value(&encoded, 0, 2) else {
+            panic!("should be a list length error");
+        };
+        let Err(Error::Verification(_)) = status_value(&encoded[1..], 0, 1) else {
+            panic!("should be a verification error");
+        };
+
+        // a list expanding beyond the maximum size is not decompressed
+        let bits = BitVec::<u8, Msb0>::repeat(false, MAX_LIST_BITS + 8);
+        let encoded = encode(&bits);
+        let Err(Error::Verification(e)) = status_value(&encoded, 0, 1) else {
+            panic!("should be a verification error");
+        };
+        assert!(e.contains("exceeds"));
+    }
+
+    #[tokio::test]
+    async fn status_list_not_supported() {
+        // a verifier using the default `Status` implementation
+        struct Unsupported;
+        impl verifier::Status for Unsupported {}
+        impl DidResolver for Unsupported {
+            async fn resolve(&self, _url: &str) -> anyhow::Result<vercre_did::Document> {
+                Err(anyhow!("not supported"))
+            }
+        }
+
+        let status = status_entry(StatusPurpose::Revocation, REVOKED);
+        let Err(Error::Retrieval(_)) = validate(&Unsupported, &status, ISSUER).await else {
+            panic!("should fail with retrieval error");
+        };
+    }
+
+    #[tokio::test]
+    async fn validate_status() {
+        let resolver = publish(ISSUER, None).await;
+
+        // the credential's bit is set
+        let status = status_entry(StatusPurpose::Revocation, REVOKED);
+        let result = validate(&resolver, &status, ISSUER).await.expect("should validate");
+        assert_eq!(result.status, 1);
+        assert_eq!(result.purpose, StatusPurpose::Revocation);
+        assert!(!result.valid);
+
+        // the credential's bit is clear
+        let status = status_entry(StatusPurpose::Revocation, REVOKED + 1);
+        let result = validate(&resolver, &status, ISSUER).await.expect("should validate");
+        assert_eq!(result.status, 0);
+        assert!(result.valid);
+    }
+
+    #[tokio::test]
+    async fn wrong_purpose() {
+        let resolver = publish(ISSUER, None).await;
+
+        let status = status_entry(StatusPurpose::Suspension, REVOKED);
+        let Err(Error::Verification(e)) = validate(&resolver, &status, ISSUER).await else {
+            panic!("should fail with verification error");
+        };
+        assert_eq!(e, "status list does not have purpose suspension");
+    }
+
+    #[tokio::test]
+    async fn expired_list() {
+        let resolver = publish(ISSUER, Some(Utc::now() - TimeDelta::days(1))).await;
+
+        let status = status_entry(StatusPurpose::Revocation, REVOKED);
+        let Err(Error::Verification(_)) = validate(&resolver, &status, ISSUER).await else {
+            panic!("should fail with verification error");
+        };
+    }
+
+    #[tokio::test]
+    async fn different_issuer() {
+        // a list reporting the credential as not revoked, signed by a key the
+        // verifier can resolve but issued by someone else
+        let resolver = publish("http://attacker.io", None).await;
+
+        let status = status_entry(StatusPurpose::Revocation, REVOKED + 1);
+        let Err(Error::Verification(e)) = validate(&resolver, &status, ISSUER).await else {
+            panic!("should fail with verification error");
+        };
+        assert_eq!(e, format!("status list is not issued by {ISSUER}"));
+    }
+
+    #[test]
+    fn update_list() {
+        let mut list = StatusList::new(MIN_ENTRIES, 2).expect("should create");
+        list.set(5, 3).expect("should set");
+        list.set(6, 1).expect("should set");
+        assert!(list.set(7, 4).is_err());
+        assert!(list.set(MIN_ENTRIES, 1).is_err());
+
+        let encoded = list.encode().expect("should encode");
+        assert_eq!(status_value(&encoded, 5, 2).expect("should read"), 3);
+
+        let mut decoded = StatusList::decode(&encoded, 2).expect("should decode");
+        assert_eq!(decoded, list);
+        assert_eq!(decoded.get(6).expect("should get"), 1);
+
+        decoded.clear(5).expect("should clear");
+        assert_eq!(decoded.get(5).expect("should get"), 0);
+        assert_eq!(decoded.entries(), MIN_ENTRIES);
+
+        assert!(StatusList::new(MIN_ENTRIES - 1, 1).is_err());
+        assert!(StatusList::new(MIN_ENTRIES, 0).is_err());
+    }
+
+    #[test]
+    fn shard_list() {
+        let config = ListConfig {
+            purpose: StatusPurpose::Revocation,
+            list: 1,
+            size: 1,
+            messages: None,
+            reference: None,
+            entries: None,
+        };
+        let mut list = ShardedList::new(&config).expect("should create");
+        assert_eq!(list.allocate().expect("should allocate"), Position { shard: 0, index: 0 });
+        assert_eq!(list.allocate().expect("should allocate"), Position { shard: 0, index: 1 });
+
+        // a new shard is started once the first is full
+        list.shards[0].allocated.bits.fill(true);
+        let position = list.allocate().expect("should allocate");
+        assert_eq!(position, Position { shard: 1, index: 0 });
+        assert_eq!(list.shards(), 2);
+
+        list.set(position, 1).expect("should set");
+        assert_eq!(list.get(position).expect("should get"), 1);
+        assert!(list.set(Position { shard: 2, index: 0 }, 1).is_err());
+
+        let random = list.allocate_random().expect("should allocate");
+        assert_eq!(random.shard, 1);
+        assert!(list.is_allocated(random));
+
+        // released positions are reset and re-used
+        let released = Position { shard: 0, index: 7 };
+        list.set(released, 1).expect("should set");
+        list.release(released).expect("should release");
+        assert_eq!(list.get(released).expect("should get"), 0);
+        assert_eq!(list.allocate().expect("should allocate"), released);
+
+        let ser = serde_json::to_value(&list).expect("should serialize");
+        let de: ShardedList = serde_json::from_value(ser).expect("should deserialize");
+        assert_eq!(de, list);
+    }
+
+    #[test]
+    fn bitstring_from_log() {
+        let config = ListConfig {
+            purpose: StatusPurpose::Revocation,
+            list: 1,
+            size: 1,
+            messages: None,
+            reference: None,
+            entries: None,
+        };
+        let status = |shard, list_index| StatusValue {
+            purpose: StatusPurpose::Revocation,
+            list: 1,
+            shard,
+            list_index,
+            value: 1,
+        };
+        let log = vec![StatusLogEntry {
+            credential_id: "credential".into(),
+            subject_id: "subject".into(),
+            status: vec![status(0, 3), status(1, 4)],
+        }];
+
+        let encoded = bitstring(&config, 1, &log).expect("should encode");
+        assert_eq!(status_value(&encoded, 3, 1).expect("should read"), 0);
+        assert_eq!(status_value(&encoded, 4, 1).expect("should read"), 1);
+    }
+
+    #[test]
+    fn parse_message_status() {
+        assert_eq!(parse_status("0x0"), Some(0));
+        assert_eq!(parse_status("0x1a"), Some(26));
+        assert_eq!(parse_status("invalid"), None);
+    }
 }
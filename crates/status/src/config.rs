@@ -6,7 +6,7 @@ use serde::{Deserialize, Serialize};
 use vercre_w3c_vc::model::{StatusMessage, StatusPurpose};
 
 /// Configuration for a status list for a credential.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
 #[allow(clippy::module_name_repetitions)]
 pub struct ListConfig {
     /// Type of status
@@ -32,10 +32,28 @@ pub struct ListConfig {
     /// URL to reference information for the status.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reference: Option<String>,
+
+    /// Number of entries in each shard of the list.
+    ///
+    /// A new shard is started when all entries in the current shards have been
+    /// allocated. Defaults to the minimum list length required for herd
+    /// privacy ([`crate::bitstring::MIN_ENTRIES`]).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entries: Option<usize>,
+}
+
+impl ListConfig {
+    /// The URL a shard of the list is published at, relative to the base URL
+    /// for status lists.
+    #[must_use]
+    pub fn list_url(&self, status_list_base_url: &str, shard: usize) -> String {
+        let base_url = status_list_base_url.trim_end_matches('/');
+        format!("{base_url}/{}/{shard}", self.list)
+    }
 }
 
 /// Configuration for the possible statuses a credential type can have.
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
 #[allow(clippy::module_name_repetitions)]
 pub struct CredentialStatusConfig {
     /// Credential type.
@@ -5,21 +5,406 @@
 
 use std::future::Future;
 
+use anyhow::anyhow;
 pub use vercre_core::Quota;
 pub use vercre_w3c_vc::model::CredentialStatus;
+use vercre_w3c_vc::model::{Bitstring, CredentialStatusType};
 
+use crate::bitstring::Position;
+pub use crate::bitstring::ShardedList;
+pub use crate::config::{CredentialStatusConfig, ListConfig};
+pub use crate::log::StatusLogEntry;
+use crate::log::StatusValue;
 use crate::provider;
 
+// Number of times an update to a status list is attempted when it conflicts
+// with concurrent updates.
+const MAX_UPDATE_ATTEMPTS: usize = 10;
+
 /// The `Status` trait specifies how status information can be looked up for a
 /// credential.
 pub trait Status: Send + Sync {
     /// Returns information on how to look up the status of a credential.
     ///
     /// A default implementation is provided that just returns `None` for cases
-    /// where the issuer will not be providing a status endpoint.
+    /// where the issuer will not be providing a status endpoint or relies on
+    /// [`allocate`] to assign status list entries.
     fn status(
         &self, _subject_id: &str, _credential_identifier: &str,
     ) -> impl Future<Output = provider::Result<Option<Quota<CredentialStatus>>>> + Send {
         async { Ok(None) }
     }
+
+    /// Returns the status lists configured for a credential type.
+    ///
+    /// The default implementation returns `None`, meaning no status list
+    /// entries are allocated for the credential type.
+    fn status_config(
+        &self, _credential_type: &str,
+    ) -> impl Future<Output = provider::Result<Option<CredentialStatusConfig>>> + Send {
+        async { Ok(None) }
+    }
+
+    /// Returns the current state of a status list, or `None` if no entries
+    /// have been allocated from the list.
+    fn sharded_list(
+        &self, _list: usize,
+    ) -> impl Future<Output = provider::Result<Option<ShardedList>>> + Send {
+        async { Ok(None) }
+    }
+
+    /// Saves the state of a status list, provided the stored list is still at
+    /// `version` (or, when `version` is `None`, that no list has been saved).
+    ///
+    /// Returns `false` without saving if the list was changed in the meantime.
+    /// The check and write must be atomic (for example, a conditional update
+    /// on the list's version) so concurrent issuances are never allocated the
+    /// same entry.
+    fn put_sharded_list(
+        &self, _list: usize, _version: Option<u64>, _sharded_list: &ShardedList,
+    ) -> impl Future<Output = provider::Result<bool>> + Send {
+        async { Err(anyhow!("saving status lists is not supported")) }
+    }
+
+    /// Records the status list entries allocated to a credential, replacing
+    /// any existing entry for the credential.
+    fn put_log_entry(
+        &self, _entry: &StatusLogEntry,
+    ) -> impl Future<Output = provider::Result<()>> + Send {
+        async { Err(anyhow!("saving the status log is not supported")) }
+    }
+
+    /// Returns the status log entry for a credential, or `None` if no status
+    /// list entries were allocated to it.
+    fn log_entry(
+        &self, _credential_id: &str,
+    ) -> impl Future<Output = provider::Result<Option<StatusLogEntry>>> + Send {
+        async { Ok(None) }
+    }
+
+    /// Returns the most recently signed status list credential for a shard of
+    /// a list, or `None` if the shard has not yet been published.
+    fn status_list_credential(
+        &self, _list: usize, _shard: usize,
+    ) -> impl Future<Output = provider::Result<Option<String>>> + Send {
+        async { Ok(None) }
+    }
+
+    /// Saves a signed status list credential for a shard of a list.
+    fn put_status_list_credential(
+        &self, _list: usize, _shard: usize, _credential: &str,
+    ) -> impl Future<Output = provider::Result<()>> + Send {
+        async { Err(anyhow!("saving status list credentials is not supported")) }
+    }
+}
+
+/// Allocates a random, unused entry in each of the status lists configured for
+/// a credential type and records them in the status log.
+///
+/// Returns the `BitstringStatusListEntry` objects to embed in the credential,
+/// or `None` if no status lists are configured for the credential type.
+///
+/// Lists are published at [`crate::config::ListConfig::list_url`] relative to
+/// `status_list_base_url`. If the status log can't be updated, the allocated
+/// entries are released so they aren't lost.
+///
+/// # Errors
+///
+/// Returns an error if the status list or log cannot be retrieved or updated.
+pub async fn allocate(
+    provider: &impl Status, credential_type: &str, credential_id: &str, subject_id: &str,
+    status_list_base_url: &str,
+) -> provider::Result<Option<Quota<CredentialStatus>>> {
+    let Some(config) = provider.status_config(credential_type).await? else {
+        return Ok(None);
+    };
+    let Some(lists) = config.status_list.filter(|lists| !lists.is_empty()) else {
+        return Ok(None);
+    };
+
+    let mut allocated = vec![];
+    let result = match allocate_entries(provider, &lists, &mut allocated).await {
+        Ok(values) => {
+            provider
+                .put_log_entry(&StatusLogEntry {
+                    credential_id: credential_id.into(),
+                    subject_id: subject_id.into(),
+                    status: values,
+                })
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        // release entries allocated before the failure, on a best effort basis
+        // as the original error is the one reported
+        for (list_config, position) in allocated {
+            let release = |sharded: &mut ShardedList| sharded.release(position);
+            let _ = update_list(provider, list_config.list, None, release).await;
+        }
+        return Err(e);
+    }
+
+    let mut statuses = allocated
+        .into_iter()
+        .map(|(list_config, position)| {
+            let url = list_config.list_url(status_list_base_url, position.shard);
+            CredentialStatus {
+                id: Some(format!("{url}#{}", position.index)),
+                credential_status_type: CredentialStatusType::Bitstring(Bitstring {
+                    status_purpose: list_config.purpose.clone(),
+                    status_list_index: position.index,
+                    status_list_credential: url,
+                    status_size: (list_config.size > 1).then_some(list_config.size),
+                    status_message: list_config.messages.clone(),
+                    status_reference: list_config.reference.clone(),
+                }),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if statuses.len() == 1 {
+        return Ok(statuses.pop().map(Quota::One));
+    }
+    Ok(Some(Quota::Many(statuses)))
+}
+
+/// Releases the status list entries allocated to a credential by [`allocate`]
+/// so they can be reused, for example when the credential could not be
+/// issued.
+///
+/// Does nothing if no entries were allocated to the credential.
+///
+/// # Errors
+///
+/// Returns an error if the status log or lists cannot be read or saved.
+pub async fn release(provider: &impl Status, credential_id: &str) -> provider::Result<()> {
+    let Some(entry) = provider.log_entry(credential_id).await? else {
+        return Ok(());
+    };
+    for value in &entry.status {
+        let position = Position {
+            shard: value.shard,
+            index: value.list_index,
+        };
+        let release = |sharded: &mut ShardedList| sharded.release(position);
+        update_list(provider, value.list, None, release).await?;
+    }
+    provider
+        .put_log_entry(&StatusLogEntry {
+            status: vec![],
+            ..entry
+        })
+        .await
+}
+
+// Allocate an entry in each list, recording the allocations made so far so
+// they can be released if a later step fails.
+async fn allocate_entries<'a>(
+    provider: &impl Status, lists: &'a [ListConfig],
+    allocated: &mut Vec<(&'a ListConfig, Position)>,
+) -> provider::Result<Vec<StatusValue>> {
+    let mut values = vec![];
+    for list_config in lists {
+        let (_, position) = update_list(
+            provider,
+            list_config.list,
+            Some(list_config),
+            ShardedList::allocate_random,
+        )
+        .await?;
+        allocated.push((list_config, position));
+        values.push(StatusValue {
+            purpose: list_config.purpose.clone(),
+            list: list_config.list,
+            shard: position.shard,
+            list_index: position.index,
+            value: 0,
+        });
+    }
+    Ok(values)
+}
+
+/// Applies `update` to a status list and saves the result, re-reading the list
+/// and retrying if it was changed by a concurrent update.
+///
+/// A new list is created from `config` if the list has not yet been saved.
+/// Returns the saved list along with the result of the update.
+///
+/// # Errors
+///
+/// Returns an error if the list does not exist and no `config` is provided,
+/// the update fails, or the list cannot be saved.
+pub async fn update_list<T>(
+    provider: &impl Status, list: usize, config: Option<&ListConfig>,
+    update: impl Fn(&mut ShardedList) -> anyhow::Result<T>,
+) -> provider::Result<(ShardedList, T)> {
+    for _ in 0..MAX_UPDATE_ATTEMPTS {
+        let stored = provider.sharded_list(list).await?;
+        let version = stored.as_ref().map(ShardedList::version);
+        let mut sharded = match (stored, config) {
+            (Some(sharded), _) => sharded,
+            (None, Some(config)) => ShardedList::new(config)?,
+            (None, None) => return Err(anyhow!("status list {list} not found")),
+        };
+
+        let result = update(&mut sharded)?;
+        sharded.next_version();
+        if provider.put_sharded_list(list, version, &sharded).await? {
+            return Ok((sharded, result));
+        }
+    }
+    Err(anyhow!("status list {list} is being updated concurrently"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    use vercre_w3c_vc::model::StatusPurpose;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Provider {
+        lists: Arc<Mutex<HashMap<usize, ShardedList>>>,
+        log: Arc<Mutex<Vec<StatusLogEntry>>>,
+        // simulate another issuer saving the list before the next save
+        conflict: Mutex<bool>,
+        // simulate a failure saving the status log
+        fail_log: bool,
+    }
+
+    impl Status for Provider {
+        async fn status_config(
+            &self, credential_type: &str,
+        ) -> provider::Result<Option<CredentialStatusConfig>> {
+            let list_config = |purpose, list| ListConfig {
+                purpose,
+                list,
+                size: 1,
+                messages: None,
+                reference: None,
+                entries: None,
+            };
+            Ok((credential_type == "EmployeeIDCredential").then(|| CredentialStatusConfig {
+                credential_type: credential_type.into(),
+                status_list: Some(vec![
+                    list_config(StatusPurpose::Revocation, 1),
+                    list_config(StatusPurpose::Suspension, 2),
+                ]),
+            }))
+        }
+
+        async fn sharded_list(&self, list: usize) -> provider::Result<Option<ShardedList>> {
+            Ok(self.lists.lock().expect("should lock").get(&list).cloned())
+        }
+
+        async fn put_sharded_list(
+            &self, list: usize, version: Option<u64>, sharded_list: &ShardedList,
+        ) -> provider::Result<bool> {
+            let mut lists = self.lists.lock().expect("should lock");
+            if std::mem::take(&mut *self.conflict.lock().expect("should lock")) {
+                let mut concurrent = sharded_list.clone();
+                concurrent.next_version();
+                lists.insert(list, concurrent);
+            }
+            if lists.get(&list).map(ShardedList::version) != version {
+                return Ok(false);
+            }
+            lists.insert(list, sharded_list.clone());
+            drop(lists);
+            Ok(true)
+        }
+
+        async fn put_log_entry(&self, entry: &StatusLogEntry) -> provider::Result<()> {
+            if self.fail_log {
+                return Err(anyhow!("log unavailable"));
+            }
+            self.log.lock().expect("should lock").push(entry.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn allocate_entries() {
+        let provider = Provider::default();
+
+        let Some(Quota::Many(statuses)) = allocate(
+            &provider,
+            "EmployeeIDCredential",
+            "cred-1",
+            "user",
+            "http://vercre.io/status",
+        )
+        .await
+        .expect("should allocate") else {
+            panic!("should have a status for each list");
+        };
+        assert_eq!(statuses.len(), 2);
+
+        let CredentialStatusType::Bitstring(revocation) = &statuses[0].credential_status_type;
+        assert_eq!(revocation.status_purpose, StatusPurpose::Revocation);
+        assert_eq!(revocation.status_list_credential, "http://vercre.io/status/1/0");
+
+        // the allocated entry is recorded in both the list and the log
+        let list = provider.lists.lock().expect("should lock").get(&1).cloned();
+        let list = list.expect("should have list");
+        assert!(list.is_allocated(crate::bitstring::Position {
+            shard: 0,
+            index: revocation.status_list_index,
+        }));
+        let log = provider.log.lock().expect("should lock").clone();
+        assert_eq!(log[0].credential_id, "cred-1");
+        assert_eq!(log[0].status[0].list_index, revocation.status_list_index);
+        assert_eq!(log[0].status[1].purpose, StatusPurpose::Suspension);
+
+        // credential types without a configuration have no status
+        let status = allocate(&provider, "OtherCredential", "cred-2", "user", "http://vercre.io")
+            .await
+            .expect("should allocate");
+        assert!(status.is_none());
+    }
+
+    #[tokio::test]
+    async fn concurrent_update() {
+        let provider = Provider {
+            conflict: Mutex::new(true),
+            ..Provider::default()
+        };
+
+        // the conflicting save is detected and the allocation retried
+        allocate(&provider, "EmployeeIDCredential", "cred-1", "user", "http://vercre.io/status")
+            .await
+            .expect("should allocate");
+        let list = provider.lists.lock().expect("should lock").get(&1).cloned();
+        assert_eq!(list.expect("should have list").version(), 3);
+        assert_eq!(provider.log.lock().expect("should lock").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn release_on_failure() {
+        let provider = Provider {
+            fail_log: true,
+            ..Provider::default()
+        };
+
+        allocate(&provider, "EmployeeIDCredential", "cred-1", "user", "http://vercre.io/status")
+            .await
+            .expect_err("should fail to save log entry");
+
+        // the allocated entries are released
+        let unversioned = |list: &ShardedList| {
+            let mut value = serde_json::to_value(list).expect("should serialize");
+            value["version"] = 0.into();
+            value
+        };
+        let config = provider.status_config("EmployeeIDCredential").await.expect("should get");
+        for list_config in config.and_then(|c| c.status_list).expect("should have lists") {
+            let list = provider.lists.lock().expect("should lock").get(&list_config.list).cloned();
+            let empty = ShardedList::new(&list_config).expect("should create");
+            assert_eq!(unversioned(&list.expect("should have list")), unversioned(&empty));
+        }
+    }
 }
@@ -8,8 +8,8 @@
 //! [Status section of Verifiable Credentials Data Model v2.0](https://www.w3.org/TR/vc-data-model-2.0/#status)
 
 pub mod bitstring;
-mod config;
+pub mod config;
 pub mod issuer;
-mod log;
+pub mod log;
 mod provider;
 pub mod verifier;
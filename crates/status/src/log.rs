@@ -7,7 +7,7 @@ use serde::{Deserialize, Serialize};
 use vercre_w3c_vc::model::StatusPurpose;
 
 /// Entry in a log of issued credentials and their current status.
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[allow(clippy::module_name_repetitions)]
 pub struct StatusLogEntry {
     /// Credential identifier.
@@ -26,15 +26,22 @@ pub struct StatusLogEntry {
 
 /// Status value for a credential and the lookup identifier in a published
 /// status list.
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct StatusValue {
     /// Type of status.
     pub purpose: StatusPurpose,
 
+    /// Identifier of the status list the status is published in.
+    pub list: usize,
+
+    /// Shard of the status list the credential's status is recorded in.
+    #[serde(default)]
+    pub shard: usize,
+
     /// Identifier in the status list.
     ///
-    /// For example, in a bitstring list this would be the index of the first
-    /// bit in the bitstring that represents the status of this credential.
+    /// For example, in a bitstring list this would be the index of the entry
+    /// in the bitstring that represents the status of this credential.
     pub list_index: usize,
 
     /// Status value.
@@ -5,24 +5,42 @@
 
 use std::future::Future;
 
-pub use vercre_w3c_vc::model::CredentialStatus;
+use anyhow::anyhow;
+pub use vercre_core::Kind;
+pub use vercre_w3c_vc::model::{CredentialStatus, VerifiableCredential};
 
 use crate::provider;
 
 /// The `Status` trait is used to proxy the resolution of a credential status.
 ///
 /// Given a credential's status look-up information, the implementer should use
-/// that to retrieve a published credential status list and look into that for
-/// the current status of the credential.
+/// that to retrieve a published credential status list. The status of the
+/// credential can then be looked up in the list using
+/// [`crate::bitstring::validate`].
 pub trait Status: Send + Sync {
-    /// Returns `true` if the credential currently has the requested status,
-    /// `false` otherwise.
+    /// Returns the status list credential published at the given URL (the
+    /// `statusListCredential` of a credential's status entry).
+    ///
+    /// A default implementation is provided that returns an error for cases
+    /// where the verifier does not retrieve status lists. Credentials with a
+    /// `credentialStatus` will fail verification unless the verifier opts out
+    /// of status checks using [`Status::check_status`].
     ///
     /// # Errors
     ///
-    /// Returns an error if the status list cannot be retrieved or the status
-    /// for the given credential cannot be resolved from the list.
-    fn status(
-        &self, status: &CredentialStatus, credential_identifier: &str,
-    ) -> impl Future<Output = provider::Result<bool>> + Send;
+    /// Returns an error if the status list credential cannot be retrieved.
+    fn status_list(
+        &self, _url: &str,
+    ) -> impl Future<Output = provider::Result<Kind<VerifiableCredential>>> + Send {
+        async { Err(anyhow!("status list retrieval is not supported")) }
+    }
+
+    /// Whether the status of presented credentials is checked. Defaults to
+    /// `true`.
+    ///
+    /// Verifiers that accept credentials without checking their status must
+    /// opt out explicitly by returning `false`.
+    fn check_status(&self) -> bool {
+        true
+    }
 }
@@ -1,4 +1,5 @@
-use vercre_infosec::{Algorithm, Signer};
+use vercre_infosec::{Algorithm, Decryptor, Encryptor, Signer};
+use vercre_openid::jwe::KeyAgreement;
 use vercre_openid::provider::Result;
 
 use crate::store::keystore::HolderKeystore;
@@ -35,3 +36,25 @@ impl Signer for Provider {
         HolderKeystore::verification_method()
     }
 }
+
+impl Encryptor for Provider {
+    async fn encrypt(&self, plaintext: &[u8], recipient_public_key: &[u8]) -> Result<Vec<u8>> {
+        HolderKeystore::encrypt(plaintext, recipient_public_key)
+    }
+
+    fn public_key(&self) -> Vec<u8> {
+        HolderKeystore::encryption_key().expect("should derive encryption key")
+    }
+}
+
+impl Decryptor for Provider {
+    async fn decrypt(&self, ciphertext: &[u8], sender_public_key: &[u8]) -> Result<Vec<u8>> {
+        HolderKeystore::decrypt(ciphertext, sender_public_key)
+    }
+}
+
+impl KeyAgreement for Provider {
+    async fn shared_secret(&self, _kid: Option<&str>, sender_public_key: &[u8]) -> Result<Vec<u8>> {
+        HolderKeystore::shared_secret(sender_public_key)
+    }
+}
@@ -6,10 +6,10 @@ use vercre_infosec::{Algorithm, Decryptor, Encryptor, SecOps, Signer};
 use vercre_openid::issuer::{
     Client, Dataset, Issuer, Metadata, Result, Server, StateStore, Subject,
 };
-use vercre_status::issuer::Status;
+use vercre_status::issuer::{CredentialStatusConfig, ShardedList, Status, StatusLogEntry};
 
 use crate::store::keystore::IssuerKeystore;
-use crate::store::{issuance, resolver, state};
+use crate::store::{issuance, resolver, state, status};
 
 pub const CREDENTIAL_ISSUER: &str = "http://vercre.io";
 pub const CLIENT_ID: &str = "96bfb9cb-0513-7d64-5532-bed74c48f9ab";
@@ -24,6 +24,10 @@ pub struct Provider {
     pub server: issuance::ServerStore,
     pub subject: issuance::DatasetStore,
     pub state: state::Store,
+    pub status: status::IssuerStore,
+
+    /// Simulate a failure signing credentials.
+    pub fail_signing: bool,
 }
 
 impl Provider {
@@ -35,6 +39,8 @@ impl Provider {
             server: issuance::ServerStore::new(),
             subject: issuance::DatasetStore::new(),
             state: state::Store::new(),
+            status: status::IssuerStore::new(),
+            fail_signing: false,
         }
     }
 }
@@ -92,24 +98,31 @@ impl DidResolver for Provider {
     }
 }
 
-struct IssuerSec(IssuerKeystore);
+struct IssuerSec {
+    fail_signing: bool,
+}
 
 impl SecOps for Provider {
     fn signer(&self, _identifier: &str) -> anyhow::Result<impl Signer> {
-        Ok(IssuerSec(IssuerKeystore {}))
+        Ok(IssuerSec {
+            fail_signing: self.fail_signing,
+        })
     }
 
     fn encryptor(&self, _identifier: &str) -> anyhow::Result<impl Encryptor> {
-        Ok(IssuerSec(IssuerKeystore {}))
+        Ok(IssuerSec { fail_signing: false })
     }
 
     fn decryptor(&self, _identifier: &str) -> anyhow::Result<impl Decryptor> {
-        Ok(IssuerSec(IssuerKeystore {}))
+        Ok(IssuerSec { fail_signing: false })
     }
 }
 
 impl Signer for IssuerSec {
     async fn try_sign(&self, msg: &[u8]) -> Result<Vec<u8>> {
+        if self.fail_signing {
+            return Err(anyhow::anyhow!("signing failed"));
+        }
         IssuerKeystore::try_sign(msg)
     }
 
@@ -127,19 +140,53 @@ impl Signer for IssuerSec {
 }
 
 impl Encryptor for IssuerSec {
-    async fn encrypt(&self, _plaintext: &[u8], _recipient_public_key: &[u8]) -> Result<Vec<u8>> {
-        todo!()
+    async fn encrypt(&self, plaintext: &[u8], recipient_public_key: &[u8]) -> Result<Vec<u8>> {
+        IssuerKeystore::encrypt(plaintext, recipient_public_key)
     }
 
     fn public_key(&self) -> Vec<u8> {
-        todo!()
+        IssuerKeystore::encryption_key().expect("should derive encryption key")
     }
 }
 
 impl Decryptor for IssuerSec {
-    async fn decrypt(&self, _ciphertext: &[u8], _sender_public_key: &[u8]) -> Result<Vec<u8>> {
-        todo!()
+    async fn decrypt(&self, ciphertext: &[u8], sender_public_key: &[u8]) -> Result<Vec<u8>> {
+        IssuerKeystore::decrypt(ciphertext, sender_public_key)
     }
 }
 
-impl Status for Provider {}
+impl Status for Provider {
+    async fn status_config(&self, credential_type: &str) -> Result<Option<CredentialStatusConfig>> {
+        Ok(self.status.status_config(credential_type))
+    }
+
+    async fn sharded_list(&self, list: usize) -> Result<Option<ShardedList>> {
+        Ok(self.status.sharded_list(list))
+    }
+
+    async fn put_sharded_list(
+        &self, list: usize, version: Option<u64>, sharded_list: &ShardedList,
+    ) -> Result<bool> {
+        Ok(self.status.put_sharded_list(list, version, sharded_list))
+    }
+
+    async fn put_log_entry(&self, entry: &StatusLogEntry) -> Result<()> {
+        self.status.put_log_entry(entry);
+        Ok(())
+    }
+
+    async fn log_entry(&self, credential_id: &str) -> Result<Option<StatusLogEntry>> {
+        Ok(self.status.log_entry(credential_id))
+    }
+
+    async fn status_list_credential(&self, list: usize, shard: usize) -> Result<Option<String>> {
+        Ok(self.status.credential(list, shard))
+    }
+
+    async fn put_status_list_credential(
+        &self, list: usize, shard: usize, credential: &str,
+    ) -> Result<()> {
+        self.status.put_credential(list, shard, credential);
+        Ok(())
+    }
+}
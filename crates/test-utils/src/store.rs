@@ -3,3 +3,4 @@ pub mod keystore;
 pub mod presentation;
 pub mod resolver;
 pub mod state;
+pub mod status;
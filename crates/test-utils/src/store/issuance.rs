@@ -36,6 +36,10 @@ impl IssuerStore {
         };
         Ok(issuer.clone())
     }
+
+    pub fn put(&mut self, issuer: Issuer) {
+        self.issuers.insert(issuer.credential_issuer.clone(), issuer);
+    }
 }
 
 #[derive(Default, Clone, Debug)]
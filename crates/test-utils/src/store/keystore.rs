@@ -1,6 +1,8 @@
-use anyhow::anyhow;
+use anyhow::{anyhow, bail};
 use base64ct::{Base64UrlUnpadded, Encoding};
+use curve25519_dalek::MontgomeryPoint;
 use ed25519_dalek::{SecretKey, Signer, SigningKey};
+use sha2::{Digest, Sha256};
 use vercre_infosec::jose::jwa::Algorithm;
 use vercre_openid::provider::Result;
 
@@ -40,6 +42,18 @@ impl IssuerKeystore {
     pub fn verification_method() -> String {
         format!("{ISSUER_DID}#{ISSUER_VERIFY_KEY}")
     }
+
+    pub fn encryption_key() -> Result<Vec<u8>> {
+        cipher::public_key(ISSUER_SECRET)
+    }
+
+    pub fn encrypt(plaintext: &[u8], recipient_public_key: &[u8]) -> Result<Vec<u8>> {
+        cipher::encrypt(ISSUER_SECRET, plaintext, recipient_public_key)
+    }
+
+    pub fn decrypt(ciphertext: &[u8], sender_public_key: &[u8]) -> Result<Vec<u8>> {
+        cipher::decrypt(ISSUER_SECRET, ciphertext, sender_public_key)
+    }
 }
 
 #[derive(Default, Clone, Debug)]
@@ -72,6 +86,22 @@ impl VerifierKeystore {
     pub fn verification_method() -> String {
         format!("{VERIFIER_DID}#{VERIFIER_VERIFY_KEY}")
     }
+
+    pub fn encryption_key() -> Result<Vec<u8>> {
+        cipher::public_key(VERIFIER_SECRET)
+    }
+
+    pub fn encrypt(plaintext: &[u8], recipient_public_key: &[u8]) -> Result<Vec<u8>> {
+        cipher::encrypt(VERIFIER_SECRET, plaintext, recipient_public_key)
+    }
+
+    pub fn decrypt(ciphertext: &[u8], sender_public_key: &[u8]) -> Result<Vec<u8>> {
+        cipher::decrypt(VERIFIER_SECRET, ciphertext, sender_public_key)
+    }
+
+    pub fn shared_secret(sender_public_key: &[u8]) -> Result<Vec<u8>> {
+        Ok(cipher::shared_secret(VERIFIER_SECRET, sender_public_key)?.to_vec())
+    }
 }
 
 const HOLDER_DID: &str = "did:key:z6Mkj8Jr1rg3YjVWWhg7ahEYJibqhjBgZt1pDCbT4Lv7D4HX";
@@ -91,10 +121,10 @@ impl HolderKeystore {
     }
 
     pub fn public_key() -> Result<Vec<u8>> {
-        let decoded = Base64UrlUnpadded::decode_vec(HOLDER_VERIFY_KEY)?;
+        let decoded = Base64UrlUnpadded::decode_vec(HOLDER_SECRET)?;
         let bytes: [u8; 32] = decoded.as_slice().try_into().expect("should convert ");
-        let verify_key = ed25519_dalek::VerifyingKey::from_bytes(&bytes)?;
-        Ok(verify_key.as_bytes().to_vec())
+        let signing_key = ed25519_dalek::SigningKey::from_bytes(&bytes);
+        Ok(signing_key.verifying_key().as_bytes().to_vec())
     }
 
     pub fn algorithm() -> Algorithm {
@@ -104,4 +134,92 @@ impl HolderKeystore {
     pub fn verification_method() -> String {
         format!("{HOLDER_DID}#{HOLDER_VERIFY_KEY}")
     }
+
+    pub fn encryption_key() -> Result<Vec<u8>> {
+        cipher::public_key(HOLDER_SECRET)
+    }
+
+    pub fn encrypt(plaintext: &[u8], recipient_public_key: &[u8]) -> Result<Vec<u8>> {
+        cipher::encrypt(HOLDER_SECRET, plaintext, recipient_public_key)
+    }
+
+    pub fn decrypt(ciphertext: &[u8], sender_public_key: &[u8]) -> Result<Vec<u8>> {
+        cipher::decrypt(HOLDER_SECRET, ciphertext, sender_public_key)
+    }
+
+    pub fn shared_secret(sender_public_key: &[u8]) -> Result<Vec<u8>> {
+        Ok(cipher::shared_secret(HOLDER_SECRET, sender_public_key)?.to_vec())
+    }
+}
+
+// A minimal X25519 key agreement and SHA-256 keystream cipher, sufficient to
+// exercise encrypted responses in tests. It is NOT suitable for production
+// use.
+mod cipher {
+    use super::*;
+
+    const NONCE_LEN: usize = 16;
+    const TAG_LEN: usize = 32;
+
+    // The X25519 public key corresponding to the Ed25519 secret.
+    pub fn public_key(secret: &str) -> Result<Vec<u8>> {
+        let signing_key = signing_key(secret)?;
+        Ok(signing_key.verifying_key().to_montgomery().to_bytes().to_vec())
+    }
+
+    pub fn encrypt(secret: &str, plaintext: &[u8], public_key: &[u8]) -> Result<Vec<u8>> {
+        let key = shared_secret(secret, public_key)?;
+        let nonce = uuid::Uuid::new_v4().into_bytes();
+
+        let mut ciphertext = nonce.to_vec();
+        ciphertext.extend(apply_keystream(&key, &nonce, plaintext));
+        let tag = tag(&key, &ciphertext);
+        ciphertext.extend(tag);
+        Ok(ciphertext)
+    }
+
+    pub fn decrypt(secret: &str, ciphertext: &[u8], public_key: &[u8]) -> Result<Vec<u8>> {
+        if ciphertext.len() < NONCE_LEN + TAG_LEN {
+            bail!("ciphertext is too short");
+        }
+        let key = shared_secret(secret, public_key)?;
+
+        let (sealed, expected) = ciphertext.split_at(ciphertext.len() - TAG_LEN);
+        if tag(&key, sealed) != expected {
+            bail!("ciphertext failed authentication");
+        }
+        let (nonce, encrypted) = sealed.split_at(NONCE_LEN);
+        Ok(apply_keystream(&key, nonce, encrypted))
+    }
+
+    fn signing_key(secret: &str) -> Result<SigningKey> {
+        let decoded = Base64UrlUnpadded::decode_vec(secret)?;
+        let secret_key: SecretKey =
+            decoded.try_into().map_err(|_| anyhow!("Invalid secret key"))?;
+        Ok(SigningKey::from_bytes(&secret_key))
+    }
+
+    pub fn shared_secret(secret: &str, public_key: &[u8]) -> Result<[u8; 32]> {
+        let public_key: [u8; 32] =
+            public_key.try_into().map_err(|_| anyhow!("Invalid public key"))?;
+        let scalar = signing_key(secret)?.to_scalar_bytes();
+        Ok(MontgomeryPoint(public_key).mul_clamped(scalar).to_bytes())
+    }
+
+    fn apply_keystream(key: &[u8], nonce: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut output = Vec::with_capacity(data.len());
+        for (counter, chunk) in (0_u64..).zip(data.chunks(32)) {
+            let block = Sha256::new()
+                .chain_update(key)
+                .chain_update(nonce)
+                .chain_update(counter.to_be_bytes())
+                .finalize();
+            output.extend(chunk.iter().zip(block).map(|(b, k)| b ^ k));
+        }
+        output
+    }
+
+    fn tag(key: &[u8], data: &[u8]) -> Vec<u8> {
+        Sha256::new().chain_update(key).chain_update(data).finalize().to_vec()
+    }
 }
@@ -4,7 +4,7 @@ use std::sync::{Arc, Mutex};
 use anyhow::anyhow;
 use uuid::Uuid;
 use vercre_openid::provider::Result;
-use vercre_openid::verifier::Verifier;
+use vercre_openid::verifier::{Verifier, Wallet};
 
 #[derive(Default, Clone, Debug)]
 pub struct Store {
@@ -45,3 +45,28 @@ impl Store {
         Ok(verifier)
     }
 }
+
+#[derive(Default, Clone, Debug)]
+pub struct WalletStore {
+    wallets: Arc<Mutex<HashMap<String, Wallet>>>,
+}
+
+impl WalletStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, wallet_id: &str) -> Result<Wallet> {
+        let Some(wallet) = self.wallets.lock().expect("should lock").get(wallet_id).cloned() else {
+            return Err(anyhow!("wallet not found for wallet_id: {wallet_id}"));
+        };
+        Ok(wallet)
+    }
+
+    pub fn put(&self, wallet: &Wallet) {
+        self.wallets
+            .lock()
+            .expect("should lock")
+            .insert(wallet.oauth.issuer.clone(), wallet.clone());
+    }
+}
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use anyhow::anyhow;
+use vercre_openid::provider::Result;
+use vercre_status::issuer::{CredentialStatusConfig, ShardedList, StatusLogEntry};
+
+#[derive(Default, Clone, Debug)]
+pub struct Store {
+    lists: Arc<Mutex<HashMap<String, String>>>,
+}
+
+impl Store {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[allow(clippy::unnecessary_wraps)]
+    pub fn put(&self, url: &str, status_list: &str) -> Result<()> {
+        self.lists.lock().expect("should lock").insert(url.to_string(), status_list.to_string());
+        Ok(())
+    }
+
+    pub fn get(&self, url: &str) -> Result<String> {
+        let Some(status_list) = self.lists.lock().expect("should lock").get(url).cloned() else {
+            return Err(anyhow!("status list not found for {url}"));
+        };
+        Ok(status_list)
+    }
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct IssuerStore {
+    configs: Arc<Mutex<HashMap<String, CredentialStatusConfig>>>,
+    sharded: Arc<Mutex<HashMap<usize, ShardedList>>>,
+    log: Arc<Mutex<HashMap<String, StatusLogEntry>>>,
+    credentials: Arc<Mutex<HashMap<(usize, usize), String>>>,
+}
+
+impl IssuerStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn status_config(&self, credential_type: &str) -> Option<CredentialStatusConfig> {
+        self.configs.lock().expect("should lock").get(credential_type).cloned()
+    }
+
+    pub fn put_status_config(&self, config: &CredentialStatusConfig) {
+        self.configs
+            .lock()
+            .expect("should lock")
+            .insert(config.credential_type.clone(), config.clone());
+    }
+
+    pub fn sharded_list(&self, list: usize) -> Option<ShardedList> {
+        self.sharded.lock().expect("should lock").get(&list).cloned()
+    }
+
+    pub fn put_sharded_list(
+        &self, list: usize, version: Option<u64>, sharded_list: &ShardedList,
+    ) -> bool {
+        let mut sharded = self.sharded.lock().expect("should lock");
+        if sharded.get(&list).map(ShardedList::version) != version {
+            return false;
+        }
+        sharded.insert(list, sharded_list.clone());
+        true
+    }
+
+    pub fn log_entry(&self, credential_id: &str) -> Option<StatusLogEntry> {
+        self.log.lock().expect("should lock").get(credential_id).cloned()
+    }
+
+    pub fn put_log_entry(&self, entry: &StatusLogEntry) {
+        self.log.lock().expect("should lock").insert(entry.credential_id.clone(), entry.clone());
+    }
+
+    pub fn credential(&self, list: usize, shard: usize) -> Option<String> {
+        self.credentials.lock().expect("should lock").get(&(list, shard)).cloned()
+    }
+
+    pub fn put_credential(&self, list: usize, shard: usize, credential: &str) {
+        self.credentials.lock().expect("should lock").insert((list, shard), credential.to_string());
+    }
+}
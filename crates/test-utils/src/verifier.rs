@@ -3,17 +3,20 @@ use serde::de::DeserializeOwned;
 use serde::Serialize;
 use vercre_did::{DidResolver, Document};
 use vercre_infosec::{self, Algorithm, Decryptor, Encryptor, SecOps, Signer};
-use vercre_openid::verifier::{Metadata, Result, StateStore, Verifier, Wallet};
+use vercre_openid::verifier::{KeyAgreement, Metadata, Result, StateStore, Verifier, Wallet};
+use vercre_status::verifier::{Kind, Status, VerifiableCredential};
 
 use crate::store::keystore::VerifierKeystore;
-use crate::store::{presentation, resolver, state};
+use crate::store::{presentation, resolver, state, status};
 
 pub const VERIFIER_ID: &str = "http://localhost:8080";
 
 #[derive(Default, Clone, Debug)]
 pub struct Provider {
     pub verifier: presentation::Store,
+    pub wallet: presentation::WalletStore,
     pub state: state::Store,
+    pub status: status::Store,
 }
 
 impl Provider {
@@ -21,7 +24,9 @@ impl Provider {
     pub fn new() -> Self {
         Self {
             verifier: presentation::Store::new(),
+            wallet: presentation::WalletStore::new(),
             state: state::Store::new(),
+            status: status::Store::new(),
         }
     }
 }
@@ -37,8 +42,8 @@ impl Metadata for Provider {
         self.verifier.add(verifier)
     }
 
-    async fn wallet(&self, _wallet_id: &str) -> Result<Wallet> {
-        unimplemented!("WalletMetadata")
+    async fn wallet(&self, wallet_id: &str) -> Result<Wallet> {
+        self.wallet.get(wallet_id)
     }
 }
 
@@ -56,12 +61,24 @@ impl StateStore for Provider {
     }
 }
 
+impl Status for Provider {
+    async fn status_list(&self, url: &str) -> Result<Kind<VerifiableCredential>> {
+        Ok(Kind::String(self.status.get(url)?))
+    }
+}
+
 impl DidResolver for Provider {
     async fn resolve(&self, url: &str) -> anyhow::Result<Document> {
         resolver::resolve_did(url).await
     }
 }
 
+impl KeyAgreement for Provider {
+    async fn shared_secret(&self, _kid: Option<&str>, sender_public_key: &[u8]) -> Result<Vec<u8>> {
+        VerifierKeystore::shared_secret(sender_public_key)
+    }
+}
+
 struct VerifierSec(VerifierKeystore);
 
 impl SecOps for Provider {
@@ -97,17 +114,17 @@ impl Signer for VerifierSec {
 }
 
 impl Encryptor for VerifierSec {
-    async fn encrypt(&self, _plaintext: &[u8], _recipient_public_key: &[u8]) -> Result<Vec<u8>> {
-        todo!()
+    async fn encrypt(&self, plaintext: &[u8], recipient_public_key: &[u8]) -> Result<Vec<u8>> {
+        VerifierKeystore::encrypt(plaintext, recipient_public_key)
     }
 
     fn public_key(&self) -> Vec<u8> {
-        todo!()
+        VerifierKeystore::encryption_key().expect("should derive encryption key")
     }
 }
 
 impl Decryptor for VerifierSec {
-    async fn decrypt(&self, _ciphertext: &[u8], _sender_public_key: &[u8]) -> Result<Vec<u8>> {
-        todo!()
+    async fn decrypt(&self, ciphertext: &[u8], sender_public_key: &[u8]) -> Result<Vec<u8>> {
+        VerifierKeystore::decrypt(ciphertext, sender_public_key)
     }
 }
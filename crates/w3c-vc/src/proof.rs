@@ -31,15 +31,19 @@
 mod controller;
 pub mod integrity;
 mod jose;
+pub mod sdjwt;
 
 use anyhow::bail;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 use vercre_core::{Kind, Quota};
 use vercre_did::DidResolver;
 use vercre_infosec::jose::{jws, jwt};
 use vercre_infosec::Signer;
 
 use crate::model::{VerifiableCredential, VerifiablePresentation};
+use crate::proof::integrity::Proof;
 use crate::verify_key;
 
 /// Credential format options for the resulting proof.
@@ -53,8 +57,9 @@ pub enum W3cFormat {
     #[serde(rename = "jwt_vc_json-ld")]
     JwtVcJsonLd,
 
-    /// VC secured using Data Integrity, using JSON-LD, with a proof suite
-    /// requiring Linked Data canonicalization.
+    /// VC secured using an embedded Data Integrity proof, using JSON-LD. Only
+    /// the `eddsa-jcs-2022` cryptosuite (JSON Canonicalization Scheme) is
+    /// supported, so the signer must use `EdDSA`.
     #[serde(rename = "ldp_vc")]
     DataIntegrityJsonLd,
 }
@@ -87,13 +92,19 @@ pub enum Payload {
 
 /// Create a proof from a proof provider.
 ///
+/// Credentials and presentations in `jwt_vc_json` and `jwt_vc_json-ld` format
+/// are returned as a JWT. For `ldp_vc`, the credential or presentation is
+/// secured with an embedded Data Integrity proof and returned serialized as
+/// JSON.
+///
 /// # Errors
 /// TODO: document errors
 pub async fn create(
     format: W3cFormat, payload: Payload, signer: impl Signer,
 ) -> anyhow::Result<String> {
-    if format != W3cFormat::JwtVcJson && format != W3cFormat::JwtVcJsonLd {
-        return Err(anyhow::anyhow!("Unsupported proof format"));
+    if format == W3cFormat::DataIntegrityJsonLd {
+        // boxed to keep the (large) embedded proof future off the stack
+        return Box::pin(embed(payload, signer)).await;
     }
 
     let jwt = match payload {
@@ -109,21 +120,49 @@ pub async fn create(
         }
     };
 
-    // TODO: add data integrity proof payload
-    // let proof = Proof {
-    //     id: Some(format!("urn:uuid:{}", Uuid::new_v4())),
-    //     type_: Signer::algorithm(provider).proof_type(),
-    //     verification_method: Signer::verification_method(provider),
-    //     created: Some(Utc::now()),
-    //     expires:
-    // Utc::now().checked_add_signed(TimeDelta::try_hours(1).unwrap_or_default()),
-    //     ..Proof::default()
-    // };
-
-    // Ok(serde_json::to_value(jwt)?)
     Ok(jwt)
 }
 
+// Secure the payload with an embedded Data Integrity proof.
+async fn embed(payload: Payload, signer: impl Signer) -> anyhow::Result<String> {
+    let config = Proof {
+        id: Some(format!("urn:uuid:{}", Uuid::new_v4())),
+        type_: integrity::DATA_INTEGRITY_PROOF.into(),
+        cryptosuite: Some(integrity::cryptosuite(&signer.algorithm())?.into()),
+        verification_method: signer.verification_method(),
+        ..Proof::default()
+    };
+
+    match payload {
+        Payload::Vc { mut vc, issued_at } => {
+            let config = Proof {
+                proof_purpose: "assertionMethod".into(),
+                created: DateTime::from_timestamp(issued_at, 0),
+                ..config
+            };
+            let proof = integrity::create(&vc, config, &signer).await?;
+            vc.proof = Some(Quota::One(proof));
+            Ok(serde_json::to_string(&vc)?)
+        }
+        Payload::Vp {
+            mut vp,
+            client_id,
+            nonce,
+        } => {
+            let config = Proof {
+                proof_purpose: "authentication".into(),
+                created: Some(Utc::now()),
+                domain: Some(Quota::One(client_id)),
+                challenge: Some(nonce),
+                ..config
+            };
+            let proof = integrity::create(&vp, config, &signer).await?;
+            vp.proof = Some(Quota::One(proof));
+            Ok(serde_json::to_string(&vp)?)
+        }
+    }
+}
+
 /// Data type to verify.
 pub enum Verify<'a> {
     /// A Verifiable Credential proof either encoded as a JWT or with an
@@ -142,16 +181,39 @@ pub enum Verify<'a> {
 #[allow(clippy::unused_async)]
 pub async fn verify(proof: Verify<'_>, resolver: &impl DidResolver) -> anyhow::Result<Payload> {
     match proof {
-        Verify::Vc(value) => {
-            let Kind::String(token) = value else {
-                bail!("VerifiableCredential is not a JWT");
-            };
-            let jwt: jwt::Jwt<jose::VcClaims> = jws::decode(token, verify_key!(resolver)).await?;
-            Ok(Payload::Vc {
-                vc: jwt.claims.vc,
-                issued_at: jwt.claims.iat,
-            })
-        }
+        Verify::Vc(value) => match value {
+            Kind::String(token) => {
+                let jwt: jwt::Jwt<jose::VcClaims> =
+                    jws::decode(token, verify_key!(resolver)).await?;
+                Ok(Payload::Vc {
+                    vc: jwt.claims.vc,
+                    issued_at: jwt.claims.iat,
+                })
+            }
+            Kind::Object(vc) => {
+                let Some(proofs) = &vc.proof else {
+                    bail!("VerifiableCredential has no proof");
+                };
+                let proofs = match proofs {
+                    Quota::One(proof) => vec![proof],
+                    Quota::Many(proofs) => proofs.iter().collect(),
+                };
+                let issuer = match &vc.issuer {
+                    Kind::String(id) => id,
+                    Kind::Object(issuer) => &issuer.id,
+                };
+                for proof in &proofs {
+                    check_controller(&proof.verification_method, issuer)?;
+                    integrity::verify(vc, proof, "assertionMethod", resolver).await?;
+                }
+
+                let issued_at = proofs[0].created.or(vc.valid_from);
+                Ok(Payload::Vc {
+                    vc: vc.clone(),
+                    issued_at: issued_at.map_or(0, |dt| dt.timestamp()),
+                })
+            }
+        },
         Verify::Vp(value) => {
             match value {
                 Kind::String(token) => {
@@ -164,19 +226,43 @@ pub async fn verify(proof: Verify<'_>, resolver: &impl DidResolver) -> anyhow::R
                     })
                 }
                 Kind::Object(vp) => {
-                    // TODO: Implement embedded proof verification
                     let Some(Quota::One(proof)) = &vp.proof else {
                         bail!("invalid VerifiablePresentation proof")
                     };
-                    let challenge = proof.challenge.clone().unwrap_or_default();
+                    // the proof must be created by the holder
+                    let Some(holder) = &vp.holder else {
+                        bail!("presentation holder is required");
+                    };
+                    check_controller(&proof.verification_method, holder)?;
+                    integrity::verify(vp, proof, "authentication", resolver).await?;
+
+                    // the challenge and domain bind the proof to the Verifier's
+                    // request
+                    let Some(challenge) = &proof.challenge else {
+                        bail!("proof challenge is required");
+                    };
+                    let Some(Quota::One(domain)) = &proof.domain else {
+                        bail!("proof domain is required");
+                    };
 
                     Ok(Payload::Vp {
                         vp: vp.clone(),
-                        nonce: challenge,
-                        client_id: String::new(),
+                        nonce: challenge.clone(),
+                        client_id: domain.clone(),
                     })
                 }
             }
         }
     }
 }
+
+// Check the verification method used for a proof belongs to the expected
+// controller (the credential's issuer or the presentation's holder).
+fn check_controller(verification_method: &str, controller: &str) -> anyhow::Result<()> {
+    let did = verification_method.split('#').next().unwrap_or_default();
+    let controller = controller.split('#').next().unwrap_or_default();
+    if did != controller {
+        bail!("verification method {verification_method} is not controlled by {controller}");
+    }
+    Ok(())
+}
@@ -46,12 +46,40 @@
 //! (referred to as a Proof Graph) detailing all the claims about the proof
 //! itself:
 
-use std::convert::Infallible;
 use std::str::FromStr;
 
+use anyhow::bail;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
 use vercre_core::Quota;
+use vercre_did::DidResolver;
+use vercre_infosec::{Algorithm, Signer};
+
+use crate::verify_key;
+
+/// JSON-LD context defining Data Integrity proof terms for documents using the
+/// v1 credentials context.
+pub const DATA_INTEGRITY_CONTEXT: &str = "https://w3id.org/security/data-integrity/v2";
+
+/// The proof type for proofs created using a Data Integrity cryptosuite.
+pub const DATA_INTEGRITY_PROOF: &str = "DataIntegrityProof";
+
+/// `EdDSA` (Ed25519) signatures over a JCS-canonicalized document.
+pub const EDDSA_JCS_2022: &str = "eddsa-jcs-2022";
+
+/// `EdDSA` (Ed25519) signatures over an RDF-canonicalized document.
+pub const EDDSA_RDFC_2022: &str = "eddsa-rdfc-2022";
+
+/// ECDSA signatures over a JCS-canonicalized document.
+pub const ECDSA_JCS_2019: &str = "ecdsa-jcs-2019";
+
+/// ECDSA signatures over an RDF-canonicalized document.
+pub const ECDSA_RDFC_2019: &str = "ecdsa-rdfc-2019";
+
+/// Multibase prefix for base58btc-encoded values.
+const MULTIBASE_BASE58BTC: &str = "z";
 
 /// To be verifiable, a credential must contain at least one proof mechanism,
 /// and details necessary to evaluate that proof.
@@ -136,16 +164,285 @@ pub struct Proof {
     // pub extra: Option<HashMap<String, Value>>,
 }
 
-// Unused, but required by 'option_flexvec' deserializer FromStr trait
 impl FromStr for Proof {
-    type Err = Infallible;
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+/// Returns the JCS cryptosuite used to create Data Integrity proofs with the
+/// signing algorithm.
+///
+/// The JCS cryptosuites are used as they do not require a JSON-LD processor to
+/// canonicalize the document.
+///
+/// # Errors
+///
+/// Returns an error for `ES256K`, as the ECDSA cryptosuites only define
+/// signatures using P-256 or P-384 keys.
+pub fn cryptosuite(algorithm: &Algorithm) -> anyhow::Result<&'static str> {
+    match algorithm {
+        Algorithm::EdDSA => Ok(EDDSA_JCS_2022),
+        Algorithm::ES256K => bail!("no Data Integrity cryptosuite supports ES256K"),
+    }
+}
+
+/// Creates a Data Integrity proof securing a credential or presentation.
+///
+/// The proof configuration (`type`, `cryptosuite`, `verificationMethod`,
+/// `proofPurpose`, etc.) is taken from `config`, with the returned proof
+/// containing the signature as its `proofValue`. Any existing proof in the
+/// document is not secured by the new proof.
+///
+/// # Errors
+///
+/// Returns an error if the cryptosuite is not supported or the document
+/// cannot be canonicalized or signed.
+pub async fn create(
+    document: &(impl Serialize + Sync), config: Proof, signer: &impl Signer,
+) -> anyhow::Result<Proof> {
+    if config.type_ != DATA_INTEGRITY_PROOF {
+        bail!("unsupported proof type: {}", config.type_);
+    }
+    let Some(cryptosuite) = config.cryptosuite.as_deref() else {
+        bail!("cryptosuite is required");
+    };
+    match cryptosuite {
+        EDDSA_JCS_2022 => {}
+        ECDSA_JCS_2019 => {
+            bail!("{cryptosuite} requires a P-256 or P-384 key, which is not supported")
+        }
+        EDDSA_RDFC_2022 | ECDSA_RDFC_2019 => {
+            bail!("{cryptosuite} requires RDF dataset canonicalization, which is not supported")
+        }
+        _ => bail!("unsupported cryptosuite: {cryptosuite}"),
+    }
+
+    let document = serde_json::to_value(document)?;
+    let hash_data = hash_data(&document, &config)?;
+    let signature = signer.try_sign(&hash_data).await?;
+
+    Ok(Proof {
+        proof_value: format!("{MULTIBASE_BASE58BTC}{}", bs58::encode(signature).into_string()),
+        ..config
+    })
+}
+
+/// Verifies a Data Integrity proof securing a credential or presentation.
+///
+/// The proof's `verificationMethod` is resolved to the public key used to
+/// check the signature. The proof must have been created for `proof_purpose`
+/// and be within its validity period. Checking the `challenge` and `domain` of
+/// the proof against those expected is left to the caller.
+///
+/// # Errors
+///
+/// Returns an error if the proof is invalid, the cryptosuite is not supported,
+/// or the verification method cannot be resolved.
+pub async fn verify(
+    document: &(impl Serialize + Sync), proof: &Proof, proof_purpose: &str,
+    resolver: &impl DidResolver,
+) -> anyhow::Result<()> {
+    if proof.type_ != DATA_INTEGRITY_PROOF {
+        bail!("unsupported proof type: {}", proof.type_);
+    }
+    match proof.cryptosuite.as_deref() {
+        Some(EDDSA_JCS_2022) => {}
+        Some(cryptosuite) => bail!("unsupported cryptosuite: {cryptosuite}"),
+        None => bail!("cryptosuite is required"),
+    }
+    if proof.proof_purpose != proof_purpose {
+        bail!("proof purpose {} is not {proof_purpose}", proof.proof_purpose);
+    }
+
+    let now = Utc::now();
+    if proof.created.is_some_and(|created| created > now) {
+        bail!("proof was created in the future");
+    }
+    if proof.expires.is_some_and(|expires| expires < now) {
+        bail!("proof has expired");
+    }
+
+    let Some(encoded) = proof.proof_value.strip_prefix(MULTIBASE_BASE58BTC) else {
+        bail!("proof value is not multibase base58btc");
+    };
+    let signature = bs58::decode(encoded).into_vec()?;
 
-    fn from_str(_: &str) -> anyhow::Result<Self, Self::Err> {
-        unimplemented!("Proof::from_str")
+    let resolve = verify_key!(resolver);
+    let jwk = resolve(proof.verification_method.clone()).await?;
+
+    // the proof value is not part of the proof configuration that was signed
+    let config = Proof {
+        proof_value: String::new(),
+        ..proof.clone()
+    };
+    let hash_data = hash_data(&serde_json::to_value(document)?, &config)?;
+    vercre_core::signature::verify(&jwk, &hash_data, &signature)
+}
+
+// The JCS cryptosuites' transformation and hashing algorithms: the SHA-256
+// hash of the canonical proof configuration followed by the SHA-256 hash of
+// the canonical (unsecured) document.
+fn hash_data(document: &Value, config: &Proof) -> anyhow::Result<Vec<u8>> {
+    let mut unsecured = document.clone();
+    if let Value::Object(map) = &mut unsecured {
+        map.remove("proof");
+    }
+
+    // the proof configuration shares the document's context
+    let mut proof_config = serde_json::to_value(config)?;
+    if let Value::Object(map) = &mut proof_config {
+        map.remove("proofValue");
+        if let Some(context) = document.get("@context") {
+            map.insert("@context".into(), context.clone());
+        }
     }
+
+    let config_hash = Sha256::digest(serde_jcs::to_vec(&proof_config)?);
+    let document_hash = Sha256::digest(serde_jcs::to_vec(&unsecured)?);
+    Ok([config_hash.as_slice(), document_hash.as_slice()].concat())
 }
 
-/// Encode proof
-pub fn encode() {
-    todo!()
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+    use ed25519_dalek::{Signer as _, SigningKey, Verifier as _, VerifyingKey};
+    use serde_json::json;
+    use vercre_did::Document;
+
+    use super::*;
+
+    struct Ed25519Signer(SigningKey);
+
+    impl Signer for Ed25519Signer {
+        async fn try_sign(&self, msg: &[u8]) -> anyhow::Result<Vec<u8>> {
+            Ok(self.0.sign(msg).to_bytes().to_vec())
+        }
+
+        async fn public_key(&self) -> anyhow::Result<Vec<u8>> {
+            Ok(self.0.verifying_key().to_bytes().to_vec())
+        }
+
+        fn algorithm(&self) -> Algorithm {
+            Algorithm::EdDSA
+        }
+
+        fn verification_method(&self) -> String {
+            "did:example:issuer#key-0".into()
+        }
+    }
+
+    // Resolution is not needed for proofs rejected before the key is resolved.
+    struct NoResolver;
+
+    impl DidResolver for NoResolver {
+        async fn resolve(&self, url: &str) -> anyhow::Result<Document> {
+            bail!("cannot resolve {url}")
+        }
+    }
+
+    #[tokio::test]
+    async fn create_jcs_proof() {
+        let signer = Ed25519Signer(SigningKey::from_bytes(&[7; 32]));
+        let document = json!({
+            "@context": ["https://www.w3.org/ns/credentials/v2"],
+            "type": ["VerifiableCredential"],
+            "issuer": "did:example:issuer",
+            "credentialSubject": {"name": "Normal Person", "id": "did:example:holder"}
+        });
+        let config = Proof {
+            type_: DATA_INTEGRITY_PROOF.into(),
+            cryptosuite: Some(EDDSA_JCS_2022.into()),
+            proof_purpose: "assertionMethod".into(),
+            verification_method: signer.verification_method(),
+            ..Proof::default()
+        };
+
+        let proof = create(&document, config.clone(), &signer).await.expect("should create");
+        assert_eq!(proof.cryptosuite.as_deref(), Some(EDDSA_JCS_2022));
+
+        // the signature is over the hash of the configuration and document
+        let Some(encoded) = proof.proof_value.strip_prefix(MULTIBASE_BASE58BTC) else {
+            panic!("proof value should be base58btc");
+        };
+        let signature = bs58::decode(encoded).into_vec().expect("should decode");
+        let signature =
+            ed25519_dalek::Signature::from_slice(&signature).expect("should be a signature");
+        let verifying_key: VerifyingKey = signer.0.verifying_key();
+
+        let hash = hash_data(&document, &config).expect("should hash");
+        verifying_key.verify(&hash, &signature).expect("should verify");
+
+        let mut tampered = document;
+        tampered["credentialSubject"]["name"] = json!("Someone Else");
+        let hash = hash_data(&tampered, &config).expect("should hash");
+        assert!(verifying_key.verify(&hash, &signature).is_err());
+
+        // RDF canonicalization is not supported
+        let config = Proof {
+            cryptosuite: Some(EDDSA_RDFC_2022.into()),
+            ..config
+        };
+        assert!(create(&tampered, config, &signer).await.is_err());
+    }
+
+    #[test]
+    fn es256k_unsupported() {
+        assert_eq!(cryptosuite(&Algorithm::EdDSA).expect("should map"), EDDSA_JCS_2022);
+        assert!(cryptosuite(&Algorithm::ES256K).is_err());
+    }
+
+    #[tokio::test]
+    async fn verify_proof_checks() {
+        let signer = Ed25519Signer(SigningKey::from_bytes(&[7; 32]));
+        let document = json!({
+            "@context": ["https://www.w3.org/ns/credentials/v2"],
+            "type": ["VerifiableCredential"],
+            "issuer": "did:example:issuer",
+            "credentialSubject": {"id": "did:example:holder"}
+        });
+        let config = Proof {
+            type_: DATA_INTEGRITY_PROOF.into(),
+            cryptosuite: Some(EDDSA_JCS_2022.into()),
+            proof_purpose: "assertionMethod".into(),
+            verification_method: signer.verification_method(),
+            ..Proof::default()
+        };
+        let proof = create(&document, config, &signer).await.expect("should create");
+
+        // the proof must be used for the purpose it was created for
+        let err = verify(&document, &proof, "authentication", &NoResolver)
+            .await
+            .expect_err("should fail");
+        assert!(err.to_string().contains("proof purpose"));
+
+        let expired = Proof {
+            expires: Some(Utc::now() - TimeDelta::minutes(1)),
+            ..proof.clone()
+        };
+        let err = verify(&document, &expired, "assertionMethod", &NoResolver)
+            .await
+            .expect_err("should fail");
+        assert_eq!(err.to_string(), "proof has expired");
+
+        let future = Proof {
+            created: Some(Utc::now() + TimeDelta::hours(1)),
+            ..proof.clone()
+        };
+        let err = verify(&document, &future, "assertionMethod", &NoResolver)
+            .await
+            .expect_err("should fail");
+        assert_eq!(err.to_string(), "proof was created in the future");
+
+        let rdfc = Proof {
+            cryptosuite: Some(EDDSA_RDFC_2022.into()),
+            ..proof
+        };
+        let err = verify(&document, &rdfc, "assertionMethod", &NoResolver)
+            .await
+            .expect_err("should fail");
+        assert!(err.to_string().contains("unsupported cryptosuite"));
+    }
 }
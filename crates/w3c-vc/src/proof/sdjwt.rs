@@ -1,6 +1,6 @@
 //! # SD-JWT Proofs
 //!
-//! Selective Disclosure for JWTs proofs are a form of enveloping proofs 
+//! Selective Disclosure for JWTs proofs are a form of enveloping proofs
 //! of Credentials based on [SD-JWT].
 //!
 //! The Securing Verifiable Credentials using JOSE and COSE [VC-JOSE-COSE]
@@ -15,5 +15,624 @@
 //! is the payload for SD-JWT; and it is the holder's responsibility to use the SD-JWT
 //!  when presenting the Credential to a verifier using selective disclosure.
 //!
+//! The issued credential uses the compact serialization:
+//!
+//! ```text
+//! <Issuer-signed JWT>~<Disclosure 1>~<Disclosure 2>~...~<Disclosure N>~
+//! ```
+//!
+//! When presenting, the holder discloses a subset of the claims and appends a
+//! Key Binding JWT (KB-JWT) proving possession of the key in the `cnf` claim:
+//!
+//! ```text
+//! <Issuer-signed JWT>~<Disclosure 1>~...~<Disclosure M>~<KB-JWT>
+//! ```
+//!
+//! A Verifier checks the issuer's signature, that each disclosure is
+//! referenced by the issuer-signed JWT, and that the KB-JWT was signed by the
+//! holder for the Verifier's `nonce` and `client_id`.
+//!
+//! The issuer-signed JWT has a `typ` of `vc+sd-jwt` and the KB-JWT a `typ` of
+//! `kb+jwt`.
+//!
+//! Only top-level claims are selectively disclosable. An object-valued claim
+//! (for example, `address`) is disclosed or withheld as a whole: its members
+//! are not individually disclosable and nested `_sd` digests are not
+//! supported.
+//!
 //! [SD-JWT]: https://datatracker.ietf.org/doc/draft-ietf-oauth-selective-disclosure-jwt
-//! [VC-JOSE-COSE]: https://w3c.github.io/vc-jose-cose/
\ No newline at end of file
+//! [VC-JOSE-COSE]: https://w3c.github.io/vc-jose-cose
+
+use anyhow::{anyhow, bail};
+use base64ct::{Base64UrlUnpadded, Encoding};
+use chrono::{DateTime, Utc};
+use rand::{thread_rng, Rng};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use vercre_core::{Kind, Quota};
+use vercre_did::DidResolver;
+use vercre_infosec::jose::jwk::PublicKeyJwk;
+use vercre_infosec::{Algorithm, Signer};
+
+use crate::model::{CredentialSubject, VerifiableCredential};
+use crate::verify_key;
+
+/// The hash algorithm used to digest disclosures.
+pub const SD_ALG: &str = "sha-256";
+
+/// The format identifier of an SD-JWT VC, also used as the `typ` header of its
+/// issuer-signed JWT.
+pub const SD_JWT_TYPE: &str = "vc+sd-jwt";
+
+/// The `typ` header of a Key Binding JWT.
+pub const KB_JWT_TYPE: &str = "kb+jwt";
+
+/// Separator between the components of a compact SD-JWT.
+pub const SEPARATOR: char = '~';
+
+/// The maximum age, in seconds, of a Key Binding JWT accepted by a Verifier.
+///
+/// As for DPoP proofs, the same window is allowed for an `iat` in the future
+/// to tolerate clock skew between Wallet and Verifier.
+pub const KB_JWT_MAX_AGE: i64 = 300;
+
+/// Claims contained in the issuer-signed JWT of an SD-JWT VC.
+///
+/// Selectively disclosable claims are replaced by their digests in `_sd`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[allow(clippy::module_name_repetitions)]
+pub struct SdJwtClaims {
+    /// The Issuer of the Verifiable Credential: the DID controlling the key
+    /// the JWT is signed with.
+    pub iss: String,
+
+    /// The time of issuance of the Verifiable Credential, encoded as a UNIX
+    /// timestamp.
+    pub iat: i64,
+
+    /// The time before which the Verifiable Credential MUST NOT be accepted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nbf: Option<i64>,
+
+    /// The expiry time of the Verifiable Credential.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exp: Option<i64>,
+
+    /// The type of the Verifiable Credential, e.g.
+    /// `https://credentials.example.com/identity_credential`.
+    pub vct: String,
+
+    /// The identifier of the Subject of the Verifiable Credential.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub: Option<String>,
+
+    /// Confirmation method binding the credential to the holder's key.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cnf: Option<KeyBinding>,
+
+    /// Digests of the selectively disclosable claims.
+    #[serde(rename = "_sd")]
+    pub sd: Vec<String>,
+
+    /// The hash algorithm used to generate disclosure digests.
+    #[serde(rename = "_sd_alg")]
+    pub sd_alg: String,
+}
+
+impl SdJwtClaims {
+    /// Represent the SD-JWT VC as a W3C-shaped [`VerifiableCredential`] with
+    /// the disclosed claims as `credentialSubject` claims.
+    ///
+    /// The resulting credential is not secured in any way. It allows SD-JWT
+    /// claims to be queried in the same way as other credentials, for example
+    /// when matching credentials against a Presentation Definition.
+    #[must_use]
+    pub fn to_vc(&self, disclosed: Map<String, Value>) -> VerifiableCredential {
+        VerifiableCredential {
+            type_: Quota::Many(vec!["VerifiableCredential".into(), self.vct.clone()]),
+            issuer: Kind::String(self.iss.clone()),
+            credential_subject: Quota::One(CredentialSubject {
+                id: self.sub.clone(),
+                claims: disclosed,
+            }),
+            valid_from: DateTime::from_timestamp(self.nbf.unwrap_or(self.iat), 0),
+            valid_until: self.exp.and_then(|exp| DateTime::from_timestamp(exp, 0)),
+            ..VerifiableCredential::default()
+        }
+    }
+}
+
+/// Claims contained in a Key Binding JWT.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct KbJwtClaims {
+    /// The Verifier's `nonce` (from the Presentation request).
+    pub nonce: String,
+
+    /// The Verifier's `client_id` (from the Presentation request).
+    pub aud: String,
+
+    /// The time the Key Binding JWT was issued.
+    pub iat: i64,
+
+    /// The base64url-encoded hash of the SD-JWT the Key Binding JWT is bound
+    /// to.
+    pub sd_hash: String,
+}
+
+/// The `cnf` claim containing the holder's public key.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyBinding {
+    /// The holder's public key as a JWK.
+    Jwk(PublicKeyJwk),
+
+    /// A reference to the holder's public key.
+    Kid(String),
+}
+
+/// A salted claim that can be selectively disclosed by the holder.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Disclosure {
+    /// A random value ensuring the digest cannot be guessed from the claim.
+    pub salt: String,
+
+    /// The claim name.
+    pub name: String,
+
+    /// The claim value.
+    pub value: Value,
+}
+
+impl Disclosure {
+    /// Create a new disclosure for the claim using a randomly generated salt.
+    #[must_use]
+    pub fn new(name: impl Into<String>, value: Value) -> Self {
+        let salt = Base64UrlUnpadded::encode_string(&thread_rng().gen::<[u8; 16]>());
+        Self {
+            salt,
+            name: name.into(),
+            value,
+        }
+    }
+
+    /// Base64url-encode the disclosure as a JSON array of salt, name, and
+    /// value.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the claim value cannot be serialized.
+    pub fn encode(&self) -> anyhow::Result<String> {
+        let array = Value::Array(vec![
+            Value::String(self.salt.clone()),
+            Value::String(self.name.clone()),
+            self.value.clone(),
+        ]);
+        Ok(Base64UrlUnpadded::encode_string(&serde_json::to_vec(&array)?))
+    }
+
+    /// Decode a base64url-encoded disclosure.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the disclosure is not a base64url-encoded JSON array
+    /// of salt, name, and value.
+    pub fn decode(encoded: &str) -> anyhow::Result<Self> {
+        let bytes = Base64UrlUnpadded::decode_vec(encoded)
+            .map_err(|e| anyhow!("issue decoding disclosure: {e}"))?;
+        let Value::Array(array) = serde_json::from_slice(&bytes)? else {
+            bail!("disclosure is not an array");
+        };
+        let [Value::String(salt), Value::String(name), value] = array.as_slice() else {
+            bail!("disclosure should contain salt, claim name, and value");
+        };
+
+        Ok(Self {
+            salt: salt.clone(),
+            name: name.clone(),
+            value: value.clone(),
+        })
+    }
+}
+
+/// Generate the `_sd` digest for an encoded disclosure.
+///
+/// The digest is calculated over the encoded disclosure exactly as it appears
+/// in the SD-JWT.
+#[must_use]
+pub fn digest(encoded: &str) -> String {
+    Base64UrlUnpadded::encode_string(&Sha256::digest(encoded.as_bytes()))
+}
+
+/// A compact SD-JWT separated into its components.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[allow(clippy::module_name_repetitions)]
+pub struct SdJwt {
+    /// The issuer-signed JWT.
+    pub jwt: String,
+
+    /// Encoded disclosures, as they appear in the SD-JWT.
+    pub disclosures: Vec<String>,
+
+    /// The Key Binding JWT, when presented by the holder.
+    pub key_binding: Option<String>,
+}
+
+impl SdJwt {
+    /// Parse a compact SD-JWT.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the SD-JWT is not correctly serialized.
+    pub fn parse(sd_jwt: &str) -> anyhow::Result<Self> {
+        let mut parts = sd_jwt.split(SEPARATOR).collect::<Vec<_>>();
+        if parts.len() < 2 {
+            bail!("SD-JWT should end with a '{SEPARATOR}' or a Key Binding JWT");
+        }
+        let jwt = parts.remove(0).to_string();
+        let key_binding = parts.pop().filter(|kb| !kb.is_empty()).map(ToString::to_string);
+        if parts.iter().any(|d| d.is_empty()) {
+            bail!("SD-JWT contains an empty disclosure");
+        }
+
+        Ok(Self {
+            jwt,
+            disclosures: parts.into_iter().map(ToString::to_string).collect(),
+            key_binding,
+        })
+    }
+
+    /// The SD-JWT without the Key Binding JWT. This is the value hashed for
+    /// the Key Binding JWT's `sd_hash` claim.
+    #[must_use]
+    pub fn without_key_binding(&self) -> String {
+        let mut sd_jwt = format!("{}{SEPARATOR}", self.jwt);
+        for disclosure in &self.disclosures {
+            sd_jwt.push_str(disclosure);
+            sd_jwt.push(SEPARATOR);
+        }
+        sd_jwt
+    }
+
+    /// Decode the SD-JWT's disclosures, checking each has a matching digest in
+    /// the issuer-signed claims.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a disclosure cannot be decoded or is not referenced
+    /// by the issuer-signed claims.
+    pub fn disclosed(&self, claims: &SdJwtClaims) -> anyhow::Result<Map<String, Value>> {
+        if claims.sd_alg != SD_ALG {
+            bail!("unsupported _sd_alg: {}", claims.sd_alg);
+        }
+
+        let mut disclosed = Map::new();
+        for encoded in &self.disclosures {
+            if !claims.sd.contains(&digest(encoded)) {
+                bail!("disclosure digest not found in SD-JWT");
+            }
+            let disclosure = Disclosure::decode(encoded)?;
+            if disclosed.insert(disclosure.name.clone(), disclosure.value).is_some() {
+                bail!("claim {} disclosed more than once", disclosure.name);
+            }
+        }
+
+        Ok(disclosed)
+    }
+
+    /// Retain only the disclosures for the named claims.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a disclosure cannot be decoded.
+    pub fn select(&mut self, names: &[String]) -> anyhow::Result<()> {
+        let mut selected = vec![];
+        for encoded in &self.disclosures {
+            if names.contains(&Disclosure::decode(encoded)?.name) {
+                selected.push(encoded.clone());
+            }
+        }
+        self.disclosures = selected;
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for SdJwt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.without_key_binding())?;
+        if let Some(kb) = &self.key_binding {
+            write!(f, "{kb}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The unsecured data used to issue an SD-JWT VC.
+#[derive(Clone, Debug)]
+#[allow(clippy::module_name_repetitions)]
+pub struct SdJwtVc {
+    /// The Credential Issuer's DID. The SD-JWT must be signed with a key it
+    /// controls.
+    pub issuer: String,
+
+    /// The Verifiable Credential type.
+    pub vct: String,
+
+    /// The holder (subject) identifier.
+    pub subject: Option<String>,
+
+    /// Claims to be made selectively disclosable.
+    pub claims: Map<String, Value>,
+
+    /// The holder's public key, used to bind the credential to the holder.
+    pub holder_jwk: Option<PublicKeyJwk>,
+
+    /// The issuance date and time of the Credential.
+    pub issued_at: i64,
+
+    /// The expiry date and time of the Credential.
+    pub expires_at: Option<i64>,
+}
+
+/// Create an SD-JWT VC, returning the compact serialization of the
+/// issuer-signed JWT followed by a disclosure for each top-level claim.
+///
+/// # Errors
+///
+/// Returns an error if the claims cannot be serialized or the signer fails to
+/// sign the JWT.
+pub async fn create(vc: SdJwtVc, signer: impl Signer) -> anyhow::Result<String> {
+    let mut disclosures = vec![];
+    let mut sd = vec![];
+
+    for (name, value) in vc.claims {
+        let encoded = Disclosure::new(name, value).encode()?;
+        sd.push(digest(&encoded));
+        disclosures.push(encoded);
+    }
+    // digests are sorted to hide the original order of claims
+    sd.sort();
+
+    let claims = SdJwtClaims {
+        iss: vc.issuer,
+        iat: vc.issued_at,
+        nbf: None,
+        exp: vc.expires_at,
+        vct: vc.vct,
+        sub: vc.subject,
+        cnf: vc.holder_jwk.map(KeyBinding::Jwk),
+        sd,
+        sd_alg: SD_ALG.into(),
+    };
+    let jwt = sign(SD_JWT_TYPE, &claims, &signer).await?;
+
+    let sd_jwt = SdJwt {
+        jwt,
+        disclosures,
+        key_binding: None,
+    };
+
+    Ok(sd_jwt.to_string())
+}
+
+/// Present an SD-JWT VC, disclosing only the named claims and binding the
+/// presentation to the Verifier using a Key Binding JWT.
+///
+/// # Errors
+///
+/// Returns an error if the SD-JWT cannot be parsed or the signer fails to sign
+/// the Key Binding JWT.
+pub async fn present(
+    issued: &str, names: &[String], client_id: &str, nonce: &str, signer: impl Signer,
+) -> anyhow::Result<String> {
+    let mut sd_jwt = SdJwt::parse(issued)?;
+    sd_jwt.select(names)?;
+
+    let claims = KbJwtClaims {
+        nonce: nonce.into(),
+        aud: client_id.into(),
+        iat: Utc::now().timestamp(),
+        sd_hash: digest(&sd_jwt.without_key_binding()),
+    };
+    sd_jwt.key_binding = Some(key_binding(&claims, &signer).await?);
+
+    Ok(sd_jwt.to_string())
+}
+
+/// Sign a Key Binding JWT with the holder's key.
+///
+/// # Errors
+///
+/// Returns an error if the claims cannot be serialized or the signer fails to
+/// sign the JWT.
+pub async fn key_binding(claims: &KbJwtClaims, signer: &impl Signer) -> anyhow::Result<String> {
+    sign(KB_JWT_TYPE, claims, signer).await
+}
+
+/// Verify a presented SD-JWT VC, returning the disclosed claims as a W3C-shaped
+/// [`VerifiableCredential`].
+///
+/// The issued SD-JWT is verified as for [`verify_issued`] and must be within
+/// its validity period. The Key Binding JWT is verified using the holder key in
+/// the `cnf` claim, must have been issued within [`KB_JWT_MAX_AGE`] seconds
+/// of the current time, and must be bound to the SD-JWT and the Verifier's `client_id` and
+/// `nonce`.
+///
+/// # Errors
+///
+/// Returns an error if the SD-JWT or Key Binding JWT are invalid or cannot be
+/// verified.
+pub async fn verify(
+    presented: &str, client_id: &str, nonce: &str, resolver: &impl DidResolver,
+) -> anyhow::Result<VerifiableCredential> {
+    let sd_jwt = SdJwt::parse(presented)?;
+    let (claims, disclosed) = verify_sd_jwt(&sd_jwt, resolver).await?;
+
+    let now = Utc::now().timestamp();
+    if claims.exp.is_some_and(|exp| exp < now) {
+        bail!("SD-JWT has expired");
+    }
+    if claims.nbf.is_some_and(|nbf| nbf > now) {
+        bail!("SD-JWT is not yet valid");
+    }
+
+    // key binding: the holder's key is taken from the `cnf` claim
+    let Some(kb_jwt) = &sd_jwt.key_binding else {
+        bail!("SD-JWT is missing the Key Binding JWT");
+    };
+    let holder_jwk = match &claims.cnf {
+        Some(KeyBinding::Jwk(jwk)) => jwk.clone(),
+        Some(KeyBinding::Kid(kid)) => verify_key!(resolver)(kid.clone()).await?,
+        None => bail!("SD-JWT is not bound to a holder key"),
+    };
+    let header = decode_header(kb_jwt)?;
+    if header.typ != KB_JWT_TYPE {
+        bail!("invalid Key Binding JWT typ: {}", header.typ);
+    }
+    let kb: KbJwtClaims = verify_jws(kb_jwt, &holder_jwk)?;
+    if (kb.iat - now).abs() > KB_JWT_MAX_AGE {
+        bail!("Key Binding JWT is not fresh");
+    }
+    if kb.nonce != nonce {
+        bail!("Key Binding JWT nonce does not match");
+    }
+    if kb.aud != client_id {
+        bail!("Key Binding JWT audience does not match");
+    }
+    if kb.sd_hash != digest(&sd_jwt.without_key_binding()) {
+        bail!("Key Binding JWT sd_hash does not match");
+    }
+
+    Ok(claims.to_vc(disclosed))
+}
+
+/// Verify an issued SD-JWT VC, as received by the holder, returning the
+/// issuer-signed claims and the claims disclosed.
+///
+/// The issuer's signature is verified using the key referenced by the JWT's
+/// `kid` header, which must belong to the issuer (the DID in `iss`), and each
+/// disclosure must be referenced by a digest in the issuer-signed claims. Any
+/// Key Binding JWT is ignored.
+///
+/// # Errors
+///
+/// Returns an error if the SD-JWT is invalid or cannot be verified.
+pub async fn verify_issued(
+    issued: &str, resolver: &impl DidResolver,
+) -> anyhow::Result<(SdJwtClaims, Map<String, Value>)> {
+    verify_sd_jwt(&SdJwt::parse(issued)?, resolver).await
+}
+
+// Verify the issuer-signed JWT and disclosures of an SD-JWT.
+async fn verify_sd_jwt(
+    sd_jwt: &SdJwt, resolver: &impl DidResolver,
+) -> anyhow::Result<(SdJwtClaims, Map<String, Value>)> {
+    let header = decode_header(&sd_jwt.jwt)?;
+    if header.typ != SD_JWT_TYPE {
+        bail!("invalid SD-JWT typ: {}", header.typ);
+    }
+    let Some(kid) = &header.kid else {
+        bail!("SD-JWT is missing the `kid` header");
+    };
+    let jwk = verify_key!(resolver)(kid.clone()).await?;
+    let claims: SdJwtClaims = verify_jws(&sd_jwt.jwt, &jwk)?;
+
+    // the signing key must be controlled by the issuer
+    if kid.split('#').next() != Some(claims.iss.as_str()) {
+        bail!("issuer key {kid} is not controlled by {}", claims.iss);
+    }
+
+    let disclosed = sd_jwt.disclosed(&claims)?;
+    Ok((claims, disclosed))
+}
+
+// JOSE header of the JWTs making up an SD-JWT.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct Header {
+    alg: Algorithm,
+    typ: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    kid: Option<String>,
+}
+
+// Sign `claims` as a compact JWS with the `typ` header set to `typ`.
+async fn sign<T: Serialize + Sync>(
+    typ: &str, claims: &T, signer: &impl Signer,
+) -> anyhow::Result<String> {
+    let header = Header {
+        alg: signer.algorithm(),
+        typ: typ.into(),
+        kid: Some(signer.verification_method()),
+    };
+    let header = Base64UrlUnpadded::encode_string(&serde_json::to_vec(&header)?);
+    let claims = Base64UrlUnpadded::encode_string(&serde_json::to_vec(claims)?);
+    let payload = format!("{header}.{claims}");
+
+    let signature = signer.try_sign(payload.as_bytes()).await?;
+    Ok(format!("{payload}.{}", Base64UrlUnpadded::encode_string(&signature)))
+}
+
+// Decode the header of a compact JWS without verifying it.
+fn decode_header(compact: &str) -> anyhow::Result<Header> {
+    let Some((header, _)) = compact.split_once('.') else {
+        bail!("invalid JWT: expected 3 segments");
+    };
+    let header = Base64UrlUnpadded::decode_vec(header)
+        .map_err(|e| anyhow!("issue decoding JWT header: {e}"))?;
+    Ok(serde_json::from_slice(&header)?)
+}
+
+// Verify the signature of a compact JWS using `jwk`, returning its claims.
+fn verify_jws<T: DeserializeOwned>(compact: &str, jwk: &PublicKeyJwk) -> anyhow::Result<T> {
+    let parts = compact.split('.').collect::<Vec<_>>();
+    if parts.len() != 3 {
+        bail!("invalid JWT: expected 3 segments");
+    }
+    let signature = Base64UrlUnpadded::decode_vec(parts[2])
+        .map_err(|e| anyhow!("issue decoding JWT signature: {e}"))?;
+    let payload = format!("{}.{}", parts[0], parts[1]);
+    vercre_core::signature::verify(jwk, payload.as_bytes(), &signature)?;
+
+    let claims = Base64UrlUnpadded::decode_vec(parts[1])
+        .map_err(|e| anyhow!("issue decoding JWT claims: {e}"))?;
+    Ok(serde_json::from_slice(&claims)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn disclosure_roundtrip() {
+        let disclosure = Disclosure::new("address", json!({"locality": "Wellington"}));
+        let encoded = disclosure.encode().expect("should encode");
+
+        let decoded = Disclosure::decode(&encoded).expect("should decode");
+        assert_eq!(decoded, disclosure);
+        assert_eq!(digest(&encoded).len(), 43);
+    }
+
+    #[test]
+    fn select_disclosures() {
+        let given = Disclosure::new("given_name", json!("Normal")).encode().expect("should encode");
+        let family =
+            Disclosure::new("family_name", json!("Person")).encode().expect("should encode");
+        let issued = format!("header.claims.signature~{given}~{family}~");
+
+        let mut sd_jwt = SdJwt::parse(&issued).expect("should parse");
+        assert_eq!(sd_jwt.disclosures.len(), 2);
+        assert!(sd_jwt.key_binding.is_none());
+        assert_eq!(sd_jwt.to_string(), issued);
+
+        sd_jwt.select(&["family_name".into()]).expect("should select");
+        assert_eq!(sd_jwt.disclosures, vec![family.clone()]);
+
+        sd_jwt.key_binding = Some("kb.jwt.signature".into());
+        let presented = sd_jwt.to_string();
+        assert_eq!(presented, format!("header.claims.signature~{family}~kb.jwt.signature"));
+        assert_eq!(SdJwt::parse(&presented).expect("should parse"), sd_jwt);
+    }
+}
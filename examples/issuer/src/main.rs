@@ -30,7 +30,8 @@ use vercre_issuer::{
     CredentialOfferRequest, CredentialOfferResponse, CredentialRequest, CredentialResponse,
     DeferredCredentialRequest, DeferredCredentialResponse, MetadataRequest, MetadataResponse,
     NotificationRequest, NotificationResponse, OAuthServerRequest, OAuthServerResponse,
-    PushedAuthorizationRequest, PushedAuthorizationResponse, TokenRequest, TokenResponse,
+    PushedAuthorizationRequest, PushedAuthorizationResponse, StatusListRequest, StatusListResponse,
+    TokenRequest, TokenResponse,
 };
 
 use crate::provider::Provider;
@@ -60,6 +61,7 @@ async fn main() {
         .route("/token", post(token))
         .route("/credential", post(credential))
         .route("/deferred_credential", post(deferred_credential))
+        .route("/status/:list/:shard", get(status_list))
         .layer(TraceLayer::new_for_http())
         .layer(cors)
         .layer(SetResponseHeaderLayer::if_not_present(
@@ -301,6 +303,20 @@ async fn notification(
     vercre_issuer::notification(provider.clone(), req).await.into()
 }
 
+// Status list endpoint
+#[axum::debug_handler]
+async fn status_list(
+    State(provider): State<Provider>, TypedHeader(host): TypedHeader<Host>,
+    Path((list, shard)): Path<(usize, usize)>,
+) -> AxResult<StatusListResponse> {
+    let request = StatusListRequest {
+        credential_issuer: format!("http://{host}"),
+        list,
+        shard,
+    };
+    vercre_issuer::status_list(provider, request).await.into()
+}
+
 /// Token endpoint
 /// RFC 6749: https://tools.ietf.org/html/rfc6749#section-5.1
 ///
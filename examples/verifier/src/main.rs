@@ -21,8 +21,8 @@ use tower_http::trace::TraceLayer;
 use tracing::Level;
 use tracing_subscriber::FmtSubscriber;
 use vercre_verifier::{
-    CreateRequestRequest, CreateRequestResponse, RequestObjectRequest, RequestObjectResponse,
-    ResponseRequest,
+    CreateRequestRequest, CreateRequestResponse, PresentationDefinitionRequest,
+    PresentationDefinitionResponse, RequestObjectRequest, RequestObjectResponse, ResponseRequest,
 };
 
 use crate::provider::Provider;
@@ -38,6 +38,7 @@ async fn main() {
     let router = Router::new()
         .route("/create_request", post(create_request))
         .route("/request/:object_id", get(request_object))
+        .route("/presentation_definition/:object_id", get(presentation_definition))
         .route("/callback", get(response))
         .route("/post", post(response))
         .layer(TraceLayer::new_for_http())
@@ -72,6 +73,19 @@ async fn request_object(
     vercre_verifier::request_object(provider, &request).await.into()
 }
 
+// Retrieve a Presentation Definition passed by reference
+#[axum::debug_handler]
+async fn presentation_definition(
+    State(provider): State<Provider>, TypedHeader(host): TypedHeader<Host>,
+    Path(object_id): Path<String>,
+) -> AxResult<PresentationDefinitionResponse> {
+    let request = PresentationDefinitionRequest {
+        client_id: format!("http://{host}"),
+        id: object_id,
+    };
+    vercre_verifier::presentation_definition(provider, &request).await.into()
+}
+
 // Wallet Authorization response endpoint
 #[axum::debug_handler]
 async fn response(
@@ -1,11 +1,16 @@
+use std::time::Duration;
+
+use anyhow::anyhow;
 use chrono::{DateTime, Utc};
+use reqwest::redirect::Policy;
+use reqwest::Url;
 use serde::de::DeserializeOwned;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use vercre_test_utils::store::keystore::VerifierKeystore;
 use vercre_test_utils::store::{presentation, resolver, state};
 use vercre_verifier::provider::{
-    Algorithm, Decryptor, DidResolver, Document, Encryptor, Metadata, Result, SecOps, Signer,
-    StateStore, Verifier, Wallet,
+    Algorithm, Decryptor, DidResolver, Document, Encryptor, KeyAgreement, Kind, Metadata, Result,
+    SecOps, Signer, StateStore, Status, VerifiableCredential, Verifier, Wallet,
 };
 
 #[derive(Default, Clone, Debug)]
@@ -60,6 +65,12 @@ impl DidResolver for Provider {
     }
 }
 
+impl KeyAgreement for Provider {
+    async fn shared_secret(&self, _kid: Option<&str>, sender_public_key: &[u8]) -> Result<Vec<u8>> {
+        VerifierKeystore::shared_secret(sender_public_key)
+    }
+}
+
 struct VerifierSec(VerifierKeystore);
 
 impl SecOps for Provider {
@@ -95,17 +106,57 @@ impl Signer for VerifierSec {
 }
 
 impl Encryptor for VerifierSec {
-    async fn encrypt(&self, _plaintext: &[u8], _recipient_public_key: &[u8]) -> Result<Vec<u8>> {
-        todo!()
+    async fn encrypt(&self, plaintext: &[u8], recipient_public_key: &[u8]) -> Result<Vec<u8>> {
+        VerifierKeystore::encrypt(plaintext, recipient_public_key)
     }
 
     fn public_key(&self) -> Vec<u8> {
-        todo!()
+        VerifierKeystore::encryption_key().expect("should derive encryption key")
     }
 }
 
 impl Decryptor for VerifierSec {
-    async fn decrypt(&self, _ciphertext: &[u8], _sender_public_key: &[u8]) -> Result<Vec<u8>> {
-        todo!()
+    async fn decrypt(&self, ciphertext: &[u8], sender_public_key: &[u8]) -> Result<Vec<u8>> {
+        VerifierKeystore::decrypt(ciphertext, sender_public_key)
+    }
+}
+
+// Origins of the issuers whose status lists are trusted. The
+// `statusListCredential` URL is taken from the presented credential, so it must
+// not be fetched from anywhere else.
+const TRUSTED_ISSUERS: [&str; 1] = ["http://localhost:8080"];
+
+// Limits on fetching a status list.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_RESPONSE_BYTES: usize = 1024 * 1024;
+
+// Status lists are fetched from the issuer's status list endpoint, which
+// returns the signed list credential.
+impl Status for Provider {
+    async fn status_list(&self, url: &str) -> Result<Kind<VerifiableCredential>> {
+        #[derive(Deserialize)]
+        struct StatusListResponse {
+            credential: String,
+        }
+
+        let url = Url::parse(url)?;
+        if !TRUSTED_ISSUERS.contains(&url.origin().ascii_serialization().as_str()) {
+            return Err(anyhow!("status list {url} is not published by a trusted issuer"));
+        }
+
+        // redirects could lead away from the trusted issuer
+        let client =
+            reqwest::Client::builder().timeout(FETCH_TIMEOUT).redirect(Policy::none()).build()?;
+        let mut response = client.get(url).send().await?.error_for_status()?;
+
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if body.len() + chunk.len() > MAX_RESPONSE_BYTES {
+                return Err(anyhow!("status list response is too large"));
+            }
+            body.extend_from_slice(&chunk);
+        }
+        let response: StatusListResponse = serde_json::from_slice(&body)?;
+        Ok(Kind::String(response.credential))
     }
 }
@@ -13,7 +13,8 @@ use futures::lock::Mutex;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use vercre_holder::provider::{
-    Algorithm, DidResolver, Document, HolderProvider, Result, Signer, StateStore,
+    Algorithm, Decryptor, DidResolver, Document, Encryptor, HolderProvider, KeyAgreement, Result,
+    Signer, StateStore,
 };
 use vercre_test_utils::store::keystore::HolderKeystore;
 use vercre_test_utils::store::resolver;
@@ -81,3 +82,25 @@ impl Signer for Provider {
         HolderKeystore::verification_method()
     }
 }
+
+impl Encryptor for Provider {
+    async fn encrypt(&self, plaintext: &[u8], recipient_public_key: &[u8]) -> Result<Vec<u8>> {
+        HolderKeystore::encrypt(plaintext, recipient_public_key)
+    }
+
+    fn public_key(&self) -> Vec<u8> {
+        HolderKeystore::encryption_key().expect("should derive encryption key")
+    }
+}
+
+impl Decryptor for Provider {
+    async fn decrypt(&self, ciphertext: &[u8], sender_public_key: &[u8]) -> Result<Vec<u8>> {
+        HolderKeystore::decrypt(ciphertext, sender_public_key)
+    }
+}
+
+impl KeyAgreement for Provider {
+    async fn shared_secret(&self, _kid: Option<&str>, sender_public_key: &[u8]) -> Result<Vec<u8>> {
+        HolderKeystore::shared_secret(sender_public_key)
+    }
+}
@@ -1,7 +1,9 @@
 use http::header::{ACCEPT, CONTENT_TYPE};
 use tauri_plugin_http::reqwest;
 use vercre_holder::provider::Verifier;
-use vercre_holder::{RequestObjectResponse, ResponseRequest, ResponseResponse};
+use vercre_holder::{
+    PresentationDefinitionResponse, RequestObjectResponse, ResponseRequest, ResponseResponse,
+};
 
 use super::Provider;
 
@@ -21,6 +23,22 @@ impl Verifier for Provider {
         Ok(response)
     }
 
+    /// Get a Presentation Definition passed by reference.
+    async fn presentation_definition(
+        &self, uri: &str,
+    ) -> anyhow::Result<PresentationDefinitionResponse> {
+        let client = reqwest::Client::new();
+        let result = client.get(uri).header(ACCEPT, "application/json").send().await?;
+        let response = match result.json::<PresentationDefinitionResponse>().await {
+            Ok(response) => response,
+            Err(e) => {
+                log::error!("Error getting presentation definition: {}", e);
+                return Err(e.into());
+            }
+        };
+        Ok(response)
+    }
+
     /// Send the presentation to the verifier.
     async fn present(
         &self, uri: Option<&str>, presentation: &ResponseRequest,
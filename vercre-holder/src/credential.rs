@@ -7,9 +7,15 @@ use std::ops::Deref;
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use vercre_core::Quota;
+use vercre_dif_exch::Claims;
 use vercre_openid::issuer::CredentialDisplay;
 use vercre_w3c_vc::model::VerifiableCredential;
 
+/// The format of an ISO mdoc credential.
+pub(crate) const MDOC_FORMAT: &str = "mso_mdoc";
+
 /// The Credential model contains information about a credential owned by the
 /// Wallet.
 #[derive(Clone, Default, Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -22,15 +28,20 @@ pub struct Credential {
     /// The credential issuer.
     pub issuer: String,
 
-    /// The unpacked Verifiable Credential. Used to display VC details and for
-    /// `JSONPath` Presentation Definition queries.
+    /// The unpacked Verifiable Credential. Used to display VC details and, for
+    /// all but mdoc credentials, `JSONPath` Presentation Definition queries.
     pub vc: VerifiableCredential,
 
     /// The Verifiable Credential as issued, for use in Presentation
-    /// Submissions. This could be a base64-encoded JWT or 'stringified'
-    /// JSON.
+    /// Submissions. This could be a base64-encoded JWT, a compact SD-JWT, or
+    /// 'stringified' JSON.
     pub issued: String,
 
+    /// The format of the issued credential. For example, `jwt_vc_json`,
+    /// `vc+sd-jwt`, or `mso_mdoc`.
+    #[serde(default)]
+    pub format: String,
+
     /// The date the credential was issued.
     pub issuance_date: DateTime<Utc>,
 
@@ -53,6 +64,32 @@ impl Deref for Credential {
     }
 }
 
+/// Serializes to the JSON queried by Presentation Definition constraints.
+///
+/// An mdoc credential is queried in the same form the Verifier sees it once
+/// presented: an object with the document type as `docType` and the data
+/// elements of each namespace under the namespace's name. Other credentials are
+/// queried as their unpacked Verifiable Credential.
+impl Claims for Credential {
+    fn to_json(&self) -> anyhow::Result<Value> {
+        if self.format != MDOC_FORMAT {
+            return self.vc.to_json();
+        }
+
+        // the document type is the credential's most specific type
+        let doc_type = match &self.vc.type_ {
+            Quota::One(type_) => Some(type_),
+            Quota::Many(types) => types.last(),
+        };
+        let mut claims = match &self.vc.credential_subject {
+            Quota::One(subject) => subject.claims.clone(),
+            Quota::Many(_) => anyhow::bail!("mdoc credential has more than one subject"),
+        };
+        claims.insert("docType".into(), Value::String(doc_type.cloned().unwrap_or_default()));
+        Ok(Value::Object(claims))
+    }
+}
+
 /// Logo information for a credential.
 #[derive(Clone, Default, Debug, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename = "EncodedLogo")]
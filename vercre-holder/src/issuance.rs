@@ -8,6 +8,7 @@ pub(crate) mod authorize;
 pub(crate) mod cancel;
 pub(crate) mod credentials;
 pub(crate) mod deferred;
+pub(crate) mod dpop;
 pub(crate) mod offer;
 pub(crate) mod pin;
 pub(crate) mod save;
@@ -28,9 +29,10 @@ use serde::{Deserialize, Serialize};
 pub use token::{token, AuthorizedCredentials};
 use uuid::Uuid;
 use vercre_issuer::{
-    AuthorizationDetail, CredentialOffer, MetadataRequest, OAuthServerRequest, TokenResponse,
+    AuthorizationDetail, CredentialOffer, MetadataRequest, NotificationEvent, NotificationRequest,
+    OAuthServerRequest, TokenResponse,
 };
-use vercre_openid::issuer::{Issuer, Server};
+use vercre_openid::issuer::{Issuer, Server, TokenType};
 
 use crate::credential::Credential;
 use crate::provider::{HolderProvider, Issuer as IssuerProvider};
@@ -79,6 +81,9 @@ pub struct Issuance {
     /// The `TokenResponse` received from the issuer.
     pub token: TokenResponse,
 
+    /// The most recent nonce provided by the issuer for use in DPoP proofs.
+    pub dpop_nonce: Option<String>,
+
     /// Requested scope for scope-based authorization.
     pub scope: Option<String>,
 
@@ -139,6 +144,43 @@ impl Issuance {
     pub fn add_credential(&mut self, credential: Credential) {
         self.credentials.push(credential);
     }
+
+    /// Notifies the issuer of an event for the credential identified by
+    /// `notification_id`, attaching a DPoP proof if the access token is
+    /// DPoP-bound.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the provider's notification request fails.
+    pub async fn notify(
+        &mut self, provider: &impl HolderProvider, notification_id: &str, event: NotificationEvent,
+        event_description: &str,
+    ) -> anyhow::Result<()> {
+        let request = NotificationRequest {
+            credential_issuer: self.issuer.credential_issuer.clone(),
+            access_token: self.token.access_token.clone(),
+            dpop: None,
+            notification_id: notification_id.into(),
+            event,
+            event_description: Some(event_description.into()),
+        };
+        let use_dpop = self.token.token_type == TokenType::DPoP;
+        let endpoint = self.issuer.notification_endpoint.clone().unwrap_or_default();
+        dpop::send(
+            provider,
+            use_dpop,
+            &endpoint,
+            Some(&self.token.access_token),
+            &mut self.dpop_nonce,
+            move |dpop| {
+                let mut request = request.clone();
+                request.dpop = dpop;
+                IssuerProvider::notification(provider, request)
+            },
+        )
+        .await?;
+        Ok(())
+    }
 }
 
 /// Issuance flow status values.
@@ -21,7 +21,7 @@ use vercre_issuer::{
     TokenGrantType, TokenRequest,
 };
 
-use super::{Issuance, Status};
+use super::{dpop, Issuance, Status};
 use crate::issuance::token::{authorized_credentials, AuthorizedCredentials};
 use crate::provider::{HolderProvider, Issuer, StateStore};
 
@@ -189,10 +189,29 @@ pub async fn authorize(
     // Construct a token request using the authorization response and request an
     // access token from the issuer.
     let token_request = token_request(&issuance, request, &auth_response);
-    issuance.token = Issuer::token(&provider, token_request).await.map_err(|e| {
+    let use_dpop = dpop::supported(&provider, &issuance.authorization_server);
+    let token_endpoint = issuance.authorization_server.oauth.token_endpoint.clone();
+    let p = &provider;
+    issuance.token = dpop::send(
+        &provider,
+        use_dpop,
+        &token_endpoint,
+        None,
+        &mut issuance.dpop_nonce,
+        move |dpop| {
+            let mut token_request = token_request.clone();
+            token_request.dpop = dpop;
+            Issuer::token(p, token_request)
+        },
+    )
+    .await
+    .map_err(|e| {
         tracing::error!(target: "Endpoint::authorize", ?e);
         e
     })?;
+    if issuance.token.dpop_nonce.is_some() {
+        issuance.dpop_nonce.clone_from(&issuance.token.dpop_nonce);
+    }
     issuance.status = Status::TokenReceived;
 
     let mut response = AuthorizedCredentials {
@@ -298,6 +317,7 @@ fn token_request(
         authorization_details: issuance.accepted.clone(),
         // TODO: support this
         client_assertion: None,
+        dpop: None,
     }
 }
 
@@ -6,10 +6,10 @@
 
 use serde::{Deserialize, Serialize};
 use tracing::instrument;
-use vercre_issuer::{NotificationEvent, NotificationRequest};
+use vercre_issuer::NotificationEvent;
 
 use super::Issuance;
-use crate::provider::{HolderProvider, Issuer, StateStore};
+use crate::provider::{HolderProvider, StateStore};
 
 /// Cancel request.
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
@@ -30,25 +30,22 @@ pub async fn cancel(
 ) -> anyhow::Result<String> {
     tracing::debug!("Endpoint::cancel");
 
-    let issuance: Issuance =
+    let mut issuance: Issuance =
         StateStore::get(&provider, &request.issuance_id).await.map_err(|e| {
             tracing::error!(target: "Endpoint::cancel", ?e);
             e
         })?;
 
     // Notify issuer if we have been given a notification ID.
-    if let Some(notification_id) = issuance.notification_id {
-        if let Err(e) = Issuer::notification(
-            &provider,
-            NotificationRequest {
-                credential_issuer: issuance.issuer.credential_issuer.clone(),
-                access_token: issuance.token.access_token.clone(),
-                notification_id,
-                event: NotificationEvent::CredentialDeleted,
-                event_description: Some("Issuance cancelled".into()),
-            },
-        )
-        .await
+    if let Some(notification_id) = issuance.notification_id.clone() {
+        if let Err(e) = issuance
+            .notify(
+                &provider,
+                &notification_id,
+                NotificationEvent::CredentialDeleted,
+                "Issuance cancelled",
+            )
+            .await
         {
             tracing::error!(target: "Endpoint::cancel", ?e);
             return Err(e);
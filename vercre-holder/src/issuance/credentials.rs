@@ -5,23 +5,27 @@
 use std::collections::HashMap;
 
 use anyhow::{anyhow, bail};
+use base64ct::{Base64UrlUnpadded, Encoding};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use tracing::instrument;
-use vercre_core::Kind;
+use vercre_core::{Kind, Quota};
+use vercre_infosec::jose::jwk::{Curve, KeyType, PublicKeyJwk};
 use vercre_infosec::jose::jws::{self, Type};
 use vercre_issuer::{CredentialAuthorization, CredentialIssuance, Format, SingleProof};
 use vercre_macros::credential_request;
 use vercre_openid::issuer::{
-    CredentialConfiguration, CredentialRequest, CredentialResponse, CredentialResponseType, Proof,
-    ProofClaims,
+    CredentialConfiguration, CredentialRequest, CredentialResponse, CredentialResponseEncryption,
+    CredentialResponseType, Issuer as IssuerMetadata, Proof, ProofClaims, TokenType,
 };
-use vercre_w3c_vc::model::VerifiableCredential;
-use vercre_w3c_vc::proof::{Payload, Verify};
+use vercre_openid::jwe;
+use vercre_w3c_vc::model::{CredentialSubject, VerifiableCredential};
+use vercre_w3c_vc::proof::{sdjwt, Payload, Verify};
+use vercre_w3c_vc::verify_key;
 
-use super::{Issuance, Status};
-use crate::credential::Credential;
-use crate::provider::{HolderProvider, Issuer, StateStore};
+use super::{dpop, Issuance, Status};
+use crate::credential::{Credential, Logo};
+use crate::provider::{Encryptor, HolderProvider, Issuer, StateStore};
 
 /// `CredentialsRequest` provides the issuance flow ID and an optional set of
 /// credential identifiers to the `credentials` endpoint.
@@ -160,12 +164,29 @@ async fn credential_by_format(
         proof: Some(Proof::Single {
             proof_type: SingleProof::Jwt { jwt: jwt.into() },
         }),
-        ..Default::default()
+        credential_response_encryption: response_encryption(&provider, &issuance.issuer),
+        dpop: None,
     };
-    let cred_res = Issuer::credential(&provider, request).await.map_err(|e| {
+    let use_dpop = issuance.token.token_type == TokenType::DPoP;
+    let p = &provider;
+    let cred_res = dpop::send(
+        &provider,
+        use_dpop,
+        &issuance.issuer.credential_endpoint,
+        Some(&issuance.token.access_token),
+        &mut issuance.dpop_nonce,
+        move |dpop| {
+            let mut request = request.clone();
+            request.dpop = dpop;
+            Issuer::credential(p, request)
+        },
+    )
+    .await
+    .map_err(|e| {
         tracing::error!(target: "Endpoint::credentials", ?e);
         e
     })?;
+    let cred_res = decrypt_response(&provider, cred_res).await?;
     match process_credential_response(provider.clone(), config, &cred_res).await {
         Ok((credentials, transaction_id)) => {
             if let Some(credentials) = credentials {
@@ -217,7 +238,7 @@ async fn credential_by_identifier(
                     continue;
                 }
             }
-            let request = credential_request!({
+            let mut request = credential_request!({
                 "credential_issuer": issuance.issuer.credential_issuer.clone(),
                 "access_token": issuance.token.access_token.clone(),
                 "credential_identifier": cred_id.to_string(),
@@ -226,7 +247,24 @@ async fn credential_by_identifier(
                     "jwt": jwt.to_string()
                 }
             });
-            let cred_res = Issuer::credential(&provider, request).await?;
+            request.credential_response_encryption =
+                response_encryption(&provider, &issuance.issuer);
+            let use_dpop = issuance.token.token_type == TokenType::DPoP;
+            let p = &provider;
+            let cred_res = dpop::send(
+                &provider,
+                use_dpop,
+                &issuance.issuer.credential_endpoint,
+                Some(&issuance.token.access_token),
+                &mut issuance.dpop_nonce,
+                move |dpop| {
+                    let mut request = request.clone();
+                    request.dpop = dpop;
+                    Issuer::credential(p, request)
+                },
+            )
+            .await?;
+            let cred_res = decrypt_response(&provider, cred_res).await?;
             match process_credential_response(provider.clone(), config, &cred_res).await {
                 Ok((credentials, transaction_id)) => {
                     if let Some(credentials) = credentials {
@@ -276,14 +314,60 @@ pub async fn process_credential_response(
             Ok((Some(credentials), None))
         }
         CredentialResponseType::TransactionId(id) => Ok((None, Some(id.clone()))),
+        CredentialResponseType::Encrypted(_) => bail!("credential response is encrypted"),
     }
 }
 
+/// Ask for the credential response to be encrypted whenever the issuer
+/// supports an algorithm pair usable with the wallet's X25519 key.
+fn response_encryption(
+    provider: &impl HolderProvider, issuer: &IssuerMetadata,
+) -> Option<CredentialResponseEncryption> {
+    let supported = issuer.credential_response_encryption.as_ref()?;
+    if !supported.alg_values_supported.iter().any(|alg| alg == jwe::ECDH_ES) {
+        return None;
+    }
+    let enc = [jwe::A256GCM, jwe::A128GCM]
+        .into_iter()
+        .find(|enc| supported.enc_values_supported.iter().any(|e| e == enc))?;
+
+    Some(CredentialResponseEncryption {
+        jwk: PublicKeyJwk {
+            kty: KeyType::Okp,
+            crv: Curve::X25519,
+            x: Base64UrlUnpadded::encode_string(&Encryptor::public_key(provider)),
+            ..PublicKeyJwk::default()
+        },
+        alg: jwe::ECDH_ES.into(),
+        enc: enc.into(),
+    })
+}
+
+/// Decrypt the credential response if the issuer returned it encrypted,
+/// otherwise return it unchanged.
+pub async fn decrypt_response(
+    provider: &impl HolderProvider, resp: CredentialResponse,
+) -> anyhow::Result<CredentialResponse> {
+    let CredentialResponseType::Encrypted(compact) = &resp.response else {
+        return Ok(resp);
+    };
+    jwe::decrypt(compact, provider).await.map_err(|e| {
+        tracing::error!(target: "Endpoint::credentials", ?e);
+        anyhow!("issue decrypting credential response: {e}")
+    })
+}
+
 /// Construct a credential from a credential response.
 async fn credential(
     provider: &impl HolderProvider, config: &CredentialConfiguration,
     vc_kind: &Kind<VerifiableCredential>,
 ) -> anyhow::Result<Credential> {
+    match &config.format {
+        Format::VcSdJwt(_) => return sd_jwt_credential(provider, config, vc_kind).await,
+        Format::IsoMdl(_) => return mdoc_credential(provider, config, vc_kind).await,
+        _ => {}
+    }
+
     let Payload::Vc { vc, issued_at } = vercre_w3c_vc::proof::verify(Verify::Vc(vc_kind), provider)
         .await
         .map_err(|e| anyhow!("issue parsing credential: {e}"))?
@@ -309,23 +393,97 @@ async fn credential(
         issuer: issuer_id.clone(),
         vc: vc.clone(),
         issued: token.into(),
+        format: config.format.to_string(),
         issuance_date,
         display: config.display.clone(),
 
         ..Credential::default()
     };
-
-    // Base64-encoded logo if possible.
-    if let Some(display) = &config.display {
-        // TODO: Locale?
-        if let Some(logo_info) = &display[0].logo {
-            if let Some(uri) = &logo_info.uri {
-                if let Ok(logo) = Issuer::logo(provider, uri).await {
-                    storable_credential.logo = Some(logo);
-                }
-            }
-        }
-    }
+    storable_credential.logo = logo(provider, config).await;
 
     Ok(storable_credential)
 }
+
+/// Construct a credential from an SD-JWT VC credential response. The SD-JWT is
+/// verified to detect tampering and its disclosed claims are unpacked into a
+/// `VerifiableCredential` so they can be displayed and queried in the same way
+/// as other credentials.
+async fn sd_jwt_credential(
+    provider: &impl HolderProvider, config: &CredentialConfiguration,
+    vc_kind: &Kind<VerifiableCredential>,
+) -> anyhow::Result<Credential> {
+    let Kind::String(issued) = vc_kind else {
+        bail!("credential is not an SD-JWT");
+    };
+    let (claims, disclosed) = sdjwt::verify_issued(issued, provider)
+        .await
+        .map_err(|e| anyhow!("issue verifying credential: {e}"))?;
+
+    let Some(issuance_date) = DateTime::from_timestamp(claims.iat, 0) else {
+        bail!("invalid issuance date");
+    };
+
+    Ok(Credential {
+        id: format!("urn:uuid:{}", uuid::Uuid::new_v4()),
+        issuer: claims.iss.clone(),
+        vc: claims.to_vc(disclosed),
+        issued: issued.clone(),
+        format: config.format.to_string(),
+        issuance_date,
+        display: config.display.clone(),
+        logo: logo(provider, config).await,
+    })
+}
+
+/// Construct a credential from an ISO mdoc credential response. The mdoc is
+/// verified to detect tampering and its data elements, keyed by namespace, are
+/// unpacked into a `VerifiableCredential` for display and querying.
+async fn mdoc_credential(
+    provider: &impl HolderProvider, config: &CredentialConfiguration,
+    vc_kind: &Kind<VerifiableCredential>,
+) -> anyhow::Result<Credential> {
+    let Kind::String(issued) = vc_kind else {
+        bail!("credential is not an mdoc");
+    };
+    let document = vercre_iso_mdl::verify(issued, verify_key!(provider))
+        .await
+        .map_err(|e| anyhow!("issue verifying credential: {e}"))?;
+
+    let validity = &document.validity_info;
+    let parse = |ts: &str| DateTime::parse_from_rfc3339(ts).map(|dt| dt.with_timezone(&Utc));
+    let issuance_date =
+        parse(&validity.signed).map_err(|e| anyhow!("invalid issuance date: {e}"))?;
+
+    // an issuer identified by certificate chain has no DID
+    let issuer_did = document.key_id.as_deref().and_then(|kid| kid.split('#').next());
+
+    let vc = VerifiableCredential {
+        type_: Quota::Many(vec!["VerifiableCredential".into(), document.doc_type.clone()]),
+        issuer: Kind::String(issuer_did.unwrap_or_default().to_string()),
+        credential_subject: Quota::One(CredentialSubject {
+            id: None,
+            claims: document.name_spaces,
+        }),
+        valid_from: parse(&validity.valid_from).ok(),
+        valid_until: parse(&validity.valid_until).ok(),
+        ..VerifiableCredential::default()
+    };
+
+    Ok(Credential {
+        id: format!("urn:uuid:{}", uuid::Uuid::new_v4()),
+        issuer: issuer_did.unwrap_or_default().to_string(),
+        vc,
+        issued: issued.clone(),
+        format: config.format.to_string(),
+        issuance_date,
+        display: config.display.clone(),
+        logo: logo(provider, config).await,
+    })
+}
+
+// Base64-encoded logo if possible.
+async fn logo(provider: &impl HolderProvider, config: &CredentialConfiguration) -> Option<Logo> {
+    // TODO: Locale?
+    let uri = config.display.as_ref()?.first()?.logo.as_ref()?.uri.as_ref()?;
+    Issuer::logo(provider, uri).await.ok()
+}
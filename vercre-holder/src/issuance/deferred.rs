@@ -7,9 +7,12 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use tracing::instrument;
 use vercre_issuer::DeferredCredentialRequest;
+use vercre_openid::issuer::TokenType;
 
-use super::{Issuance, Status};
-use crate::issuance::credentials::{process_credential_response, CredentialsResponse};
+use super::{dpop, Issuance, Status};
+use crate::issuance::credentials::{
+    decrypt_response, process_credential_response, CredentialsResponse,
+};
 use crate::provider::{HolderProvider, Issuer, StateStore};
 
 /// Deferred credential request.
@@ -54,11 +57,34 @@ pub async fn deferred(
         transaction_id: request.transaction_id.clone(),
         credential_issuer: issuance.issuer.credential_issuer.clone(),
         access_token: issuance.token.access_token.clone(),
+        dpop: None,
     };
-    let deferred_response = Issuer::deferred(&provider, def_cred_request).await.map_err(|e| {
+    let Some(deferred_endpoint) = &issuance.issuer.deferred_credential_endpoint else {
+        let e = anyhow!("issuer does not support deferred issuance");
+        tracing::error!(target: "Endpoint::deferred", ?e);
+        return Err(e);
+    };
+    let use_dpop = issuance.token.token_type == TokenType::DPoP;
+    let p = &provider;
+    let deferred_response = dpop::send(
+        &provider,
+        use_dpop,
+        deferred_endpoint,
+        Some(&issuance.token.access_token),
+        &mut issuance.dpop_nonce,
+        move |dpop| {
+            let mut def_cred_request = def_cred_request.clone();
+            def_cred_request.dpop = dpop;
+            Issuer::deferred(p, def_cred_request)
+        },
+    )
+    .await
+    .map_err(|e| {
         tracing::error!(target: "Endpoint::deferred", ?e);
         e
     })?;
+    let credential_response =
+        decrypt_response(&provider, deferred_response.credential_response).await?;
 
     let Some(config) = issuance
         .issuer
@@ -70,13 +96,7 @@ pub async fn deferred(
         return Err(e);
     };
 
-    match process_credential_response(
-        provider.clone(),
-        config,
-        &deferred_response.credential_response,
-    )
-    .await
-    {
+    match process_credential_response(provider.clone(), config, &credential_response).await {
         Ok((credentials, transaction_id)) => {
             if let Some(credentials) = credentials {
                 issuance.credentials.extend(credentials);
@@ -0,0 +1,81 @@
+//! # DPoP
+//!
+//! When the issuer's authorization server supports DPoP ([RFC9449]), the
+//! wallet sends a proof of possession of its signing key with the token
+//! request so the access token issued is bound to that key. Each request made
+//! with the token must then carry a fresh proof.
+//!
+//! The issuer may require proofs to include a nonce it provides. The latest
+//! nonce is kept in the issuance state and a request rejected for want of a
+//! (fresh) nonce is retried once using the nonce from the error.
+//!
+//! [RFC9449]: (https://www.rfc-editor.org/rfc/rfc9449.html)
+
+use std::future::Future;
+
+use chrono::Utc;
+use uuid::Uuid;
+use vercre_infosec::Signer;
+use vercre_openid::dpop::{self, DpopClaims};
+use vercre_openid::issuer::Server;
+use vercre_openid::Error;
+
+use crate::provider::HolderProvider;
+
+/// Whether the authorization server issues DPoP-bound access tokens using the
+/// wallet's signing algorithm.
+pub fn supported(signer: &impl Signer, server: &Server) -> bool {
+    let Some(algs) = &server.oauth.dpop_signing_alg_values_supported else {
+        return false;
+    };
+    let Ok(serde_json::Value::String(alg)) = serde_json::to_value(signer.algorithm()) else {
+        return false;
+    };
+    algs.contains(&alg)
+}
+
+/// Send a request to the issuer endpoint `htu`, attaching a DPoP proof when
+/// `enabled`. The proof includes a hash of `access_token`, if any.
+///
+/// `send` is called with the proof to make the request. If the issuer rejects
+/// the proof for want of a nonce, `nonce` is updated with the one provided and
+/// the request is retried.
+pub async fn send<T, Fut>(
+    provider: &impl HolderProvider, enabled: bool, htu: &str, access_token: Option<&str>,
+    nonce: &mut Option<String>, send: impl Fn(Option<String>) -> Fut,
+) -> anyhow::Result<T>
+where
+    Fut: Future<Output = anyhow::Result<T>>,
+{
+    if !enabled {
+        return send(None).await;
+    }
+
+    let proof = create_proof(provider, htu, access_token, nonce.clone()).await?;
+    let err = match send(Some(proof)).await {
+        Ok(response) => return Ok(response),
+        Err(e) => e,
+    };
+    let Some(Error::UseDpopNonce { dpop_nonce, .. }) = err.downcast_ref::<Error>() else {
+        return Err(err);
+    };
+
+    nonce.replace(dpop_nonce.clone());
+    let proof = create_proof(provider, htu, access_token, nonce.clone()).await?;
+    send(Some(proof)).await
+}
+
+// Create a DPoP proof for a POST request to `htu`.
+async fn create_proof(
+    provider: &impl HolderProvider, htu: &str, access_token: Option<&str>, nonce: Option<String>,
+) -> anyhow::Result<String> {
+    let claims = DpopClaims {
+        jti: Uuid::new_v4().to_string(),
+        htm: "POST".into(),
+        htu: htu.into(),
+        iat: Utc::now().timestamp(),
+        ath: access_token.map(dpop::access_token_hash),
+        nonce,
+    };
+    dpop::encode(&claims, provider).await
+}
@@ -7,10 +7,10 @@
 
 use serde::{Deserialize, Serialize};
 use tracing::instrument;
-use vercre_issuer::{NotificationEvent, NotificationRequest};
+use vercre_issuer::NotificationEvent;
 
 use super::Issuance;
-use crate::provider::{CredentialStorer, HolderProvider, Issuer, StateStore};
+use crate::provider::{CredentialStorer, HolderProvider, StateStore};
 
 /// Save request.
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
@@ -42,24 +42,18 @@ pub async fn save(provider: impl HolderProvider, request: &SaveRequest) -> anyho
                 tracing::error!(target: "Endpoint::save", ?e);
                 // Notify issuer of failure if possible. If notification fails, do
                 // nothing except trace and return the original error.
-                if let Some(notification_id) = issuance.notification_id {
-                    async {
-                        if let Err(err) = Issuer::notification(
+                if let Some(notification_id) = issuance.notification_id.clone() {
+                    if let Err(err) = issuance
+                        .notify(
                             &provider,
-                            NotificationRequest {
-                                credential_issuer: issuance.issuer.credential_issuer.clone(),
-                                access_token: issuance.token.access_token.clone(),
-                                notification_id,
-                                event: NotificationEvent::CredentialFailure,
-                                event_description: Some("Failed to save credential".into()),
-                            },
+                            &notification_id,
+                            NotificationEvent::CredentialFailure,
+                            "Failed to save credential",
                         )
                         .await
-                        {
-                            tracing::error!(target: "Endpoint::save", ?err);
-                        }
+                    {
+                        tracing::error!(target: "Endpoint::save", ?err);
                     }
-                    .await;
                 }
                 return Err(e);
             }
@@ -68,18 +62,15 @@ pub async fn save(provider: impl HolderProvider, request: &SaveRequest) -> anyho
     issuance.credentials.clear();
 
     // Notify issuer if we have been given a notification ID.
-    if let Some(notification_id) = issuance.notification_id {
-        if let Err(e) = Issuer::notification(
-            &provider,
-            NotificationRequest {
-                credential_issuer: issuance.issuer.credential_issuer.clone(),
-                access_token: issuance.token.access_token.clone(),
-                notification_id,
-                event: NotificationEvent::CredentialAccepted,
-                event_description: Some("Issuance completed".into()),
-            },
-        )
-        .await
+    if let Some(notification_id) = issuance.notification_id.clone() {
+        if let Err(e) = issuance
+            .notify(
+                &provider,
+                &notification_id,
+                NotificationEvent::CredentialAccepted,
+                "Issuance completed",
+            )
+            .await
         {
             tracing::error!(target: "Endpoint::save", ?e);
             return Err(e);
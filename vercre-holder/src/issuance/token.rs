@@ -12,7 +12,7 @@ use serde::{Deserialize, Serialize};
 use tracing::instrument;
 use vercre_issuer::{AuthorizedDetail, CredentialAuthorization, TokenGrantType, TokenRequest};
 
-use super::{Issuance, Status};
+use super::{dpop, Issuance, Status};
 use crate::provider::{HolderProvider, Issuer, StateStore};
 
 /// `AuthorizedCredentials` is the response from the `token` endpoint.
@@ -71,10 +71,29 @@ pub async fn token(
         tracing::error!(target: "Endpoint::token", ?e);
         e
     })?;
-    issuance.token = Issuer::token(&provider, token_request).await.map_err(|e| {
+    let use_dpop = dpop::supported(&provider, &issuance.authorization_server);
+    let token_endpoint = issuance.authorization_server.oauth.token_endpoint.clone();
+    let p = &provider;
+    issuance.token = dpop::send(
+        &provider,
+        use_dpop,
+        &token_endpoint,
+        None,
+        &mut issuance.dpop_nonce,
+        move |dpop| {
+            let mut token_request = token_request.clone();
+            token_request.dpop = dpop;
+            Issuer::token(p, token_request)
+        },
+    )
+    .await
+    .map_err(|e| {
         tracing::error!(target: "Endpoint::token", ?e);
         e
     })?;
+    if issuance.token.dpop_nonce.is_some() {
+        issuance.dpop_nonce.clone_from(&issuance.token.dpop_nonce);
+    }
     issuance.status = Status::TokenReceived;
 
     let mut response = AuthorizedCredentials {
@@ -119,6 +138,7 @@ fn token_request(issuance: &Issuance) -> anyhow::Result<TokenRequest> {
         authorization_details: issuance.accepted.clone(),
         // TODO: support this
         client_assertion: None,
+        dpop: None,
     })
 }
 
@@ -56,7 +56,8 @@ pub use vercre_openid::issuer::{
     PreAuthorizedCodeGrant, ProfileClaims, Proof, ProofClaims, TokenRequest, TokenResponse, TxCode,
 };
 pub use vercre_openid::verifier::{
-    RequestObject, RequestObjectRequest, RequestObjectResponse, ResponseRequest, ResponseResponse,
+    PresentationDefinitionRequest, PresentationDefinitionResponse, RequestObject,
+    RequestObjectRequest, RequestObjectResponse, ResponseRequest, ResponseResponse, VpToken,
 };
 
 pub use crate::credential::{Credential, Logo};
@@ -16,7 +16,7 @@ use chrono::{DateTime, Utc};
 pub use present::present;
 pub use request::request;
 use serde::{Deserialize, Serialize};
-use vercre_dif_exch::{Constraints, PresentationSubmission};
+use vercre_dif_exch::PresentationSubmission;
 use vercre_openid::verifier::RequestObject;
 
 use crate::credential::Credential;
@@ -40,10 +40,6 @@ pub struct Presentation {
     /// Definition).
     pub credentials: Vec<Credential>,
 
-    /// The `JSONPath` query used to match credentials to the verifier's
-    /// request.
-    pub filter: Constraints,
-
     /// The presentation submission token.
     pub submission: PresentationSubmission,
 }
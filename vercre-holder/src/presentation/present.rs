@@ -2,17 +2,33 @@
 //!
 //! The `present` endpoint creates a presentation submission, signs it, and
 //! sends it to the verifier.
+//!
+//! When the verifier requests the Response Mode "`direct_post.jwt`", the
+//! response is encrypted with the key published in the verifier's client
+//! metadata so no personal information is posted in the clear.
+
+use std::collections::{BTreeMap, HashMap};
 
 use anyhow::{anyhow, bail};
+use serde_json::Value;
 use tracing::instrument;
 use uuid::Uuid;
 use vercre_core::Kind;
-use vercre_dif_exch::{DescriptorMap, FilterValue, PathNested, PresentationSubmission};
-use vercre_openid::verifier::{ResponseRequest, ResponseResponse};
+use vercre_dif_exch::{
+    Constraints, DescriptorMap, Directive, InputDescriptor, PathNested, PresentationDefinition,
+    PresentationSubmission,
+};
+use vercre_iso_mdl::SessionTranscript;
+use vercre_openid::jwe;
+use vercre_openid::verifier::{
+    ResponseRequest, ResponseResponse, Verifier as VerifierMetadata, VpToken,
+};
 use vercre_w3c_vc::model::vp::VerifiablePresentation;
+use vercre_w3c_vc::proof::sdjwt::{self, SD_JWT_TYPE as SD_JWT_FORMAT};
 use vercre_w3c_vc::proof::{self, Payload, W3cFormat};
 
 use super::{Presentation, Status};
+use crate::credential::{Credential, MDOC_FORMAT};
 use crate::provider::{HolderProvider, Signer, Verifier};
 
 /// Creates a presentation submission, signs it and sends it to the verifier.
@@ -35,40 +51,54 @@ pub async fn present(
         return Err(e);
     }
 
-    // Construct a presentation submission.
-    let submission = create_submission(&presentation).map_err(|e| {
-        tracing::error!(target: "Endpoint::present", ?e);
-        e
-    })?;
-    presentation.submission.clone_from(&submission);
-
-    // create vp
     let kid = Signer::verification_method(&provider);
     let holder_did = kid.split('#').collect::<Vec<&str>>()[0];
 
-    let vp = create_vp(&presentation, holder_did).map_err(|e| {
+    // Select a credential for each input descriptor to be submitted.
+    let pd = match &presentation.request.presentation_definition {
+        Some(Kind::Object(pd)) => pd,
+        Some(Kind::String(_)) => {
+            let e = anyhow!("presentation definition has not been retrieved");
+            tracing::error!(target: "Endpoint::present", ?e);
+            return Err(e);
+        }
+        None => {
+            let e = anyhow!("only presentation definition requests are supported");
+            tracing::error!(target: "Endpoint::present", ?e);
+            return Err(e);
+        }
+    };
+    let selection = select_credentials(pd, &presentation.credentials).map_err(|e| {
         tracing::error!(target: "Endpoint::present", ?e);
         e
     })?;
 
-    let payload = Payload::Vp {
-        vp,
-        client_id: presentation.request.client_id.clone(),
-        nonce: presentation.request.nonce.clone(),
-    };
-    let jwt =
-        proof::create(W3cFormat::JwtVcJson, payload, provider.clone()).await.map_err(|e| {
-            tracing::error!(target: "Endpoint::present", ?e);
-            e
-        })?;
+    // Construct the VP token and a presentation submission describing where
+    // each credential can be found in it.
+    let (vp_token, locations) =
+        create_vp_token(provider.clone(), &presentation, holder_did, &selection).await.map_err(
+            |e| {
+                tracing::error!(target: "Endpoint::present", ?e);
+                e
+            },
+        )?;
+    let submission = create_submission(pd, &selection, &locations, vp_token.len());
+    presentation.submission.clone_from(&submission);
 
     // Assemble the presentation response to the verifier and ask the wallet client
     // to send it.
-    let res_req = ResponseRequest {
-        vp_token: Some(vec![Kind::String(jwt)]),
+    let mut res_req = ResponseRequest {
+        vp_token: Some(VpToken::Presentations(vp_token)),
         presentation_submission: Some(submission),
         state: presentation.request.state.clone(),
+        response: None,
     };
+    if presentation.request.response_mode.as_deref() == Some("direct_post.jwt") {
+        res_req = seal(&presentation.request.client_metadata, &res_req).map_err(|e| {
+            tracing::error!(target: "Endpoint::present", ?e);
+            e
+        })?;
+    }
     let res_uri =
         presentation.request.response_uri.map(|uri| uri.trim_end_matches('/').to_string());
     let response =
@@ -80,57 +110,321 @@ pub async fn present(
     Ok(response)
 }
 
-/// Create a presentation submission from the presentation request and matched
-/// credentials.
-fn create_submission(presentation: &Presentation) -> anyhow::Result<PresentationSubmission> {
-    let request = presentation.request.clone();
-    let pd = match &request.presentation_definition {
-        Kind::Object(pd) => pd,
-        Kind::String(_) => bail!("presentation_definition_uri is unsupported"),
+/// Encrypt the response for the verifier, returning a request carrying only the
+/// encrypted `response` parameter.
+fn seal(verifier: &VerifierMetadata, res_req: &ResponseRequest) -> anyhow::Result<ResponseRequest> {
+    let Some(alg) = &verifier.authorization_encrypted_response_alg else {
+        bail!("verifier has not specified a response encryption algorithm");
+    };
+    // the default `enc`, "A128CBC-HS256", is not supported
+    let Some(enc) = verifier.authorization_encrypted_response_enc.as_deref() else {
+        bail!("unsupported enc: the default A128CBC-HS256 is not supported");
+    };
+    if enc != jwe::A128GCM && enc != jwe::A256GCM {
+        bail!("unsupported enc: {enc}");
+    }
+
+    // use the first key not reserved for signing that supports the algorithms
+    let keys = verifier.oauth.jwks.as_ref().map(|jwks| jwks.keys.as_slice()).unwrap_or_default();
+    let Some(jwk) = keys
+        .iter()
+        .filter(|jwk| jwk.use_.as_deref() != Some("sig"))
+        .find(|jwk| jwe::check(jwk, alg, enc).is_ok())
+    else {
+        bail!("verifier has not published a key supporting {alg} and {enc}");
     };
 
-    let mut desc_map: Vec<DescriptorMap> = vec![];
-    for n in 0..pd.input_descriptors.len() {
-        let in_desc = &pd.input_descriptors[n];
-        let dm = DescriptorMap {
-            id: in_desc.id.clone(),
-            path: "$".to_string(),
-            path_nested: PathNested {
-                format: "jwt_vc_json".to_string(),
-                // URGENT: index matched VCs not input descriptors!!
-                path: "$.verifiableCredential[0]".to_string(),
-            },
-            format: "jwt_vc_json".to_string(),
+    let compact = jwe::encrypt(res_req, jwk, alg, enc)?;
+    Ok(ResponseRequest {
+        response: Some(compact),
+        ..ResponseRequest::default()
+    })
+}
+
+/// The location of a presented credential in the VP token.
+#[derive(Clone, Debug)]
+struct Location {
+    /// Index of the VP token entry containing the credential.
+    index: usize,
+
+    /// Format of the VP token entry.
+    format: String,
+
+    /// Format and location of the credential within the VP token entry.
+    path_nested: PathNested,
+}
+
+/// An input descriptor to be submitted along with the index of the credential
+/// selected to satisfy it.
+type Selection<'a> = Vec<(&'a InputDescriptor, usize)>;
+
+/// Select the input descriptors to submit and a credential to satisfy each.
+///
+/// Without submission requirements, every input descriptor must be satisfied.
+/// Otherwise, the fewest input descriptors needed to satisfy the definition's
+/// submission requirements are submitted.
+fn select_credentials<'a>(
+    pd: &'a PresentationDefinition, credentials: &[Credential],
+) -> anyhow::Result<Selection<'a>> {
+    // use the first combination where each input descriptor can be satisfied
+    // by a credential presentable within the descriptor's constraints
+    let mut empty = None;
+    'combinations: for combination in pd.combinations(credentials)? {
+        let mut selection = vec![];
+        for matched in combination {
+            let Some(input) = pd.input_descriptors.iter().find(|input| input.id == matched.id)
+            else {
+                bail!("input descriptor {} not found", matched.id);
+            };
+            let Some(n) = matched
+                .candidates
+                .into_iter()
+                .find(|&n| can_present(&input.constraints, &credentials[n]))
+            else {
+                continue 'combinations;
+            };
+            selection.push((input, n));
+        }
+        // prefer submitting something over nothing
+        if !selection.is_empty() {
+            return Ok(selection);
+        }
+        empty = Some(selection);
+    }
+    if let Some(selection) = empty {
+        return Ok(selection);
+    }
+
+    if pd.submission_requirements.is_some() {
+        bail!("submission requirements cannot be satisfied");
+    }
+    let unmatched = pd.input_descriptors.iter().find(|input| {
+        !credentials.iter().any(|c| {
+            input.constraints.satisfied(c).unwrap_or_default() && can_present(&input.constraints, c)
+        })
+    });
+    let id = unmatched.map(|input| input.id.as_str()).unwrap_or_default();
+    bail!("no credential satisfies input descriptor {id}");
+}
+
+/// Whether the credential can be presented without disclosing more than the
+/// constraints allow.
+///
+/// Predicates require support for zero-knowledge proofs, so no credential can
+/// satisfy a field requiring one. When disclosure must be limited, only SD-JWT
+/// and mdoc credentials, which allow claims to be selectively disclosed, can be
+/// used.
+fn can_present(constraints: &Constraints, credential: &Credential) -> bool {
+    if constraints.predicate_required() {
+        return false;
+    }
+    constraints.limit_disclosure != Some(Directive::Required)
+        || [SD_JWT_FORMAT, MDOC_FORMAT].contains(&credential.format.as_str())
+}
+
+/// Create the VP token from the selected credentials.
+///
+/// W3C credentials are wrapped in a single Verifiable Presentation signed as a
+/// JWT. Each SD-JWT credential is presented as its own VP token entry,
+/// disclosing only the claims needed to satisfy the Verifier's constraints and
+/// bound to the Verifier with a Key Binding JWT. Likewise, each mdoc credential
+/// is presented as a `DeviceResponse` containing only the data elements needed,
+/// authenticated by the device over the request's session transcript.
+///
+/// Returns the VP token entries along with the location of each selected
+/// credential, keyed by the credential's index.
+async fn create_vp_token(
+    provider: impl HolderProvider, presentation: &Presentation, holder_did: &str,
+    selection: &Selection<'_>,
+) -> anyhow::Result<(Vec<Kind<VerifiablePresentation>>, HashMap<usize, Location>)> {
+    let client_id = &presentation.request.client_id;
+    let nonce = &presentation.request.nonce;
+
+    // each selected credential is presented once, even when it satisfies more
+    // than one input descriptor
+    let mut selected = vec![];
+    for &(_, n) in selection {
+        if !selected.contains(&n) {
+            selected.push(n);
+        }
+    }
+
+    let mut vp_token = vec![];
+    let mut locations = HashMap::new();
+
+    let w3c = selected
+        .iter()
+        .copied()
+        .filter(|&n| {
+            ![SD_JWT_FORMAT, MDOC_FORMAT].contains(&presentation.credentials[n].format.as_str())
+        })
+        .collect::<Vec<_>>();
+
+    if !w3c.is_empty() {
+        let vp = create_vp(presentation, holder_did, &w3c)?;
+        let payload = Payload::Vp {
+            vp,
+            client_id: client_id.clone(),
+            nonce: nonce.clone(),
+        };
+        let jwt = proof::create(W3cFormat::JwtVcJson, payload, provider.clone()).await?;
+
+        for (i, n) in w3c.into_iter().enumerate() {
+            locations.insert(
+                n,
+                Location {
+                    index: vp_token.len(),
+                    format: "jwt_vp_json".into(),
+                    path_nested: PathNested {
+                        format: "jwt_vc_json".into(),
+                        path: format!("$.verifiableCredential[{i}]"),
+                    },
+                },
+            );
+        }
+        vp_token.push(Kind::String(jwt));
+    }
+
+    for n in selected {
+        let credential = &presentation.credentials[n];
+        let inputs = selection.iter().filter(|(_, m)| *m == n).map(|(input, _)| *input);
+
+        let presented = match credential.format.as_str() {
+            SD_JWT_FORMAT => {
+                // disclose the claims needed by every input descriptor the
+                // credential satisfies
+                let mut names = vec![];
+                for input in inputs {
+                    for name in disclosures(&input.constraints, credential)? {
+                        if !names.contains(&name) {
+                            names.push(name);
+                        }
+                    }
+                }
+                sdjwt::present(&credential.issued, &names, client_id, nonce, provider.clone())
+                    .await?
+            }
+            MDOC_FORMAT => {
+                let mut requested = BTreeMap::new();
+                for input in inputs {
+                    for (name_space, name) in data_elements(&input.constraints, credential)? {
+                        let names: &mut Vec<String> = requested.entry(name_space).or_default();
+                        if !names.contains(&name) {
+                            names.push(name);
+                        }
+                    }
+                }
+                let transcript = SessionTranscript::openid4vp(
+                    client_id,
+                    nonce,
+                    presentation.request.response_uri.as_deref(),
+                )?;
+                vercre_iso_mdl::present(
+                    &credential.issued,
+                    &requested,
+                    &transcript,
+                    provider.clone(),
+                )
+                .await?
+            }
+            _ => continue,
         };
-        desc_map.push(dm);
+
+        locations.insert(
+            n,
+            Location {
+                index: vp_token.len(),
+                format: credential.format.clone(),
+                path_nested: PathNested {
+                    format: credential.format.clone(),
+                    path: "$".into(),
+                },
+            },
+        );
+        vp_token.push(Kind::String(presented));
     }
-    let submission = PresentationSubmission {
+
+    Ok((vp_token, locations))
+}
+
+/// The names of SD-JWT claims to disclose in order to satisfy the Verifier's
+/// constraints.
+fn disclosures(filter: &Constraints, credential: &Credential) -> anyhow::Result<Vec<String>> {
+    let paths = filter.matched_paths(&credential.vc)?;
+    let names = paths
+        .into_iter()
+        .filter_map(|path| match path.as_slice() {
+            [subject, name, ..] if subject == "credentialSubject" => Some(name.clone()),
+            _ => None,
+        })
+        .collect();
+    Ok(names)
+}
+
+/// The data elements of an mdoc credential, as (namespace, element identifier)
+/// pairs, to present in order to satisfy the Verifier's constraints.
+fn data_elements(
+    filter: &Constraints, credential: &Credential,
+) -> anyhow::Result<Vec<(String, String)>> {
+    let paths = filter.matched_paths(credential)?;
+    let elements = paths
+        .into_iter()
+        .filter_map(|path| match path.as_slice() {
+            [name_space, name, ..] => Some((name_space.clone(), name.clone())),
+            _ => None,
+        })
+        .collect();
+    Ok(elements)
+}
+
+/// Create a presentation submission mapping each selected input descriptor to
+/// the location of its credential in the VP token.
+fn create_submission(
+    pd: &PresentationDefinition, selection: &Selection<'_>, locations: &HashMap<usize, Location>,
+    token_len: usize,
+) -> PresentationSubmission {
+    let descriptor_map = selection
+        .iter()
+        .map(|(input, n)| {
+            let location = &locations[n];
+
+            // a single VP token entry is not wrapped in an array
+            let path =
+                if token_len == 1 { "$".to_string() } else { format!("$[{}]", location.index) };
+
+            DescriptorMap {
+                id: input.id.clone(),
+                path,
+                path_nested: location.path_nested.clone(),
+                format: location.format.clone(),
+            }
+        })
+        .collect();
+
+    PresentationSubmission {
         id: Uuid::new_v4().to_string(),
         definition_id: pd.id.clone(),
-        descriptor_map: desc_map,
-    };
-    Ok(submission)
+        descriptor_map,
+    }
 }
 
-/// Construct a Verifiable Presentation.
+/// Construct a Verifiable Presentation containing the specified credentials.
 fn create_vp(
-    presentation: &Presentation, holder_did: impl Into<String>,
+    presentation: &Presentation, holder_did: impl Into<String>, credentials: &[usize],
 ) -> anyhow::Result<VerifiablePresentation> {
-    // presentation with 2 VCs: one as JSON, one as base64url encoded JWT
     let mut builder = VerifiablePresentation::builder()
         .add_context(Kind::String("https://www.w3.org/2018/credentials/examples/v1".into()))
         .holder(holder_did);
 
-    let pd = match &presentation.request.presentation_definition {
-        Kind::Object(pd) => pd,
-        Kind::String(_) => bail!("presentation_definition_uri is unsupported"),
+    let Some(Kind::Object(pd)) = &presentation.request.presentation_definition else {
+        bail!("presentation definition has not been retrieved");
     };
 
     for input in &pd.input_descriptors {
         if let Some(fields) = &input.constraints.fields {
             for field in fields {
                 if let Some(filter) = &field.filter {
-                    if let FilterValue::Const(val) = &filter.value {
+                    if let Some(Value::String(val)) = &filter.const_ {
                         builder = builder.add_type(val.clone());
                     }
                 }
@@ -138,8 +432,8 @@ fn create_vp(
         }
     }
 
-    for c in &presentation.credentials {
-        builder = builder.add_credential(Kind::String(c.issued.clone()));
+    for &i in credentials {
+        builder = builder.add_credential(Kind::String(presentation.credentials[i].issued.clone()));
     }
     builder.build()
 }
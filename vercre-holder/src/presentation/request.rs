@@ -10,7 +10,6 @@ use tracing::instrument;
 use uuid::Uuid;
 pub use vercre_core::urlencode;
 use vercre_core::Kind;
-use vercre_dif_exch::Constraints;
 use vercre_infosec::jose::jws;
 use vercre_openid::verifier::{RequestObject, RequestObjectResponse, RequestObjectType};
 use vercre_w3c_vc::verify_key;
@@ -56,7 +55,8 @@ pub async fn request(
     };
 
     // Parse or get-then-parse the presentation request
-    let req_obj = if request.contains("&presentation_definition") {
+    let embedded = request.contains("&presentation_definition") || request.contains("&dcql_query");
+    let mut req_obj = if embedded {
         urlencode::from_str::<RequestObject>(request).map_err(|e| {
             tracing::error!(target: "Endpoint::request", ?e);
             anyhow!("issue parsing RequestObject: {e}")
@@ -72,16 +72,23 @@ pub async fn request(
             e
         })?
     };
+
+    // retrieve a Presentation Definition passed by reference
+    if let Some(Kind::String(uri)) = &req_obj.presentation_definition {
+        let response = Verifier::presentation_definition(&provider, uri).await.map_err(|e| {
+            tracing::error!(target: "Endpoint::request", ?e);
+            e
+        })?;
+        req_obj.presentation_definition = Some(Kind::Object(response.presentation_definition));
+    }
     presentation.request.clone_from(&req_obj);
 
     // Get the credentials from the holder's credential store that match the
     // verifier's request.
-    let filter = build_filter(&req_obj).map_err(|e| {
+    let credentials = find_credentials(&provider, &req_obj).await.map_err(|e| {
         tracing::error!(target: "Endpoint::request", ?e);
         e
     })?;
-    presentation.filter.clone_from(&filter);
-    let credentials = CredentialStorer::find(&provider, Some(filter)).await?;
     presentation.credentials.clone_from(&credentials);
 
     // Stash the presentation flow for subsequent steps
@@ -115,18 +122,29 @@ async fn parse_request_object_response(
     Ok(jwt.claims)
 }
 
-/// Construct a credential filter (`JSONPath`) from the presentation definition
-/// contained in the presentation request.
-// TODO: How to handle multiple input descriptors?
-fn build_filter(request: &RequestObject) -> anyhow::Result<Constraints> {
+/// Find the credentials in the holder's credential store that satisfy at least
+/// one of the input descriptors in the presentation request.
+async fn find_credentials(
+    provider: &impl CredentialStorer, request: &RequestObject,
+) -> anyhow::Result<Vec<Credential>> {
     let pd = match &request.presentation_definition {
-        Kind::Object(pd) => pd,
-        Kind::String(_) => bail!("presentation_definition_uri is unsupported"),
+        Some(Kind::Object(pd)) => pd,
+        Some(Kind::String(_)) => bail!("presentation definition has not been retrieved"),
+        None => bail!("only presentation definition requests are supported"),
     };
     if pd.input_descriptors.is_empty() {
         bail!("no input descriptors found");
     }
-    let constraints = pd.input_descriptors[0].constraints.clone();
 
-    Ok(constraints)
+    let mut credentials: Vec<Credential> = vec![];
+    for input in &pd.input_descriptors {
+        let matched = CredentialStorer::find(provider, Some(input.constraints.clone())).await?;
+        for credential in matched {
+            if !credentials.iter().any(|c| c.id == credential.id) {
+                credentials.push(credential);
+            }
+        }
+    }
+
+    Ok(credentials)
 }
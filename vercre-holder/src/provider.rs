@@ -10,15 +10,18 @@ use std::future::Future;
 pub use vercre_did::{DidResolver, Document};
 pub use vercre_dif_exch::Constraints;
 pub use vercre_infosec::jose::jwk::PublicKeyJwk;
-pub use vercre_infosec::{Algorithm, Signer};
+pub use vercre_infosec::{Algorithm, Decryptor, Encryptor, Signer};
 pub use vercre_issuer::{
     AuthorizationRequest, AuthorizationResponse, CredentialRequest, CredentialResponse,
     DeferredCredentialRequest, DeferredCredentialResponse, MetadataRequest, MetadataResponse,
     NotificationRequest, NotificationResponse, OAuthServerRequest, OAuthServerResponse,
     TokenRequest, TokenResponse, TxCode,
 };
+pub use vercre_openid::jwe::KeyAgreement;
 pub use vercre_openid::provider::{Result, StateStore};
-use vercre_openid::verifier::{RequestObjectResponse, ResponseRequest, ResponseResponse};
+use vercre_openid::verifier::{
+    PresentationDefinitionResponse, RequestObjectResponse, ResponseRequest, ResponseResponse,
+};
 
 use crate::credential::{Credential, Logo};
 
@@ -26,7 +29,16 @@ use crate::credential::{Credential, Logo};
 /// by holder clients.
 #[allow(clippy::module_name_repetitions)]
 pub trait HolderProvider:
-    Issuer + Verifier + CredentialStorer + StateStore + Signer + DidResolver + Clone
+    Issuer
+    + Verifier
+    + CredentialStorer
+    + StateStore
+    + Signer
+    + Encryptor
+    + Decryptor
+    + KeyAgreement
+    + DidResolver
+    + Clone
 {
 }
 
@@ -88,6 +100,12 @@ pub trait Verifier {
         &self, req: &str,
     ) -> impl Future<Output = anyhow::Result<RequestObjectResponse>> + Send;
 
+    /// Get a Presentation Definition passed by reference in a request object's
+    /// `presentation_definition_uri`.
+    fn presentation_definition(
+        &self, uri: &str,
+    ) -> impl Future<Output = anyhow::Result<PresentationDefinitionResponse>> + Send;
+
     /// Send the presentation to the verifier.
     fn present(
         &self, uri: Option<&str>, presentation: &ResponseRequest,
@@ -6,6 +6,7 @@ mod provider;
 use std::sync::LazyLock;
 
 use insta::assert_yaml_snapshot as assert_snapshot;
+use vercre_core::Quota;
 use vercre_holder::issuance::{
     AcceptRequest, CredentialsRequest, OfferRequest, PinRequest, SaveRequest,
 };
@@ -107,6 +108,8 @@ async fn preauth() {
     assert_eq!(credentials.len(), 1);
 
     assert_snapshot!("credentials", credentials, {
+        "[].id" => "[id]",
+        "[].vc.id" => "[id]",
         "[].vc.validFrom" => "[validFrom]",
         "[].vc" => insta::sorted_redaction(),
         "[].vc.credentialSubject" => insta::sorted_redaction(),
@@ -118,3 +121,142 @@ async fn preauth() {
         "[].issuance_date" => "[issuance_date]",
     });
 }
+
+// Test end-to-end pre-authorized issuance of an ISO mdoc credential. The mdoc's
+// data elements are verified and unpacked on receipt.
+#[tokio::test]
+async fn preauth_mdoc() {
+    let issuer_provider = issuer::Provider::new();
+    let holder_provider = holder::Provider::new(Some(issuer_provider.clone()), None);
+
+    let request = create_offer_request!({
+        "credential_issuer": CREDENTIAL_ISSUER,
+        "credential_configuration_ids": ["org.iso.18013.5.1.mDL"],
+        "subject_id": NORMAL_USER,
+        "grant_types": ["urn:ietf:params:oauth:grant-type:pre-authorized_code"],
+        "tx_code_required": false,
+        "send_type": SendType::ByVal,
+    });
+    let offer_resp =
+        vercre_issuer::create_offer(issuer_provider, request).await.expect("should get offer");
+    let OfferType::Object(offer) = offer_resp.offer_type else {
+        panic!("expected CredentialOfferType::Object");
+    };
+
+    let offer_req = OfferRequest {
+        client_id: CLIENT_ID.into(),
+        subject_id: NORMAL_USER.into(),
+        offer,
+    };
+    let issuance = vercre_holder::issuance::offer(holder_provider.clone(), &offer_req)
+        .await
+        .expect("should process offer");
+    let accept_req = AcceptRequest {
+        issuance_id: issuance.issuance_id.clone(),
+        accept: None,
+    };
+    vercre_holder::issuance::accept(holder_provider.clone(), &accept_req)
+        .await
+        .expect("should accept offer");
+    vercre_holder::issuance::token(holder_provider.clone(), &issuance.issuance_id)
+        .await
+        .expect("should get token");
+
+    let cred_req = CredentialsRequest {
+        issuance_id: issuance.issuance_id.clone(),
+        ..Default::default()
+    };
+    vercre_holder::issuance::credentials(holder_provider.clone(), &cred_req)
+        .await
+        .expect("should get credentials");
+    vercre_holder::issuance::save(
+        holder_provider.clone(),
+        &SaveRequest {
+            issuance_id: issuance.issuance_id.clone(),
+        },
+    )
+    .await
+    .expect("should save credentials");
+
+    let credentials =
+        CredentialStorer::find(&holder_provider, None).await.expect("should retrieve credentials");
+    assert_eq!(credentials.len(), 1);
+
+    let credential = &credentials[0];
+    assert_eq!(credential.format, "mso_mdoc");
+    assert_eq!(credential.issuer, "did:web:demo.credibil.io");
+
+    let Quota::One(subject) = &credential.vc.credential_subject else {
+        panic!("expected a single credential subject");
+    };
+    assert_eq!(subject.claims["org.iso.18013.5.1"]["family_name"], "Person");
+}
+
+// Test end-to-end pre-authorized issuance of an SD-JWT VC credential. The
+// SD-JWT is verified and its disclosed claims unpacked on receipt.
+#[tokio::test]
+async fn preauth_sd_jwt() {
+    let issuer_provider = issuer::Provider::new();
+    let holder_provider = holder::Provider::new(Some(issuer_provider.clone()), None);
+
+    let request = create_offer_request!({
+        "credential_issuer": CREDENTIAL_ISSUER,
+        "credential_configuration_ids": ["EmployeeID_SD_JWT"],
+        "subject_id": NORMAL_USER,
+        "grant_types": ["urn:ietf:params:oauth:grant-type:pre-authorized_code"],
+        "tx_code_required": false,
+        "send_type": SendType::ByVal,
+    });
+    let offer_resp =
+        vercre_issuer::create_offer(issuer_provider, request).await.expect("should get offer");
+    let OfferType::Object(offer) = offer_resp.offer_type else {
+        panic!("expected CredentialOfferType::Object");
+    };
+
+    let offer_req = OfferRequest {
+        client_id: CLIENT_ID.into(),
+        subject_id: NORMAL_USER.into(),
+        offer,
+    };
+    let issuance = vercre_holder::issuance::offer(holder_provider.clone(), &offer_req)
+        .await
+        .expect("should process offer");
+    let accept_req = AcceptRequest {
+        issuance_id: issuance.issuance_id.clone(),
+        accept: None,
+    };
+    vercre_holder::issuance::accept(holder_provider.clone(), &accept_req)
+        .await
+        .expect("should accept offer");
+    vercre_holder::issuance::token(holder_provider.clone(), &issuance.issuance_id)
+        .await
+        .expect("should get token");
+
+    let cred_req = CredentialsRequest {
+        issuance_id: issuance.issuance_id.clone(),
+        ..Default::default()
+    };
+    vercre_holder::issuance::credentials(holder_provider.clone(), &cred_req)
+        .await
+        .expect("should get credentials");
+    vercre_holder::issuance::save(
+        holder_provider.clone(),
+        &SaveRequest {
+            issuance_id: issuance.issuance_id.clone(),
+        },
+    )
+    .await
+    .expect("should save credentials");
+
+    let credentials =
+        CredentialStorer::find(&holder_provider, None).await.expect("should retrieve credentials");
+    assert_eq!(credentials.len(), 1);
+
+    let credential = &credentials[0];
+    assert_eq!(credential.format, "vc+sd-jwt");
+
+    let Quota::One(subject) = &credential.vc.credential_subject else {
+        panic!("expected a single credential subject");
+    };
+    assert_eq!(subject.claims["family_name"], "Person");
+}
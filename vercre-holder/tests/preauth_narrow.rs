@@ -121,6 +121,8 @@ async fn preauth_narrow() {
     assert_eq!(credentials.len(), 1);
 
     assert_snapshot!("credentials", credentials, {
+        "[].id" => "[id]",
+        "[].vc.id" => "[id]",
         "[].vc.validFrom" => "[validFrom]",
         "[].vc" => insta::sorted_redaction(),
         "[].vc.credentialSubject" => insta::sorted_redaction(),
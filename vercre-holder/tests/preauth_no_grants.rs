@@ -128,6 +128,8 @@ async fn preauth_no_grants() {
     assert_eq!(credentials.len(), 1);
 
     assert_snapshot!("credentials", credentials, {
+        "[].id" => "[id]",
+        "[].vc.id" => "[id]",
         "[].vc.validFrom" => "[validFrom]",
         "[].vc" => insta::sorted_redaction(),
         "[].vc.credentialSubject" => insta::sorted_redaction(),
@@ -6,17 +6,21 @@ use std::sync::LazyLock;
 
 use chrono::Utc;
 use insta::assert_yaml_snapshot as assert_snapshot;
-use serde_json::Map;
+use serde_json::{json, Map, Value};
 use vercre_core::{urlencode, Kind, Quota};
-use vercre_dif_exch::{Constraints, Field, Filter, FilterValue, InputDescriptor};
+use vercre_dif_exch::{
+    Constraints, Directive, Field, Filter, InputDescriptor, Rule, SubmissionRequirement,
+};
 use vercre_holder::credential::Credential;
 use vercre_holder::presentation::Status;
-use vercre_holder::provider::CredentialStorer;
+use vercre_holder::provider::{CredentialStorer, Signer};
 use vercre_infosec::SecOps;
-use vercre_openid::verifier::{CreateRequestRequest, DeviceFlow};
+use vercre_iso_mdl::SessionTranscript;
+use vercre_openid::verifier::{CreateRequestRequest, DeviceFlow, VpToken};
 use vercre_test_utils::verifier::{self, VERIFIER_ID};
 use vercre_w3c_vc::model::{CredentialSubject, VerifiableCredential};
 use vercre_w3c_vc::proof::{self, Payload, W3cFormat};
+use vercre_w3c_vc::verify_key;
 
 use crate::provider as holder;
 
@@ -35,8 +39,9 @@ fn setup_create_request() -> CreateRequestRequest {
                 fields: Some(vec![Field {
                     path: vec!["$.type".into()],
                     filter: Some(Filter {
-                        type_: "string".into(),
-                        value: FilterValue::Const("EmployeeIDCredential".into()),
+                        type_: Some("string".into()),
+                        const_: Some(json!("EmployeeIDCredential")),
+                        ..Default::default()
                     }),
                     ..Default::default()
                 }]),
@@ -45,29 +50,121 @@ fn setup_create_request() -> CreateRequestRequest {
             name: None,
             purpose: None,
             format: None,
+            group: None,
         }],
         ..Default::default()
     }
 }
 
+// Input descriptor requesting a credential of the specified type.
+fn type_descriptor(id: &str, type_: &str, group: &str) -> InputDescriptor {
+    InputDescriptor {
+        id: id.into(),
+        constraints: Constraints {
+            fields: Some(vec![Field {
+                path: vec!["$.type".into()],
+                filter: Some(Filter {
+                    type_: Some("string".into()),
+                    const_: Some(json!(type_)),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        },
+        name: None,
+        purpose: None,
+        format: None,
+        group: Some(vec![group.into()]),
+    }
+}
+
+const MDL_DOC_TYPE: &str = "org.iso.18013.5.1.mDL";
+
+// Input descriptor requesting the family name from a mobile driving licence.
+fn mdl_descriptor() -> InputDescriptor {
+    InputDescriptor {
+        id: MDL_DOC_TYPE.into(),
+        constraints: Constraints {
+            fields: Some(vec![
+                Field {
+                    path: vec!["$.docType".into()],
+                    filter: Some(Filter {
+                        type_: Some("string".into()),
+                        const_: Some(json!(MDL_DOC_TYPE)),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+                Field {
+                    path: vec![format!("$['{MDL_DOC_TYPE}'].family_name")],
+                    ..Default::default()
+                },
+            ]),
+            ..Default::default()
+        },
+        name: None,
+        purpose: None,
+        format: None,
+        group: None,
+    }
+}
+
+// Issue a mobile driving licence bound to the holder's device key.
+async fn mdoc_credential(holder: &holder::Provider) -> Credential {
+    let provider = VERIFIER_PROVIDER.clone();
+    let resolve = verify_key!(&provider);
+    let device_key =
+        resolve(Signer::verification_method(holder)).await.expect("should resolve holder key");
+
+    let dataset = json!({
+        MDL_DOC_TYPE: {
+            "given_name": "Normal",
+            "family_name": "Person",
+        }
+    });
+    let claims = dataset.as_object().cloned().expect("should be an object");
+    let signer = SecOps::signer(&provider, VERIFIER_ID).expect("should get verifier");
+    let issued = vercre_iso_mdl::to_credential(claims.clone(), &device_key, signer)
+        .await
+        .expect("should issue mdoc");
+
+    Credential {
+        issuer: VERIFIER_ID.into(),
+        id: "urn:uuid:5d2b8b5e-e3c9-4bd4-a8a1-1d2e0e7a0f3c".into(),
+        vc: VerifiableCredential {
+            type_: Quota::Many(vec!["VerifiableCredential".into(), MDL_DOC_TYPE.into()]),
+            credential_subject: Quota::One(CredentialSubject { id: None, claims }),
+            ..VerifiableCredential::default()
+        },
+        display: None,
+        issued,
+        format: "mso_mdoc".into(),
+        issuance_date: Utc::now(),
+        logo: None,
+    }
+}
+
 async fn sample_credential() -> Credential {
+    let claims = json!({"employeeId": "1234567890"});
+    issue_credential("EmployeeIDCredential", "https://example.com/credentials/3732", claims).await
+}
+
+async fn issue_credential(type_: &str, id: &str, claims: serde_json::Value) -> Credential {
     use chrono::TimeZone;
-    use serde_json::json;
 
     let vc = VerifiableCredential {
         context: vec![
             Kind::String("https://www.w3.org/2018/credentials/v1".into()),
             Kind::String("https://www.w3.org/2018/credentials/examples/v1".into()),
         ],
-        type_: Quota::Many(vec!["VerifiableCredential".into(), "EmployeeIDCredential".into()]),
+        type_: Quota::Many(vec!["VerifiableCredential".into(), type_.into()]),
         issuer: Kind::String("https://example.com/issuers/14".into()),
-        id: Some("https://example.com/credentials/3732".into()),
+        id: Some(id.into()),
         valid_from: Some(Utc.with_ymd_and_hms(2023, 11, 20, 23, 21, 55).unwrap()),
         credential_subject: Quota::One(CredentialSubject {
             id: Some("did:example:ebfeb1f712ebc6f1c276e12ec21".into()),
-            claims: json!({"employeeId": "1234567890"})
-                .as_object()
-                .map_or_else(Map::default, Clone::clone),
+            claims: claims.as_object().map_or_else(Map::default, Clone::clone),
         }),
         valid_until: Some(Utc.with_ymd_and_hms(2033, 12, 20, 23, 21, 55).unwrap()),
 
@@ -90,6 +187,7 @@ async fn sample_credential() -> Credential {
         vc: vc.clone(),
         display: None,
         issued: jwt,
+        format: "jwt_vc_json".into(),
         issuance_date,
         logo: None,
     }
@@ -196,3 +294,268 @@ async fn e2e_presentation_obj() {
     .expect("should process present");
     assert_snapshot!("response_response2", response);
 }
+
+#[tokio::test]
+async fn e2e_encryption_enc_omitted() {
+    let credential = sample_credential().await;
+    CredentialStorer::save(&HOLDER_PROVIDER.clone(), &credential)
+        .await
+        .expect("should save credential");
+
+    let mut request_request = setup_create_request();
+    request_request.device_flow = DeviceFlow::SameDevice;
+    let init_request = vercre_verifier::create_request(VERIFIER_PROVIDER.clone(), &request_request)
+        .await
+        .expect("should get request");
+
+    // request an encrypted response without specifying the content encryption,
+    // leaving the unsupported default ("A128CBC-HS256")
+    let mut obj = init_request.request_object.expect("should have request object");
+    obj.response_mode = Some("direct_post.jwt".into());
+    obj.client_metadata.authorization_encrypted_response_enc = None;
+    let qs = urlencode::to_string(&obj).expect("should serialize");
+    let presentation = vercre_holder::presentation::request(HOLDER_PROVIDER.clone(), &qs)
+        .await
+        .expect("should process request");
+    vercre_holder::presentation::authorize(
+        HOLDER_PROVIDER.clone(),
+        presentation.presentation_id.clone(),
+    )
+    .await
+    .expect("should authorize presentation");
+
+    let Err(e) =
+        vercre_holder::presentation::present(HOLDER_PROVIDER.clone(), presentation.presentation_id)
+            .await
+    else {
+        panic!("should fail with an unsupported enc");
+    };
+    assert_eq!(e.to_string(), "unsupported enc: the default A128CBC-HS256 is not supported");
+}
+
+#[tokio::test]
+async fn e2e_presentation_definition_uri() {
+    let credential = sample_credential().await;
+    CredentialStorer::save(&HOLDER_PROVIDER.clone(), &credential)
+        .await
+        .expect("should save credential");
+
+    // Ask the verifier to pass the presentation definition by reference.
+    let mut request_request = setup_create_request();
+    request_request.definition_by_reference = true;
+    let init_request = vercre_verifier::create_request(VERIFIER_PROVIDER.clone(), &request_request)
+        .await
+        .expect("should get request");
+
+    let url = init_request.request_uri.expect("should have request uri");
+    let presentation = vercre_holder::presentation::request(HOLDER_PROVIDER.clone(), &url)
+        .await
+        .expect("should process request");
+
+    assert_eq!(presentation.status, Status::Requested);
+    assert_eq!(presentation.credentials.len(), 1);
+
+    let status = vercre_holder::presentation::authorize(
+        HOLDER_PROVIDER.clone(),
+        presentation.presentation_id.clone(),
+    )
+    .await
+    .expect("should authorize presentation");
+    assert_eq!(status, Status::Authorized);
+
+    vercre_holder::presentation::present(HOLDER_PROVIDER.clone(), presentation.presentation_id)
+        .await
+        .expect("should process present");
+}
+
+#[tokio::test]
+async fn e2e_multiple_descriptors() {
+    // The holder has credentials of two different types.
+    let employee = sample_credential().await;
+    let claims = json!({"licenseNumber": "123-456-789"});
+    let license = issue_credential(
+        "DriversLicenseCredential",
+        "https://example.com/credentials/9021",
+        claims,
+    )
+    .await;
+    for credential in [&employee, &license] {
+        CredentialStorer::save(&HOLDER_PROVIDER.clone(), credential)
+            .await
+            .expect("should save credential");
+    }
+
+    // Request both credentials.
+    let mut request_request = setup_create_request();
+    request_request.input_descriptors = vec![
+        type_descriptor("EmployeeID_JWT", "EmployeeIDCredential", "A"),
+        type_descriptor("DriversLicense_JWT", "DriversLicenseCredential", "A"),
+    ];
+    request_request.submission_requirements = Some(vec![SubmissionRequirement {
+        rule: Rule::All,
+        from: Some("A".into()),
+        ..Default::default()
+    }]);
+    let init_request = vercre_verifier::create_request(VERIFIER_PROVIDER.clone(), &request_request)
+        .await
+        .expect("should get request");
+
+    let url = init_request.request_uri.expect("should have request uri");
+    let presentation = vercre_holder::presentation::request(HOLDER_PROVIDER.clone(), &url)
+        .await
+        .expect("should process request");
+
+    let mut ids = presentation.credentials.iter().map(|c| c.id.as_str()).collect::<Vec<_>>();
+    ids.sort_unstable();
+    assert_eq!(ids, [employee.id.as_str(), license.id.as_str()]);
+
+    vercre_holder::presentation::authorize(
+        HOLDER_PROVIDER.clone(),
+        presentation.presentation_id.clone(),
+    )
+    .await
+    .expect("should authorize presentation");
+
+    // The verifier checks a credential has been submitted for each descriptor.
+    vercre_holder::presentation::present(HOLDER_PROVIDER.clone(), presentation.presentation_id)
+        .await
+        .expect("should process present");
+}
+
+#[tokio::test]
+async fn e2e_unsatisfied_requirement() {
+    let credential = sample_credential().await;
+    CredentialStorer::save(&HOLDER_PROVIDER.clone(), &credential)
+        .await
+        .expect("should save credential");
+
+    // Request one of two credentials the holder does not have.
+    let mut request_request = setup_create_request();
+    request_request.input_descriptors = vec![
+        type_descriptor("Passport_JWT", "PassportCredential", "travel"),
+        type_descriptor("Visa_JWT", "VisaCredential", "travel"),
+    ];
+    request_request.submission_requirements = Some(vec![SubmissionRequirement {
+        name: Some("Travel document".into()),
+        rule: Rule::Pick,
+        count: Some(1),
+        from: Some("travel".into()),
+        ..Default::default()
+    }]);
+    let init_request = vercre_verifier::create_request(VERIFIER_PROVIDER.clone(), &request_request)
+        .await
+        .expect("should get request");
+
+    let url = init_request.request_uri.expect("should have request uri");
+    let presentation = vercre_holder::presentation::request(HOLDER_PROVIDER.clone(), &url)
+        .await
+        .expect("should process request");
+    assert!(presentation.credentials.is_empty());
+
+    vercre_holder::presentation::authorize(
+        HOLDER_PROVIDER.clone(),
+        presentation.presentation_id.clone(),
+    )
+    .await
+    .expect("should authorize presentation");
+
+    let Err(e) =
+        vercre_holder::presentation::present(HOLDER_PROVIDER.clone(), presentation.presentation_id)
+            .await
+    else {
+        panic!("should fail to present");
+    };
+    assert_eq!(e.to_string(), "submission requirements cannot be satisfied");
+}
+
+#[tokio::test]
+async fn e2e_limit_disclosure() {
+    let credential = sample_credential().await;
+    CredentialStorer::save(&HOLDER_PROVIDER.clone(), &credential)
+        .await
+        .expect("should save credential");
+
+    // A JWT credential cannot limit disclosure to the requested claims.
+    let mut request_request = setup_create_request();
+    request_request.input_descriptors[0].constraints.limit_disclosure = Some(Directive::Required);
+    let init_request = vercre_verifier::create_request(VERIFIER_PROVIDER.clone(), &request_request)
+        .await
+        .expect("should get request");
+
+    let url = init_request.request_uri.expect("should have request uri");
+    let presentation = vercre_holder::presentation::request(HOLDER_PROVIDER.clone(), &url)
+        .await
+        .expect("should process request");
+    vercre_holder::presentation::authorize(
+        HOLDER_PROVIDER.clone(),
+        presentation.presentation_id.clone(),
+    )
+    .await
+    .expect("should authorize presentation");
+
+    let Err(e) =
+        vercre_holder::presentation::present(HOLDER_PROVIDER.clone(), presentation.presentation_id)
+            .await
+    else {
+        panic!("should fail to present");
+    };
+    assert_eq!(e.to_string(), "no credential satisfies input descriptor EmployeeID_JWT");
+}
+
+#[tokio::test]
+async fn e2e_mdoc() {
+    let provider = holder::Provider::new(None, Some(VERIFIER_PROVIDER.clone()));
+    let credential = mdoc_credential(&provider).await;
+    CredentialStorer::save(&provider, &credential).await.expect("should save credential");
+
+    let mut request_request = setup_create_request();
+    request_request.device_flow = DeviceFlow::SameDevice;
+    request_request.input_descriptors = vec![mdl_descriptor()];
+
+    // Only the requested data elements may be disclosed.
+    request_request.input_descriptors[0].constraints.limit_disclosure = Some(Directive::Required);
+    let init_request = vercre_verifier::create_request(VERIFIER_PROVIDER.clone(), &request_request)
+        .await
+        .expect("should get request");
+
+    let obj = init_request.request_object.expect("should have request object");
+    let qs = urlencode::to_string(&obj).expect("should serialize");
+    let presentation = vercre_holder::presentation::request(provider.clone(), &qs)
+        .await
+        .expect("should process request");
+    assert_eq!(presentation.credentials.len(), 1);
+
+    vercre_holder::presentation::authorize(provider.clone(), presentation.presentation_id.clone())
+        .await
+        .expect("should authorize presentation");
+
+    // The verifier checks the device response and the submission.
+    vercre_holder::presentation::present(provider.clone(), presentation.presentation_id)
+        .await
+        .expect("should process present");
+
+    // The device response presents only the requested data element.
+    let response = provider.presented().expect("should have sent response");
+    let submission = response.presentation_submission.expect("should have submission");
+    assert_eq!(submission.descriptor_map[0].format, "mso_mdoc");
+    assert_eq!(submission.descriptor_map[0].path_nested.format, "mso_mdoc");
+
+    let Some(VpToken::Presentations(vp_token)) = &response.vp_token else {
+        panic!("should have VP token");
+    };
+    let Kind::String(device_response) = &vp_token[0] else {
+        panic!("should be a device response");
+    };
+    let transcript =
+        SessionTranscript::openid4vp(&obj.client_id, &obj.nonce, obj.response_uri.as_deref())
+            .expect("should create transcript");
+    let verifier = VERIFIER_PROVIDER.clone();
+    let documents =
+        vercre_iso_mdl::verify_presentation(device_response, &transcript, verify_key!(&verifier))
+            .await
+            .expect("should verify device response");
+    assert_eq!(
+        Value::Object(documents[0].name_spaces.clone()),
+        json!({MDL_DOC_TYPE: {"family_name": "Person"}})
+    );
+}
@@ -10,14 +10,15 @@ use serde::Serialize;
 // TODO: remove this import
 use vercre_dif_exch::Constraints;
 use vercre_holder::provider::{
-    Algorithm, CredentialStorer, DidResolver, Document, HolderProvider, Issuer, Result, Signer,
-    StateStore, Verifier,
+    Algorithm, CredentialStorer, Decryptor, DidResolver, Document, Encryptor, HolderProvider,
+    Issuer, KeyAgreement, Result, Signer, StateStore, Verifier,
 };
 use vercre_holder::{
     AuthorizationRequest, AuthorizationResponse, Credential, CredentialRequest, CredentialResponse,
     DeferredCredentialRequest, DeferredCredentialResponse, Logo, MetadataRequest, MetadataResponse,
-    OAuthServerRequest, OAuthServerResponse, RequestObjectRequest, RequestObjectResponse,
-    ResponseRequest, ResponseResponse, TokenRequest, TokenResponse,
+    OAuthServerRequest, OAuthServerResponse, PresentationDefinitionRequest,
+    PresentationDefinitionResponse, RequestObjectRequest, RequestObjectResponse, ResponseRequest,
+    ResponseResponse, TokenRequest, TokenResponse,
 };
 use vercre_issuer::{NotificationRequest, NotificationResponse};
 use vercre_test_utils::store::keystore::HolderKeystore;
@@ -31,6 +32,7 @@ pub struct Provider {
     verifier: Option<verifier::Provider>,
     state: state::Store,
     cred_store: Arc<Mutex<HashMap<String, Credential>>>,
+    presented: Arc<Mutex<Option<ResponseRequest>>>,
 }
 
 impl Provider {
@@ -42,8 +44,16 @@ impl Provider {
             verifier,
             state: state::Store::new(),
             cred_store: Arc::new(Mutex::new(HashMap::new())),
+            presented: Arc::new(Mutex::new(None)),
         }
     }
+
+    /// The last response sent to the verifier.
+    #[must_use]
+    #[allow(dead_code)]
+    pub fn presented(&self) -> Option<ResponseRequest> {
+        self.presented.lock().expect("should lock").clone()
+    }
 }
 
 impl HolderProvider for Provider {}
@@ -107,9 +117,25 @@ impl Verifier for Provider {
         Ok(vercre_verifier::request_object(self.verifier.clone().unwrap(), &request).await?)
     }
 
+    async fn presentation_definition(
+        &self, uri: &str,
+    ) -> anyhow::Result<PresentationDefinitionResponse> {
+        let parts = uri.rsplitn(3, '/').collect::<Vec<&str>>();
+        if parts.len() < 3 {
+            return Err(anyhow::anyhow!("invalid presentation definition uri"));
+        }
+        let request = PresentationDefinitionRequest {
+            client_id: parts[2].into(),
+            id: parts[0].into(),
+        };
+        Ok(vercre_verifier::presentation_definition(self.verifier.clone().unwrap(), &request)
+            .await?)
+    }
+
     async fn present(
         &self, _uri: Option<&str>, req: &ResponseRequest,
     ) -> anyhow::Result<ResponseResponse> {
+        *self.presented.lock().expect("should lock") = Some(req.clone());
         Ok(vercre_verifier::response(self.verifier.clone().unwrap(), req).await?)
     }
 }
@@ -135,7 +161,7 @@ impl CredentialStorer for Provider {
         let mut matched: Vec<Credential> = vec![];
         let constraints = filter.expect("constraints exist");
         for cred in creds {
-            match constraints.satisfied(&cred.vc) {
+            match constraints.satisfied(&cred) {
                 Ok(true) => matched.push(cred.clone()),
                 Ok(false) => continue,
                 Err(e) => return Err(e),
@@ -187,3 +213,25 @@ impl Signer for Provider {
         HolderKeystore::verification_method()
     }
 }
+
+impl Encryptor for Provider {
+    async fn encrypt(&self, plaintext: &[u8], recipient_public_key: &[u8]) -> Result<Vec<u8>> {
+        HolderKeystore::encrypt(plaintext, recipient_public_key)
+    }
+
+    fn public_key(&self) -> Vec<u8> {
+        HolderKeystore::encryption_key().expect("should derive encryption key")
+    }
+}
+
+impl Decryptor for Provider {
+    async fn decrypt(&self, ciphertext: &[u8], sender_public_key: &[u8]) -> Result<Vec<u8>> {
+        HolderKeystore::decrypt(ciphertext, sender_public_key)
+    }
+}
+
+impl KeyAgreement for Provider {
+    async fn shared_secret(&self, _kid: Option<&str>, sender_public_key: &[u8]) -> Result<Vec<u8>> {
+        HolderKeystore::shared_secret(sender_public_key)
+    }
+}
@@ -0,0 +1,159 @@
+//! # Client Authentication
+//!
+//! Authenticates clients at the token and pushed authorization request
+//! endpoints using a JWT client assertion as defined in [RFC7523].
+//!
+//! The client's configured `token_endpoint_auth_method` is enforced: a client
+//! registered for `private_key_jwt` must present a signed assertion, while a
+//! public client must not. The assertion's signing key must be bound to the
+//! client: it is taken from the `jwks` registered for the client or, when the
+//! client is identified by a DID, resolved from that DID's document. Keys
+//! published only at a `jwks_uri` cannot be retrieved by the issuer, so such
+//! clients are rejected.
+//!
+//! [RFC7523]: (https://www.rfc-editor.org/rfc/rfc7523.html)
+
+use anyhow::anyhow;
+use base64ct::{Base64UrlUnpadded, Encoding};
+use chrono::{DateTime, Utc};
+use vercre_core::Quota;
+use vercre_infosec::jose::jws;
+use vercre_infosec::PublicKeyJwk;
+use vercre_openid::issuer::{
+    Client, ClientAssertion, ClientAssertionClaims, Metadata, Provider, Server, StateStore,
+};
+use vercre_openid::oauth::{OAuthClient, TokenEndpointAuth};
+use vercre_openid::{Error, Result};
+use vercre_w3c_vc::verify_key;
+
+/// Authenticate the client making a request to the authorization server.
+///
+/// When a client assertion is provided, the client is identified by its
+/// issuer, which must match `client_id` when set.
+pub async fn authenticate(
+    provider: &impl Provider, server: &Server, client_id: Option<&str>,
+    client_assertion: Option<&ClientAssertion>,
+) -> Result<()> {
+    let Some(ClientAssertion::JwtBearer { client_assertion }) = client_assertion else {
+        // a client registered for JWT authentication must authenticate
+        let Some(client_id) = client_id else {
+            return Ok(());
+        };
+        if let Ok(client) = Metadata::client(provider, client_id).await {
+            if auth_method(&client) != TokenEndpointAuth::None {
+                return Err(Error::InvalidClient("client authentication is required".into()));
+            }
+        }
+        return Ok(());
+    };
+
+    // identify the client from the assertion before verifying it
+    let unverified = peek_claims(client_assertion)
+        .map_err(|e| Error::InvalidClient(format!("invalid client assertion: {e}")))?;
+    if client_id.is_some_and(|id| id != unverified.iss) {
+        return Err(Error::InvalidClient("client assertion issuer is not the client".into()));
+    }
+    let Ok(client) = Metadata::client(provider, &unverified.iss).await else {
+        return Err(Error::InvalidClient("invalid `client_id`".into()));
+    };
+
+    match auth_method(&client) {
+        TokenEndpointAuth::PrivateKeyJwt => {}
+        TokenEndpointAuth::ClientSecretJwt => {
+            return Err(Error::InvalidClient("`client_secret_jwt` is not supported".into()));
+        }
+        TokenEndpointAuth::None => {
+            return Err(Error::InvalidClient(
+                "client is not registered for JWT authentication".into(),
+            ));
+        }
+    }
+
+    // verify the assertion's signature
+    let jwt: jws::Jwt<ClientAssertionClaims> =
+        jws::decode(client_assertion, |kid: String| resolve_key(provider, &client.oauth, kid))
+            .await
+            .map_err(|e| Error::InvalidClient(format!("invalid client assertion: {e}")))?;
+    let claims = jwt.claims;
+
+    if claims.iss != client.oauth.client_id || claims.sub != claims.iss {
+        return Err(Error::InvalidClient("client assertion `sub` is not the client".into()));
+    }
+
+    // the assertion must be intended for this authorization server
+    let audiences = match &claims.aud {
+        Quota::One(aud) => vec![aud],
+        Quota::Many(auds) => auds.iter().collect(),
+    };
+    let oauth = &server.oauth;
+    let accepted = [Some(&oauth.issuer), Some(&oauth.token_endpoint)]
+        .into_iter()
+        .chain([oauth.pushed_authorization_request_endpoint.as_ref()]);
+    let accepted = accepted.flatten().collect::<Vec<_>>();
+    if !audiences.iter().any(|aud| accepted.contains(aud)) {
+        return Err(Error::InvalidClient("client assertion `aud` is invalid".into()));
+    }
+
+    let Some(expires_at) = DateTime::from_timestamp(claims.exp, 0) else {
+        return Err(Error::InvalidClient("client assertion `exp` is invalid".into()));
+    };
+    if expires_at <= Utc::now() {
+        return Err(Error::InvalidClient("client assertion has expired".into()));
+    }
+
+    // an assertion may only be used once
+    if claims.jti.is_empty() {
+        return Err(Error::InvalidClient("client assertion `jti` is missing".into()));
+    }
+    let jti_key = format!("client_assertion:{}:{}", claims.iss, claims.jti);
+    if StateStore::get::<i64>(provider, &jti_key).await.is_ok() {
+        return Err(Error::InvalidClient("client assertion has already been used".into()));
+    }
+    StateStore::put(provider, &jti_key, &claims.exp, expires_at)
+        .await
+        .map_err(|e| Error::ServerError(format!("issue saving state: {e}")))?;
+
+    Ok(())
+}
+
+// The authentication method the client registered, defaulting to a public
+// client.
+fn auth_method(client: &Client) -> TokenEndpointAuth {
+    client.oauth.token_endpoint_auth_method.clone().unwrap_or_default()
+}
+
+// Read the assertion's claims without verifying its signature.
+fn peek_claims(assertion: &str) -> anyhow::Result<ClientAssertionClaims> {
+    let Some(payload) = assertion.split('.').nth(1) else {
+        return Err(anyhow!("client assertion is not a JWT"));
+    };
+    let decoded = Base64UrlUnpadded::decode_vec(payload)
+        .map_err(|e| anyhow!("issue decoding client assertion: {e}"))?;
+    Ok(serde_json::from_slice(&decoded)?)
+}
+
+// Resolve the client's public key from its registered key set or, for a
+// client identified by a DID, from that DID's document. Keys not bound to the
+// client are rejected.
+async fn resolve_key(
+    provider: &impl Provider, client: &OAuthClient, kid: String,
+) -> anyhow::Result<PublicKeyJwk> {
+    if let Some(jwks) = &client.jwks {
+        return jwks
+            .keys
+            .iter()
+            .find(|key| key.kid.as_deref() == Some(kid.as_str()))
+            .cloned()
+            .ok_or_else(|| anyhow!("client key {kid} is not registered"));
+    }
+    if client.jwks_uri.is_some() {
+        return Err(anyhow!("keys published at `jwks_uri` are not supported"));
+    }
+
+    // the key must belong to the DID identifying the client
+    let did = kid.split('#').next().unwrap_or_default();
+    if !did.starts_with("did:") || did != client.client_id {
+        return Err(anyhow!("client key {kid} is not bound to the client"));
+    }
+    verify_key!(provider)(kid).await
+}
@@ -7,27 +7,40 @@
 //! A Wallet can request issuance of multiple Credentials of certain types and
 //! formats in one Batch Credential Request. This includes Credentials of the
 //! same type and multiple formats, different types and one format, or both.
+//!
+//! The `issuer` of a W3C credential depends on how it is secured. JWT-secured
+//! formats (`jwt_vc_json` and `jwt_vc_json-ld`) use the Credential Issuer's
+//! URL. `ldp_vc` credentials carry an embedded Data Integrity proof, which is
+//! verified against the key controlled by the `issuer`, so their `issuer` is
+//! the DID of the signing key instead.
 
 use std::fmt::Debug;
 
 use chrono::{DateTime, Utc};
 use tracing::instrument;
+use uuid::Uuid;
 use vercre_core::{gen, Kind};
 use vercre_infosec::jose::jws::{self, KeyType, Type};
-use vercre_infosec::{SecOps, Signer};
+use vercre_infosec::{PublicKeyJwk, SecOps, Signer};
 use vercre_openid::issuer::{
     CredentialConfiguration, CredentialDefinition, CredentialDisplay, CredentialIssuance,
-    CredentialRequest, CredentialResponse, CredentialResponseType, Dataset, Format, Issuer,
-    Metadata, MultipleProofs, Proof, ProofClaims, Provider, SingleProof, StateStore, Subject,
+    CredentialRequest, CredentialResponse, CredentialResponseEncryption, CredentialResponseType,
+    Dataset, Format, Issuer, Metadata, MultipleProofs, ProfileSdJwt, Proof, ProofClaims, Provider,
+    SingleProof, StateStore, Subject,
 };
-use vercre_openid::{Error, Result};
-use vercre_status::issuer::Status;
+use vercre_openid::{jwe, Error, Result};
+use vercre_status::issuer::{self, Status};
 use vercre_w3c_vc::model::types::{LangString, LangValue};
 use vercre_w3c_vc::model::{CredentialSubject, VerifiableCredential};
-use vercre_w3c_vc::proof::{self, Payload, W3cFormat};
+use vercre_w3c_vc::proof::sdjwt::{self, SdJwtVc};
+use vercre_w3c_vc::proof::{self, integrity, Payload, W3cFormat};
 use vercre_w3c_vc::verify_key;
 
 use crate::state::{Authorized, Deferrance, Expire, Stage, State};
+use crate::{dpop, status_list};
+
+// The base context of the credentials created by `VerifiableCredential::builder`.
+const BASE_CONTEXT: &str = "https://www.w3.org/2018/credentials/v1";
 
 /// Credential request handler.
 ///
@@ -39,17 +52,35 @@ use crate::state::{Authorized, Deferrance, Expire, Stage, State};
 pub async fn credential(
     provider: impl Provider, request: CredentialRequest,
 ) -> Result<CredentialResponse> {
-    let Ok(state) = StateStore::get::<State>(&provider, &request.access_token).await else {
-        return Err(Error::AccessDenied("invalid access token".into()));
-    };
     let issuer = Metadata::issuer(&provider, &request.credential_issuer)
         .await
         .map_err(|e| Error::ServerError(format!("metadata issue: {e}")))?;
+    dpop::verify_token(
+        &provider,
+        &request.access_token,
+        request.dpop.as_deref(),
+        &issuer.credential_endpoint,
+    )
+    .await?;
+
+    issue(&provider, issuer, request, false).await
+}
+
+/// Verify the request and issue the credential, or defer issuance when claims
+/// are pending. Shared with the Deferred Credential Endpoint, which sets
+/// `deferred` so pending claims are reported rather than deferred again.
+pub(crate) async fn issue(
+    provider: &impl Provider, issuer: Issuer, request: CredentialRequest, deferred: bool,
+) -> Result<CredentialResponse> {
+    let Ok(state) = StateStore::get::<State>(provider, &request.access_token).await else {
+        return Err(Error::AccessDenied("invalid access token".into()));
+    };
 
     // create a request context with data accessed more than once
     let mut ctx = Context {
         state,
         issuer,
+        deferred,
         ..Context::default()
     };
 
@@ -63,8 +94,28 @@ pub async fn credential(
     };
     ctx.configuration = config.clone();
 
-    ctx.verify(&provider, &request).await?;
-    ctx.process(&provider, request).await
+    ctx.verify(provider, &request).await?;
+    ctx.process(provider, request).await
+}
+
+// Encrypt the Credential Response when the Wallet has asked for it, using the
+// encryption parameters already validated against the Issuer's metadata.
+fn encrypt(
+    encryption: Option<&CredentialResponseEncryption>, response: CredentialResponse,
+) -> Result<CredentialResponse> {
+    let Some(encryption) = encryption else {
+        return Ok(response);
+    };
+
+    let jwe = jwe::encrypt(&response, &encryption.jwk, &encryption.alg, &encryption.enc)
+        .map_err(|e| Error::ServerError(format!("issue encrypting response: {e}")))?;
+
+    Ok(CredentialResponse {
+        response: CredentialResponseType::Encrypted(jwe),
+        c_nonce: None,
+        c_nonce_expires_in: None,
+        notification_id: None,
+    })
 }
 
 #[derive(Debug, Default)]
@@ -74,6 +125,8 @@ struct Context {
     authorized: Authorized,
     configuration: CredentialConfiguration,
     holder_did: String,
+    holder_kid: String,
+    deferred: bool,
 }
 
 impl Context {
@@ -101,6 +154,38 @@ impl Context {
             return Err(Error::AccessDenied("c_nonce has expired".into()));
         }
 
+        // requested response encryption must be supported by the issuer
+        let supported = self.issuer.credential_response_encryption.as_ref();
+        match (&request.credential_response_encryption, supported) {
+            (Some(encryption), Some(supported)) => {
+                if !supported.alg_values_supported.contains(&encryption.alg) {
+                    return Err(Error::InvalidEncryptionParameters(format!(
+                        "unsupported alg: {}",
+                        encryption.alg
+                    )));
+                }
+                if !supported.enc_values_supported.contains(&encryption.enc) {
+                    return Err(Error::InvalidEncryptionParameters(format!(
+                        "unsupported enc: {}",
+                        encryption.enc
+                    )));
+                }
+                jwe::check(&encryption.jwk, &encryption.alg, &encryption.enc)
+                    .map_err(|e| Error::InvalidEncryptionParameters(e.to_string()))?;
+            }
+            (Some(_), None) => {
+                return Err(Error::InvalidEncryptionParameters(
+                    "credential response encryption is not supported".into(),
+                ));
+            }
+            (None, Some(supported)) if supported.encryption_required => {
+                return Err(Error::InvalidEncryptionParameters(
+                    "credential response encryption is required".into(),
+                ));
+            }
+            (None, _) => {}
+        }
+
         // TODO: refactor into separate function.
         if let Some(supported_types) = &self.configuration.proof_types_supported {
             let Some(proof) = &request.proof else {
@@ -160,6 +245,7 @@ impl Context {
 
                 // TODO: support multiple DID bindings
                 self.holder_did = did.into();
+                self.holder_kid.clone_from(kid);
             }
         }
 
@@ -176,6 +262,10 @@ impl Context {
 
         // defer issuance as claims are pending (approval)
         if dataset.pending {
+            if self.deferred {
+                // TODO: make retry interval configurable
+                return Err(Error::IssuancePending(5));
+            }
             return self.defer_response(provider, request).await;
         }
 
@@ -183,9 +273,24 @@ impl Context {
         self.issue_response(provider, request, dataset).await
     }
 
-    // Issue the requested credential.
+    // Issue the requested credential, releasing any status list entries
+    // allocated to it if issuance fails.
     async fn issue_response(
         &self, provider: &impl Provider, request: CredentialRequest, dataset: Dataset,
+    ) -> Result<CredentialResponse> {
+        let credential_id = format!("urn:uuid:{}", Uuid::new_v4());
+        let result = self.create_response(provider, request, dataset, &credential_id).await;
+        if result.is_err() {
+            // best effort as the original error is the one reported
+            let _ = issuer::release(provider, &credential_id).await;
+        }
+        result
+    }
+
+    // Create the credential response for the requested credential.
+    async fn create_response(
+        &self, provider: &impl Provider, request: CredentialRequest, dataset: Dataset,
+        credential_id: &str,
     ) -> Result<CredentialResponse> {
         // generate the issuance time stamp
         let issuance_date = Utc::now();
@@ -195,16 +300,30 @@ impl Context {
 
         // determine credential format
         let response = match &self.configuration.format {
-            Format::JwtVcJson(w3c) => {
-                let vc = self.w3c_vc(provider, &w3c.credential_definition, dataset).await?;
-                self.jwt_vc_json(vc, signer, issuance_date).await?
+            Format::JwtVcJson(w3c) | Format::JwtVcJsonLd(w3c) => {
+                let format = if matches!(self.configuration.format, Format::JwtVcJsonLd(_)) {
+                    json_ld_context(&w3c.credential_definition)?;
+                    W3cFormat::JwtVcJsonLd
+                } else {
+                    W3cFormat::JwtVcJson
+                };
+                let vc = self
+                    .w3c_vc(provider, &w3c.credential_definition, dataset, credential_id)
+                    .await?;
+                self.jwt_vc(format, vc, signer, issuance_date).await?
+            }
+            Format::IsoMdl(_) => Box::pin(self.mso_mdoc(provider, dataset, signer)).await?,
+            Format::VcSdJwt(sd_jwt) => {
+                self.vc_sd_jwt(provider, sd_jwt, dataset, signer, issuance_date).await?
             }
-            Format::IsoMdl(_) => self.mso_mdoc(dataset, signer).await?,
 
-            // TODO: remaining credential formats
-            Format::JwtVcJsonLd(_) => todo!(),
-            Format::LdpVc(_) => todo!(),
-            Format::VcSdJwt(_) => todo!(),
+            Format::LdpVc(w3c) => {
+                json_ld_context(&w3c.credential_definition)?;
+                let vc = self
+                    .w3c_vc(provider, &w3c.credential_definition, dataset, credential_id)
+                    .await?;
+                Box::pin(self.ldp_vc(vc, signer, issuance_date)).await?
+            }
         };
 
         // update token state with new `c_nonce`
@@ -217,6 +336,18 @@ impl Context {
         token_state.c_nonce = gen::nonce();
         token_state.c_nonce_expires_at = Utc::now() + Expire::Nonce.duration();
         state.stage = Stage::Validated(token_state.clone());
+        let notification_id = gen::notification_id();
+
+        // encrypt before saving state so a failure leaves the `c_nonce` unchanged
+        let response = encrypt(
+            request.credential_response_encryption.as_ref(),
+            CredentialResponse {
+                response,
+                c_nonce: Some(token_state.c_nonce.clone()),
+                c_nonce_expires_in: Some(token_state.c_nonce_expires_in()),
+                notification_id: Some(notification_id.clone()),
+            },
+        )?;
 
         StateStore::put(provider, &token_state.access_token, &state, state.expires_at)
             .await
@@ -226,18 +357,11 @@ impl Context {
         // TODO: save credential in state !!
         // state.stage = Stage::Issued(Credential { credential: vc, issuance:
         // issuance_dt });
-        let notification_id = gen::notification_id();
-
         StateStore::put(provider, &notification_id, &state, state.expires_at)
             .await
             .map_err(|e| Error::ServerError(format!("issue saving state: {e}")))?;
 
-        Ok(CredentialResponse {
-            response,
-            c_nonce: Some(token_state.c_nonce.clone()),
-            c_nonce_expires_in: Some(token_state.c_nonce_expires_in()),
-            notification_id: Some(notification_id),
-        })
+        Ok(response)
     }
 
     // Generate a W3C Verifiable Credential.
@@ -246,7 +370,7 @@ impl Context {
 
     async fn w3c_vc(
         &self, provider: &impl Provider, credential_definition: &CredentialDefinition,
-        dataset: Dataset,
+        dataset: Dataset, credential_id: &str,
     ) -> Result<VerifiableCredential> {
         // credential type
         let Some(types) = &credential_definition.type_ else {
@@ -260,18 +384,30 @@ impl Context {
         let Some(subject_id) = &self.state.subject_id else {
             return Err(Error::AccessDenied("invalid subject id".into()));
         };
-        let status = Status::status(provider, subject_id, "credential_identifier")
+        let credential_issuer = &self.issuer.credential_issuer;
+
+        let mut status = Status::status(provider, subject_id, "credential_identifier")
             .await
             .map_err(|e| Error::ServerError(format!("issue populating credential status: {e}")))?;
+        if status.is_none() {
+            // allocate entries from any status lists configured for the type
+            status = issuer::allocate(
+                provider,
+                credential_type,
+                credential_id,
+                subject_id,
+                &status_list::base_url(credential_issuer),
+            )
+            .await
+            .map_err(|e| Error::ServerError(format!("issue allocating credential status: {e}")))?;
+        }
 
-        let credential_issuer = &self.issuer.credential_issuer;
         let (name, description) =
             self.configuration.display.as_ref().map_or((None, None), create_names);
 
-        VerifiableCredential::builder()
+        let mut vc = VerifiableCredential::builder()
             .add_context(Kind::String(format!("{credential_issuer}/credentials/v1")))
-            // TODO: generate credential id
-            .id(format!("{credential_issuer}/credentials/{credential_type}"))
+            .id(credential_id)
             .add_type(credential_type)
             .add_name(name)
             .add_description(description)
@@ -282,16 +418,27 @@ impl Context {
             })
             .status(status)
             .build()
-            .map_err(|e| Error::ServerError(format!("issue building VC: {e}")))
+            .map_err(|e| Error::ServerError(format!("issue building VC: {e}")))?;
+
+        // add any JSON-LD contexts configured for the credential
+        for context in credential_definition.context.iter().flatten() {
+            let context = Kind::String(context.clone());
+            if !vc.context.contains(&context) {
+                vc.context.push(context);
+            }
+        }
+
+        Ok(vc)
     }
 
-    // Generate a `jwt_vc_json` format credential .
-    async fn jwt_vc_json(
-        &self, vc: VerifiableCredential, signer: impl Signer, issuance_date: DateTime<Utc>,
+    // Generate a `jwt_vc_json` or `jwt_vc_json-ld` format credential.
+    async fn jwt_vc(
+        &self, format: W3cFormat, vc: VerifiableCredential, signer: impl Signer,
+        issuance_date: DateTime<Utc>,
     ) -> Result<CredentialResponseType> {
         // sign and return JWT
         let jwt = proof::create(
-            W3cFormat::JwtVcJson,
+            format,
             Payload::Vc {
                 vc: vc.clone(),
                 issued_at: issuance_date.timestamp(),
@@ -300,26 +447,116 @@ impl Context {
         )
         .await
         .map_err(|e| {
-            Error::ServerError(format!("issue generating `jwt_vc_json` credential: {e}"))
+            Error::ServerError(format!(
+                "issue generating `{}` credential: {e}",
+                self.configuration.format
+            ))
         })?;
         Ok(CredentialResponseType::Credential(Kind::String(jwt)))
     }
 
+    // Generate an `ldp_vc` format credential secured with an embedded Data
+    // Integrity proof.
+    async fn ldp_vc(
+        &self, mut vc: VerifiableCredential, signer: impl Signer, issuance_date: DateTime<Utc>,
+    ) -> Result<CredentialResponseType> {
+        // the v1 credentials context does not define `DataIntegrityProof`
+        let context = Kind::String(integrity::DATA_INTEGRITY_CONTEXT.into());
+        if !vc.context.contains(&context) {
+            vc.context.push(context);
+        }
+
+        // the proof is verified against the issuer, so the issuer must be the DID
+        // controlling the signing key rather than the Credential Issuer's URL
+        // used by `jwt_vc` (see module docs)
+        let verification_method = signer.verification_method();
+        let did = verification_method.split('#').next().unwrap_or_default();
+        vc.issuer = Kind::String(did.into());
+
+        let secured = proof::create(
+            W3cFormat::DataIntegrityJsonLd,
+            Payload::Vc {
+                vc,
+                issued_at: issuance_date.timestamp(),
+            },
+            signer,
+        )
+        .await
+        .map_err(|e| Error::ServerError(format!("issue generating `ldp_vc` credential: {e}")))?;
+
+        let vc = serde_json::from_str(&secured)
+            .map_err(|e| Error::ServerError(format!("issue deserializing `ldp_vc`: {e}")))?;
+        Ok(CredentialResponseType::Credential(Kind::Object(vc)))
+    }
+
     // Generate a `mso_mdoc` format credential.
     async fn mso_mdoc(
-        &self, dataset: Dataset, signer: impl Signer,
+        &self, provider: &impl Provider, dataset: Dataset, signer: impl Signer,
     ) -> Result<CredentialResponseType> {
-        let mdl = vercre_iso_mdl::to_credential(dataset.claims, signer).await.map_err(|e| {
-            Error::ServerError(format!("issue generating `mso_mdoc` credential: {e}"))
-        })?;
+        // the mdoc's device key is used to authenticate presentations
+        let Some(device_key) = self.holder_jwk(provider).await? else {
+            return Err(self
+                .invalid_proof(provider, "`mso_mdoc` credentials require a proof")
+                .await?);
+        };
+        let mdl =
+            vercre_iso_mdl::to_credential(dataset.claims, &device_key, signer).await.map_err(
+                |e| Error::ServerError(format!("issue generating `mso_mdoc` credential: {e}")),
+            )?;
         Ok(CredentialResponseType::Credential(Kind::String(mdl)))
     }
 
+    // Generate a `vc+sd-jwt` format credential.
+    async fn vc_sd_jwt(
+        &self, provider: &impl Provider, sd_jwt: &ProfileSdJwt, dataset: Dataset,
+        signer: impl Signer, issuance_date: DateTime<Utc>,
+    ) -> Result<CredentialResponseType> {
+        // bind the credential to the key used to sign the proof of possession
+        let holder_jwk = self.holder_jwk(provider).await?;
+
+        // the issuer is identified by the DID controlling its signing key
+        let verification_method = signer.verification_method();
+        let issuer_did = verification_method.split('#').next().unwrap_or_default();
+
+        let vc = SdJwtVc {
+            issuer: issuer_did.into(),
+            vct: sd_jwt.vct.clone(),
+            subject: Some(self.holder_did.clone()).filter(|did| !did.is_empty()),
+            claims: dataset.claims,
+            holder_jwk,
+            issued_at: issuance_date.timestamp(),
+            expires_at: None,
+        };
+        let sd_jwt = sdjwt::create(vc, signer).await.map_err(|e| {
+            Error::ServerError(format!("issue generating `vc+sd-jwt` credential: {e}"))
+        })?;
+        Ok(CredentialResponseType::Credential(Kind::String(sd_jwt)))
+    }
+
+    // Resolve the key used to sign the proof of possession, if any.
+    async fn holder_jwk(&self, provider: &impl Provider) -> Result<Option<PublicKeyJwk>> {
+        if self.holder_kid.is_empty() {
+            return Ok(None);
+        }
+        let resolve = verify_key!(provider);
+        let jwk = resolve(self.holder_kid.clone())
+            .await
+            .map_err(|e| Error::ServerError(format!("issue resolving holder key: {e}")))?;
+        Ok(Some(jwk))
+    }
+
     // Defer issuance of the requested credential.
     async fn defer_response(
         &self, provider: &impl Provider, request: CredentialRequest,
     ) -> Result<CredentialResponse> {
         let txn_id = gen::transaction_id();
+        let response = encrypt(
+            request.credential_response_encryption.as_ref(),
+            CredentialResponse {
+                response: CredentialResponseType::TransactionId(txn_id.clone()),
+                ..CredentialResponse::default()
+            },
+        )?;
 
         let state = State {
             subject_id: None,
@@ -333,10 +570,7 @@ impl Context {
             .await
             .map_err(|e| Error::ServerError(format!("issue saving state: {e}")))?;
 
-        Ok(CredentialResponse {
-            response: CredentialResponseType::TransactionId(txn_id),
-            ..CredentialResponse::default()
-        })
+        Ok(response)
     }
 
     // Get `Authorized` for `credential_identifier` and
@@ -435,6 +669,32 @@ impl Context {
     }
 }
 
+// JSON-LD credential formats require the `@context` used to interpret the
+// credential's terms to be configured in the Credential Definition.
+//
+// This is a structural check only: the base VC context must come first and,
+// as it defines neither the credential's type nor its subject claims, be
+// followed by at least one other context. Contexts are not dereferenced, so
+// whether they actually define the credential's type and claim names is the
+// responsibility of whoever configures the Credential Definition.
+fn json_ld_context(credential_definition: &CredentialDefinition) -> Result<()> {
+    let Some((base, terms)) = credential_definition.context.as_ref().and_then(|c| c.split_first())
+    else {
+        return Err(Error::ServerError("credential definition has no `@context`".into()));
+    };
+    if base != BASE_CONTEXT {
+        return Err(Error::ServerError(format!(
+            "credential definition `@context` must start with {BASE_CONTEXT}"
+        )));
+    }
+    if terms.is_empty() {
+        return Err(Error::ServerError(
+            "credential definition `@context` does not define the credential's terms".into(),
+        ));
+    }
+    Ok(())
+}
+
 // Extract language object name and description from a `CredentialDisplay`
 // vector.
 fn create_names(display: &Vec<CredentialDisplay>) -> (Option<LangString>, Option<LangString>) {
@@ -472,10 +732,15 @@ mod tests {
     use std::collections::HashMap;
 
     use assert_let_bind::assert_let;
+    use base64ct::{Base64UrlUnpadded, Encoding};
     use insta::assert_yaml_snapshot as assert_snapshot;
     use serde_json::json;
+    use vercre_infosec::Encryptor;
+    use vercre_status::bitstring::{Position, MIN_ENTRIES};
+    use vercre_status::config::{CredentialStatusConfig, ListConfig};
     use vercre_test_utils::issuer::{Provider, CLIENT_ID, CREDENTIAL_ISSUER, NORMAL_USER};
     use vercre_test_utils::{holder, snapshot};
+    use vercre_w3c_vc::model::StatusPurpose;
     use vercre_w3c_vc::proof::{self, Verify};
 
     use super::*;
@@ -504,6 +769,7 @@ mod tests {
                 )]),
                 c_nonce: c_nonce.into(),
                 c_nonce_expires_at: Utc::now() + Expire::Nonce.duration(),
+                dpop_jkt: None,
             }),
             subject_id: Some(NORMAL_USER.into()),
             expires_at: Utc::now() + Expire::Authorized.duration(),
@@ -550,6 +816,7 @@ mod tests {
         };
 
         assert_snapshot!("credential:identifier:vc", vc, {
+            ".id" => "[id]",
             ".validFrom" => "[validFrom]",
             ".credentialSubject" => insta::sorted_redaction(),
             ".credentialSubject.address" => insta::sorted_redaction()
@@ -564,6 +831,542 @@ mod tests {
         });
     }
 
+    #[tokio::test]
+    async fn status_allocation() {
+        vercre_test_utils::init_tracer();
+
+        let provider = Provider::new();
+        provider.status.put_status_config(&CredentialStatusConfig {
+            credential_type: "EmployeeIDCredential".into(),
+            status_list: Some(vec![ListConfig {
+                purpose: StatusPurpose::Revocation,
+                list: 1,
+                size: 1,
+                messages: None,
+                reference: None,
+                entries: None,
+            }]),
+        });
+
+        // issue two credentials of the same type
+        let mut credential_ids = vec![];
+        for access_token in ["ABCDEF", "GHIJKL"] {
+            let c_nonce = "1234ABCD";
+            let state = State {
+                stage: Stage::Validated(Token {
+                    access_token: access_token.into(),
+                    credentials: HashMap::from([(
+                        "PHLEmployeeID".into(),
+                        Authorized {
+                            credential_identifier: "PHLEmployeeID".into(),
+                            credential_configuration_id: "EmployeeID_JWT".into(),
+                            claim_ids: None,
+                        },
+                    )]),
+                    c_nonce: c_nonce.into(),
+                    c_nonce_expires_at: Utc::now() + Expire::Nonce.duration(),
+                    dpop_jkt: None,
+                }),
+                subject_id: Some(NORMAL_USER.into()),
+                expires_at: Utc::now() + Expire::Authorized.duration(),
+            };
+            StateStore::put(&provider, access_token, &state, state.expires_at)
+                .await
+                .expect("state exists");
+
+            let claims = ProofClaims {
+                iss: Some(CLIENT_ID.into()),
+                aud: CREDENTIAL_ISSUER.into(),
+                iat: Utc::now().timestamp(),
+                nonce: Some(c_nonce.into()),
+            };
+            let jwt =
+                jws::encode(Type::Proof, &claims, holder::Provider).await.expect("should encode");
+            let value = json!({
+                "credential_issuer": CREDENTIAL_ISSUER,
+                "access_token": access_token,
+                "credential_identifier": "PHLEmployeeID",
+                "proof":{
+                    "proof_type": "jwt",
+                    "jwt": jwt
+                }
+            });
+            let request = serde_json::from_value(value).expect("request is valid");
+            let response = credential(provider.clone(), request).await.expect("response is valid");
+
+            let CredentialResponseType::Credential(vc_kind) = &response.response else {
+                panic!("expected a single credential");
+            };
+            let Payload::Vc { vc, .. } =
+                proof::verify(Verify::Vc(vc_kind), &provider).await.expect("should decode")
+            else {
+                panic!("should be VC");
+            };
+            credential_ids.push(vc.id.expect("credential should have an id"));
+        }
+        assert_ne!(credential_ids[0], credential_ids[1]);
+
+        // each credential is allocated its own status list entry
+        let mut indexes = vec![];
+        for credential_id in &credential_ids {
+            let entry =
+                Status::log_entry(&provider, credential_id).await.expect("should get entry");
+            indexes.push(entry.expect("entry should exist").status[0].list_index);
+        }
+        assert_ne!(indexes[0], indexes[1]);
+    }
+
+    #[tokio::test]
+    async fn status_released() {
+        vercre_test_utils::init_tracer();
+
+        let mut provider = Provider::new();
+        provider.status.put_status_config(&CredentialStatusConfig {
+            credential_type: "EmployeeIDCredential".into(),
+            status_list: Some(vec![ListConfig {
+                purpose: StatusPurpose::Revocation,
+                list: 1,
+                size: 1,
+                messages: None,
+                reference: None,
+                entries: None,
+            }]),
+        });
+        provider.fail_signing = true;
+
+        let access_token = "ABCDEF";
+        let c_nonce = "1234ABCD";
+        let state = State {
+            stage: Stage::Validated(Token {
+                access_token: access_token.into(),
+                credentials: HashMap::from([(
+                    "PHLEmployeeID".into(),
+                    Authorized {
+                        credential_identifier: "PHLEmployeeID".into(),
+                        credential_configuration_id: "EmployeeID_JWT".into(),
+                        claim_ids: None,
+                    },
+                )]),
+                c_nonce: c_nonce.into(),
+                c_nonce_expires_at: Utc::now() + Expire::Nonce.duration(),
+                dpop_jkt: None,
+            }),
+            subject_id: Some(NORMAL_USER.into()),
+            expires_at: Utc::now() + Expire::Authorized.duration(),
+        };
+        StateStore::put(&provider, access_token, &state, state.expires_at)
+            .await
+            .expect("state exists");
+
+        let claims = ProofClaims {
+            iss: Some(CLIENT_ID.into()),
+            aud: CREDENTIAL_ISSUER.into(),
+            iat: Utc::now().timestamp(),
+            nonce: Some(c_nonce.into()),
+        };
+        let jwt = jws::encode(Type::Proof, &claims, holder::Provider).await.expect("should encode");
+        let value = json!({
+            "credential_issuer": CREDENTIAL_ISSUER,
+            "access_token": access_token,
+            "credential_identifier": "PHLEmployeeID",
+            "proof":{
+                "proof_type": "jwt",
+                "jwt": jwt
+            }
+        });
+        let request = serde_json::from_value(value).expect("request is valid");
+        credential(provider.clone(), request).await.expect_err("signing should fail");
+
+        // the entry allocated before signing failed is free again
+        let sharded = Status::sharded_list(&provider, 1)
+            .await
+            .expect("should get list")
+            .expect("list should exist");
+        assert_eq!(sharded.shards(), 1);
+        assert!((0..MIN_ENTRIES).all(|index| !sharded.is_allocated(Position { shard: 0, index })));
+    }
+
+    #[tokio::test]
+    async fn encrypted() {
+        vercre_test_utils::init_tracer();
+
+        let provider = Provider::new();
+        let access_token = "ABCDEF";
+        let c_nonce = "1234ABCD";
+
+        // set up state
+        let state = State {
+            stage: Stage::Validated(Token {
+                access_token: access_token.into(),
+                credentials: HashMap::from([(
+                    "PHLEmployeeID".into(),
+                    Authorized {
+                        credential_identifier: "PHLEmployeeID".into(),
+                        credential_configuration_id: "EmployeeID_JWT".into(),
+                        claim_ids: None,
+                    },
+                )]),
+                c_nonce: c_nonce.into(),
+                c_nonce_expires_at: Utc::now() + Expire::Nonce.duration(),
+                dpop_jkt: None,
+            }),
+            subject_id: Some(NORMAL_USER.into()),
+            expires_at: Utc::now() + Expire::Authorized.duration(),
+        };
+
+        StateStore::put(&provider, access_token, &state, state.expires_at)
+            .await
+            .expect("state exists");
+
+        let claims = ProofClaims {
+            iss: Some(CLIENT_ID.into()),
+            aud: CREDENTIAL_ISSUER.into(),
+            iat: Utc::now().timestamp(),
+            nonce: Some(c_nonce.into()),
+        };
+        let jwt = jws::encode(Type::Proof, &claims, holder::Provider).await.expect("should encode");
+        let holder_key = Encryptor::public_key(&holder::Provider);
+
+        let value = json!({
+            "credential_issuer": CREDENTIAL_ISSUER,
+            "access_token": access_token,
+            "credential_identifier": "PHLEmployeeID",
+            "proof":{
+                "proof_type": "jwt",
+                "jwt": jwt
+            },
+            "credential_response_encryption": {
+                "jwk": {
+                    "kty": "OKP",
+                    "crv": "X25519",
+                    "x": Base64UrlUnpadded::encode_string(&holder_key)
+                },
+                "alg": "RSA-OAEP",
+                "enc": "A256GCM"
+            }
+        });
+
+        // unsupported algorithms are rejected
+        let request: CredentialRequest =
+            serde_json::from_value(value.clone()).expect("request is valid");
+        let Err(Error::InvalidEncryptionParameters(_)) =
+            credential(provider.clone(), request).await
+        else {
+            panic!("should reject unsupported alg");
+        };
+
+        let mut request: CredentialRequest =
+            serde_json::from_value(value).expect("request is valid");
+        if let Some(encryption) = request.credential_response_encryption.as_mut() {
+            encryption.alg = "ECDH-ES".into();
+        }
+        let response = credential(provider.clone(), request).await.expect("response is valid");
+
+        // only the holder can decrypt the response
+        let CredentialResponseType::Encrypted(compact) = &response.response else {
+            panic!("expected an encrypted response");
+        };
+        assert!(response.c_nonce.is_none());
+
+        let decrypted: CredentialResponse =
+            jwe::decrypt(compact, &holder::Provider).await.expect("should decrypt");
+        assert!(decrypted.c_nonce.is_some());
+
+        let CredentialResponseType::Credential(vc_kind) = &decrypted.response else {
+            panic!("expected a single credential");
+        };
+        let Payload::Vc { vc, .. } =
+            proof::verify(Verify::Vc(&vc_kind), &provider).await.expect("should decode")
+        else {
+            panic!("should be VC");
+        };
+        let types = serde_json::to_value(&vc.type_).expect("should serialize");
+        assert_eq!(types, json!(["VerifiableCredential", "EmployeeIDCredential"]));
+    }
+
+    #[tokio::test]
+    async fn sd_jwt() {
+        vercre_test_utils::init_tracer();
+
+        let provider = Provider::new();
+        let access_token = "ABCDEF";
+        let c_nonce = "1234ABCD";
+
+        // set up state
+        let state = State {
+            stage: Stage::Validated(Token {
+                access_token: access_token.into(),
+                credentials: HashMap::from([(
+                    "PHLEmployeeSD".into(),
+                    Authorized {
+                        credential_identifier: "PHLEmployeeSD".into(),
+                        credential_configuration_id: "EmployeeID_SD_JWT".into(),
+                        claim_ids: None,
+                    },
+                )]),
+                c_nonce: c_nonce.into(),
+                c_nonce_expires_at: Utc::now() + Expire::Nonce.duration(),
+                dpop_jkt: None,
+            }),
+            subject_id: Some(NORMAL_USER.into()),
+            expires_at: Utc::now() + Expire::Authorized.duration(),
+        };
+
+        StateStore::put(&provider, access_token, &state, state.expires_at)
+            .await
+            .expect("state exists");
+
+        let claims = ProofClaims {
+            iss: Some(CLIENT_ID.into()),
+            aud: CREDENTIAL_ISSUER.into(),
+            iat: Utc::now().timestamp(),
+            nonce: Some(c_nonce.into()),
+        };
+        let jwt = jws::encode(Type::Proof, &claims, holder::Provider).await.expect("should encode");
+
+        let value = json!({
+            "credential_issuer": CREDENTIAL_ISSUER,
+            "access_token": access_token,
+            "credential_identifier": "PHLEmployeeSD",
+            "proof":{
+                "proof_type": "jwt",
+                "jwt": jwt
+            }
+        });
+        let request = serde_json::from_value(value).expect("request is valid");
+        let response = credential(provider.clone(), request).await.expect("response is valid");
+
+        // split SD-JWT into issuer-signed JWT and disclosures
+        let CredentialResponseType::Credential(Kind::String(sd_jwt)) = &response.response else {
+            panic!("expected a single SD-JWT credential");
+        };
+        let parts = sd_jwt.split(sdjwt::SEPARATOR).collect::<Vec<&str>>();
+        assert_eq!(parts.len(), 5, "should be JWT, 3 disclosures, and empty key binding");
+        assert_eq!(parts[4], "");
+
+        let payload = parts[0].split('.').nth(1).expect("should have payload");
+        let decoded = Base64UrlUnpadded::decode_vec(payload).expect("should decode");
+        let sd_claims: sdjwt::SdJwtClaims =
+            serde_json::from_slice(&decoded).expect("should deserialize");
+
+        assert_eq!(sd_claims.vct, "EmployeeIDCredential");
+        assert_eq!(sd_claims.sd_alg, sdjwt::SD_ALG);
+        assert!(sd_claims.cnf.is_some());
+
+        // each disclosure should be referenced by a digest
+        for encoded in &parts[1..4] {
+            assert!(sd_claims.sd.contains(&sdjwt::digest(encoded)));
+            let disclosure = sdjwt::Disclosure::decode(encoded).expect("should decode");
+            assert!(["given_name", "family_name", "email"].contains(&disclosure.name.as_str()));
+        }
+    }
+
+    #[tokio::test]
+    async fn json_ld() {
+        vercre_test_utils::init_tracer();
+
+        // issue the employee ID credential as `jwt_vc_json-ld`
+        let mut provider = Provider::new();
+        let mut issuer = provider.issuer.get(CREDENTIAL_ISSUER).expect("should get issuer");
+        let config = issuer
+            .credential_configurations_supported
+            .get_mut("EmployeeID_JWT")
+            .expect("should have configuration");
+        let Format::JwtVcJson(mut w3c) = config.format.clone() else {
+            panic!("should be a W3C credential");
+        };
+        w3c.credential_definition.context = Some(vec![
+            "https://www.w3.org/2018/credentials/v1".into(),
+            "https://www.w3.org/2018/credentials/examples/v1".into(),
+        ]);
+        config.format = Format::JwtVcJsonLd(w3c);
+        provider.issuer.put(issuer.clone());
+
+        let access_token = "ABCDEF";
+        let c_nonce = "1234ABCD";
+        let state = State {
+            stage: Stage::Validated(Token {
+                access_token: access_token.into(),
+                credentials: HashMap::from([(
+                    "PHLEmployeeID".into(),
+                    Authorized {
+                        credential_identifier: "PHLEmployeeID".into(),
+                        credential_configuration_id: "EmployeeID_JWT".into(),
+                        claim_ids: None,
+                    },
+                )]),
+                c_nonce: c_nonce.into(),
+                c_nonce_expires_at: Utc::now() + Expire::Nonce.duration(),
+                dpop_jkt: None,
+            }),
+            subject_id: Some(NORMAL_USER.into()),
+            expires_at: Utc::now() + Expire::Authorized.duration(),
+        };
+        StateStore::put(&provider, access_token, &state, state.expires_at)
+            .await
+            .expect("state exists");
+
+        let claims = ProofClaims {
+            iss: Some(CLIENT_ID.into()),
+            aud: CREDENTIAL_ISSUER.into(),
+            iat: Utc::now().timestamp(),
+            nonce: Some(c_nonce.into()),
+        };
+        let jwt = jws::encode(Type::Proof, &claims, holder::Provider).await.expect("should encode");
+        let value = json!({
+            "credential_issuer": CREDENTIAL_ISSUER,
+            "access_token": access_token,
+            "credential_identifier": "PHLEmployeeID",
+            "proof":{
+                "proof_type": "jwt",
+                "jwt": jwt
+            }
+        });
+        let request: CredentialRequest = serde_json::from_value(value).expect("request is valid");
+        let response =
+            credential(provider.clone(), request.clone()).await.expect("response is valid");
+
+        // the credential is a JWT with the configured contexts
+        let CredentialResponseType::Credential(vc_kind) = &response.response else {
+            panic!("expected a single credential");
+        };
+        assert!(matches!(vc_kind, Kind::String(_)));
+        let Payload::Vc { vc, .. } =
+            proof::verify(Verify::Vc(vc_kind), &provider).await.expect("should decode")
+        else {
+            panic!("should be VC");
+        };
+        assert_eq!(
+            vc.context,
+            vec![
+                Kind::String("https://www.w3.org/2018/credentials/v1".into()),
+                Kind::String(format!("{CREDENTIAL_ISSUER}/credentials/v1")),
+                Kind::String("https://www.w3.org/2018/credentials/examples/v1".into()),
+            ]
+        );
+
+        // JSON-LD formats cannot be issued without a valid `@context`
+        let cases = [
+            (None, "credential definition has no `@context`"),
+            (
+                Some(vec!["https://www.w3.org/2018/credentials/examples/v1".into()]),
+                "credential definition `@context` must start with https://www.w3.org/2018/credentials/v1",
+            ),
+            (
+                Some(vec!["https://www.w3.org/2018/credentials/v1".into()]),
+                "credential definition `@context` does not define the credential's terms",
+            ),
+        ];
+        for (context, expected) in cases {
+            let config = issuer
+                .credential_configurations_supported
+                .get_mut("EmployeeID_JWT")
+                .expect("should have configuration");
+            let Format::JwtVcJsonLd(w3c) = &mut config.format else {
+                panic!("should be a JSON-LD credential");
+            };
+            w3c.credential_definition.context = context;
+            provider.issuer.put(issuer.clone());
+
+            let state = State {
+                expires_at: Utc::now() + Expire::Authorized.duration(),
+                ..state.clone()
+            };
+            StateStore::put(&provider, access_token, &state, state.expires_at)
+                .await
+                .expect("state exists");
+            let Err(Error::ServerError(e)) = credential(provider.clone(), request.clone()).await
+            else {
+                panic!("should fail with an invalid context");
+            };
+            assert_eq!(e, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn ldp_vc() {
+        vercre_test_utils::init_tracer();
+
+        // issue the employee ID credential as `ldp_vc`
+        let mut provider = Provider::new();
+        let mut issuer = provider.issuer.get(CREDENTIAL_ISSUER).expect("should get issuer");
+        let config = issuer
+            .credential_configurations_supported
+            .get_mut("EmployeeID_JWT")
+            .expect("should have configuration");
+        let Format::JwtVcJson(mut w3c) = config.format.clone() else {
+            panic!("should be a W3C credential");
+        };
+        w3c.credential_definition.context = Some(vec![
+            "https://www.w3.org/2018/credentials/v1".into(),
+            "https://www.w3.org/2018/credentials/examples/v1".into(),
+        ]);
+        config.format = Format::LdpVc(w3c);
+        provider.issuer.put(issuer);
+
+        let access_token = "ABCDEF";
+        let c_nonce = "1234ABCD";
+        let state = State {
+            stage: Stage::Validated(Token {
+                access_token: access_token.into(),
+                credentials: HashMap::from([(
+                    "PHLEmployeeID".into(),
+                    Authorized {
+                        credential_identifier: "PHLEmployeeID".into(),
+                        credential_configuration_id: "EmployeeID_JWT".into(),
+                        claim_ids: None,
+                    },
+                )]),
+                c_nonce: c_nonce.into(),
+                c_nonce_expires_at: Utc::now() + Expire::Nonce.duration(),
+                dpop_jkt: None,
+            }),
+            subject_id: Some(NORMAL_USER.into()),
+            expires_at: Utc::now() + Expire::Authorized.duration(),
+        };
+        StateStore::put(&provider, access_token, &state, state.expires_at)
+            .await
+            .expect("state exists");
+
+        let claims = ProofClaims {
+            iss: Some(CLIENT_ID.into()),
+            aud: CREDENTIAL_ISSUER.into(),
+            iat: Utc::now().timestamp(),
+            nonce: Some(c_nonce.into()),
+        };
+        let jwt = jws::encode(Type::Proof, &claims, holder::Provider).await.expect("should encode");
+        let value = json!({
+            "credential_issuer": CREDENTIAL_ISSUER,
+            "access_token": access_token,
+            "credential_identifier": "PHLEmployeeID",
+            "proof":{
+                "proof_type": "jwt",
+                "jwt": jwt
+            }
+        });
+        let request = serde_json::from_value(value).expect("request is valid");
+        let response = credential(provider.clone(), request).await.expect("response is valid");
+
+        // the credential is a JSON object secured with an embedded proof
+        let CredentialResponseType::Credential(vc_kind) = &response.response else {
+            panic!("expected a single credential");
+        };
+        assert!(matches!(vc_kind, Kind::Object(_)));
+        let Payload::Vc { vc, .. } =
+            proof::verify(Verify::Vc(vc_kind), &provider).await.expect("should verify")
+        else {
+            panic!("should be VC");
+        };
+
+        // the issuer is the DID of the signing key, not the Credential Issuer
+        let signer = SecOps::signer(&provider, CREDENTIAL_ISSUER).expect("should get signer");
+        let verification_method = signer.verification_method();
+        let did = verification_method.split('#').next().expect("should have DID");
+        assert_eq!(vc.issuer, Kind::String(did.into()));
+        assert!(vc.context.contains(&Kind::String(integrity::DATA_INTEGRITY_CONTEXT.into())));
+        assert!(vc.proof.is_some());
+    }
+
     #[tokio::test]
     #[ignore]
     async fn format() {
@@ -588,6 +1391,7 @@ mod tests {
                 )]),
                 c_nonce: c_nonce.into(),
                 c_nonce_expires_at: Utc::now() + Expire::Nonce.duration(),
+                dpop_jkt: None,
             }),
             subject_id: Some(NORMAL_USER.into()),
             expires_at: Utc::now() + Expire::Authorized.duration(),
@@ -678,6 +1482,7 @@ mod tests {
                 )]),
                 c_nonce: c_nonce.into(),
                 c_nonce_expires_at: Utc::now() + Expire::Nonce.duration(),
+                dpop_jkt: None,
             }),
             subject_id: Some(NORMAL_USER.into()),
             expires_at: Utc::now() + Expire::Authorized.duration(),
@@ -10,13 +10,12 @@
 
 use tracing::instrument;
 use vercre_openid::issuer::{
-    CredentialResponseType, DeferredCredentialRequest, DeferredCredentialResponse, Provider,
-    StateStore,
+    DeferredCredentialRequest, DeferredCredentialResponse, Metadata, Provider, StateStore,
 };
 use vercre_openid::{Error, Result};
 
-use crate::credential::credential;
 use crate::state::{Stage, State};
+use crate::{credential, dpop};
 
 /// Deferred credential request handler.
 ///
@@ -36,6 +35,14 @@ async fn process(
 ) -> Result<DeferredCredentialResponse> {
     tracing::debug!("deferred::process");
 
+    let issuer = Metadata::issuer(provider, &request.credential_issuer)
+        .await
+        .map_err(|e| Error::ServerError(format!("metadata issue: {e}")))?;
+    let Some(endpoint) = &issuer.deferred_credential_endpoint else {
+        return Err(Error::ServerError("deferred credential endpoint not set".into()));
+    };
+    dpop::verify_token(provider, &request.access_token, request.dpop.as_deref(), endpoint).await?;
+
     // retrieve deferred credential request from state
     let Ok(state) = StateStore::get::<State>(provider, &request.transaction_id).await else {
         return Err(Error::InvalidTransactionId("deferred state not found".into()));
@@ -58,13 +65,8 @@ async fn process(
     cred_req.credential_issuer.clone_from(&request.credential_issuer);
     cred_req.access_token.clone_from(&request.access_token);
 
-    let response = credential(provider.clone(), cred_req).await?;
-
-    // is issuance still pending?
-    if let CredentialResponseType::TransactionId(_) = response.response {
-        // TODO: make retry interval configurable
-        return Err(Error::IssuancePending(5));
-    };
+    // issuance fails with `IssuancePending` if claims are still pending
+    let response = credential::issue(provider, issuer, cred_req, true).await?;
 
     Ok(DeferredCredentialResponse {
         credential_response: response,
@@ -132,6 +134,7 @@ mod tests {
                 )]),
                 c_nonce: c_nonce.into(),
                 c_nonce_expires_at: Utc::now() + Expire::Nonce.duration(),
+                dpop_jkt: None,
             }),
             subject_id: Some(NORMAL_USER.into()),
             expires_at: Utc::now() + Expire::Authorized.duration(),
@@ -154,6 +157,7 @@ mod tests {
             credential_issuer: CREDENTIAL_ISSUER.into(),
             access_token: access_token.into(),
             transaction_id: transaction_id.into(),
+            dpop: None,
         };
 
         let response = deferred(provider.clone(), request).await.expect("response is valid");
@@ -178,6 +182,7 @@ mod tests {
         };
 
         assert_snapshot!("deferred:deferred_ok:vc", vc, {
+            ".id" => "[id]",
             ".validFrom" => "[validFrom]",
             ".credentialSubject" => insta::sorted_redaction()
         });
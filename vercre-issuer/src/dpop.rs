@@ -0,0 +1,127 @@
+//! # DPoP
+//!
+//! Sender-constrained access tokens using Demonstrating Proof of Possession
+//! ([RFC9449]).
+//!
+//! A Token Request carrying a DPoP proof is issued an access token bound to the
+//! proof's key. Subsequent requests to the Credential, Deferred Credential,
+//! and Notification endpoints using the token must then be accompanied by a
+//! fresh proof, signed with the same key, that includes a hash of the token.
+//!
+//! Proofs must include a server-provided nonce. Requests with a missing or
+//! unknown nonce are rejected with a `use_dpop_nonce` error containing a fresh
+//! nonce for the Wallet to retry with.
+//!
+//! [RFC9449]: (https://www.rfc-editor.org/rfc/rfc9449.html)
+
+use chrono::{DateTime, Utc};
+use vercre_core::gen;
+use vercre_openid::issuer::{Metadata, Provider, StateStore, TokenRequest};
+use vercre_openid::{dpop, Error, Result};
+
+use crate::state::{Expire, Stage, State};
+
+/// Verify the DPoP proof accompanying a Token Request, if any, returning the
+/// thumbprint of the key the access token is to be bound to.
+pub async fn bind(provider: &impl Provider, request: &TokenRequest) -> Result<Option<String>> {
+    let Some(proof) = &request.dpop else {
+        return Ok(None);
+    };
+    let Ok(server) = Metadata::server(provider, &request.credential_issuer, None).await else {
+        return Err(Error::InvalidRequest("unknown authorization server".into()));
+    };
+    if server.oauth.dpop_signing_alg_values_supported.is_none() {
+        return Err(Error::InvalidDpopProof("DPoP is not supported".into()));
+    }
+    verify(provider, proof, &server.oauth.token_endpoint, None).await.map(Some)
+}
+
+/// Verify the access token presented to a Credential Issuer endpoint, `htu`,
+/// is accompanied by a DPoP proof signed with the key the token is bound to.
+/// Bearer tokens need no proof.
+pub async fn verify_token(
+    provider: &impl Provider, access_token: &str, proof: Option<&str>, htu: &str,
+) -> Result<()> {
+    let Ok(state) = StateStore::get::<State>(provider, access_token).await else {
+        return Err(Error::AccessDenied("invalid access token".into()));
+    };
+    let Stage::Validated(token_state) = state.stage else {
+        return Err(Error::AccessDenied("invalid access token state".into()));
+    };
+    let Some(dpop_jkt) = token_state.dpop_jkt else {
+        return Ok(());
+    };
+
+    let Some(proof) = proof else {
+        return Err(Error::InvalidDpopProof("DPoP proof is required".into()));
+    };
+    if verify(provider, proof, htu, Some(access_token)).await? != dpop_jkt {
+        return Err(Error::InvalidDpopProof("DPoP key does not match the access token".into()));
+    }
+    Ok(())
+}
+
+/// Generate and save a fresh DPoP nonce.
+pub async fn nonce(provider: &impl Provider) -> Result<String> {
+    let nonce = gen::nonce();
+    StateStore::put(provider, &nonce_key(&nonce), &nonce, Utc::now() + Expire::Nonce.duration())
+        .await
+        .map_err(|e| Error::ServerError(format!("issue saving DPoP nonce: {e}")))?;
+    Ok(nonce)
+}
+
+// Verify a DPoP proof was created for a POST to `htu`, returning the
+// thumbprint of its key.
+async fn verify(
+    provider: &impl Provider, proof: &str, htu: &str, access_token: Option<&str>,
+) -> Result<String> {
+    let (header, claims) = dpop::verify(proof)
+        .map_err(|e| Error::InvalidDpopProof(format!("invalid DPoP proof: {e}")))?;
+
+    if claims.htm != "POST" || claims.htu != htu {
+        return Err(Error::InvalidDpopProof("DPoP proof does not match the request".into()));
+    }
+    let Some(iat) = DateTime::from_timestamp(claims.iat, 0) else {
+        return Err(Error::InvalidDpopProof("DPoP proof `iat` is invalid".into()));
+    };
+    let window = Expire::Nonce.duration();
+    if iat < Utc::now() - window || iat > Utc::now() + window {
+        return Err(Error::InvalidDpopProof("DPoP proof `iat` is not acceptable".into()));
+    }
+    if let Some(access_token) = access_token {
+        if claims.ath != Some(dpop::access_token_hash(access_token)) {
+            return Err(Error::InvalidDpopProof(
+                "DPoP proof `ath` does not match the access token".into(),
+            ));
+        }
+    }
+
+    // the proof must carry a nonce issued by this server
+    let nonce_valid = match &claims.nonce {
+        Some(nonce) => StateStore::get::<String>(provider, &nonce_key(nonce)).await.is_ok(),
+        None => false,
+    };
+    if !nonce_valid {
+        return Err(Error::UseDpopNonce {
+            hint: "DPoP proof must include a server-provided nonce".into(),
+            dpop_nonce: nonce(provider).await?,
+        });
+    }
+
+    // a proof may only be used once
+    let jkt = dpop::thumbprint(&header.jwk)
+        .map_err(|e| Error::InvalidDpopProof(format!("invalid DPoP key: {e}")))?;
+    let jti_key = format!("dpop_jti:{jkt}:{}", claims.jti);
+    if claims.jti.is_empty() || StateStore::get::<String>(provider, &jti_key).await.is_ok() {
+        return Err(Error::InvalidDpopProof("DPoP proof has already been used".into()));
+    }
+    StateStore::put(provider, &jti_key, &claims.jti, iat + window)
+        .await
+        .map_err(|e| Error::ServerError(format!("issue saving state: {e}")))?;
+
+    Ok(jkt)
+}
+
+fn nonce_key(nonce: &str) -> String {
+    format!("dpop_nonce:{nonce}")
+}
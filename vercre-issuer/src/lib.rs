@@ -91,17 +91,21 @@
 //! [RFC6749]: (https://www.rfc-editor.org/rfc/rfc6749.html)
 
 mod authorize;
+mod client_auth;
 mod create_offer;
 mod credential;
 mod credential_offer;
 mod deferred;
+mod dpop;
 mod metadata;
 mod notification;
 mod oauth_server;
 mod par;
 mod register;
 mod state;
+mod status_list;
 mod token;
+mod update_status;
 
 /// Re-export provider traits and types.
 pub mod provider {
@@ -112,7 +116,9 @@ pub mod provider {
         ClaimDefinition, Client, Dataset, GrantType, Issuer, Metadata, Provider, Result, Server,
         StateStore, Subject,
     };
-    pub use vercre_status::issuer::Status;
+    pub use vercre_status::issuer::{
+        CredentialStatusConfig, ListConfig, ShardedList, Status, StatusLogEntry,
+    };
 }
 
 pub use authorize::authorize;
@@ -125,20 +131,24 @@ pub use notification::notification;
 pub use oauth_server::oauth_server;
 pub use par::par;
 pub use register::register;
+pub use status_list::status_list;
 pub use token::token;
+pub use update_status::update_status;
 pub use vercre_core::urlencode;
 pub use vercre_openid::issuer::{
     AuthorizationCodeGrant, AuthorizationDetail, AuthorizationDetailType, AuthorizationRequest,
-    AuthorizationResponse, AuthorizedDetail, Claim, ClaimDefinition, CreateOfferRequest,
-    CreateOfferResponse, CredentialAuthorization, CredentialConfiguration, CredentialDefinition,
-    CredentialIssuance, CredentialOffer, CredentialOfferRequest, CredentialOfferResponse,
-    CredentialRequest, CredentialResponse, CredentialResponseType, DeferredCredentialRequest,
-    DeferredCredentialResponse, Format, GrantType, Grants, MetadataRequest, MetadataResponse,
-    NotificationEvent, NotificationRequest, NotificationResponse, OAuthServerRequest,
-    OAuthServerResponse, OfferType, PreAuthorizedCodeGrant, ProfileClaims, ProfileIsoMdl,
-    ProfileSdJwt, ProfileW3c, Proof, ProofClaims, PushedAuthorizationRequest,
-    PushedAuthorizationResponse, RegistrationRequest, RegistrationResponse, RequestObject,
-    SendType, SingleProof, TokenGrantType, TokenRequest, TokenResponse, TxCode,
+    AuthorizationResponse, AuthorizedDetail, Claim, ClaimDefinition, ClientAssertion,
+    ClientAssertionClaims, CreateOfferRequest, CreateOfferResponse, CredentialAuthorization,
+    CredentialConfiguration, CredentialDefinition, CredentialIssuance, CredentialOffer,
+    CredentialOfferRequest, CredentialOfferResponse, CredentialRequest, CredentialResponse,
+    CredentialResponseType, DeferredCredentialRequest, DeferredCredentialResponse, Format,
+    GrantType, Grants, MetadataRequest, MetadataResponse, NotificationEvent, NotificationRequest,
+    NotificationResponse, OAuthServerRequest, OAuthServerResponse, OfferType,
+    PreAuthorizedCodeGrant, ProfileClaims, ProfileIsoMdl, ProfileSdJwt, ProfileW3c, Proof,
+    ProofClaims, PushedAuthorizationRequest, PushedAuthorizationResponse, RegistrationRequest,
+    RegistrationResponse, RequestObject, SendType, SingleProof, StatusListRequest,
+    StatusListResponse, TokenGrantType, TokenRequest, TokenResponse, TxCode, UpdateStatusRequest,
+    UpdateStatusResponse,
 };
 pub use vercre_openid::Result;
 pub use vercre_w3c_vc::model::{
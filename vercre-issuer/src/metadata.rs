@@ -78,7 +78,8 @@ mod tests {
             ".**.credentialSubject" => insta::sorted_redaction(),
             ".**.credentialSubject.address" => insta::sorted_redaction(),
             ".**[\"org.iso.18013.5.1.mDL\"].claims" => insta::sorted_redaction(),
-            ".**[\"org.iso.18013.5.1.mDL\"].claims[\"org.iso.18013.5.1\"]" => insta::sorted_redaction()
+            ".**[\"org.iso.18013.5.1.mDL\"].claims[\"org.iso.18013.5.1\"]" => insta::sorted_redaction(),
+            ".**.EmployeeID_SD_JWT.claims" => insta::sorted_redaction()
         });
     }
 }
@@ -20,9 +20,12 @@
 //! certain time period or at all.
 
 use tracing::instrument;
-use vercre_openid::issuer::{NotificationRequest, NotificationResponse, Provider, StateStore};
+use vercre_openid::issuer::{
+    Metadata, NotificationRequest, NotificationResponse, Provider, StateStore,
+};
 use vercre_openid::{Error, Result};
 
+use crate::dpop;
 use crate::state::{Stage, State};
 
 /// Notification request handler.
@@ -38,13 +41,20 @@ pub async fn notification(
     process(&provider, request).await
 }
 
-#[allow(clippy::unused_async)]
 #[allow(dead_code)]
 async fn process(
     provider: &impl Provider, request: NotificationRequest,
 ) -> Result<NotificationResponse> {
     tracing::debug!("notification::process");
 
+    let issuer = Metadata::issuer(provider, &request.credential_issuer)
+        .await
+        .map_err(|e| Error::ServerError(format!("metadata issue: {e}")))?;
+    let Some(endpoint) = &issuer.notification_endpoint else {
+        return Err(Error::ServerError("notification endpoint not set".into()));
+    };
+    dpop::verify_token(provider, &request.access_token, request.dpop.as_deref(), endpoint).await?;
+
     let Ok(state) = StateStore::get::<State>(provider, &request.notification_id).await else {
         return Err(Error::AccessDenied("invalid access token".into()));
     };
@@ -77,7 +87,7 @@ mod tests {
     use vercre_w3c_vc::model::VerifiableCredential;
 
     use super::*;
-    use crate::state::{Credential, Expire};
+    use crate::state::{Credential, Expire, Token};
 
     #[tokio::test]
     async fn notification_ok() {
@@ -85,8 +95,21 @@ mod tests {
         snapshot!("");
 
         let provider = Provider::new();
+        let access_token = "ABCDEF";
         let notification_id = "123456";
 
+        let state = State {
+            expires_at: Utc::now() + Expire::Authorized.duration(),
+            subject_id: Some(NORMAL_USER.into()),
+            stage: Stage::Validated(Token {
+                access_token: access_token.into(),
+                ..Token::default()
+            }),
+        };
+        StateStore::put(&provider, access_token, &state, state.expires_at)
+            .await
+            .expect("state exists");
+
         let state = State {
             expires_at: Utc::now() + Expire::Authorized.duration(),
             subject_id: Some(NORMAL_USER.into()),
@@ -100,10 +123,11 @@ mod tests {
 
         let request = NotificationRequest {
             credential_issuer: CREDENTIAL_ISSUER.to_string(),
-            access_token: "ABCDEF".into(),
+            access_token: access_token.into(),
             notification_id: notification_id.into(),
             event: NotificationEvent::CredentialAccepted,
             event_description: Some("Credential accepted".into()),
+            dpop: None,
         };
         let response = notification(provider.clone(), request).await.expect("response is ok");
 
@@ -14,8 +14,8 @@ use vercre_openid::issuer::{
 };
 use vercre_openid::{Error, Result};
 
-use crate::authorize;
 use crate::state::{PushedAuthorization, Stage, State};
+use crate::{authorize, client_auth};
 
 /// Endpoint for the Wallet to push an Authorization Request when using Pushed
 /// Authorization Requests.
@@ -33,15 +33,23 @@ pub async fn par(
 }
 
 // Verify the pushed Authorization Request.
-#[allow(clippy::unused_async)]
 async fn verify(provider: &impl Provider, request: &PushedAuthorizationRequest) -> Result<()> {
     tracing::debug!("par::verify");
 
-    // TODO: authenticate the client in the same way as at the token endpoint
-    //       (client assertion)
-
     let req_obj = &request.request;
 
+    // authenticate the client in the same way as at the token endpoint
+    let Ok(server) = Metadata::server(provider, &req_obj.credential_issuer, None).await else {
+        return Err(Error::InvalidRequest("unknown authorization server".into()));
+    };
+    client_auth::authenticate(
+        provider,
+        &server,
+        Some(&req_obj.client_id),
+        request.client_assertion.as_ref(),
+    )
+    .await?;
+
     // verify the pushed RequestObject using `/authorize` endpoint logic
     let Ok(issuer) = Metadata::issuer(provider, &req_obj.credential_issuer).await else {
         return Err(Error::InvalidClient("invalid `credential_issuer`".into()));
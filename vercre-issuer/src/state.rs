@@ -6,7 +6,8 @@ use std::collections::HashMap;
 use chrono::{DateTime, TimeDelta, Utc};
 use serde::{Deserialize, Serialize};
 use vercre_openid::issuer::{
-    AuthorizationDetail, CodeChallengeMethod, CredentialOffer, CredentialRequest, RequestObject,
+    AuthorizationDetail, AuthorizedDetail, CodeChallengeMethod, CredentialOffer, CredentialRequest,
+    RequestObject,
 };
 use vercre_w3c_vc::model::VerifiableCredential;
 
@@ -66,6 +67,10 @@ pub enum Stage {
     /// the Wallet is authorized to request.
     Validated(Token),
 
+    /// Holds the credentials the Wallet remains authorized to request using
+    /// a refresh token.
+    Refreshable(Refresh),
+
     /// Issued Credential state.
     Issued(Credential),
 
@@ -185,6 +190,11 @@ pub struct Token {
     /// Credentials (configuration id and identifier) validated for issuance
     /// using the accompanying access token.
     pub credentials: HashMap<CredentialIdentifier, Authorized>,
+
+    /// The JWK thumbprint of the Wallet's DPoP key when the access token is
+    /// DPoP-bound.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dpop_jkt: Option<String>,
 }
 
 impl Token {
@@ -197,6 +207,27 @@ impl Token {
     }
 }
 
+/// Refresh token state.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Refresh {
+    /// The `client_id` of the Wallet the refresh token was issued to.
+    pub client_id: String,
+
+    /// Authorization details returned with the original access token, to be
+    /// returned with each new access token.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authorization_details: Option<Vec<AuthorizedDetail>>,
+
+    /// Credentials (configuration id and identifier) carried over from the
+    /// original access token.
+    pub credentials: HashMap<CredentialIdentifier, Authorized>,
+
+    /// The JWK thumbprint of the Wallet's DPoP key when the original access
+    /// token was DPoP-bound. The refresh token is bound to the same key.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dpop_jkt: Option<String>,
+}
+
 /// Issued Credential state (for Notification endpoint).
 #[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
 pub struct Credential {
@@ -218,6 +249,7 @@ pub enum Expire {
     Authorized,
     Access,
     Nonce,
+    Refresh,
 }
 
 impl Expire {
@@ -226,6 +258,7 @@ impl Expire {
             Self::Authorized => TimeDelta::try_minutes(5).unwrap_or_default(),
             Self::Access => TimeDelta::try_minutes(15).unwrap_or_default(),
             Self::Nonce => TimeDelta::try_minutes(10).unwrap_or_default(),
+            Self::Refresh => TimeDelta::try_days(365).unwrap_or_default(),
         }
     }
 }
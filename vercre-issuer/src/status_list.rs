@@ -0,0 +1,93 @@
+//! # Status List Endpoint
+//!
+//! Serves the `BitstringStatusListCredential` for a shard of a status list.
+//! Verifiers dereference a credential's `statusListCredential` URL to retrieve
+//! the list and check the credential's status.
+//!
+//! Lists are published at `{credential_issuer}/status/{list}/{shard}`. A shard
+//! that has not yet been signed (e.g. because none of its entries have been
+//! updated since they were allocated) is signed and saved on first request.
+//!
+//! [Bitstring Status List v1.0](https://www.w3.org/TR/vc-bitstring-status-list/)
+
+use tracing::instrument;
+use vercre_infosec::SecOps;
+use vercre_openid::issuer::{Provider, StatusListRequest, StatusListResponse};
+use vercre_openid::{Error, Result};
+use vercre_status::bitstring;
+use vercre_status::issuer::{ShardedList, Status};
+
+/// Status list request handler.
+///
+/// # Errors
+///
+/// Returns an `OpenID4VCI` error if the requested list does not exist or the
+/// list cannot be signed.
+#[instrument(level = "debug", skip(provider))]
+pub async fn status_list(
+    provider: impl Provider, request: StatusListRequest,
+) -> Result<StatusListResponse> {
+    process(&provider, request).await
+}
+
+async fn process(
+    provider: &impl Provider, request: StatusListRequest,
+) -> Result<StatusListResponse> {
+    tracing::debug!("status_list::process");
+
+    let published = Status::status_list_credential(provider, request.list, request.shard)
+        .await
+        .map_err(|e| Error::ServerError(format!("issue getting status list: {e}")))?;
+    if let Some(credential) = published {
+        return Ok(StatusListResponse { credential });
+    }
+
+    let Some(sharded) = Status::sharded_list(provider, request.list)
+        .await
+        .map_err(|e| Error::ServerError(format!("issue getting status list: {e}")))?
+    else {
+        return Err(Error::InvalidRequest(format!("status list {} not found", request.list)));
+    };
+    let credential = publish(provider, &request.credential_issuer, &sharded, request.shard).await?;
+
+    Ok(StatusListResponse { credential })
+}
+
+/// The base URL status lists are published under.
+pub fn base_url(credential_issuer: &str) -> String {
+    format!("{}/status", credential_issuer.trim_end_matches('/'))
+}
+
+// Sign a shard of the list as a `BitstringStatusListCredential` and save it
+// for serving from the status list endpoint.
+pub async fn publish(
+    provider: &impl Provider, credential_issuer: &str, sharded: &ShardedList, shard: usize,
+) -> Result<String> {
+    let Some(list) = sharded.shard(shard) else {
+        return Err(Error::InvalidRequest(format!("status list shard {shard} not found")));
+    };
+    let encoded = list
+        .encode()
+        .map_err(|e| Error::ServerError(format!("issue encoding status list: {e}")))?;
+
+    let signer = SecOps::signer(provider, credential_issuer)
+        .map_err(|e| Error::ServerError(format!("issue getting signer: {e}")))?;
+    let config = sharded.config();
+    let credential = bitstring::credential(
+        credential_issuer,
+        config,
+        shard,
+        &base_url(credential_issuer),
+        &encoded,
+        None,
+        signer,
+    )
+    .await
+    .map_err(|e| Error::ServerError(format!("issue signing status list: {e}")))?;
+
+    Status::put_status_list_credential(provider, config.list, shard, &credential)
+        .await
+        .map_err(|e| Error::ServerError(format!("issue saving status list: {e}")))?;
+
+    Ok(credential)
+}
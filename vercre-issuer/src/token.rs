@@ -11,8 +11,6 @@
 //! credentials, or other sensitive information, as well as the "Pragma"
 //! response header field [RFC2616](https://www.rfc-editor.org/rfc/rfc2616) with a value of "no-cache".
 
-// TODO: verify `client_assertion` JWT, when set
-
 use std::collections::HashMap;
 use std::fmt::Debug;
 
@@ -26,7 +24,8 @@ use vercre_openid::issuer::{
 use vercre_openid::oauth::GrantType;
 use vercre_openid::{Error, Result};
 
-use crate::state::{Authorized, AuthorizedItem, Expire, ItemType, Stage, State, Token};
+use crate::state::{Authorized, AuthorizedItem, Expire, ItemType, Refresh, Stage, State, Token};
+use crate::{client_auth, dpop};
 
 /// Token request handler.
 ///
@@ -42,20 +41,31 @@ pub async fn token(provider: impl Provider, request: TokenRequest) -> Result<Tok
         TokenGrantType::PreAuthorizedCode {
             pre_authorized_code, ..
         } => pre_authorized_code,
+        TokenGrantType::RefreshToken { refresh_token } => refresh_token,
     };
 
+    // check any DPoP proof before the one-time code is used so the Wallet can
+    // retry with a server-provided nonce
+    let dpop_jkt = dpop::bind(&provider, &request).await?;
+
     // RFC 6749 requires a particular error here
     let Ok(state) = StateStore::get(&provider, state_key).await else {
         return Err(Error::InvalidGrant("authorization code is invalid".into()));
     };
 
-    // authorization code is one-time use
+    let ctx = Context { state, dpop_jkt };
+
+    // check a refresh token's binding before it is purged so one presented by
+    // another client (or DPoP key) can't destroy the legitimate one
+    if let TokenGrantType::RefreshToken { .. } = &request.grant_type {
+        ctx.verify_refresh(&request)?;
+    }
+
+    // authorization code (and refresh token) is one-time use
     StateStore::purge(&provider, state_key)
         .await
         .map_err(|e| Error::ServerError(format!("issue purging authorizaiton state: {e}")))?;
 
-    let ctx = Context { state };
-
     ctx.verify(&provider, &request).await?;
     ctx.process(&provider, request).await
 }
@@ -63,6 +73,7 @@ pub async fn token(provider: impl Provider, request: TokenRequest) -> Result<Tok
 #[derive(Debug)]
 struct Context {
     state: State,
+    dpop_jkt: Option<String>,
 }
 
 impl Context {
@@ -82,6 +93,15 @@ impl Context {
             return Err(Error::ServerError("authorization server grant types not set".into()));
         };
 
+        // authenticate the client when it is registered to do so
+        client_auth::authenticate(
+            provider,
+            &server,
+            request.client_id.as_deref(),
+            request.client_assertion.as_ref(),
+        )
+        .await?;
+
         // grant_type
         match &request.grant_type {
             TokenGrantType::PreAuthorizedCode { tx_code, .. } => {
@@ -150,6 +170,13 @@ impl Context {
                     return Err(Error::AccessDenied("`code_verifier` is invalid".into()));
                 }
             }
+            TokenGrantType::RefreshToken { .. } => {
+                // grant_type supported?
+                if !grant_types_supported.contains(&GrantType::RefreshToken) {
+                    return Err(Error::InvalidGrant("unsupported `grant_type`".into()));
+                }
+                self.verify_refresh(request)?;
+            }
         }
 
         if let Some(client_id) = &request.client_id {
@@ -175,7 +202,23 @@ impl Context {
         Ok(())
     }
 
-    // TODO: add `client_assertion` JWT verification
+    // Verify the refresh token is being used by the client it was issued to.
+    fn verify_refresh(&self, request: &TokenRequest) -> Result<()> {
+        let Stage::Refreshable(refresh_state) = &self.state.stage else {
+            return Err(Error::InvalidGrant("refresh token is invalid".into()));
+        };
+        if request.client_id.as_ref() != Some(&refresh_state.client_id) {
+            return Err(Error::InvalidGrant("refresh token was issued to another client".into()));
+        }
+
+        // a DPoP-bound refresh token can only be used with the same key
+        if refresh_state.dpop_jkt.is_some() && self.dpop_jkt != refresh_state.dpop_jkt {
+            return Err(Error::InvalidDpopProof(
+                "DPoP key does not match the refresh token".into(),
+            ));
+        }
+        Ok(())
+    }
 
     // Exchange authorization/pre-authorized code for access token.
     async fn process(
@@ -206,6 +249,12 @@ impl Context {
                 let authorized = authorized_credentials(&auth_state.items);
                 (authorized_details, authorized)
             }
+            TokenGrantType::RefreshToken { .. } => {
+                let Stage::Refreshable(refresh_state) = &self.state.stage else {
+                    return Err(Error::ServerError("refresh state not set".into()));
+                };
+                (refresh_state.authorization_details.clone(), refresh_state.credentials.clone())
+            }
         };
 
         let access_token = gen::token();
@@ -215,26 +264,73 @@ impl Context {
         let mut state = self.state.clone();
         state.stage = Stage::Validated(Token {
             access_token: access_token.clone(),
-            credentials: authorized,
+            credentials: authorized.clone(),
             c_nonce: c_nonce.clone(),
             c_nonce_expires_at: Utc::now() + Expire::Nonce.duration(),
+            dpop_jkt: self.dpop_jkt.clone(),
         });
+        state.expires_at = Utc::now() + Expire::Access.duration();
         StateStore::put(provider, &access_token, &state, state.expires_at)
             .await
             .map_err(|e| Error::ServerError(format!("issue saving state: {e}")))?;
 
+        // issue a new refresh token, replacing any used to make this request
+        let refresh_token = if let Some(client_id) = refresh_client(provider, &request).await {
+            let refresh_token = gen::token();
+            state.stage = Stage::Refreshable(Refresh {
+                client_id,
+                authorization_details: authorization_details.clone(),
+                credentials: authorized,
+                dpop_jkt: self.dpop_jkt.clone(),
+            });
+            state.expires_at = Utc::now() + Expire::Refresh.duration();
+            StateStore::put(provider, &refresh_token, &state, state.expires_at)
+                .await
+                .map_err(|e| Error::ServerError(format!("issue saving state: {e}")))?;
+            Some(refresh_token)
+        } else {
+            None
+        };
+
+        // a DPoP-bound token is returned with a nonce for the Wallet's next proof
+        let (token_type, dpop_nonce) = if self.dpop_jkt.is_some() {
+            (TokenType::DPoP, Some(dpop::nonce(provider).await?))
+        } else {
+            (TokenType::Bearer, None)
+        };
+
         // return response
         Ok(TokenResponse {
             access_token,
-            token_type: TokenType::Bearer,
+            token_type,
             expires_in: Expire::Access.duration().num_seconds(),
             c_nonce: Some(c_nonce),
             c_nonce_expires_in: Some(Expire::Nonce.duration().num_seconds()),
+            refresh_token,
             authorization_details,
+            dpop_nonce,
         })
     }
 }
 
+// The client a refresh token should be issued to, provided both the client and
+// the authorization server support the `refresh_token` grant.
+async fn refresh_client(provider: &impl Provider, request: &TokenRequest) -> Option<String> {
+    let client_id = request.client_id.as_ref().filter(|id| !id.is_empty())?;
+    let server = Metadata::server(provider, &request.credential_issuer, None).await.ok()?;
+    let client = Metadata::client(provider, client_id).await.ok()?;
+
+    let supports_refresh = |grant_types: Option<&Vec<GrantType>>| {
+        grant_types.is_some_and(|types| types.contains(&GrantType::RefreshToken))
+    };
+    if supports_refresh(server.oauth.grant_types_supported.as_ref())
+        && supports_refresh(client.oauth.grant_types.as_ref())
+    {
+        return Some(client_id.clone());
+    }
+    None
+}
+
 // Filter previously authorized DetailItems by requested
 // `authorization_details`.
 async fn retain_details(
@@ -347,15 +443,22 @@ fn authorized_credentials(items: &[AuthorizedItem]) -> HashMap<String, Authorize
 
 #[cfg(test)]
 mod tests {
-    use chrono::Utc;
+    use base64ct::{Base64UrlUnpadded, Encoding};
+    use chrono::{Duration, Utc};
     use insta::assert_yaml_snapshot as assert_snapshot;
     use serde_json::json;
+    use vercre_core::Quota;
+    use vercre_infosec::jose::jwk::{Curve, KeyType, PublicKeyJwk};
+    use vercre_infosec::jose::jws::{self, Type};
+    use vercre_infosec::{SecOps, Signer};
+    use vercre_openid::dpop::{self as dpop_proof, DpopClaims};
     use vercre_openid::issuer::{
-        AuthorizationDetail, AuthorizationDetailType, CredentialAuthorization,
-        CredentialDefinition, Format, ProfileW3c,
+        AuthorizationDetail, AuthorizationDetailType, ClientAssertionClaims,
+        CredentialAuthorization, CredentialDefinition, Format, ProfileW3c,
     };
+    use vercre_openid::oauth::{GrantType, Jwks, TokenEndpointAuth};
     use vercre_test_utils::issuer::{Provider, CLIENT_ID, CREDENTIAL_ISSUER, NORMAL_USER};
-    use vercre_test_utils::snapshot;
+    use vercre_test_utils::{holder, snapshot};
 
     use super::*;
     use crate::state::{Authorization, Offer};
@@ -427,6 +530,58 @@ mod tests {
         });
     }
 
+    #[tokio::test]
+    async fn invalid_tx_code() {
+        vercre_test_utils::init_tracer();
+
+        let provider = Provider::new();
+
+        // set up Offered state
+        let state = State {
+            stage: Stage::Offered(Offer {
+                items: Some(vec![AuthorizedItem {
+                    item: ItemType::AuthorizationDetail(AuthorizationDetail {
+                        type_: AuthorizationDetailType::OpenIdCredential,
+                        credential: CredentialAuthorization::ConfigurationId {
+                            credential_configuration_id: "EmployeeID_JWT".into(),
+                            claims: None,
+                        },
+                        locations: None,
+                    }),
+                    credential_configuration_id: "EmployeeID_JWT".into(),
+                    credential_identifiers: vec!["PHLEmployeeID".into()],
+                }]),
+                tx_code: Some("1234".into()),
+            }),
+            subject_id: Some(NORMAL_USER.into()),
+            expires_at: Utc::now() + Expire::Authorized.duration(),
+        };
+        let pre_auth_code = "ABCDEF";
+        StateStore::put(&provider, pre_auth_code, &state, state.expires_at)
+            .await
+            .expect("state exists");
+
+        let request = |tx_code: &str| {
+            let value = json!({
+                "credential_issuer": CREDENTIAL_ISSUER,
+                "client_id": CLIENT_ID,
+                "grant_type": "urn:ietf:params:oauth:grant-type:pre-authorized_code",
+                "pre-authorized_code": pre_auth_code,
+                "tx_code": tx_code
+            });
+            serde_json::from_value::<TokenRequest>(value).expect("request is valid")
+        };
+
+        let Err(Error::InvalidGrant(_)) = token(provider.clone(), request("0000")).await else {
+            panic!("should reject invalid tx_code");
+        };
+
+        // a failed attempt uses the code so the PIN can't be guessed
+        let Err(Error::InvalidGrant(_)) = token(provider.clone(), request("1234")).await else {
+            panic!("should reject used pre-authorized code");
+        };
+    }
+
     #[tokio::test]
     async fn authorized() {
         vercre_test_utils::init_tracer();
@@ -581,4 +736,311 @@ mod tests {
             ".stage.c_nonce_expires_at" => "[c_nonce_expires_at]",
         });
     }
+
+    #[tokio::test]
+    async fn client_assertion() {
+        async fn assertion(client_id: &str, aud: &str, signer: impl Signer) -> String {
+            let claims = ClientAssertionClaims {
+                iss: client_id.into(),
+                sub: client_id.into(),
+                aud: Quota::One(aud.into()),
+                exp: (Utc::now() + Duration::minutes(5)).timestamp(),
+                iat: Some(Utc::now().timestamp()),
+                jti: gen::nonce(),
+            };
+            jws::encode(Type::Jwt, &claims, signer).await.expect("should encode")
+        }
+
+        vercre_test_utils::init_tracer();
+
+        let provider = Provider::new();
+
+        // register a client that authenticates using a signed JWT, publishing
+        // the holder's signing key in its key set
+        let mut client = Metadata::client(&provider, CLIENT_ID).await.expect("client exists");
+        client.oauth.token_endpoint_auth_method = Some(TokenEndpointAuth::PrivateKeyJwt);
+        let public_key = Signer::public_key(&holder::Provider).await.expect("should get key");
+        let mut jwt_client = client.clone();
+        jwt_client.oauth.jwks = Some(Jwks {
+            keys: vec![PublicKeyJwk {
+                kid: Some(holder::Provider.verification_method()),
+                kty: KeyType::Okp,
+                crv: Curve::Ed25519,
+                x: Base64UrlUnpadded::encode_string(&public_key),
+                ..PublicKeyJwk::default()
+            }],
+        });
+        let jwt_client = Metadata::register(&provider, &jwt_client).await.expect("registered");
+        let client_id = jwt_client.oauth.client_id;
+
+        // a client with no registered keys
+        let keyless = Metadata::register(&provider, &client).await.expect("registered");
+        let keyless_id = keyless.oauth.client_id;
+
+        // set up Offered state
+        let state = State {
+            stage: Stage::Offered(Offer {
+                items: Some(vec![AuthorizedItem {
+                    item: ItemType::AuthorizationDetail(AuthorizationDetail {
+                        type_: AuthorizationDetailType::OpenIdCredential,
+                        credential: CredentialAuthorization::ConfigurationId {
+                            credential_configuration_id: "EmployeeID_JWT".into(),
+                            claims: None,
+                        },
+                        locations: None,
+                    }),
+                    credential_configuration_id: "EmployeeID_JWT".into(),
+                    credential_identifiers: vec!["PHLEmployeeID".into()],
+                }]),
+                tx_code: None,
+            }),
+            subject_id: Some(NORMAL_USER.into()),
+            expires_at: Utc::now() + Expire::Authorized.duration(),
+        };
+        // codes are one-time use, so each request needs its own
+        for code in ["ABCDEF", "GHIJKL", "MNOPQR", "STUVWX", "YZABCD", "EFGHIJ"] {
+            StateStore::put(&provider, code, &state, state.expires_at).await.expect("state exists");
+        }
+
+        let request = |client_id: &str, code: &str, client_assertion: Option<String>| {
+            let mut value = json!({
+                "credential_issuer": CREDENTIAL_ISSUER,
+                "client_id": client_id,
+                "grant_type": "urn:ietf:params:oauth:grant-type:pre-authorized_code",
+                "pre-authorized_code": code,
+            });
+            if let Some(client_assertion) = client_assertion {
+                value["client_assertion_type"] =
+                    json!("urn:ietf:params:oauth:client-assertion-type:jwt-bearer");
+                value["client_assertion"] = json!(client_assertion);
+            }
+            serde_json::from_value::<TokenRequest>(value).expect("request is valid")
+        };
+
+        // the client must authenticate
+        let Err(Error::InvalidClient(_)) =
+            token(provider.clone(), request(&client_id, "ABCDEF", None)).await
+        else {
+            panic!("should require client assertion");
+        };
+
+        // the assertion must be intended for this server
+        let jwt = assertion(&client_id, "https://example.com", holder::Provider).await;
+        let Err(Error::InvalidClient(_)) =
+            token(provider.clone(), request(&client_id, "GHIJKL", Some(jwt))).await
+        else {
+            panic!("should reject invalid audience");
+        };
+
+        // a key not registered for the client is rejected
+        let signer = SecOps::signer(&provider, CREDENTIAL_ISSUER).expect("should get signer");
+        let jwt = assertion(&client_id, CREDENTIAL_ISSUER, signer).await;
+        let Err(Error::InvalidClient(_)) =
+            token(provider.clone(), request(&client_id, "YZABCD", Some(jwt))).await
+        else {
+            panic!("should reject unregistered key");
+        };
+
+        // a DID key cannot be used to claim a client it does not identify
+        let jwt = assertion(&keyless_id, CREDENTIAL_ISSUER, holder::Provider).await;
+        let Err(Error::InvalidClient(_)) =
+            token(provider.clone(), request(&keyless_id, "EFGHIJ", Some(jwt))).await
+        else {
+            panic!("should reject foreign key");
+        };
+
+        let jwt = assertion(&client_id, CREDENTIAL_ISSUER, holder::Provider).await;
+        token(provider.clone(), request(&client_id, "MNOPQR", Some(jwt.clone())))
+            .await
+            .expect("response is valid");
+
+        // an assertion cannot be replayed
+        let Err(Error::InvalidClient(_)) =
+            token(provider.clone(), request(&client_id, "STUVWX", Some(jwt))).await
+        else {
+            panic!("should reject replayed assertion");
+        };
+    }
+
+    #[tokio::test]
+    async fn dpop_bound() {
+        vercre_test_utils::init_tracer();
+
+        let provider = Provider::new();
+
+        // set up Offered state
+        let state = State {
+            stage: Stage::Offered(Offer {
+                items: Some(vec![AuthorizedItem {
+                    item: ItemType::AuthorizationDetail(AuthorizationDetail {
+                        type_: AuthorizationDetailType::OpenIdCredential,
+                        credential: CredentialAuthorization::ConfigurationId {
+                            credential_configuration_id: "EmployeeID_JWT".into(),
+                            claims: None,
+                        },
+                        locations: None,
+                    }),
+                    credential_configuration_id: "EmployeeID_JWT".into(),
+                    credential_identifiers: vec!["PHLEmployeeID".into()],
+                }]),
+                tx_code: None,
+            }),
+            subject_id: Some(NORMAL_USER.into()),
+            expires_at: Utc::now() + Expire::Authorized.duration(),
+        };
+        let pre_auth_code = "ABCDEF";
+        StateStore::put(&provider, pre_auth_code, &state, state.expires_at)
+            .await
+            .expect("state exists");
+
+        let proof = |htu: &str, access_token: Option<&str>, nonce: Option<String>| {
+            let claims = DpopClaims {
+                jti: gen::nonce(),
+                htm: "POST".into(),
+                htu: htu.into(),
+                iat: Utc::now().timestamp(),
+                ath: access_token.map(dpop_proof::access_token_hash),
+                nonce,
+            };
+            async move { dpop_proof::encode(&claims, &holder::Provider).await.expect("should encode") }
+        };
+        let request = |dpop: String| {
+            let value = json!({
+                "credential_issuer": CREDENTIAL_ISSUER,
+                "client_id": CLIENT_ID,
+                "grant_type": "urn:ietf:params:oauth:grant-type:pre-authorized_code",
+                "pre-authorized_code": pre_auth_code,
+                "dpop": dpop,
+            });
+            serde_json::from_value::<TokenRequest>(value).expect("request is valid")
+        };
+
+        // the first proof is rejected for want of a server-provided nonce
+        let dpop = proof("/token", None, None).await;
+        let Err(Error::UseDpopNonce { dpop_nonce, .. }) =
+            token(provider.clone(), request(dpop)).await
+        else {
+            panic!("should require DPoP nonce");
+        };
+
+        let dpop = proof("/token", None, Some(dpop_nonce)).await;
+        let token_resp = token(provider.clone(), request(dpop)).await.expect("response is valid");
+        assert_eq!(token_resp.token_type, TokenType::DPoP);
+        let access_token = token_resp.access_token.as_str();
+        let credential_endpoint = "http://vercre.io/credential";
+
+        // the bound token cannot be used without a proof
+        let Err(Error::InvalidDpopProof(_)) =
+            dpop::verify_token(&provider, access_token, None, credential_endpoint).await
+        else {
+            panic!("should require DPoP proof");
+        };
+
+        // the proof must be for the token presented
+        let dpop = proof(credential_endpoint, Some("GHIJKL"), token_resp.dpop_nonce.clone()).await;
+        let Err(Error::InvalidDpopProof(_)) =
+            dpop::verify_token(&provider, access_token, Some(&dpop), credential_endpoint).await
+        else {
+            panic!("should reject proof for another token");
+        };
+
+        let dpop = proof(credential_endpoint, Some(access_token), token_resp.dpop_nonce).await;
+        dpop::verify_token(&provider, access_token, Some(&dpop), credential_endpoint)
+            .await
+            .expect("proof is valid");
+    }
+
+    #[tokio::test]
+    async fn refresh_token() {
+        vercre_test_utils::init_tracer();
+
+        let provider = Provider::new();
+
+        // register a client that uses refresh tokens
+        let mut client = Metadata::client(&provider, CLIENT_ID).await.expect("client exists");
+        client.oauth.grant_types =
+            Some(vec![GrantType::PreAuthorizedCode, GrantType::RefreshToken]);
+        let client = Metadata::register(&provider, &client).await.expect("client registered");
+        let client_id = client.oauth.client_id;
+
+        // set up Offered state
+        let state = State {
+            stage: Stage::Offered(Offer {
+                items: Some(vec![AuthorizedItem {
+                    item: ItemType::AuthorizationDetail(AuthorizationDetail {
+                        type_: AuthorizationDetailType::OpenIdCredential,
+                        credential: CredentialAuthorization::ConfigurationId {
+                            credential_configuration_id: "EmployeeID_JWT".into(),
+                            claims: None,
+                        },
+                        locations: None,
+                    }),
+                    credential_configuration_id: "EmployeeID_JWT".into(),
+                    credential_identifiers: vec!["PHLEmployeeID".into()],
+                }]),
+                tx_code: None,
+            }),
+            subject_id: Some(NORMAL_USER.into()),
+            expires_at: Utc::now() + Expire::Authorized.duration(),
+        };
+        let pre_auth_code = "ABCDEF";
+        StateStore::put(&provider, pre_auth_code, &state, state.expires_at)
+            .await
+            .expect("state exists");
+
+        let value = json!({
+            "credential_issuer": CREDENTIAL_ISSUER,
+            "client_id": client_id,
+            "grant_type": "urn:ietf:params:oauth:grant-type:pre-authorized_code",
+            "pre-authorized_code": pre_auth_code,
+        });
+        let request = serde_json::from_value(value).expect("request is valid");
+        let token_resp = token(provider.clone(), request).await.expect("response is valid");
+        let refresh_token = token_resp.refresh_token.expect("refresh token issued");
+
+        let request = |client_id: &str, refresh_token: &str| {
+            let value = json!({
+                "credential_issuer": CREDENTIAL_ISSUER,
+                "client_id": client_id,
+                "grant_type": "refresh_token",
+                "refresh_token": refresh_token,
+            });
+            serde_json::from_value::<TokenRequest>(value).expect("request is valid")
+        };
+
+        // the new access token is authorized for the same credentials
+        let refresh_resp = token(provider.clone(), request(&client_id, &refresh_token))
+            .await
+            .expect("response is valid");
+        assert_eq!(refresh_resp.authorization_details, token_resp.authorization_details);
+
+        let state = StateStore::get::<State>(&provider, &refresh_resp.access_token)
+            .await
+            .expect("state exists");
+        let Stage::Validated(token_state) = state.stage else {
+            panic!("should be validated state");
+        };
+        assert!(token_state.credentials.contains_key("PHLEmployeeID"));
+        assert_eq!(state.subject_id, Some(NORMAL_USER.into()));
+
+        // the refresh token is replaced when used
+        let rotated = refresh_resp.refresh_token.expect("refresh token issued");
+        assert_ne!(rotated, refresh_token);
+
+        let Err(Error::InvalidGrant(_)) =
+            token(provider.clone(), request(&client_id, &refresh_token)).await
+        else {
+            panic!("should reject used refresh token");
+        };
+
+        // the refresh token can only be used by the client it was issued to
+        let Err(Error::InvalidGrant(_)) =
+            token(provider.clone(), request(CLIENT_ID, &rotated)).await
+        else {
+            panic!("should reject refresh token issued to another client");
+        };
+
+        // ...and the rejected request does not consume it
+        token(provider.clone(), request(&client_id, &rotated)).await.expect("response is valid");
+    }
 }
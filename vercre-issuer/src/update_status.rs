@@ -0,0 +1,257 @@
+//! # Update Status Endpoint
+//!
+//! Used by the Credential Issuer to revoke, suspend, or otherwise change the
+//! status of a previously issued credential.
+//!
+//! The credential's entry in the status log is updated and the affected shard
+//! of the status list is re-signed and saved, ready to be served by the
+//! [`status_list`](crate::status_list) endpoint.
+//!
+//! The endpoint does not authorize requests itself. It is intended for the
+//! issuer's administrative tooling and must only be exposed behind the
+//! issuer's own authentication and authorization.
+
+use tracing::instrument;
+use vercre_openid::issuer::{Provider, UpdateStatusRequest, UpdateStatusResponse};
+use vercre_openid::{Error, Result};
+use vercre_status::bitstring::Position;
+use vercre_status::issuer::{self, Status};
+
+use crate::status_list::{self, base_url};
+
+/// Update status request handler.
+///
+/// Callers are responsible for checking the request is authorized to change
+/// the credential's status.
+///
+/// # Errors
+///
+/// Returns an `OpenID4VCI` error if the credential has no status list entry for
+/// the requested purpose, the value is not valid for the list, or the list
+/// cannot be updated.
+#[instrument(level = "debug", skip(provider))]
+pub async fn update_status(
+    provider: impl Provider, request: UpdateStatusRequest,
+) -> Result<UpdateStatusResponse> {
+    process(&provider, request).await
+}
+
+async fn process(
+    provider: &impl Provider, request: UpdateStatusRequest,
+) -> Result<UpdateStatusResponse> {
+    tracing::debug!("update_status::process");
+
+    let Some(mut entry) = Status::log_entry(provider, &request.credential_id)
+        .await
+        .map_err(|e| Error::ServerError(format!("issue getting status log entry: {e}")))?
+    else {
+        return Err(Error::InvalidRequest(format!(
+            "no status for credential {}",
+            request.credential_id
+        )));
+    };
+    let Some(status) = entry.status.iter_mut().find(|s| s.purpose == request.purpose) else {
+        return Err(Error::InvalidRequest(format!("credential has no {} status", request.purpose)));
+    };
+
+    // check the update is valid before applying it to the latest version of
+    // the list
+    let Some(mut sharded) = Status::sharded_list(provider, status.list)
+        .await
+        .map_err(|e| Error::ServerError(format!("issue getting status list: {e}")))?
+    else {
+        return Err(Error::ServerError(format!("status list {} not found", status.list)));
+    };
+    let position = Position {
+        shard: status.shard,
+        index: status.list_index,
+    };
+    let value = usize::from(request.value);
+    sharded
+        .set(position, value)
+        .map_err(|e| Error::InvalidRequest(format!("issue updating status: {e}")))?;
+
+    // update the list
+    let (sharded, ()) =
+        issuer::update_list(provider, status.list, None, |sharded| sharded.set(position, value))
+            .await
+            .map_err(|e| Error::ServerError(format!("issue saving status list: {e}")))?;
+
+    // record the new status in the log
+    status.value = request.value;
+    let shard = status.shard;
+    Status::put_log_entry(provider, &entry)
+        .await
+        .map_err(|e| Error::ServerError(format!("issue saving status log entry: {e}")))?;
+
+    let credential =
+        status_list::publish(provider, &request.credential_issuer, &sharded, shard).await?;
+
+    Ok(UpdateStatusResponse {
+        status_list_url: sharded.config().list_url(&base_url(&request.credential_issuer), shard),
+        credential,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::Utc;
+    use serde_json::json;
+    use vercre_core::Kind;
+    use vercre_infosec::jose::jws::{self, Type};
+    use vercre_openid::issuer::{
+        CredentialResponseType, ProofClaims, StateStore, StatusListRequest, StatusListResponse,
+    };
+    use vercre_status::config::{CredentialStatusConfig, ListConfig};
+    use vercre_test_utils::holder;
+    use vercre_test_utils::issuer::{Provider, CLIENT_ID, CREDENTIAL_ISSUER, NORMAL_USER};
+    use vercre_w3c_vc::model::StatusPurpose;
+    use vercre_w3c_vc::proof::{self, Payload, Verify};
+
+    use super::*;
+    use crate::credential::credential;
+    use crate::state::{Authorized, Expire, Stage, State, Token};
+    use crate::status_list::status_list;
+
+    #[tokio::test]
+    async fn revoke_credential() {
+        vercre_test_utils::init_tracer();
+
+        let provider = Provider::new();
+        provider.status.put_status_config(&CredentialStatusConfig {
+            credential_type: "EmployeeIDCredential".into(),
+            status_list: Some(vec![ListConfig {
+                purpose: StatusPurpose::Revocation,
+                list: 1,
+                size: 1,
+                messages: None,
+                reference: None,
+                entries: None,
+            }]),
+        });
+
+        // two credentials of the same type, each with an entry in list 1
+        let revoked_id = issue(&provider, "ABCDEF").await;
+        let retained_id = issue(&provider, "GHIJKL").await;
+        let revoked = position(&provider, &revoked_id).await;
+        let retained = position(&provider, &retained_id).await;
+
+        let request = UpdateStatusRequest {
+            credential_issuer: CREDENTIAL_ISSUER.into(),
+            credential_id: revoked_id.clone(),
+            purpose: StatusPurpose::Revocation,
+            value: 1,
+        };
+        let response = update_status(provider.clone(), request).await.expect("response is ok");
+        assert_eq!(response.status_list_url, "http://vercre.io/status/1/0");
+
+        // the re-signed list has the credential revoked
+        let Payload::Vc { vc, .. } =
+            proof::verify(Verify::Vc(&Kind::String(response.credential.clone())), &provider)
+                .await
+                .expect("should verify")
+        else {
+            panic!("should be a credential");
+        };
+        assert_eq!(vc.id, Some(response.status_list_url.clone()));
+
+        // only the revoked credential's status is changed
+        let sharded = Status::sharded_list(&provider, 1).await.expect("should get list");
+        let sharded = sharded.expect("list should exist");
+        assert_eq!(sharded.get(revoked).expect("should get status"), 1);
+        assert_eq!(sharded.get(retained).expect("should get status"), 0);
+
+        let entry = Status::log_entry(&provider, &revoked_id).await.expect("should get entry");
+        assert_eq!(entry.expect("entry should exist").status[0].value, 1);
+        let entry = Status::log_entry(&provider, &retained_id).await.expect("should get entry");
+        assert_eq!(entry.expect("entry should exist").status[0].value, 0);
+
+        // the status list endpoint serves the re-signed list
+        let request = StatusListRequest {
+            credential_issuer: CREDENTIAL_ISSUER.into(),
+            list: 1,
+            shard: 0,
+        };
+        let StatusListResponse { credential } =
+            status_list(provider.clone(), request).await.expect("response is ok");
+        assert_eq!(credential, response.credential);
+
+        // credentials without a suspension entry cannot be suspended
+        let request = UpdateStatusRequest {
+            credential_issuer: CREDENTIAL_ISSUER.into(),
+            credential_id: revoked_id,
+            purpose: StatusPurpose::Suspension,
+            value: 1,
+        };
+        let Err(Error::InvalidRequest(_)) = update_status(provider, request).await else {
+            panic!("should fail with invalid request");
+        };
+    }
+
+    // Issue an employee ID credential, returning its identifier.
+    async fn issue(provider: &Provider, access_token: &str) -> String {
+        let c_nonce = "1234ABCD";
+        let state = State {
+            stage: Stage::Validated(Token {
+                access_token: access_token.into(),
+                credentials: HashMap::from([(
+                    "PHLEmployeeID".into(),
+                    Authorized {
+                        credential_identifier: "PHLEmployeeID".into(),
+                        credential_configuration_id: "EmployeeID_JWT".into(),
+                        claim_ids: None,
+                    },
+                )]),
+                c_nonce: c_nonce.into(),
+                c_nonce_expires_at: Utc::now() + Expire::Nonce.duration(),
+                dpop_jkt: None,
+            }),
+            subject_id: Some(NORMAL_USER.into()),
+            expires_at: Utc::now() + Expire::Authorized.duration(),
+        };
+        StateStore::put(provider, access_token, &state, state.expires_at)
+            .await
+            .expect("state exists");
+
+        let claims = ProofClaims {
+            iss: Some(CLIENT_ID.into()),
+            aud: CREDENTIAL_ISSUER.into(),
+            iat: Utc::now().timestamp(),
+            nonce: Some(c_nonce.into()),
+        };
+        let jwt = jws::encode(Type::Proof, &claims, holder::Provider).await.expect("should encode");
+        let value = json!({
+            "credential_issuer": CREDENTIAL_ISSUER,
+            "access_token": access_token,
+            "credential_identifier": "PHLEmployeeID",
+            "proof":{
+                "proof_type": "jwt",
+                "jwt": jwt
+            }
+        });
+        let request = serde_json::from_value(value).expect("request is valid");
+        let response = credential(provider.clone(), request).await.expect("response is valid");
+
+        let CredentialResponseType::Credential(vc_kind) = &response.response else {
+            panic!("expected a single credential");
+        };
+        let Payload::Vc { vc, .. } =
+            proof::verify(Verify::Vc(vc_kind), provider).await.expect("should decode")
+        else {
+            panic!("should be VC");
+        };
+        vc.id.expect("credential should have an id")
+    }
+
+    // The position of a credential's revocation status in list 1.
+    async fn position(provider: &Provider, credential_id: &str) -> Position {
+        let entry = Status::log_entry(provider, credential_id).await.expect("should get entry");
+        let entry = entry.expect("entry should exist");
+        Position {
+            shard: entry.status[0].shard,
+            index: entry.status[0].list_index,
+        }
+    }
+}
@@ -180,6 +180,7 @@ impl Wallet {
         };
 
         assert_snapshot!("credential", vc, {
+            ".id" => "[id]",
             ".validFrom" => "[validFrom]",
             ".credentialSubject" => insta::sorted_redaction()
         });
@@ -196,6 +197,7 @@ impl Wallet {
             credential_issuer: CREDENTIAL_ISSUER.into(),
             access_token: tkn_resp.access_token,
             transaction_id,
+            dpop: None,
         };
         vercre_issuer::deferred(self.provider.clone(), request).await
     }
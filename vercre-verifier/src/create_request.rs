@@ -14,6 +14,10 @@
 //! the Credential(s) desired using a Credential Definition and, optionally,
 //! specifying the device flow that will be used.
 //!
+//! The credential formats requested are taken from the Verifier's `vp_formats`
+//! metadata. When the request identifies the Wallet (`wallet_id`), formats and
+//! algorithms are narrowed to those in the Wallet's `vp_formats_supported`.
+//!
 //! # Example
 //!
 //! ```json
@@ -44,18 +48,22 @@
 
 use std::collections::HashMap;
 
+use base64ct::{Base64UrlUnpadded, Encoding};
 use chrono::Utc;
 use tracing::instrument;
 use uuid::Uuid;
 use vercre_core::{gen, Kind};
 use vercre_dif_exch::{ClaimFormat, PresentationDefinition};
-use vercre_infosec::Algorithm;
+use vercre_infosec::jose::jwk::{Curve, KeyType, PublicKeyJwk};
+use vercre_infosec::{Encryptor, SecOps};
+use vercre_openid::jwe::A256GCM;
 use vercre_openid::verifier::{
-    ClientIdScheme, CreateRequestRequest, CreateRequestResponse, DeviceFlow, Metadata, Provider,
-    RequestObject, ResponseType, StateStore,
+    ClientIdScheme, CreateRequestRequest, CreateRequestResponse, DeviceFlow, Format, Jwks,
+    Metadata, Provider, RequestObject, ResponseType, StateStore, Verifier, VpFormat,
 };
 use vercre_openid::{Error, Result};
 
+use crate::presentation_definition;
 use crate::state::{Expire, State};
 
 // TODO: request supported Client Identifier schemes from the Wallet
@@ -98,6 +106,12 @@ pub async fn create_request(
 async fn verify(request: &CreateRequestRequest) -> Result<()> {
     tracing::debug!("create_request::verify");
 
+    if let Some(dcql_query) = &request.dcql_query {
+        if dcql_query.credentials.is_empty() {
+            return Err(Error::InvalidRequest("no credentials specified".into()));
+        }
+        return Ok(());
+    }
     if request.input_descriptors.is_empty() {
         return Err(Error::InvalidRequest("no credentials specified".into()));
     }
@@ -109,42 +123,144 @@ async fn process(
 ) -> Result<CreateRequestResponse> {
     tracing::debug!("create_request::process");
 
-    // TODO: build dynamically...
-    let fmt = ClaimFormat {
-        alg: Some(vec![Algorithm::EdDSA.to_string()]),
-        proof_type: None,
+    // get client metadata
+    let Ok(mut verifier_meta) = Metadata::verifier(&provider, &request.client_id).await else {
+        return Err(Error::InvalidRequest("invalid client_id".into()));
     };
 
+    // publish the key and content encryption the Wallet should use to encrypt
+    // its response, as the default `enc` ("A128CBC-HS256") is not supported
+    if verifier_meta.authorization_encrypted_response_alg.is_some() {
+        verifier_meta.authorization_encrypted_response_enc.get_or_insert_with(|| A256GCM.into());
+        if verifier_meta.oauth.jwks.is_none() {
+            verifier_meta.oauth.jwks = Some(encryption_keys(&provider, &request.client_id)?);
+        }
+    }
+
+    // limit requested formats to those supported by the Wallet, when known
+    let wallet_meta = match &request.wallet_id {
+        Some(wallet_id) => {
+            let Ok(wallet_meta) = Metadata::wallet(&provider, wallet_id).await else {
+                return Err(Error::InvalidRequest("invalid wallet_id".into()));
+            };
+            Some(wallet_meta)
+        }
+        None => None,
+    };
+    let wallet_formats = wallet_meta.as_ref().and_then(|w| w.vp_formats_supported.as_ref());
+    let format = claim_formats(verifier_meta.vp_formats.as_ref(), wallet_formats)?;
+
+    let uri_token = gen::uri_token();
+
+    // a DCQL query is sent in place of a Presentation Definition, requesting
+    // only credential formats the Wallet supports, when known
+    if let Some(dcql_query) = &request.dcql_query {
+        if let Some(wallet_formats) = wallet_formats {
+            if let Some(query) =
+                dcql_query.credentials.iter().find(|q| !wallet_formats.contains_key(&q.format))
+            {
+                return Err(Error::InvalidRequest(format!(
+                    "credential query {} requests unsupported format {}",
+                    query.id, query.format
+                )));
+            }
+        }
+
+        let req_obj = RequestObject {
+            dcql_query: Some(dcql_query.clone()),
+            ..request_object(&uri_token, verifier_meta)
+        };
+        return save_request(provider, request, &uri_token, req_obj, None).await;
+    }
+
+    // input descriptors can only narrow the formats requested
+    if let Some(format) = &format {
+        for input in &request.input_descriptors {
+            let Some(input_format) = &input.format else {
+                continue;
+            };
+            if let Some(name) = input_format.keys().find(|name| !format.contains_key(*name)) {
+                return Err(Error::InvalidRequest(format!(
+                    "input descriptor {} requests unsupported format {name}",
+                    input.id
+                )));
+            }
+        }
+    }
+
     let pres_def = PresentationDefinition {
         id: Uuid::new_v4().to_string(),
         purpose: Some(request.purpose.clone()),
         input_descriptors: request.input_descriptors.clone(),
-        format: Some(HashMap::from([("jwt_vc".into(), fmt)])),
+        format,
         name: None,
+        submission_requirements: request.submission_requirements.clone(),
     };
-    let uri_token = gen::uri_token();
 
-    // get client metadata
-    let Ok(verifier_meta) = Metadata::verifier(&provider, &request.client_id).await else {
-        return Err(Error::InvalidRequest("invalid client_id".into()));
+    // pass the definition by reference when requested and the Wallet supports it
+    let by_reference = request.definition_by_reference
+        && wallet_meta.as_ref().map_or(true, |w| w.presentation_definition_uri_supported);
+    let (presentation_definition, saved_definition) = if by_reference {
+        let uri = presentation_definition::uri_for(&request.client_id, &uri_token);
+        (Kind::String(uri), Some(pres_def))
+    } else {
+        (Kind::Object(pres_def), None)
+    };
+
+    let req_obj = RequestObject {
+        presentation_definition: Some(presentation_definition),
+        ..request_object(&uri_token, verifier_meta)
     };
+    save_request(provider, request, &uri_token, req_obj, saved_definition).await
+}
+
+// The Verifier's public encryption key as a JWK Set. The key ID is passed to
+// the Verifier's `KeyAgreement` provider when the encrypted response is
+// received.
+fn encryption_keys(provider: &impl Provider, client_id: &str) -> Result<Jwks> {
+    let encryptor = SecOps::encryptor(provider, client_id)
+        .map_err(|e| Error::ServerError(format!("issue resolving encryptor: {e}")))?;
+
+    Ok(Jwks {
+        keys: vec![PublicKeyJwk {
+            kid: Some(client_id.to_string()),
+            kty: KeyType::Okp,
+            crv: Curve::X25519,
+            x: Base64UrlUnpadded::encode_string(&encryptor.public_key()),
+            use_: Some("enc".into()),
+            ..PublicKeyJwk::default()
+        }],
+    })
+}
 
-    let mut req_obj = RequestObject {
+// The Request Object fields common to Presentation Definition and DCQL
+// requests.
+fn request_object(uri_token: &str, verifier_meta: Verifier) -> RequestObject {
+    RequestObject {
         response_type: ResponseType::VpToken,
-        state: Some(uri_token.clone()),
+        state: Some(uri_token.to_string()),
         nonce: gen::nonce(),
-        presentation_definition: Kind::Object(pres_def),
         client_metadata: verifier_meta,
         client_id_scheme: Some(ClientIdScheme::RedirectUri),
         ..Default::default()
-    };
+    }
+}
 
+// Complete the Request Object for the requested device flow and save it in
+// state.
+async fn save_request(
+    provider: impl Provider, request: &CreateRequestRequest, uri_token: &str,
+    mut req_obj: RequestObject, saved_definition: Option<PresentationDefinition>,
+) -> Result<CreateRequestResponse> {
     let mut response = CreateRequestResponse::default();
 
-    // Response Mode "direct_post" is RECOMMENDED for cross-device flows.
+    // Response Mode "direct_post" is RECOMMENDED for cross-device flows,
+    // encrypted ("direct_post.jwt") when the Verifier requires it.
     // TODO: replace hard-coded endpoints with Provider-set values
     if request.device_flow == DeviceFlow::CrossDevice {
-        req_obj.response_mode = Some("direct_post".into());
+        let encrypted = req_obj.client_metadata.authorization_encrypted_response_alg.is_some();
+        let response_mode = if encrypted { "direct_post.jwt" } else { "direct_post" };
+        req_obj.response_mode = Some(response_mode.into());
         req_obj.client_id = format!("{}/post", request.client_id);
         req_obj.response_uri = Some(format!("{}/post", request.client_id));
         response.request_uri = Some(format!("{}/request/{uri_token}", request.client_id));
@@ -157,20 +273,77 @@ async fn process(
     let state = State {
         expires_at: Utc::now() + Expire::Request.duration(),
         request_object: req_obj,
+        presentation_definition: saved_definition,
     };
 
-    StateStore::put(&provider, &uri_token, &state, state.expires_at)
+    StateStore::put(&provider, uri_token, &state, state.expires_at)
         .await
         .map_err(|e| Error::ServerError(format!("issue saving state: {e}")))?;
 
     Ok(response)
 }
 
+// Build the Presentation Definition's `format` map from the formats supported
+// by the Verifier, narrowed to those the Wallet also supports.
+fn claim_formats(
+    verifier: Option<&HashMap<Format, VpFormat>>, wallet: Option<&HashMap<String, VpFormat>>,
+) -> Result<Option<HashMap<String, ClaimFormat>>> {
+    let Some(verifier) = verifier else {
+        return Ok(None);
+    };
+
+    let mut formats = HashMap::new();
+    for (format, vp_format) in verifier {
+        let name = format.to_string();
+        let claim_format = match wallet {
+            None => ClaimFormat {
+                alg: vp_format.alg.clone(),
+                proof_type: vp_format.proof_type.clone(),
+            },
+            Some(wallet) => {
+                let Some(supported) = wallet.get(&name) else {
+                    continue;
+                };
+                let alg = intersect(vp_format.alg.as_ref(), supported.alg.as_ref());
+                let proof_type =
+                    intersect(vp_format.proof_type.as_ref(), supported.proof_type.as_ref());
+                if alg.as_ref().is_some_and(Vec::is_empty)
+                    || proof_type.as_ref().is_some_and(Vec::is_empty)
+                {
+                    continue;
+                }
+                ClaimFormat { alg, proof_type }
+            }
+        };
+        formats.insert(name, claim_format);
+    }
+
+    if formats.is_empty() {
+        return Err(Error::VpFormatsNotSupported(
+            "the wallet does not support any of the verifier's formats".into(),
+        ));
+    }
+    Ok(Some(formats))
+}
+
+// Values supported by both parties. A missing list means any value is
+// supported.
+fn intersect(verifier: Option<&Vec<String>>, wallet: Option<&Vec<String>>) -> Option<Vec<String>> {
+    match (verifier, wallet) {
+        (Some(verifier), Some(wallet)) => {
+            Some(verifier.iter().filter(|v| wallet.contains(v)).cloned().collect())
+        }
+        (Some(values), None) | (None, Some(values)) => Some(values.clone()),
+        (None, None) => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use assert_let_bind::assert_let;
     use insta::assert_yaml_snapshot as assert_snapshot;
     use serde_json::json;
+    use vercre_openid::verifier::Wallet;
     use vercre_test_utils::verifier::Provider;
 
     use super::*;
@@ -207,7 +380,7 @@ mod tests {
         assert_eq!(response.request_uri, None);
         assert_let!(Some(req_obj), &response.request_object);
 
-        assert!(req_obj.presentation_definition.is_object());
+        assert!(req_obj.presentation_definition.as_ref().is_some_and(Kind::is_object));
 
         // compare response with saved state
         let state_key = req_obj.state.as_ref().expect("has state");
@@ -265,4 +438,147 @@ mod tests {
             ".request_object.nonce" => "[nonce]",
         });
     }
+
+    #[tokio::test]
+    async fn dcql_query() {
+        vercre_test_utils::init_tracer();
+        let provider = Provider::new();
+
+        let body = json!({
+            "purpose": "To verify employment",
+            "dcql_query": {
+                "credentials": [{
+                    "id": "employment",
+                    "format": "jwt_vc_json",
+                    "meta": {"type_values": [["EmployeeIDCredential"]]},
+                    "claims": [{"path": ["credentialSubject", "email"]}]
+                }]
+            },
+            "device_flow": "SameDevice"
+        });
+        let mut request =
+            serde_json::from_value::<CreateRequestRequest>(body).expect("should deserialize");
+        request.client_id = "http://localhost:8080".into();
+
+        let response = create_request(provider.clone(), &request).await.expect("response is ok");
+        assert_let!(Some(req_obj), &response.request_object);
+
+        // the query is sent in place of a Presentation Definition
+        assert!(req_obj.presentation_definition.is_none());
+        assert_eq!(req_obj.dcql_query, request.dcql_query);
+
+        let qs = req_obj.to_querystring().expect("should serialize");
+        assert!(qs.contains("&dcql_query="));
+        assert!(!qs.contains("presentation_definition"));
+
+        let state_key = req_obj.state.as_ref().expect("has state");
+        let state = StateStore::get::<State>(&provider, state_key).await.expect("state exists");
+        assert_eq!(state.request_object.dcql_query, request.dcql_query);
+        assert!(state.presentation_definition.is_none());
+    }
+
+    #[tokio::test]
+    async fn format_negotiation() {
+        vercre_test_utils::init_tracer();
+        let provider = Provider::new();
+
+        // a verifier requesting ES256 signed credentials in several formats
+        let es256 = || VpFormat {
+            alg: Some(vec!["ES256".into()]),
+            proof_type: None,
+        };
+        let mut verifier = Metadata::verifier(&provider, "http://localhost:8080")
+            .await
+            .expect("should get verifier");
+        verifier.vp_formats = Some(HashMap::from([
+            (Format::JwtVcJson, es256()),
+            (Format::VcSdJwt, es256()),
+            (Format::MsoMdoc, es256()),
+            (
+                Format::LdpVc,
+                VpFormat {
+                    alg: None,
+                    proof_type: Some(vec!["DataIntegrityProof".into()]),
+                },
+            ),
+        ]));
+        let verifier = provider.verifier.add(&verifier).expect("should register");
+
+        // a wallet that does not support `ldp_vc`
+        let mut wallet = Wallet::default();
+        wallet.oauth.issuer = "https://wallet.example.com".into();
+        wallet.vp_formats_supported = Some(HashMap::from([
+            (
+                "jwt_vc_json".into(),
+                VpFormat {
+                    alg: Some(vec!["ES256".into(), "EdDSA".into()]),
+                    proof_type: None,
+                },
+            ),
+            ("vc+sd-jwt".into(), es256()),
+            ("mso_mdoc".into(), es256()),
+        ]));
+        provider.wallet.put(&wallet);
+
+        let body = json!({
+            "purpose": "To verify employment",
+            "input_descriptors": [{
+                "id": "employment",
+                "constraints": {
+                    "fields": [{
+                        "path":["$.type"],
+                        "filter": {
+                            "type": "string",
+                            "const": "EmployeeIDCredential"
+                        }
+                    }]
+                },
+                "format": {
+                    "vc+sd-jwt": {"alg": ["ES256"]}
+                }
+            }],
+            "device_flow": "SameDevice"
+        });
+        let mut request =
+            serde_json::from_value::<CreateRequestRequest>(body).expect("should deserialize");
+        request.client_id.clone_from(&verifier.oauth.client_id);
+        request.wallet_id = Some(wallet.oauth.issuer.clone());
+
+        let response = create_request(provider.clone(), &request).await.expect("response is ok");
+        assert_let!(Some(req_obj), &response.request_object);
+        assert_let!(Some(Kind::Object(pres_def)), &req_obj.presentation_definition);
+
+        let format = pres_def.format.as_ref().expect("should have format");
+        let mut names = format.keys().cloned().collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["jwt_vc_json", "mso_mdoc", "vc+sd-jwt"]);
+        assert_eq!(format["jwt_vc_json"].alg, Some(vec!["ES256".into()]));
+
+        // the input descriptor's format is passed through
+        let input_format =
+            pres_def.input_descriptors[0].format.as_ref().expect("should have format");
+        assert!(input_format.contains_key("vc+sd-jwt"));
+
+        // input descriptors cannot request formats outside the negotiated set
+        let mut narrowed = request.clone();
+        narrowed.input_descriptors[0].format =
+            Some(HashMap::from([("ldp_vc".into(), ClaimFormat::default())]));
+        let Err(Error::InvalidRequest(_)) = create_request(provider.clone(), &narrowed).await
+        else {
+            panic!("should fail with invalid request");
+        };
+
+        // a wallet supporting none of the verifier's formats cannot be served
+        wallet.vp_formats_supported = Some(HashMap::from([(
+            "jwt_vc_json".into(),
+            VpFormat {
+                alg: Some(vec!["EdDSA".into()]),
+                proof_type: None,
+            },
+        )]));
+        provider.wallet.put(&wallet);
+        let Err(Error::VpFormatsNotSupported(_)) = create_request(provider, &request).await else {
+            panic!("should fail with unsupported formats");
+        };
+    }
 }
@@ -161,6 +161,7 @@
 
 mod create_request;
 mod metadata;
+mod presentation_definition;
 mod request_object;
 mod response;
 mod state;
@@ -175,15 +176,23 @@ pub mod provider {
     pub use vercre_openid::issuer::{Client, Format, Server};
     pub use vercre_openid::verifier::VpFormat;
     #[allow(clippy::module_name_repetitions)]
-    pub use vercre_openid::verifier::{Metadata, Provider, Result, StateStore, Verifier, Wallet};
+    pub use vercre_openid::verifier::{
+        KeyAgreement, Metadata, Provider, Result, StateStore, Verifier, Wallet,
+    };
+    pub use vercre_status::verifier::{Kind, Status, VerifiableCredential};
 }
 pub use create_request::create_request;
 pub use metadata::metadata;
+pub use presentation_definition::presentation_definition;
 pub use request_object::request_object;
 pub use response::response;
-pub use vercre_dif_exch::{Constraints, Field, Filter, FilterValue, InputDescriptor};
+pub use vercre_dif_exch::dcql::{ClaimsQuery, CredentialQuery, CredentialSetQuery, Meta};
+pub use vercre_dif_exch::{
+    Constraints, DcqlQuery, Directive, Field, Filter, InputDescriptor, Rule, SubmissionRequirement,
+};
 pub use vercre_openid::verifier::{
     ClientIdScheme, CreateRequestRequest, CreateRequestResponse, DeviceFlow, MetadataRequest,
-    MetadataResponse, RequestObject, RequestObjectRequest, RequestObjectResponse, ResponseRequest,
-    ResponseResponse, ResponseType,
+    MetadataResponse, PresentationDefinitionRequest, PresentationDefinitionResponse, RequestObject,
+    RequestObjectRequest, RequestObjectResponse, ResponseRequest, ResponseResponse, ResponseType,
+    VpToken,
 };
@@ -0,0 +1,142 @@
+//! # Presentation Definition Endpoint
+//!
+//! This endpoint is used by the Wallet to retrieve a Presentation Definition
+//! passed by reference in an Authorization Request.
+//!
+//! When the Verifier asks for the Presentation Definition to be passed by
+//! reference, the `Create Request` endpoint sets the Request Object's
+//! `presentation_definition_uri` to this endpoint instead of embedding the
+//! (potentially large) definition in the Request Object.
+
+use tracing::instrument;
+use vercre_core::Kind;
+use vercre_openid::verifier::{
+    PresentationDefinitionRequest, PresentationDefinitionResponse, Provider, StateStore,
+};
+use vercre_openid::{Error, Result};
+
+use crate::state::State;
+
+/// Endpoint for the Wallet to retrieve a Presentation Definition passed by
+/// reference.
+///
+/// # Errors
+///
+/// Returns an `OpenID4VP` error if the request is invalid or if the provider is
+/// not available.
+#[instrument(level = "debug", skip(provider))]
+pub async fn presentation_definition(
+    provider: impl Provider, request: &PresentationDefinitionRequest,
+) -> Result<PresentationDefinitionResponse> {
+    process(provider, request).await
+}
+
+async fn process(
+    provider: impl Provider, request: &PresentationDefinitionRequest,
+) -> Result<PresentationDefinitionResponse> {
+    tracing::debug!("presentation_definition::process");
+
+    let state = StateStore::get::<State>(&provider, &request.id)
+        .await
+        .map_err(|e| Error::InvalidRequest(format!("issue fetching state: {e}")))?;
+
+    // the definition must have been passed by reference by this Verifier
+    let Some(Kind::String(uri)) = &state.request_object.presentation_definition else {
+        return Err(Error::InvalidRequest("presentation definition is not by reference".into()));
+    };
+    if *uri != uri_for(&request.client_id, &request.id) {
+        return Err(Error::InvalidRequest("client ID mismatch".into()));
+    }
+    let Some(presentation_definition) = state.presentation_definition else {
+        return Err(Error::ServerError("presentation definition not found".into()));
+    };
+
+    Ok(PresentationDefinitionResponse {
+        presentation_definition,
+    })
+}
+
+/// The `presentation_definition_uri` the Wallet uses to retrieve the
+/// Presentation Definition for a Request Object.
+pub fn uri_for(client_id: &str, id: &str) -> String {
+    format!("{client_id}/presentation_definition/{id}")
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_yaml_snapshot as assert_snapshot;
+    use serde_json::{json, Value};
+    use vercre_infosec::jose::jws;
+    use vercre_openid::verifier::{CreateRequestRequest, RequestObjectRequest, RequestObjectType};
+    use vercre_test_utils::verifier::{Provider, VERIFIER_ID};
+    use vercre_w3c_vc::verify_key;
+
+    use super::*;
+    use crate::{create_request, request_object};
+
+    #[tokio::test]
+    async fn by_reference() {
+        vercre_test_utils::init_tracer();
+        let provider = Provider::new();
+
+        let body = json!({
+            "purpose": "To verify employment",
+            "input_descriptors": [{
+                "id": "employment",
+                "constraints": {
+                    "fields": [{
+                        "path":["$.type"],
+                        "filter": {
+                            "type": "string",
+                            "const": "EmployeeIDCredential"
+                        }
+                    }]
+                }
+            }],
+            "device_flow": "CrossDevice",
+            "definition_by_reference": true
+        });
+        let mut request =
+            serde_json::from_value::<CreateRequestRequest>(body).expect("should deserialize");
+        request.client_id = VERIFIER_ID.into();
+        let response = create_request(provider.clone(), &request).await.expect("response is ok");
+
+        let request_uri = response.request_uri.expect("should have request_uri");
+        let id = request_uri.split('/').next_back().expect("should have id");
+
+        // the Request Object references the definition rather than embedding it
+        let request = RequestObjectRequest {
+            client_id: VERIFIER_ID.into(),
+            id: id.into(),
+        };
+        let response = request_object(provider.clone(), &request).await.expect("response is ok");
+        let RequestObjectType::Jwt(jwt) = response.request_object else {
+            panic!("should be a JWT");
+        };
+        let jwt: jws::Jwt<Value> =
+            jws::decode(&jwt, verify_key!(&provider)).await.expect("jwt is valid");
+        assert!(jwt.claims.get("presentation_definition").is_none());
+        assert_eq!(jwt.claims["presentation_definition_uri"], json!(uri_for(VERIFIER_ID, id)));
+
+        // the definition can be dereferenced
+        let request = PresentationDefinitionRequest {
+            client_id: VERIFIER_ID.into(),
+            id: id.into(),
+        };
+        let response =
+            presentation_definition(provider.clone(), &request).await.expect("response is ok");
+        assert_snapshot!("response", response, {
+            ".id" => "[id]",
+        });
+
+        // but only from the Verifier that created it
+        let request = PresentationDefinitionRequest {
+            client_id: "http://other.verifier".into(),
+            id: id.into(),
+        };
+        let Err(Error::InvalidRequest(_)) = presentation_definition(provider, &request).await
+        else {
+            panic!("should fail with invalid request");
+        };
+    }
+}
@@ -92,7 +92,8 @@ mod tests {
             nonce: nonce.to_string(),
             response_mode: Some("direct_post".into()),
             response_uri: Some(format!("{VERIFIER_ID}/post")),
-            presentation_definition: Kind::Object(PresentationDefinition::default()),
+            presentation_definition: Some(Kind::Object(PresentationDefinition::default())),
+            dcql_query: None,
             client_id_scheme: Some(ClientIdScheme::RedirectUri),
             client_metadata: Verifier::default(),
 
@@ -104,6 +105,7 @@ mod tests {
         let state = State {
             expires_at: Utc::now() + Expire::Request.duration(),
             request_object: req_obj,
+            presentation_definition: None,
         };
         StateStore::put(&provider, &state_key, &state, state.expires_at)
             .await
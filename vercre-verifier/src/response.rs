@@ -20,15 +20,31 @@
 //!
 //! If the Response Type value is "code" (Authorization Code Grant Type), the VP
 //! Token is provided in the Token Response.
+//!
+//! When the Authorization Request's Response Mode is "`direct_post.jwt`", the
+//! Wallet returns the response encrypted in the `response` parameter. It is
+//! decrypted using the Verifier's `KeyAgreement` provider before being validated, and
+//! unencrypted responses are rejected.
+
+use std::collections::HashMap;
 
 use serde_json::Value;
 use serde_json_path::JsonPath;
 use tracing::instrument;
-use vercre_core::Kind;
-use vercre_openid::verifier::{Provider, ResponseRequest, ResponseResponse, StateStore};
-use vercre_openid::{Error, Result};
-use vercre_w3c_vc::model::VerifiableCredential;
-use vercre_w3c_vc::proof::{Payload, Verify};
+use vercre_core::{Kind, Quota};
+use vercre_dif_exch::{Constraints, DcqlQuery, Directive, PresentationSubmission};
+use vercre_iso_mdl::{SessionTranscript, VerifiedDocument};
+use vercre_openid::verifier::{
+    Provider, RequestObject, ResponseRequest, ResponseResponse, StateStore, VpToken,
+};
+use vercre_openid::{jwe, Error, Result};
+use vercre_status::bitstring::{self, ValidationError};
+use vercre_status::verifier::Status;
+use vercre_w3c_vc::model::{
+    CredentialStatus, StatusPurpose, VerifiableCredential, VerifiablePresentation,
+};
+use vercre_w3c_vc::proof::{sdjwt, Payload, Verify};
+use vercre_w3c_vc::verify_key;
 
 use crate::state::State;
 
@@ -43,17 +59,41 @@ pub async fn response(
     provider: impl Provider, request: &ResponseRequest,
 ) -> Result<ResponseResponse> {
     // TODO: handle case where Wallet returns error instead of submission
-    verify(provider.clone(), request).await?;
+    let decrypted = match &request.response {
+        Some(compact) => Some(decrypt(&provider, compact).await?),
+        None => None,
+    };
+    let encrypted = decrypted.is_some();
+    let request = decrypted.as_ref().unwrap_or(request);
+
+    verify(provider.clone(), request, encrypted).await?;
     process(provider, request).await
 }
 
+// Decrypt a "`direct_post.jwt`" response using the key identified in the JWE
+// header.
+async fn decrypt(provider: &impl Provider, compact: &str) -> Result<ResponseRequest> {
+    let header = jwe::decode_header(compact)
+        .map_err(|e| Error::InvalidRequest(format!("invalid encrypted response: {e}")))?;
+    if header.kid.is_none() {
+        return Err(Error::InvalidRequest("encrypted response key ID not set".into()));
+    }
+    let request: ResponseRequest = jwe::decrypt(compact, provider)
+        .await
+        .map_err(|e| Error::InvalidRequest(format!("issue decrypting response: {e}")))?;
+    if request.response.is_some() {
+        return Err(Error::InvalidRequest("encrypted response is nested".into()));
+    }
+    Ok(request)
+}
+
 // TODO: validate  Verifiable Presentation by format
 // Check integrity, authenticity, and holder binding of each Presentation
 // in the VP Token according to the rules for the Presentation's format.
 
 // Verfiy the vp_token and presentation subm
 #[allow(clippy::too_many_lines)]
-async fn verify(provider: impl Provider, request: &ResponseRequest) -> Result<()> {
+async fn verify(provider: impl Provider, request: &ResponseRequest, encrypted: bool) -> Result<()> {
     tracing::debug!("response::verify");
 
     // get state by client state key
@@ -65,37 +105,50 @@ async fn verify(provider: impl Provider, request: &ResponseRequest) -> Result<()
     };
     let saved_req = &state.request_object;
 
-    let Some(vp_token) = request.vp_token.clone() else {
+    // PII must not be posted in the clear when encryption was requested
+    if saved_req.response_mode.as_deref() == Some("direct_post.jwt") && !encrypted {
+        return Err(Error::InvalidRequest("response must be encrypted".into()));
+    }
+
+    let Some(vp_token) = &request.vp_token else {
         return Err(Error::InvalidRequest("vp_token not founnd".into()));
     };
 
-    let mut vps = vec![];
-
-    // check nonce matches
-    for vp_val in &vp_token {
-        let (vp, nonce) = match vercre_w3c_vc::proof::verify(Verify::Vp(vp_val), &provider).await {
-            Ok(Payload::Vp { vp, nonce, .. }) => (vp, nonce),
-            Ok(_) => return Err(Error::InvalidRequest("proof payload is invalid".into())),
-            Err(e) => return Err(Error::ServerError(format!("issue verifying VP proof: {e}"))),
+    // a DCQL query is answered with presentations keyed by credential query
+    if let Some(dcql_query) = &saved_req.dcql_query {
+        let VpToken::Keyed(presentations) = vp_token else {
+            return Err(Error::InvalidRequest("vp_token is not keyed by credential query".into()));
         };
-
-        // else {
-        //     return Err(Error::InvalidRequest("invalid vp_token".into()));
-        // };
-        if nonce != saved_req.nonce {
-            return Err(Error::InvalidRequest("nonce does not match".into()));
-        }
-        vps.push(vp);
+        return verify_dcql(&provider, saved_req, dcql_query, presentations).await;
     }
+    let VpToken::Presentations(vp_token) = vp_token else {
+        return Err(Error::InvalidRequest("vp_token is not a list of presentations".into()));
+    };
 
     let Some(subm) = &request.presentation_submission else {
         return Err(Error::InvalidRequest("no presentation_submission".into()));
     };
+
+    let mut vps = vec![];
+
+    // SD-JWT and mdoc credentials are presented directly in the VP token, keyed
+    // here by their serialization for lookup when processing the submission
+    let mut direct = HashMap::new();
+
+    for vp_val in vp_token {
+        let format = presentation_format(subm, vp_token, vp_val)?;
+        vps.push(verify_presentation(&provider, saved_req, vp_val, format, &mut direct).await?);
+    }
     let def = match &saved_req.presentation_definition {
-        Kind::Object(def) => def,
-        Kind::String(_) => {
-            return Err(Error::InvalidRequest("presentation_definition_uri is unsupported".into()));
+        Some(Kind::Object(def)) => def,
+        Some(Kind::String(_)) => {
+            // passed by reference, so saved separately
+            let Some(def) = &state.presentation_definition else {
+                return Err(Error::ServerError("presentation definition not found".into()));
+            };
+            def
         }
+        None => return Err(Error::ServerError("presentation definition not found".into())),
     };
 
     // verify presentation subm matches definition
@@ -112,18 +165,30 @@ async fn verify(provider: impl Provider, request: &ResponseRequest) -> Result<()
     // Vec to an req_obj
 
     let vp_val: Value = match vps.len() {
-        1 => serde_json::to_value(vps[0].clone())
-            .map_err(|e| Error::ServerError(format!("issue converting VP to Value: {e}")))?,
-        _ => serde_json::to_value(vps)
-            .map_err(|e| Error::ServerError(format!("issue aggregating vp values: {e}")))?,
+        1 => vps[0].clone(),
+        _ => Value::Array(vps),
     };
 
+    // when the definition has Submission Requirements, only the Input
+    // Descriptors needed to satisfy them are submitted
+    let submitted = desc_map.iter().map(|idmo| idmo.id.as_str()).collect::<Vec<_>>();
+    if def.submission_requirements.is_some()
+        && !def
+            .satisfied_by(&submitted)
+            .map_err(|e| Error::ServerError(format!("issue evaluating requirements: {e}")))?
+    {
+        return Err(Error::InvalidRequest("submission requirements not satisfied".into()));
+    }
+
     // Verify request has been fulfilled for each credential requested:
     //  - use the Input Descriptor Mapping Object(s) in the Submission to identify
     //    the matching VC in the VP Token, and verify the VC.
     for input in input_descs {
         // find Input Descriptor Mapping Object
         let Some(mapping) = desc_map.iter().find(|idmo| idmo.id == input.id) else {
+            if def.submission_requirements.is_some() {
+                continue;
+            }
             return Err(Error::InvalidRequest(format!(
                 "input descriptor mapping req_obj not found for {}",
                 input.id
@@ -140,31 +205,32 @@ async fn verify(provider: impl Provider, request: &ResponseRequest) -> Result<()
             }
         }
 
-        // search VP Token for VC specified by mapping path
+        // search VP Token for the presentation specified by mapping path, then
+        // the presentation for the VC specified by the nested path
+        let jpath = JsonPath::parse(&mapping.path)
+            .map_err(|e| Error::ServerError(format!("issue parsing JSON Path: {e}")))?;
+        let Ok(entry) = jpath.query(&vp_val).exactly_one() else {
+            return Err(Error::InvalidRequest(format!("no match for path {}", mapping.path)));
+        };
         let jpath = JsonPath::parse(&mapping.path_nested.path)
             .map_err(|e| Error::ServerError(format!("issue parsing JSON Path: {e}")))?;
-        let Ok(vc_node) = jpath.query(&vp_val).exactly_one() else {
+        let Ok(vc_node) = jpath.query(entry).exactly_one() else {
             return Err(Error::InvalidRequest(format!(
                 "no match for path_nested {}",
                 mapping.path_nested.path
             )));
         };
 
-        let vc_kind: Kind<VerifiableCredential> = match vc_node {
-            Value::String(token) => Kind::String(token.to_string()),
-            Value::Object(_) => {
-                let vc: VerifiableCredential = serde_json::from_value(vc_node.clone())
-                    .map_err(|e| Error::ServerError(format!("issue deserializing vc: {e}")))?;
-                Kind::Object(vc)
+        // SD-JWT and mdoc credentials have already been verified
+        let vc = match vc_node.as_str().and_then(|token| direct.get(token)) {
+            Some(Presented::SdJwt(vc)) => *vc.clone(),
+            Some(Presented::Mdoc(documents)) => {
+                for document in documents {
+                    verify_mdoc_constraints(&input.constraints, document)?;
+                }
+                continue;
             }
-            _ => return Err(Error::InvalidRequest(format!("unexpected VC format: {vc_node}"))),
-        };
-
-        let Payload::Vc { vc, .. } = vercre_w3c_vc::proof::verify(Verify::Vc(&vc_kind), &provider)
-            .await
-            .map_err(|e| Error::InvalidRequest(format!("invalid VC proof: {e}")))?
-        else {
-            return Err(Error::InvalidRequest("proof payload is invalid".into()));
+            None => verify_vc(&provider, vc_node).await?,
         };
 
         // verify input constraints have been met
@@ -176,15 +242,16 @@ async fn verify(provider: impl Provider, request: &ResponseRequest) -> Result<()
             return Err(Error::InvalidRequest("input constraints not satisfied".into()));
         }
 
-        // check VC is valid (hasn't expired, been revoked, etc)
-        if vc.valid_until.is_some_and(|exp| exp < chrono::Utc::now()) {
-            return Err(Error::InvalidRequest("credential has expired".into()));
+        // when disclosure is limited, only the claims requested may be disclosed
+        if !input
+            .constraints
+            .disclosure_limited(&vc)
+            .map_err(|e| Error::ServerError(format!("issue matching constraints: {e}")))?
+        {
+            return Err(Error::InvalidRequest("credential discloses more than requested".into()));
         }
 
-        // TODO: look up credential status using status.id
-        // if let Some(_status) = &vc.credential_status {
-        //     // TODO: look up credential status using status.id
-        // }
+        verify_validity(&provider, &vc).await?;
     }
 
     // TODO: perform Verifier policy checks
@@ -194,6 +261,299 @@ async fn verify(provider: impl Provider, request: &ResponseRequest) -> Result<()
     Ok(())
 }
 
+// Verify the presentations returned for each credential query in a DCQL query.
+async fn verify_dcql(
+    provider: &impl Provider, saved_req: &RequestObject, dcql_query: &DcqlQuery,
+    presentations: &HashMap<String, Vec<Kind<VerifiablePresentation>>>,
+) -> Result<()> {
+    for (id, vp_token) in presentations {
+        let Some(query) = dcql_query.credentials.iter().find(|q| &q.id == id) else {
+            return Err(Error::InvalidRequest(format!("unknown credential query {id}")));
+        };
+        if vp_token.is_empty() || (vp_token.len() > 1 && !query.multiple.unwrap_or_default()) {
+            return Err(Error::InvalidRequest(format!(
+                "unexpected number of presentations for {id}"
+            )));
+        }
+
+        for vp_val in vp_token {
+            let mut direct = HashMap::new();
+            let presented =
+                verify_presentation(provider, saved_req, vp_val, &query.format, &mut direct)
+                    .await?;
+
+            // SD-JWT and mdoc credentials are presented directly, other
+            // credentials are embedded in a Verifiable Presentation
+            let direct = direct.remove(presented.as_str().unwrap_or_default());
+            let vcs = match direct {
+                Some(Presented::SdJwt(vc)) => vec![*vc],
+                Some(Presented::Mdoc(documents)) => {
+                    for document in &documents {
+                        if !query
+                            .satisfied(document)
+                            .map_err(|e| Error::ServerError(format!("issue matching query: {e}")))?
+                        {
+                            return Err(Error::InvalidRequest(format!(
+                                "credential query {id} not satisfied"
+                            )));
+                        }
+                    }
+                    continue;
+                }
+                None => {
+                    let Some(Value::Array(vc_nodes)) = presented.get("verifiableCredential") else {
+                        return Err(Error::InvalidRequest(format!(
+                            "no credential presented for {id}"
+                        )));
+                    };
+                    let mut vcs = vec![];
+                    for vc_node in vc_nodes {
+                        vcs.push(verify_vc(provider, vc_node).await?);
+                    }
+                    vcs
+                }
+            };
+
+            for vc in &vcs {
+                if !query
+                    .satisfied(vc)
+                    .map_err(|e| Error::ServerError(format!("issue matching query: {e}")))?
+                {
+                    return Err(Error::InvalidRequest(format!(
+                        "credential query {id} not satisfied"
+                    )));
+                }
+                verify_validity(provider, vc).await?;
+            }
+        }
+    }
+
+    let submitted = presentations.keys().map(String::as_str).collect::<Vec<_>>();
+    if !dcql_query
+        .satisfied_by(&submitted)
+        .map_err(|e| Error::ServerError(format!("issue evaluating credential sets: {e}")))?
+    {
+        return Err(Error::InvalidRequest("credential sets not satisfied".into()));
+    }
+
+    Ok(())
+}
+
+// A verified credential presented directly in the VP Token rather than in a
+// Verifiable Presentation.
+enum Presented {
+    SdJwt(Box<VerifiableCredential>),
+    Mdoc(Vec<VerifiedDocument>),
+}
+
+// The format of a presentation in the VP Token, taken from the submission's
+// Input Descriptor Mapping Object(s) for the presentation.
+fn presentation_format<'a>(
+    subm: &'a PresentationSubmission, vp_token: &[Kind<VerifiablePresentation>],
+    vp_val: &Kind<VerifiablePresentation>,
+) -> Result<&'a str> {
+    // N.B. a single VP token entry is not wrapped in an array
+    let token_val = match vp_token {
+        [single] => serde_json::to_value(single),
+        _ => serde_json::to_value(vp_token),
+    };
+    let token_val = token_val
+        .map_err(|e| Error::ServerError(format!("issue converting VP token to Value: {e}")))?;
+    let presented = serde_json::to_value(vp_val)
+        .map_err(|e| Error::ServerError(format!("issue converting VP to Value: {e}")))?;
+
+    for mapping in &subm.descriptor_map {
+        let jpath = JsonPath::parse(&mapping.path)
+            .map_err(|e| Error::ServerError(format!("issue parsing JSON Path: {e}")))?;
+        if jpath.query(&token_val).exactly_one().is_ok_and(|entry| entry == &presented) {
+            return Ok(&mapping.format);
+        }
+    }
+    Err(Error::InvalidRequest("presentation not found in submission".into()))
+}
+
+// Verify a presentation from the VP Token according to its format, checking
+// the nonce and client_id it is bound to, and return it as JSON. SD-JWT and
+// mdoc credentials are returned as their serialization and saved to `direct`
+// for later lookup.
+async fn verify_presentation(
+    provider: &impl Provider, saved_req: &RequestObject, vp_val: &Kind<VerifiablePresentation>,
+    format: &str, direct: &mut HashMap<String, Presented>,
+) -> Result<Value> {
+    match format {
+        sdjwt::SD_JWT_TYPE => {
+            let Kind::String(token) = vp_val else {
+                return Err(Error::InvalidRequest("SD-JWT is not a string".into()));
+            };
+            let vc = sdjwt::verify(token, &saved_req.client_id, &saved_req.nonce, provider)
+                .await
+                .map_err(|e| Error::InvalidRequest(format!("invalid SD-JWT: {e}")))?;
+            direct.insert(token.clone(), Presented::SdJwt(Box::new(vc)));
+            return Ok(Value::String(token.clone()));
+        }
+
+        // an mdoc `DeviceResponse` is bound to the request by its session
+        // transcript
+        "mso_mdoc" => {
+            let Kind::String(token) = vp_val else {
+                return Err(Error::InvalidRequest("mdoc is not a string".into()));
+            };
+            let transcript = SessionTranscript::openid4vp(
+                &saved_req.client_id,
+                &saved_req.nonce,
+                saved_req.response_uri.as_deref(),
+            )
+            .map_err(|e| Error::ServerError(format!("issue creating session transcript: {e}")))?;
+            let documents =
+                vercre_iso_mdl::verify_presentation(token, &transcript, verify_key!(provider))
+                    .await
+                    .map_err(|e| Error::InvalidRequest(format!("invalid mdoc: {e}")))?;
+
+            // without trusted roots to validate against, an issuer identified
+            // only by certificate chain cannot be trusted
+            if documents.iter().any(|document| document.x5chain.is_some()) {
+                return Err(Error::InvalidRequest("untrusted mdoc issuer certificate".into()));
+            }
+            direct.insert(token.clone(), Presented::Mdoc(documents));
+            return Ok(Value::String(token.clone()));
+        }
+        _ => {}
+    }
+
+    let (vp, nonce, client_id) =
+        match vercre_w3c_vc::proof::verify(Verify::Vp(vp_val), provider).await {
+            Ok(Payload::Vp { vp, nonce, client_id }) => (vp, nonce, client_id),
+            Ok(_) => return Err(Error::InvalidRequest("proof payload is invalid".into())),
+            Err(e) => return Err(Error::InvalidRequest(format!("invalid VP proof: {e}"))),
+        };
+
+    if nonce != saved_req.nonce {
+        return Err(Error::InvalidRequest("nonce does not match".into()));
+    }
+    if client_id != saved_req.client_id {
+        return Err(Error::InvalidRequest("client_id does not match".into()));
+    }
+    serde_json::to_value(vp)
+        .map_err(|e| Error::ServerError(format!("issue converting VP to Value: {e}")))
+}
+
+// Check an mdoc document satisfies an Input Descriptor's constraints. When
+// disclosure is limited, every data element presented must be matched by a
+// constraint field.
+fn verify_mdoc_constraints(constraints: &Constraints, document: &VerifiedDocument) -> Result<()> {
+    if !constraints
+        .satisfied(document)
+        .map_err(|e| Error::ServerError(format!("issue matching constraints: {e}")))?
+    {
+        return Err(Error::InvalidRequest("input constraints not satisfied".into()));
+    }
+
+    if constraints.limit_disclosure == Some(Directive::Required) {
+        let matched = constraints
+            .matched_paths(document)
+            .map_err(|e| Error::ServerError(format!("issue matching constraints: {e}")))?;
+        for (name_space, elements) in &document.name_spaces {
+            let Some(elements) = elements.as_object() else {
+                continue;
+            };
+            if elements
+                .keys()
+                .any(|name| !matched.contains(&vec![name_space.clone(), name.clone()]))
+            {
+                return Err(Error::InvalidRequest(
+                    "credential discloses more than requested".into(),
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Check a VC is valid: it hasn't expired, been revoked, etc.
+async fn verify_validity(provider: &impl Provider, vc: &VerifiableCredential) -> Result<()> {
+    if vc.valid_until.is_some_and(|exp| exp < chrono::Utc::now()) {
+        return Err(Error::InvalidRequest("credential has expired".into()));
+    }
+
+    // check VC status (revoked, suspended, etc)
+    if let Some(status) = &vc.credential_status {
+        let issuer = match &vc.issuer {
+            Kind::String(id) => id,
+            Kind::Object(issuer) => &issuer.id,
+        };
+        verify_status(provider, status, issuer).await?;
+    }
+    Ok(())
+}
+
+// Check the status of a VC against each of its published status lists, which
+// must be issued by the VC's issuer.
+async fn verify_status(
+    provider: &impl Provider, credential_status: &Quota<CredentialStatus>, issuer: &str,
+) -> Result<()> {
+    if !Status::check_status(provider) {
+        tracing::debug!("credential status not checked");
+        return Ok(());
+    }
+
+    let statuses = match credential_status {
+        Quota::One(status) => vec![status.clone()],
+        Quota::Many(statuses) => statuses.clone(),
+    };
+
+    for status in &statuses {
+        let result = bitstring::validate(provider, status, issuer).await.map_err(|e| {
+            let error = serde_json::from_value::<ValidationError>(e.to_json());
+            let detail = error.map_or_else(
+                |e| e.to_string(),
+                |e| format!("{}: {}", e.title.unwrap_or_default(), e.detail.unwrap_or_default()),
+            );
+            Error::InvalidRequest(detail)
+        })?;
+
+        if result.valid {
+            continue;
+        }
+        match result.purpose {
+            StatusPurpose::Revocation => {
+                return Err(Error::InvalidRequest("credential has been revoked".into()));
+            }
+            StatusPurpose::Suspension => {
+                return Err(Error::InvalidRequest("credential has been suspended".into()));
+            }
+            // status messages are informational
+            StatusPurpose::Message => {
+                tracing::debug!("credential status message: {:?}", result.message);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Verify a VC embedded in a Verifiable Presentation.
+async fn verify_vc(provider: &impl Provider, vc_node: &Value) -> Result<VerifiableCredential> {
+    let vc_kind: Kind<VerifiableCredential> = match vc_node {
+        Value::String(token) => Kind::String(token.clone()),
+        Value::Object(_) => {
+            let vc: VerifiableCredential = serde_json::from_value(vc_node.clone())
+                .map_err(|e| Error::ServerError(format!("issue deserializing vc: {e}")))?;
+            Kind::Object(vc)
+        }
+        _ => return Err(Error::InvalidRequest(format!("unexpected VC format: {vc_node}"))),
+    };
+
+    let Payload::Vc { vc, .. } = vercre_w3c_vc::proof::verify(Verify::Vc(&vc_kind), provider)
+        .await
+        .map_err(|e| Error::InvalidRequest(format!("invalid VC proof: {e}")))?
+    else {
+        return Err(Error::InvalidRequest("proof payload is invalid".into()));
+    };
+
+    Ok(vc)
+}
+
 // Process the authorization request
 async fn process(provider: impl Provider, request: &ResponseRequest) -> Result<ResponseResponse> {
     tracing::debug!("response::process");
@@ -217,20 +577,33 @@ async fn process(provider: impl Provider, request: &ResponseRequest) -> Result<R
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
     use std::sync::LazyLock;
 
+    use base64ct::{Base64UrlUnpadded, Encoding};
     use chrono::Utc;
     use serde_json::json;
-    use vercre_dif_exch::PresentationDefinition;
+    use vercre_dif_exch::{Directive, PresentationDefinition, Rule};
+    use vercre_infosec::jose::jwk::{Curve, KeyType, PublicKeyJwk};
+    use vercre_infosec::jose::jws::{self, Type};
+    use vercre_infosec::{Encryptor, SecOps, Signer};
     use vercre_openid::verifier::{
         ClientIdScheme, RequestObject, ResponseRequest, ResponseType, Verifier,
     };
+    use vercre_status::config::ListConfig;
+    use vercre_status::log::{StatusLogEntry, StatusValue};
     use vercre_test_utils::verifier::Provider;
+    use vercre_w3c_vc::model::{
+        Bitstring, CredentialStatusType, CredentialSubject, VerifiablePresentation,
+    };
+    use vercre_w3c_vc::proof::sdjwt::{KbJwtClaims, SdJwt, SdJwtVc};
+    use vercre_w3c_vc::proof::{self, W3cFormat};
 
     use super::*;
     use crate::state::Expire;
 
     const CLIENT_ID: &str = "http://vercre.io";
+    const STATUS_LIST_URL: &str = "http://vercre.io/status";
 
     #[tokio::test]
     async fn send_response() {
@@ -251,7 +624,8 @@ mod tests {
             nonce: nonce.clone(),
             response_mode: Some("direct_post.jwt".into()),
             response_uri: Some(format!("{CLIENT_ID}/direct_post.jwt")),
-            presentation_definition: Kind::Object(pres_def.clone()),
+            presentation_definition: Some(Kind::Object(pres_def.clone())),
+            dcql_query: None,
             client_id_scheme: Some(ClientIdScheme::Did),
             client_metadata: Verifier::default(),
         };
@@ -260,33 +634,1009 @@ mod tests {
         let state = State {
             expires_at: Utc::now() + Expire::Request.duration(),
             request_object: req_obj,
+            presentation_definition: None,
         };
         StateStore::put(&provider, &state_key, &state, state.expires_at)
             .await
             .expect("state exists");
 
-        // replace placeholders with actual values
-        let mut vp_token = VP_TOKEN.to_owned();
-        let mut submission = SUBMISSION.to_owned();
+        // secure the presentation with a Data Integrity proof
+        let holder = vercre_test_utils::holder::Provider::new();
+        let mut vp = serde_json::from_value::<VerifiablePresentation>(VP_TOKEN.to_owned())
+            .expect("should deserialize");
+        vp.holder = Some(holder.verification_method());
+        let payload = Payload::Vp {
+            vp,
+            client_id: CLIENT_ID.into(),
+            nonce,
+        };
+        let vp = proof::create(W3cFormat::DataIntegrityJsonLd, payload, holder)
+            .await
+            .expect("should create VP");
+        let vp_token: Value = serde_json::from_str(&vp).expect("should deserialize");
 
         // replace placeholders with actual values
-        *vp_token.get_mut(0).unwrap().get_mut("proof").unwrap().get_mut("challenge").unwrap() =
-            json!(nonce);
+        let mut submission = SUBMISSION.to_owned();
         *submission.get_mut("definition_id").unwrap() = json!(pres_def.id);
 
         let body = json!({
-            "vp_token":  vp_token,
+            "vp_token":  [vp_token],
             "presentation_submission": submission,
             "state": state_key,
         });
 
         let request = serde_json::from_value::<ResponseRequest>(body).expect("should deserialize");
+
+        // a `direct_post.jwt` response must be encrypted
+        let Err(Error::InvalidRequest(e)) = response(provider.clone(), &request).await else {
+            panic!("should fail with invalid request");
+        };
+        assert_eq!(e, "response must be encrypted");
+
+        // encrypt the response with the Verifier's key
+        let encryptor = SecOps::encryptor(&provider, CLIENT_ID).expect("should get encryptor");
+        let verifier_key = Encryptor::public_key(&encryptor);
+        drop(encryptor);
+        let jwk = PublicKeyJwk {
+            kid: Some(CLIENT_ID.into()),
+            kty: KeyType::Okp,
+            crv: Curve::X25519,
+            x: Base64UrlUnpadded::encode_string(&verifier_key),
+            ..PublicKeyJwk::default()
+        };
+        let compact =
+            jwe::encrypt(&request, &jwk, jwe::ECDH_ES, jwe::A256GCM).expect("should encrypt");
+        let request = ResponseRequest {
+            response: Some(compact),
+            ..ResponseRequest::default()
+        };
         let response = response(provider, &request).await.expect("response is ok");
 
         let redirect = response.redirect_uri.as_ref().expect("has redirect_uri");
         assert_eq!(redirect, "http://localhost:3000/cb");
     }
 
+    #[tokio::test]
+    async fn data_integrity_response() {
+        vercre_test_utils::init_tracer();
+
+        let provider = Provider::new();
+        let pres_def = serde_json::from_value::<PresentationDefinition>(DEFINITION.to_owned())
+            .expect("definition to deserialize");
+        let nonce = "VWXYZAB".to_string();
+
+        // an ldp_vc issued by the issuer, presented in an ldp_vp
+        let vc = VerifiableCredential::builder()
+            .add_context(Kind::String("https://www.w3.org/2018/credentials/examples/v1".into()))
+            .id("http://vercre.io/credentials/EmployeeIDCredential")
+            .add_type("EmployeeIDCredential")
+            .issuer("did:web:demo.credibil.io")
+            .add_subject(CredentialSubject {
+                id: None,
+                claims: json!({"employeeId": "1234567890"})
+                    .as_object()
+                    .cloned()
+                    .expect("should be an object"),
+            })
+            .build()
+            .expect("should build");
+        let signer = SecOps::signer(&provider, CLIENT_ID).expect("should get signer");
+        let payload = Payload::Vc {
+            vc,
+            issued_at: Utc::now().timestamp(),
+        };
+        let vc = proof::create(W3cFormat::DataIntegrityJsonLd, payload, signer)
+            .await
+            .expect("should create VC");
+        let vc: VerifiableCredential = serde_json::from_str(&vc).expect("should deserialize");
+
+        let holder = vercre_test_utils::holder::Provider::new();
+        let vp = VerifiablePresentation::builder()
+            .add_context(Kind::String("https://www.w3.org/2018/credentials/examples/v1".into()))
+            .add_type("EmployeeIDPresentation")
+            .add_credential(Kind::Object(vc))
+            .holder(holder.verification_method())
+            .build()
+            .expect("should build");
+        let payload = Payload::Vp {
+            vp,
+            client_id: CLIENT_ID.into(),
+            nonce: nonce.clone(),
+        };
+        let vp = proof::create(W3cFormat::DataIntegrityJsonLd, payload, holder)
+            .await
+            .expect("should create VP");
+        let vp: Value = serde_json::from_str(&vp).expect("should deserialize");
+
+        let request = ldp_request(&provider, &pres_def, "DATAINT1", &nonce, vp.clone()).await;
+        response(provider.clone(), &request).await.expect("response is ok");
+
+        // tampering with the presented credential invalidates both proofs
+        let mut tampered = vp.clone();
+        tampered["verifiableCredential"][0]["credentialSubject"]["employeeId"] =
+            json!("0987654321");
+        let request = ldp_request(&provider, &pres_def, "DATAINT2", &nonce, tampered).await;
+        let Err(Error::InvalidRequest(e)) = response(provider.clone(), &request).await else {
+            panic!("should fail with invalid request");
+        };
+        assert!(e.starts_with("invalid VP proof"));
+
+        // the proof cannot be checked against the holder when it is omitted
+        let mut anonymous = vp.clone();
+        anonymous.as_object_mut().expect("should be an object").remove("holder");
+        let request = ldp_request(&provider, &pres_def, "DATAINT4", &nonce, anonymous).await;
+        let Err(Error::InvalidRequest(e)) = response(provider.clone(), &request).await else {
+            panic!("should fail with invalid request");
+        };
+        assert!(e.contains("presentation holder is required"));
+
+        // a presentation with an unsigned proof is rejected
+        let mut forged = vp;
+        forged["proof"] = json!({
+            "type": "DataIntegrityProof",
+            "cryptosuite": "eddsa-jcs-2022",
+            "proofPurpose": "authentication",
+            "verificationMethod": forged["proof"]["verificationMethod"],
+            "challenge": forged["proof"]["challenge"],
+            "domain": CLIENT_ID,
+            "proofValue": "z"
+        });
+        let request = ldp_request(&provider, &pres_def, "DATAINT3", &nonce, forged).await;
+        let Err(Error::InvalidRequest(e)) = response(provider, &request).await else {
+            panic!("should fail with invalid request");
+        };
+        assert!(e.starts_with("invalid VP proof"));
+    }
+
+    #[tokio::test]
+    async fn data_integrity_issuer() {
+        vercre_test_utils::init_tracer();
+
+        let provider = Provider::new();
+
+        // the credential is signed with a key not controlled by its issuer
+        let vc = VerifiableCredential::builder()
+            .id("http://vercre.io/credentials/EmployeeIDCredential")
+            .add_type("EmployeeIDCredential")
+            .issuer("did:web:other.example")
+            .add_subject(CredentialSubject {
+                id: None,
+                claims: json!({"employeeId": "1234567890"})
+                    .as_object()
+                    .cloned()
+                    .expect("should be an object"),
+            })
+            .build()
+            .expect("should build");
+        let signer = SecOps::signer(&provider, CLIENT_ID).expect("should get signer");
+        let payload = Payload::Vc {
+            vc,
+            issued_at: Utc::now().timestamp(),
+        };
+        let vc = proof::create(W3cFormat::DataIntegrityJsonLd, payload, signer)
+            .await
+            .expect("should create VC");
+        let vc: VerifiableCredential = serde_json::from_str(&vc).expect("should deserialize");
+
+        let Err(e) = proof::verify(Verify::Vc(&Kind::Object(vc)), &provider).await else {
+            panic!("should fail to verify");
+        };
+        assert!(e.to_string().contains("is not controlled by did:web:other.example"));
+    }
+
+    // Create a presentation response for an `ldp_vp` presentation.
+    async fn ldp_request(
+        provider: &Provider, pres_def: &PresentationDefinition, state_key: &str, nonce: &str,
+        vp: Value,
+    ) -> ResponseRequest {
+        let req_obj = RequestObject {
+            response_type: ResponseType::VpToken,
+            client_id: CLIENT_ID.to_string(),
+            redirect_uri: None,
+            scope: None,
+            state: Some(state_key.to_string()),
+            nonce: nonce.to_string(),
+            response_mode: Some("direct_post".into()),
+            response_uri: Some(format!("{CLIENT_ID}/direct_post")),
+            presentation_definition: Some(Kind::Object(pres_def.clone())),
+            dcql_query: None,
+            client_id_scheme: Some(ClientIdScheme::Did),
+            client_metadata: Verifier::default(),
+        };
+        let state = State {
+            expires_at: Utc::now() + Expire::Request.duration(),
+            request_object: req_obj,
+            presentation_definition: None,
+        };
+        StateStore::put(provider, state_key, &state, state.expires_at).await.expect("state exists");
+
+        let body = json!({
+            "vp_token": [vp],
+            "presentation_submission": {
+                "id": "e9c1a4b2-4f4f-4d0e-8d1c-5f0b2a7c3e1d",
+                "definition_id": pres_def.id,
+                "descriptor_map": [{
+                    "id": "EmployeeIDCredential",
+                    "format": "ldp_vp",
+                    "path": "$",
+                    "path_nested": {
+                        "format": "ldp_vc",
+                        "path": "$.verifiableCredential[0]"
+                    }
+                }]
+            },
+            "state": state_key,
+        });
+        serde_json::from_value::<ResponseRequest>(body).expect("should deserialize")
+    }
+
+    #[tokio::test]
+    async fn sd_jwt_response() {
+        vercre_test_utils::init_tracer();
+
+        let provider = Provider::new();
+        let state_key = "5678EFGH".to_string();
+        let nonce = "HIJKLMN".to_string();
+        let pres_def = sd_jwt_definition(&provider, &state_key, &nonce).await;
+
+        // disclose only the claim required by the definition
+        let issued = issue_sd_jwt(&provider).await;
+        let holder = vercre_test_utils::holder::Provider::new();
+        let presented = sdjwt::present(&issued, &["family_name".into()], CLIENT_ID, &nonce, holder)
+            .await
+            .expect("should present");
+
+        let request = sd_jwt_request(&state_key, &pres_def.id, &presented);
+        let response = response(provider, &request).await.expect("response is ok");
+        assert_eq!(response.redirect_uri, Some("http://localhost:3000/cb".into()));
+    }
+
+    #[tokio::test]
+    async fn sd_jwt_undisclosed() {
+        vercre_test_utils::init_tracer();
+
+        let provider = Provider::new();
+        let state_key = "9012IJKL".to_string();
+        let nonce = "OPQRSTU".to_string();
+        let pres_def = sd_jwt_definition(&provider, &state_key, &nonce).await;
+
+        // withhold the claim required by the definition
+        let issued = issue_sd_jwt(&provider).await;
+        let holder = vercre_test_utils::holder::Provider::new();
+        let presented = sdjwt::present(&issued, &["given_name".into()], CLIENT_ID, &nonce, holder)
+            .await
+            .expect("should present");
+
+        let request = sd_jwt_request(&state_key, &pres_def.id, &presented);
+        let Err(Error::InvalidRequest(e)) = response(provider, &request).await else {
+            panic!("should fail with invalid request");
+        };
+        assert_eq!(e, "input constraints not satisfied");
+    }
+
+    #[tokio::test]
+    async fn sd_jwt_expired() {
+        vercre_test_utils::init_tracer();
+
+        let provider = Provider::new();
+        let state_key = "2345QRST".to_string();
+        let nonce = "VWXYZAB".to_string();
+        let pres_def = sd_jwt_definition(&provider, &state_key, &nonce).await;
+
+        let vc = SdJwtVc {
+            expires_at: Some(Utc::now().timestamp() - 60),
+            ..sd_jwt_vc(&provider).await
+        };
+        let signer = SecOps::signer(&provider, CLIENT_ID).expect("should get signer");
+        let issued = sdjwt::create(vc, signer).await.expect("should create");
+        let holder = vercre_test_utils::holder::Provider::new();
+        let presented = sdjwt::present(&issued, &["family_name".into()], CLIENT_ID, &nonce, holder)
+            .await
+            .expect("should present");
+
+        let request = sd_jwt_request(&state_key, &pres_def.id, &presented);
+        let Err(Error::InvalidRequest(e)) = response(provider, &request).await else {
+            panic!("should fail with invalid request");
+        };
+        assert_eq!(e, "invalid SD-JWT: SD-JWT has expired");
+    }
+
+    #[tokio::test]
+    async fn sd_jwt_issuer_key() {
+        vercre_test_utils::init_tracer();
+
+        let provider = Provider::new();
+        let state_key = "6789UVWX".to_string();
+        let nonce = "CDEFGHI".to_string();
+        let pres_def = sd_jwt_definition(&provider, &state_key, &nonce).await;
+
+        // the issuer's key must be controlled by the DID in `iss`
+        let vc = SdJwtVc {
+            issuer: "did:web:other.example".into(),
+            ..sd_jwt_vc(&provider).await
+        };
+        let signer = SecOps::signer(&provider, CLIENT_ID).expect("should get signer");
+        let issued = sdjwt::create(vc, signer).await.expect("should create");
+        let holder = vercre_test_utils::holder::Provider::new();
+        let presented = sdjwt::present(&issued, &["family_name".into()], CLIENT_ID, &nonce, holder)
+            .await
+            .expect("should present");
+
+        let request = sd_jwt_request(&state_key, &pres_def.id, &presented);
+        let Err(Error::InvalidRequest(e)) = response(provider, &request).await else {
+            panic!("should fail with invalid request");
+        };
+        assert!(e.contains("is not controlled by did:web:other.example"));
+    }
+
+    #[tokio::test]
+    async fn sd_jwt_stale_key_binding() {
+        vercre_test_utils::init_tracer();
+
+        let provider = Provider::new();
+        let state_key = "0123YZAB".to_string();
+        let nonce = "JKLMNOP".to_string();
+        let pres_def = sd_jwt_definition(&provider, &state_key, &nonce).await;
+
+        // re-sign the Key Binding JWT as if created some time ago
+        let issued = issue_sd_jwt(&provider).await;
+        let mut sd_jwt = SdJwt::parse(&issued).expect("should parse");
+        sd_jwt.select(&["family_name".into()]).expect("should select");
+        let claims = KbJwtClaims {
+            nonce: nonce.clone(),
+            aud: CLIENT_ID.into(),
+            iat: Utc::now().timestamp() - sdjwt::KB_JWT_MAX_AGE - 60,
+            sd_hash: sdjwt::digest(&sd_jwt.without_key_binding()),
+        };
+        let holder = vercre_test_utils::holder::Provider::new();
+        let kb_jwt = sdjwt::key_binding(&claims, &holder).await.expect("should encode");
+        sd_jwt.key_binding = Some(kb_jwt);
+
+        let request = sd_jwt_request(&state_key, &pres_def.id, &sd_jwt.to_string());
+        let Err(Error::InvalidRequest(e)) = response(provider, &request).await else {
+            panic!("should fail with invalid request");
+        };
+        assert_eq!(e, "invalid SD-JWT: Key Binding JWT is not fresh");
+    }
+
+    #[tokio::test]
+    async fn sd_jwt_key_binding_skew() {
+        vercre_test_utils::init_tracer();
+
+        let provider = Provider::new();
+        let nonce = "QRSTUVW".to_string();
+        let issued = issue_sd_jwt(&provider).await;
+
+        // a Key Binding JWT from a Wallet whose clock is slightly ahead is
+        // accepted, but not one from too far in the future
+        let cases = [
+            ("4567CDEF", 60, None),
+            (
+                "89ABGHIJ",
+                sdjwt::KB_JWT_MAX_AGE + 60,
+                Some("invalid SD-JWT: Key Binding JWT is not fresh"),
+            ),
+        ];
+        for (state_key, skew, expected) in cases {
+            let pres_def = sd_jwt_definition(&provider, state_key, &nonce).await;
+
+            let mut sd_jwt = SdJwt::parse(&issued).expect("should parse");
+            sd_jwt.select(&["family_name".into()]).expect("should select");
+            let claims = KbJwtClaims {
+                nonce: nonce.clone(),
+                aud: CLIENT_ID.into(),
+                iat: Utc::now().timestamp() + skew,
+                sd_hash: sdjwt::digest(&sd_jwt.without_key_binding()),
+            };
+            let holder = vercre_test_utils::holder::Provider::new();
+            let kb_jwt = sdjwt::key_binding(&claims, &holder).await.expect("should encode");
+            sd_jwt.key_binding = Some(kb_jwt);
+
+            let request = sd_jwt_request(state_key, &pres_def.id, &sd_jwt.to_string());
+            let result = response(provider.clone(), &request).await;
+            match expected {
+                None => {
+                    result.expect("response is ok");
+                }
+                Some(expected) => {
+                    let Err(Error::InvalidRequest(e)) = result else {
+                        panic!("should fail with invalid request");
+                    };
+                    assert_eq!(e, expected);
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn sd_jwt_key_binding_typ() {
+        vercre_test_utils::init_tracer();
+
+        let provider = Provider::new();
+        let state_key = "6789GHIJ".to_string();
+        let nonce = "CDEFGHI".to_string();
+        let pres_def = sd_jwt_definition(&provider, &state_key, &nonce).await;
+
+        // sign the Key Binding JWT as a plain JWT
+        let issued = issue_sd_jwt(&provider).await;
+        let mut sd_jwt = SdJwt::parse(&issued).expect("should parse");
+        sd_jwt.select(&["family_name".into()]).expect("should select");
+        let claims = KbJwtClaims {
+            nonce: nonce.clone(),
+            aud: CLIENT_ID.into(),
+            iat: Utc::now().timestamp(),
+            sd_hash: sdjwt::digest(&sd_jwt.without_key_binding()),
+        };
+        let holder = vercre_test_utils::holder::Provider::new();
+        let kb_jwt = jws::encode(Type::Jwt, &claims, holder).await.expect("should encode");
+        sd_jwt.key_binding = Some(kb_jwt);
+
+        let request = sd_jwt_request(&state_key, &pres_def.id, &sd_jwt.to_string());
+        let Err(Error::InvalidRequest(e)) = response(provider, &request).await else {
+            panic!("should fail with invalid request");
+        };
+        assert!(e.starts_with("invalid SD-JWT: invalid Key Binding JWT typ"));
+    }
+
+    #[tokio::test]
+    async fn sd_jwt_wrong_typ() {
+        vercre_test_utils::init_tracer();
+
+        let provider = Provider::new();
+        let state_key = "2345CDEF".to_string();
+        let nonce = "VWXYZAB".to_string();
+        let pres_def = sd_jwt_definition(&provider, &state_key, &nonce).await;
+
+        // re-sign the issuer-signed JWT as a plain `vc+jwt`
+        let mut sd_jwt = SdJwt::parse(&issue_sd_jwt(&provider).await).expect("should parse");
+        let claims = sd_jwt.jwt.split('.').nth(1).expect("should have claims");
+        let claims = Base64UrlUnpadded::decode_vec(claims).expect("should decode");
+        let claims: Value = serde_json::from_slice(&claims).expect("should deserialize");
+        let signer = SecOps::signer(&provider, CLIENT_ID).expect("should get signer");
+        sd_jwt.jwt = jws::encode(Type::Credential, &claims, signer).await.expect("should encode");
+
+        let holder = vercre_test_utils::holder::Provider::new();
+        let presented =
+            sdjwt::present(&sd_jwt.to_string(), &["family_name".into()], CLIENT_ID, &nonce, holder)
+                .await
+                .expect("should present");
+
+        let request = sd_jwt_request(&state_key, &pres_def.id, &presented);
+        let Err(Error::InvalidRequest(e)) = response(provider, &request).await else {
+            panic!("should fail with invalid request");
+        };
+        assert!(e.starts_with("invalid SD-JWT: invalid SD-JWT typ"));
+    }
+
+    #[tokio::test]
+    async fn submission_requirements() {
+        vercre_test_utils::init_tracer();
+
+        let provider = Provider::new();
+        let nonce = "VWXYZAB".to_string();
+
+        // only one of the two descriptors needs to be submitted
+        let mut pres_def = serde_json::from_value::<PresentationDefinition>(json!({
+            "id": "c3a4b2e1-0d5f-4e6a-9b7c-8d9e0f1a2b3c",
+            "submission_requirements": [{
+                "rule": "pick",
+                "count": 1,
+                "from": "A"
+            }],
+            "input_descriptors": [{
+                "id": "EmployeeID_SD_JWT",
+                "group": ["A"],
+                "constraints":  {
+                    "fields": [{
+                        "path": ["$.type"],
+                        "filter": {
+                            "type": "string",
+                            "const": "EmployeeIDCredential"
+                        }
+                    }]
+                }
+            }, {
+                "id": "Passport_SD_JWT",
+                "group": ["A"],
+                "constraints":  {
+                    "fields": [{
+                        "path": ["$.type"],
+                        "filter": {
+                            "type": "string",
+                            "const": "PassportCredential"
+                        }
+                    }]
+                }
+            }]
+        }))
+        .expect("definition to deserialize");
+        save_definition(&provider, "3456MNOP", &nonce, &pres_def).await;
+
+        let issued = issue_sd_jwt(&provider).await;
+        let holder = vercre_test_utils::holder::Provider::new();
+        let presented =
+            sdjwt::present(&issued, &[], CLIENT_ID, &nonce, holder).await.expect("should present");
+
+        let request = sd_jwt_request("3456MNOP", &pres_def.id, &presented);
+        response(provider.clone(), &request).await.expect("response is ok");
+
+        // both descriptors are now required
+        pres_def.submission_requirements.as_mut().expect("has requirements")[0].rule = Rule::All;
+        save_definition(&provider, "7890QRST", &nonce, &pres_def).await;
+
+        let request = sd_jwt_request("7890QRST", &pres_def.id, &presented);
+        let Err(Error::InvalidRequest(e)) = response(provider, &request).await else {
+            panic!("should fail with invalid request");
+        };
+        assert_eq!(e, "submission requirements not satisfied");
+    }
+
+    #[tokio::test]
+    async fn sd_jwt_over_disclosed() {
+        vercre_test_utils::init_tracer();
+
+        let provider = Provider::new();
+        let state_key = "1357UVWX".to_string();
+        let nonce = "CDEFGHI".to_string();
+        let mut pres_def = sd_jwt_definition(&provider, &state_key, &nonce).await;
+        pres_def.input_descriptors[0].constraints.limit_disclosure = Some(Directive::Required);
+        save_definition(&provider, &state_key, &nonce, &pres_def).await;
+
+        // disclose a claim not requested by the definition
+        let issued = issue_sd_jwt(&provider).await;
+        let holder = vercre_test_utils::holder::Provider::new();
+        let names = ["family_name".into(), "given_name".into()];
+        let presented = sdjwt::present(&issued, &names, CLIENT_ID, &nonce, holder)
+            .await
+            .expect("should present");
+
+        let request = sd_jwt_request(&state_key, &pres_def.id, &presented);
+        let Err(Error::InvalidRequest(e)) = response(provider, &request).await else {
+            panic!("should fail with invalid request");
+        };
+        assert_eq!(e, "credential discloses more than requested");
+    }
+
+    #[tokio::test]
+    async fn dcql_response() {
+        vercre_test_utils::init_tracer();
+
+        let provider = Provider::new();
+        let state_key = "2468YZAB".to_string();
+        let nonce = "JKLMNOP".to_string();
+
+        let dcql_query = serde_json::from_value::<DcqlQuery>(json!({
+            "credentials": [{
+                "id": "employee",
+                "format": "vc+sd-jwt",
+                "meta": {"vct_values": ["EmployeeIDCredential"]},
+                "claims": [{"path": ["credentialSubject", "family_name"]}]
+            }]
+        }))
+        .expect("query to deserialize");
+        let req_obj = RequestObject {
+            client_id: CLIENT_ID.to_string(),
+            state: Some(state_key.clone()),
+            nonce: nonce.clone(),
+            dcql_query: Some(dcql_query),
+            ..RequestObject::default()
+        };
+        let state = State {
+            expires_at: Utc::now() + Expire::Request.duration(),
+            request_object: req_obj,
+            presentation_definition: None,
+        };
+        StateStore::put(&provider, &state_key, &state, state.expires_at)
+            .await
+            .expect("state exists");
+
+        let issued = issue_sd_jwt(&provider).await;
+        let holder = vercre_test_utils::holder::Provider::new();
+        let withheld =
+            sdjwt::present(&issued, &["given_name".into()], CLIENT_ID, &nonce, holder.clone())
+                .await
+                .expect("should present");
+        let presented = sdjwt::present(&issued, &["family_name".into()], CLIENT_ID, &nonce, holder)
+            .await
+            .expect("should present");
+
+        // presentations are keyed by credential query
+        let request = dcql_request(&state_key, "unknown", &presented);
+        let Err(Error::InvalidRequest(e)) = response(provider.clone(), &request).await else {
+            panic!("should fail with invalid request");
+        };
+        assert_eq!(e, "unknown credential query unknown");
+
+        // withhold the claim required by the query
+        let request = dcql_request(&state_key, "employee", &withheld);
+        let Err(Error::InvalidRequest(e)) = response(provider.clone(), &request).await else {
+            panic!("should fail with invalid request");
+        };
+        assert_eq!(e, "credential query employee not satisfied");
+
+        let request = dcql_request(&state_key, "employee", &presented);
+        response(provider, &request).await.expect("response is ok");
+    }
+
+    fn dcql_request(state_key: &str, query_id: &str, sd_jwt: &str) -> ResponseRequest {
+        let body = json!({
+            "vp_token": {query_id: [sd_jwt]},
+            "state": state_key,
+        });
+        serde_json::from_value::<ResponseRequest>(body).expect("should deserialize")
+    }
+
+    #[tokio::test]
+    async fn mdoc_response() {
+        vercre_test_utils::init_tracer();
+
+        let provider = Provider::new();
+        let state_key = "1357CDEF".to_string();
+        let nonce = "QRSTUVW".to_string();
+
+        let mut pres_def = serde_json::from_value::<PresentationDefinition>(json!({
+            "id": "5d2b8b5e-e3c9-4bd4-a8a1-1d2e0e7a0f3c",
+            "input_descriptors": [{
+                "id": "org.iso.18013.5.1.mDL",
+                "format": {"mso_mdoc": {"alg": ["EdDSA"]}},
+                "constraints":  {
+                    "fields": [{
+                        "path": ["$.docType"],
+                        "filter": {"type": "string", "const": "org.iso.18013.5.1.mDL"}
+                    }, {
+                        "path": ["$['org.iso.18013.5.1.mDL'].family_name"]
+                    }],
+                    "limit_disclosure": "required"
+                }
+            }]
+        }))
+        .expect("definition to deserialize");
+        save_definition(&provider, &state_key, &nonce, &pres_def).await;
+
+        let issued = issue_mdoc(&provider).await;
+        let transcript = SessionTranscript::openid4vp(
+            CLIENT_ID,
+            &nonce,
+            Some(&format!("{CLIENT_ID}/direct_post")),
+        )
+        .expect("should create transcript");
+        let present = |elements: &[&str]| {
+            let requested = BTreeMap::from([(
+                "org.iso.18013.5.1.mDL".to_string(),
+                elements.iter().map(ToString::to_string).collect(),
+            )]);
+            let transcript = transcript.clone();
+            let issued = issued.clone();
+            async move {
+                let holder = vercre_test_utils::holder::Provider::new();
+                vercre_iso_mdl::present(&issued, &requested, &transcript, holder)
+                    .await
+                    .expect("should present")
+            }
+        };
+
+        // disclosure is limited to the requested data elements
+        let over_disclosed = present(&["given_name", "family_name"]).await;
+        let request = mdoc_request(&state_key, &pres_def.id, &over_disclosed);
+        let Err(Error::InvalidRequest(e)) = response(provider.clone(), &request).await else {
+            panic!("should fail with invalid request");
+        };
+        assert_eq!(e, "credential discloses more than requested");
+
+        // device authentication is bound to the request's nonce
+        let presented = present(&["family_name"]).await;
+        pres_def.input_descriptors[0].constraints.limit_disclosure = None;
+        save_definition(&provider, "2468GHIJ", "XYZ", &pres_def).await;
+        let request = mdoc_request("2468GHIJ", &pres_def.id, &presented);
+        let Err(Error::InvalidRequest(e)) = response(provider.clone(), &request).await else {
+            panic!("should fail with invalid request");
+        };
+        assert!(e.contains("invalid device signature"));
+
+        let request = mdoc_request(&state_key, &pres_def.id, &presented);
+        response(provider, &request).await.expect("response is ok");
+    }
+
+    // Issue an mdoc credential bound to the holder's key.
+    async fn issue_mdoc(provider: &Provider) -> String {
+        let holder = vercre_test_utils::holder::Provider::new();
+        let resolve = vercre_w3c_vc::verify_key!(provider);
+        let device_key =
+            resolve(holder.verification_method()).await.expect("should resolve holder key");
+
+        let dataset = json!({
+            "org.iso.18013.5.1.mDL": {
+                "given_name": "Normal",
+                "family_name": "Person",
+            }
+        });
+        let dataset = dataset.as_object().cloned().expect("should be an object");
+        let signer = SecOps::signer(provider, CLIENT_ID).expect("should get signer");
+        vercre_iso_mdl::to_credential(dataset, &device_key, signer).await.expect("should create")
+    }
+
+    fn mdoc_request(state_key: &str, definition_id: &str, mdoc: &str) -> ResponseRequest {
+        let body = json!({
+            "vp_token": [mdoc],
+            "presentation_submission": {
+                "id": "2a514f1b-5ad7-7e53-4b5e-b7f63b4c7b0e",
+                "definition_id": definition_id,
+                "descriptor_map": [{
+                    "id": "org.iso.18013.5.1.mDL",
+                    "format": "mso_mdoc",
+                    "path": "$",
+                    "path_nested": {
+                        "format": "mso_mdoc",
+                        "path": "$"
+                    }
+                }]
+            },
+            "state": state_key,
+        });
+        serde_json::from_value::<ResponseRequest>(body).expect("should deserialize")
+    }
+
+    // Save state for a request with a definition requiring an SD-JWT credential.
+    async fn sd_jwt_definition(
+        provider: &Provider, state_key: &str, nonce: &str,
+    ) -> PresentationDefinition {
+        let pres_def = serde_json::from_value::<PresentationDefinition>(json!({
+            "id": "8b5ee3c9-5d2b-4bd4-a8a1-0e7a0f3c1d2e",
+            "input_descriptors": [{
+                "id": "EmployeeID_SD_JWT",
+                "constraints":  {
+                    "fields": [{
+                        "path": ["$.type"],
+                        "filter": {
+                            "type": "string",
+                            "const": "EmployeeIDCredential"
+                        }
+                    }, {
+                        "path": ["$.credentialSubject.family_name"]
+                    }],
+                }
+            }]
+        }))
+        .expect("definition to deserialize");
+
+        save_definition(provider, state_key, nonce, &pres_def).await;
+        pres_def
+    }
+
+    async fn save_definition(
+        provider: &Provider, state_key: &str, nonce: &str, pres_def: &PresentationDefinition,
+    ) {
+        let req_obj = RequestObject {
+            response_type: ResponseType::VpToken,
+            client_id: CLIENT_ID.to_string(),
+            redirect_uri: None,
+            scope: None,
+            state: Some(state_key.to_string()),
+            nonce: nonce.to_string(),
+            response_mode: Some("direct_post".into()),
+            response_uri: Some(format!("{CLIENT_ID}/direct_post")),
+            presentation_definition: Some(Kind::Object(pres_def.clone())),
+            dcql_query: None,
+            client_id_scheme: Some(ClientIdScheme::Did),
+            client_metadata: Verifier::default(),
+        };
+        let state = State {
+            expires_at: Utc::now() + Expire::Request.duration(),
+            request_object: req_obj,
+            presentation_definition: None,
+        };
+        StateStore::put(provider, state_key, &state, state.expires_at).await.expect("state exists");
+    }
+
+    // Issue an SD-JWT credential bound to the holder's key.
+    async fn issue_sd_jwt(provider: &Provider) -> String {
+        let vc = sd_jwt_vc(provider).await;
+        let signer = SecOps::signer(provider, CLIENT_ID).expect("should get signer");
+        sdjwt::create(vc, signer).await.expect("should create")
+    }
+
+    // The unsecured SD-JWT credential, issued by the DID of the key used to
+    // sign it.
+    async fn sd_jwt_vc(provider: &Provider) -> SdJwtVc {
+        let holder = vercre_test_utils::holder::Provider::new();
+        let resolve = vercre_w3c_vc::verify_key!(provider);
+        let holder_jwk =
+            resolve(holder.verification_method()).await.expect("should resolve holder key");
+
+        let signer = SecOps::signer(provider, CLIENT_ID).expect("should get signer");
+        let verification_method = signer.verification_method();
+        let issuer = verification_method.split('#').next().expect("should have DID");
+
+        SdJwtVc {
+            issuer: issuer.into(),
+            vct: "EmployeeIDCredential".into(),
+            subject: None,
+            claims: json!({
+                "given_name": "Normal",
+                "family_name": "Person",
+            })
+            .as_object()
+            .cloned()
+            .expect("should be an object"),
+            holder_jwk: Some(holder_jwk),
+            issued_at: Utc::now().timestamp(),
+            expires_at: None,
+        }
+    }
+
+    fn sd_jwt_request(state_key: &str, definition_id: &str, sd_jwt: &str) -> ResponseRequest {
+        let body = json!({
+            "vp_token": [sd_jwt],
+            "presentation_submission": {
+                "id": "4f1b5ad7-7e53-4b5e-b7f6-3b4c7b0e2a51",
+                "definition_id": definition_id,
+                "descriptor_map": [{
+                    "id": "EmployeeID_SD_JWT",
+                    "format": "vc+sd-jwt",
+                    "path": "$",
+                    "path_nested": {
+                        "format": "vc+sd-jwt",
+                        "path": "$"
+                    }
+                }]
+            },
+            "state": state_key,
+        });
+        serde_json::from_value::<ResponseRequest>(body).expect("should deserialize")
+    }
+
+    #[tokio::test]
+    async fn credential_status() {
+        vercre_test_utils::init_tracer();
+
+        let provider = Provider::new();
+        let pres_def = serde_json::from_value::<PresentationDefinition>(DEFINITION.to_owned())
+            .expect("definition to deserialize");
+
+        // publish a revocation list with the credential at index 3 revoked
+        let config = ListConfig {
+            purpose: StatusPurpose::Revocation,
+            list: 1,
+            size: 1,
+            messages: None,
+            reference: None,
+            entries: None,
+        };
+        let log = vec![StatusLogEntry {
+            credential_id: "http://vercre.io/credentials/EmployeeIDCredential".into(),
+            subject_id: "normal_user".into(),
+            status: vec![StatusValue {
+                purpose: StatusPurpose::Revocation,
+                list: 1,
+                shard: 0,
+                list_index: 3,
+                value: 1,
+            }],
+        }];
+        let encoded = bitstring::bitstring(&config, 0, &log).expect("should encode");
+        let signer = SecOps::signer(&provider, CLIENT_ID).expect("should get signer");
+        let list = bitstring::credential(
+            "http://vercre.io",
+            &config,
+            0,
+            STATUS_LIST_URL,
+            &encoded,
+            None,
+            signer,
+        )
+        .await
+        .expect("should create status list");
+        provider.status.put(&config.list_url(STATUS_LIST_URL, 0), &list).expect("should publish");
+
+        // a credential that has not been revoked is accepted
+        let request = status_request(&provider, &pres_def, "STATUS01", 2).await;
+        response(provider.clone(), &request).await.expect("response is ok");
+
+        // a revoked credential is rejected
+        let request = status_request(&provider, &pres_def, "STATUS02", 3).await;
+        let Err(Error::InvalidRequest(e)) = response(provider, &request).await else {
+            panic!("should fail with invalid request");
+        };
+        assert_eq!(e, "credential has been revoked");
+    }
+
+    // Create a presentation response for a credential with a status list entry
+    // at `index`.
+    async fn status_request(
+        provider: &Provider, pres_def: &PresentationDefinition, state_key: &str, index: usize,
+    ) -> ResponseRequest {
+        let nonce = format!("{state_key}-nonce");
+        let req_obj = RequestObject {
+            response_type: ResponseType::VpToken,
+            client_id: CLIENT_ID.to_string(),
+            redirect_uri: None,
+            scope: None,
+            state: Some(state_key.to_string()),
+            nonce: nonce.clone(),
+            response_mode: Some("direct_post".into()),
+            response_uri: Some(format!("{CLIENT_ID}/direct_post")),
+            presentation_definition: Some(Kind::Object(pres_def.clone())),
+            dcql_query: None,
+            client_id_scheme: Some(ClientIdScheme::Did),
+            client_metadata: Verifier::default(),
+        };
+        let state = State {
+            expires_at: Utc::now() + Expire::Request.duration(),
+            request_object: req_obj,
+            presentation_definition: None,
+        };
+        StateStore::put(provider, state_key, &state, state.expires_at).await.expect("state exists");
+
+        let status = CredentialStatus {
+            id: None,
+            credential_status_type: CredentialStatusType::Bitstring(Bitstring {
+                status_purpose: StatusPurpose::Revocation,
+                status_list_index: index,
+                status_list_credential: format!("{STATUS_LIST_URL}/1/0"),
+                status_size: None,
+                status_message: None,
+                status_reference: None,
+            }),
+        };
+        let vc = VerifiableCredential::builder()
+            .add_context(Kind::String("https://www.w3.org/2018/credentials/examples/v1".into()))
+            .id("http://vercre.io/credentials/EmployeeIDCredential")
+            .add_type("EmployeeIDCredential")
+            .issuer("http://vercre.io")
+            .add_subject(CredentialSubject {
+                id: None,
+                claims: json!({"employeeId": "1234567890"})
+                    .as_object()
+                    .cloned()
+                    .expect("should be an object"),
+            })
+            .status(Some(Quota::One(status)))
+            .build()
+            .expect("should build");
+        let signer = SecOps::signer(provider, CLIENT_ID).expect("should get signer");
+        let payload = Payload::Vc {
+            vc,
+            issued_at: Utc::now().timestamp(),
+        };
+        let credential =
+            proof::create(W3cFormat::JwtVcJson, payload, signer).await.expect("should create VC");
+
+        let holder = vercre_test_utils::holder::Provider::new();
+        let vp = VerifiablePresentation::builder()
+            .add_context(Kind::String("https://www.w3.org/2018/credentials/examples/v1".into()))
+            .add_type("EmployeeIDPresentation")
+            .add_credential(Kind::String(credential))
+            .holder(holder.verification_method())
+            .build()
+            .expect("should build");
+        let payload = Payload::Vp {
+            vp,
+            client_id: CLIENT_ID.into(),
+            nonce,
+        };
+        let presentation =
+            proof::create(W3cFormat::JwtVcJson, payload, holder).await.expect("should create VP");
+
+        let body = json!({
+            "vp_token": [presentation],
+            "presentation_submission": {
+                "id": "07b0d06d-ed7f-4c8e-b47b-2f1f3d4c9a6e",
+                "definition_id": pres_def.id,
+                "descriptor_map": [{
+                    "id": "EmployeeIDCredential",
+                    "format": "jwt_vp_json",
+                    "path": "$",
+                    "path_nested": {
+                        "format": "jwt_vc_json",
+                        "path": "$.verifiableCredential[0]"
+                    }
+                }]
+            },
+            "state": state_key,
+        });
+        serde_json::from_value::<ResponseRequest>(body).expect("should deserialize")
+    }
+
     static DEFINITION: LazyLock<Value> = LazyLock::new(|| {
         json!({
             "id": "2d1691c1-2daa-4416-9d10-bc6790e72fad",
@@ -313,14 +1663,11 @@ mod tests {
     });
 
     static VP_TOKEN: LazyLock<Value> = LazyLock::new(|| {
-        json!([{
+        json!({
             "@context": [
                 "https://www.w3.org/2018/credentials/v1",
                 "https://www.w3.org/2018/credentials/examples/v1"
             ],
-            "proof": {
-                "challenge": "<replace me!>",
-            },
             "type": [
                 "VerifiablePresentation",
                 "EmployeeIDPresentation"
@@ -328,7 +1675,7 @@ mod tests {
             "verifiableCredential": [
                 "eyJhbGciOiJFZERTQSIsInR5cCI6Imp3dCIsImtpZCI6ImRpZDp3ZWI6ZGVtby5jcmVkaWJpbC5pbyNrZXktMCJ9.eyJzdWIiOiJkaWQ6a2V5Ono2TWtqOEpyMXJnM1lqVldXaGc3YWhFWUppYnFoakJnWnQxcERDYlQ0THY3RDRIWCIsIm5iZiI6MTcyMTcwMjg5MSwiaXNzIjoiaHR0cDovL3ZlcmNyZS5pbyIsImlhdCI6MTcyMTcwMjg5MSwianRpIjoiaHR0cDovL3ZlcmNyZS5pby9jcmVkZW50aWFscy9FbXBsb3llZUlEQ3JlZGVudGlhbCIsInZjIjp7IkBjb250ZXh0IjpbImh0dHBzOi8vd3d3LnczLm9yZy8yMDE4L2NyZWRlbnRpYWxzL3YxIiwiaHR0cDovL3ZlcmNyZS5pby9jcmVkZW50aWFscy92MSJdLCJpZCI6Imh0dHA6Ly92ZXJjcmUuaW8vY3JlZGVudGlhbHMvRW1wbG95ZWVJRENyZWRlbnRpYWwiLCJ0eXBlIjpbIlZlcmlmaWFibGVDcmVkZW50aWFsIiwiRW1wbG95ZWVJRENyZWRlbnRpYWwiXSwiaXNzdWVyIjoiaHR0cDovL3ZlcmNyZS5pbyIsImlzc3VhbmNlRGF0ZSI6IjIwMjQtMDctMjNUMDI6NDg6MTEuMjgyOTg5WiIsImNyZWRlbnRpYWxTdWJqZWN0Ijp7ImlkIjoiZGlkOmtleTp6Nk1rajhKcjFyZzNZalZXV2hnN2FoRVlKaWJxaGpCZ1p0MXBEQ2JUNEx2N0Q0SFgiLCJmYW1pbHlOYW1lIjoiUGVyc29uIiwiZ2l2ZW5OYW1lIjoiTm9ybWFsIn19fQ.HQHedefAHp1PM3lKugM7nQ-ogzV1Qs4eO0QvMP5vfSVb0wT1GJ425-j_zUSSPkhAslSC4aeNosnS_3dRet7wAQ"
             ]
-        }])
+        })
     });
     static SUBMISSION: LazyLock<Value> = LazyLock::new(|| {
         json!({
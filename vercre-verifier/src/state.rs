@@ -4,6 +4,7 @@
 use chrono::{DateTime, TimeDelta, Utc};
 use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
+use vercre_dif_exch::PresentationDefinition;
 use vercre_openid::verifier::RequestObject;
 
 pub enum Expire {
@@ -28,6 +29,12 @@ pub struct State {
     /// endpoint and in comparing the Presentation Definition to the
     /// Presentation Submission.
     pub request_object: RequestObject,
+
+    /// The Presentation Definition, when passed to the Wallet by reference.
+    /// Saved for use by the `presentation_definition_uri` endpoint.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default)]
+    pub presentation_definition: Option<PresentationDefinition>,
 }
 
 // impl State {